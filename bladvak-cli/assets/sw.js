@@ -15,6 +15,17 @@ async function networkFirst(request) {
     }
 }
 
+self.addEventListener("install", (event) => {
+    event.waitUntil(caches.open(cacheName).then((cache) => cache.addAll(filesToCache)));
+});
+
+// sent by the page when the user reloads to get the new version
+self.addEventListener("message", (event) => {
+    if (event.data === "skipWaiting") {
+        self.skipWaiting();
+    }
+});
+
 self.addEventListener("fetch", (event) => {
     event.respondWith(networkFirst(event.request));
 });
@@ -4,19 +4,20 @@
 //! cargo run --example example_app
 //! ```
 
-use bladvak::app::{Bladvak, BladvakApp, MainResult};
+use bladvak::{
+    JournalPanel, LogPanel,
+    app::{Bladvak, BladvakApp, BladvakPanel, MainResult},
+};
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
 pub struct ExampleApp {}
 
 impl BladvakApp<'_> for ExampleApp {
-    fn try_new_with_args(
-        saved_state: Self,
-        _cc: &eframe::CreationContext<'_>,
-        _args: &[String],
-        _error_manager: &mut bladvak::ErrorManager,
-    ) -> Result<Self, bladvak::AppError> {
-        Ok(saved_state)
+    fn panel_list(&self) -> Vec<Box<dyn BladvakPanel<App = Self>>> {
+        vec![
+            Box::new(LogPanel::default()),
+            Box::new(JournalPanel::default()),
+        ]
     }
 
     fn version() -> String {
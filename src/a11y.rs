@@ -0,0 +1,157 @@
+//! Accessibility - `AccessKit` roles and labels
+//!
+//! The widgets of the wrapper (menus, settings, error window, panels) are annotated with these
+//! helpers; apps can use them for their own panels. The annotations are only built while
+//! `AccessKit` is active: by a screen reader or by the "Screen reader support" setting.
+//!
+//! The high contrast theme of the settings is applied to the styles by the wrapper; apps check
+//! [`is_high_contrast`] to adapt their custom painting, and [`is_reduced_motion`] to skip their
+//! animations.
+
+use eframe::egui::{self, Color32, Stroke, accesskit::Role};
+
+/// Set the role and the label of the current ui
+pub fn annotate(ui: &egui::Ui, role: Role, label: &str) {
+    ui.ctx().accesskit_node_builder(ui.unique_id(), |node| {
+        node.set_role(role);
+        node.set_label(label);
+    });
+}
+
+/// Show the contents in a child ui with a role and a label (e.g. [`Role::Region`] for a panel)
+pub fn region<R>(
+    ui: &mut egui::Ui,
+    role: Role,
+    label: &str,
+    add_contents: impl FnOnce(&mut egui::Ui) -> R,
+) -> R {
+    ui.scope(|ui| {
+        annotate(ui, role, label);
+        add_contents(ui)
+    })
+    .inner
+}
+
+/// Set the accessible label of a widget - for the widgets showing only an icon
+pub fn label(response: &egui::Response, label: &str) {
+    response.ctx.accesskit_node_builder(response.id, |node| {
+        node.set_label(label);
+    });
+}
+
+/// Enable or disable `AccessKit` - it is also enabled by the platform when a screen reader
+/// is running
+pub(crate) fn set_enabled(ctx: &egui::Context, enabled: bool) {
+    if enabled {
+        ctx.enable_accesskit();
+    } else {
+        ctx.disable_accesskit();
+    }
+}
+
+/// Id of the high contrast flag in the egui context
+fn high_contrast_id() -> egui::Id {
+    egui::Id::new("bladvak_high_contrast")
+}
+
+/// Check if the high contrast theme is enabled
+#[must_use]
+pub fn is_high_contrast(ctx: &egui::Context) -> bool {
+    ctx.data(|data| data.get_temp::<bool>(high_contrast_id()))
+        .unwrap_or(false)
+}
+
+/// Enable or disable the high contrast flag
+pub(crate) fn set_high_contrast(ctx: &egui::Context, enabled: bool) {
+    ctx.data_mut(|data| data.insert_temp(high_contrast_id(), enabled));
+}
+
+/// Turn a style into its high contrast version: plain background and text, strong borders and
+/// a larger focus ring
+pub(crate) fn high_contrast_style(style: &mut egui::Style) {
+    let visuals = &mut style.visuals;
+    let (fg, bg, accent) = if visuals.dark_mode {
+        (Color32::WHITE, Color32::BLACK, Color32::YELLOW)
+    } else {
+        (Color32::BLACK, Color32::WHITE, Color32::from_rgb(0, 0, 200))
+    };
+    visuals.override_text_color = Some(fg);
+    visuals.weak_text_color = Some(fg);
+    visuals.hyperlink_color = accent;
+    visuals.panel_fill = bg;
+    visuals.window_fill = bg;
+    visuals.faint_bg_color = bg;
+    visuals.extreme_bg_color = bg;
+    visuals.code_bg_color = bg;
+    visuals.text_edit_bg_color = Some(bg);
+    visuals.window_stroke = Stroke::new(2.0, fg);
+    visuals.selection.bg_fill = accent;
+    visuals.selection.stroke = Stroke::new(2.0, bg);
+    let widgets = &mut visuals.widgets;
+    for widget in [
+        &mut widgets.noninteractive,
+        &mut widgets.inactive,
+        &mut widgets.hovered,
+        &mut widgets.active,
+        &mut widgets.open,
+    ] {
+        widget.bg_fill = bg;
+        widget.weak_bg_fill = bg;
+        widget.bg_stroke = Stroke::new(1.5, fg);
+        widget.fg_stroke = Stroke::new(1.5, fg);
+    }
+    widgets.noninteractive.bg_stroke = Stroke::new(1.0, fg);
+    widgets.hovered.bg_stroke = Stroke::new(2.0, accent);
+    // the active visuals are also the ones of the focused widget
+    widgets.active.bg_stroke = Stroke::new(3.0, accent);
+    widgets.active.expansion = 2.0;
+}
+
+/// Id of the reduced motion flag in the egui context
+fn reduced_motion_id() -> egui::Id {
+    egui::Id::new("bladvak_reduced_motion")
+}
+
+/// Check if the animations should be avoided
+#[must_use]
+pub fn is_reduced_motion(ctx: &egui::Context) -> bool {
+    ctx.data(|data| data.get_temp::<bool>(reduced_motion_id()))
+        .unwrap_or(false)
+}
+
+/// Enable or disable the reduced motion flag
+pub(crate) fn set_reduced_motion(ctx: &egui::Context, enabled: bool) {
+    ctx.data_mut(|data| data.insert_temp(reduced_motion_id(), enabled));
+}
+
+/// Reduced motion preference of the system - only detected on web (`prefers-reduced-motion`)
+pub(crate) fn system_reduced_motion() -> bool {
+    #[cfg(target_arch = "wasm32")]
+    {
+        eframe::web_sys::window()
+            .and_then(|window| {
+                window
+                    .match_media("(prefers-reduced-motion: reduce)")
+                    .ok()
+                    .flatten()
+            })
+            .is_some_and(|query| query.matches())
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    false
+}
+
+/// Remove the animations of a style
+pub(crate) fn reduced_motion_style(style: &mut egui::Style) {
+    style.animation_time = 0.0;
+    style.scroll_animation = egui::style::ScrollAnimation::none();
+}
+
+/// Show a spinner - a static hourglass when the animations are avoided
+pub fn busy_indicator(ui: &mut egui::Ui) -> egui::Response {
+    if is_reduced_motion(ui.ctx()) {
+        ui.label("⏳")
+    } else {
+        ui.add(egui::Spinner::new())
+    }
+}
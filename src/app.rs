@@ -3,14 +3,50 @@
 use eframe::{CreationContext, egui};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, VecDeque},
     fmt::{Debug, Display},
+    path::PathBuf,
 };
 
 use crate::{
-    errors::{AppError, ErrorManager},
-    file_handler::{File, FileHandler},
+    assets::Assets,
+    command::{
+        Command, CommandPalette, FULLSCREEN_SHORTCUT, OPEN_SHORTCUT, PALETTE_SHORTCUT,
+        QUIT_SHORTCUT, SETTINGS_SHORTCUT, shortcut_button,
+    },
+    errors::{AppError, ErrorManager, ErrorPresentation, ErrorReceiver, Severity},
+    events::{Event, EventBus, PanelToggled},
+    export::{BatchExport, Converter, Exporter},
+    feedback::Feedback,
+    file_handler::{
+        DropHint, File, FileFormat, FileHandler, FileOrigin, FilePicker, PendingOpenAs,
+        PendingTrust, RfdPicker,
+    },
+    file_kind::FileKind,
+    file_registry::FileHandlers,
+    handoff::{Handoff, HandoffMessage},
+    help::{HELP_SHORTCUT, HelpBrowser, HelpTopic},
+    inbox::{Inbox, InboxMessage},
+    lifecycle::{Service, SuspendPolicy, Visibility},
+    logger::LogFileConfig,
+    menu::{MenuEntry, MenuModel},
+    overrides::PanelOverrides,
+    panel_cache::PanelCache,
+    perf::PerfStats,
+    profile::Profiles,
+    progress::ProgressHandle,
+    region::RegionDescriptor,
+    restore::PendingRestore,
+    search::{SearchBox, SearchProvider},
+    session::{AppSession, Session},
     settings::Settings,
+    startup::{StartupProfiler, StartupReport},
+    style_editor::StyleOverrides,
+    task::{RecurringJob, TaskManager},
+    telemetry::TelemetryEvent,
+    temp_files::TempFileRegistry,
+    updater::Updater,
+    utils::time::Instant,
 };
 
 /// App trait
@@ -24,6 +60,9 @@ pub trait BladvakApp<'a>: Sized {
     /// Central panel ui
     fn central_panel(&mut self, ui: &mut egui::Ui, _error_manager: &mut ErrorManager) {
         crate::utils::central_ui(ui, |ui| {
+            if let Some(logo) = crate::assets::themed_image(ui.ctx(), crate::assets::LOGO) {
+                ui.add(logo.max_height(128.0));
+            }
             ui.heading("Welcome to baldvak");
             ui.label("Use the BladvakApp trait to override function and customize the app");
         });
@@ -49,29 +88,194 @@ pub trait BladvakApp<'a>: Sized {
         egui::Frame::window(&ui.ctx().global_style())
     }
 
+    /// theme-dependent assets (e.g. [`crate::assets::LOGO`]) - see [`crate::assets::themed_image`]
+    fn assets(&self) -> Assets {
+        Assets::default()
+    }
     /// handle a file input
     /// # Errors
     /// Can return an error if fails to handle file
     fn handle_file(&mut self, _file: File) -> Result<(), AppError> {
         Ok(())
     }
+    /// handle a zip archive matching none of the file formats - by default each file of the archive is given to `handle_file`
+    /// # Errors
+    /// Can return an error if fails to handle the archive
+    #[cfg(feature = "zip")]
+    fn handle_archive(&mut self, mut archive: crate::archive::Archive) -> Result<(), AppError> {
+        for file in archive.files() {
+            self.handle_file(file?)?;
+        }
+        Ok(())
+    }
+    /// file kinds handled by the app - the other files are rejected before `handle_file`, every kind if empty
+    fn accepted_kinds(&self) -> Vec<FileKind> {
+        vec![]
+    }
+    /// file formats handled by the app - used for format detection and the "Open As…" menu
+    fn file_formats(&self) -> Vec<FileFormat> {
+        vec![]
+    }
+    /// handle a file input with the detected or user-selected format - called when no handler of `file_handlers` is registered for the format
+    /// # Errors
+    /// Can return an error if fails to handle file
+    fn handle_file_as(&mut self, file: File, _format: &FileFormat) -> Result<(), AppError> {
+        self.handle_file(file)
+    }
+    /// handlers of the file formats - their formats are detected like `file_formats`, called once at start
+    fn file_handlers(&self) -> FileHandlers<Self> {
+        FileHandlers::default()
+    }
+    /// file picker used by "Open" - replace it for tests or headless builds
+    fn file_picker(&self) -> Box<dyn FilePicker> {
+        Box::new(RfdPicker)
+    }
+    /// open the files of the sub-folders of a dropped folder
+    fn recursive_folder_drop(&self) -> bool {
+        true
+    }
+    /// ask the user before opening files from external sources (browser drop, clipboard, URL)
+    fn require_trust(&self) -> bool {
+        false
+    }
+    /// overlay shown while files are dragged over the window - `None` to hide it
+    fn drop_hint(&self, hovered: &[egui::HoveredFile]) -> Option<DropHint> {
+        Some(DropHint::for_files(hovered))
+    }
+    /// converters available in the batch export
+    fn converters(&self) -> Vec<Converter> {
+        vec![]
+    }
+    /// exporters listed in File > Export
+    fn exporters(&self) -> Vec<Box<dyn Exporter<Self>>> {
+        vec![]
+    }
+    /// inputs of the batch export (e.g. the loaded documents) - cloned when the export is run
+    fn batch_inputs(&self) -> Vec<&File> {
+        vec![]
+    }
+    /// listen for files and payloads sent by other Bladvak apps (see [`crate::handoff`])
+    fn handoff(&self) -> bool {
+        false
+    }
+    /// recurring jobs run in the background (see [`crate::task::RecurringJob`])
+    fn recurring_jobs(&self) -> Vec<RecurringJob> {
+        Vec::new()
+    }
+    /// handle the result of a run of a recurring job
+    fn handle_job_result(&mut self, job: &str, message: String, _error_manager: &mut ErrorManager) {
+        log::info!("Job {job} finished: {message}");
+    }
+    /// handle a message sent to the inbox by the background work (see [`crate::inbox::sender`])
+    fn handle_message(&mut self, msg: InboxMessage, _error_manager: &mut ErrorManager) {
+        log::warn!("Unhandled inbox message {msg:?}");
+    }
+    /// handle a payload sent by another Bladvak app - files are handled like opened files
+    /// # Errors
+    /// Can return an error if fails to handle the payload
+    fn handle_handoff(&mut self, from: &str, kind: &str, _data: Vec<u8>) -> Result<(), AppError> {
+        Err(AppError::new(format!(
+            "Unsupported payload '{kind}' sent by {from}"
+        )))
+    }
+    /// commands of the app - see [`crate::command`]
+    fn commands(&self) -> Vec<Command<Self>> {
+        vec![]
+    }
+    /// search providers of the app - see [`crate::search`]
+    fn search_providers(&self) -> Vec<Box<dyn SearchProvider<Self>>> {
+        vec![]
+    }
+    /// help topics of the app - see [`crate::help`]
+    fn help_topics(&self) -> Vec<HelpTopic> {
+        vec![]
+    }
+    /// when a built-in service is paused (see [`crate::lifecycle`])
+    fn suspend_policy(&self, _service: Service) -> SuspendPolicy {
+        SuspendPolicy::KeepRunning
+    }
+    /// called when the window is shown, hidden, focused or unfocused
+    fn on_visibility_changed(&mut self, _visibility: Visibility) {}
+    /// show the telemetry consent and the setting (see [`crate::telemetry`])
+    #[must_use]
+    fn collects_telemetry() -> bool {
+        false
+    }
+    /// telemetry event - only called once the user agreed
+    fn on_telemetry(&mut self, _event: &TelemetryEvent) {}
+    /// event published on the event bus (see [`crate::events`])
+    fn on_event(&mut self, _event: &Event, _error_manager: &mut ErrorManager) {}
+    /// open documents and their session data (see [`crate::session`]) - saved with the state
+    fn save_session(&self) -> AppSession {
+        AppSession::default()
+    }
+    /// reopen the documents of the previous session
+    fn restore_session(&mut self, _session: AppSession, _error_manager: &mut ErrorManager) {}
+    /// extra regions on the edges of the window (see [`crate::region`])
+    fn regions(&self) -> Vec<RegionDescriptor<Self>> {
+        vec![]
+    }
+    /// menus of the app (see [`crate::menu`]) - rendered after the File menu
+    fn menu_model(&self) -> MenuModel {
+        MenuModel::default()
+    }
+    /// entries contributed to context menus by context id (see [`crate::context_menu`])
+    fn context_menus(&self) -> Vec<(String, MenuEntry)> {
+        vec![]
+    }
     /// hook on the file menu
     fn menu_file(&mut self, _ui: &mut egui::Ui, _error_manager: &mut ErrorManager) {}
-    /// app name
+    /// app name - see [`crate::package_metadata`] to use the name of the package
     fn name() -> String;
-    /// app version
+    /// app version - see [`crate::package_metadata`] to use the version of the package
     fn version() -> String;
     /// repo URL - not displayed if empty
     #[must_use]
     fn repo_url() -> String {
         String::new()
     }
+    /// check the latest GitHub release of [`BladvakApp::repo_url`] at start (see [`crate::updater`])
+    #[must_use]
+    fn check_updates() -> bool {
+        false
+    }
+    /// how the new errors of a severity are presented until the user chooses in the settings
+    #[must_use]
+    fn error_presentation(_severity: Severity) -> ErrorPresentation {
+        ErrorPresentation::Window
+    }
+    /// is a file of the release the build of the current target - used by the self-update
+    #[must_use]
+    fn is_update_asset(name: &str) -> bool {
+        crate::updater::matches_current_target(name)
+    }
+    /// Ed25519 public key checking the signature of the self-update - the signature is not
+    /// required if `None`
+    #[must_use]
+    fn update_public_key() -> Option<[u8; 32]> {
+        None
+    }
+    /// URL to open a new issue (prefilled by the "Report issue" button of the error window) - not displayed if `None`
+    #[must_use]
+    fn issue_url() -> Option<String> {
+        let repo_url = Self::repo_url();
+        if repo_url.is_empty() {
+            return None;
+        }
+        Some(format!("{}/issues/new", repo_url.trim_end_matches('/')))
+    }
     /// icon
     #[must_use]
     fn icon() -> &'a [u8] {
         &[]
     }
 
+    /// log file configuration - no log file if `None` (native only)
+    #[must_use]
+    fn log_file() -> Option<LogFileConfig> {
+        None
+    }
+
     /// should display a side panel
     fn is_open_button(&self) -> bool {
         false
@@ -85,7 +289,7 @@ pub trait BladvakApp<'a>: Sized {
         true
     }
 
-    /// Builder func for native
+    /// Builder func for native - the saved state (or the default app) by default
     ///
     /// This functions is called as native AND in web - use [`crate::utils::is_native`] to make conditional code
     ///
@@ -93,13 +297,75 @@ pub trait BladvakApp<'a>: Sized {
     /// Can return an error if fails to create new app
     fn try_new_with_args(
         saved_state: Self,
-        cc: &CreationContext<'_>,
-        args: &[String],
-        error_manager: &mut ErrorManager,
-    ) -> Result<Self, AppError>;
+        _cc: &CreationContext<'_>,
+        _args: &[String],
+        _error_manager: &mut ErrorManager,
+    ) -> Result<Self, AppError> {
+        Ok(saved_state)
+    }
 
     /// Called when saving the app state
     fn on_save(&mut self) {}
+
+    /// Called once when the app exits, after the state is saved - the scratch files of the
+    /// [`crate::TempFileRegistry`] are removed after it
+    fn on_exit(&mut self) {}
+
+    /// size (in bytes) above which the saved state is loaded after the start - `None` to always load it at the start
+    ///
+    /// `try_new_with_args` then receives the default state and [`BladvakApp::on_restore`] is called once the saved state is swapped in
+    #[must_use]
+    fn progressive_load_threshold() -> Option<usize> {
+        Some(1024 * 1024)
+    }
+
+    /// key of the saved state in the storage - derived from [`BladvakApp::name`] so that several apps can share a web origin
+    #[must_use]
+    fn storage_key() -> String {
+        format!("bladvak_{}", Self::name())
+    }
+
+    /// maximum number of errors kept in the error window - the oldest are dropped first
+    #[must_use]
+    fn error_capacity() -> usize {
+        crate::errors::DEFAULT_ERROR_CAPACITY
+    }
+
+    /// id of the loading element of the web page - removed once the app is started (web only)
+    #[must_use]
+    fn loading_element_id() -> String {
+        crate::splash::LOADING_ELEMENT_ID.to_string()
+    }
+
+    /// called at the end of each startup phase of `try_new_with_args` (see [`crate::startup`])
+    fn startup_progress(_phase: &str) {}
+
+    /// changelog of the current version - shown once after an upgrade (see [`crate::whats_new`])
+    #[must_use]
+    fn whats_new() -> Option<String> {
+        None
+    }
+
+    /// HTML shown in place of the loading element when the app cannot start (web only)
+    #[must_use]
+    fn startup_error_page(error: &str) -> String {
+        crate::splash::error_page(&Self::name(), error)
+    }
+
+    /// save the app state - when `false` only the internal state of the wrapper is saved (see
+    /// [`crate::persist`] to leave out a part of the state)
+    fn persist_state(&self) -> bool {
+        true
+    }
+
+    /// the app has unsaved changes - on web the browser asks to confirm before the tab is
+    /// closed or reloaded
+    fn is_dirty(&self) -> bool {
+        false
+    }
+
+    /// Called when a large saved state was loaded after the start
+    fn on_restore(&mut self, _error_manager: &mut ErrorManager) {}
 }
 
 /// Trait for Bladvak panel
@@ -121,6 +387,190 @@ pub trait BladvakPanel: Debug {
 
     /// Panel ui
     fn ui(&self, app: &mut Self::App, ui: &mut egui::Ui, error_manager: &mut ErrorManager);
+
+    /// Opt in the geometry cache - the ui is skipped when [`BladvakPanel::changed_since_last_frame`] returns `false` and the panel gets no input
+    fn cache_ui(&self) -> bool {
+        false
+    }
+
+    /// Did the content of the panel change since the last frame - only used with [`BladvakPanel::cache_ui`]
+    fn changed_since_last_frame(&self, _app: &Self::App) -> bool {
+        true
+    }
+
+    /// Size constraints of the panel - used by the windows and the sidebar
+    fn layout(&self) -> PanelLayout {
+        PanelLayout::default()
+    }
+
+    /// Position of the panel in the sidebar and the settings
+    fn order(&self) -> PanelOrder {
+        PanelOrder::default()
+    }
+
+    /// Reset the values of the settings ui to their defaults
+    fn reset_settings(&self, _app: &mut Self::App) {}
+
+    /// Entries contributed to context menus by context id (see [`crate::context_menu`])
+    fn context_menus(&self) -> Vec<(String, MenuEntry)> {
+        vec![]
+    }
+
+    /// React to an event published on the event bus (see [`crate::events`])
+    fn on_event(&self, _app: &mut Self::App, _event: &Event, _error_manager: &mut ErrorManager) {}
+
+    /// Handle a message sent to this panel (see [`crate::events::Panels::send`])
+    fn on_message(
+        &self,
+        _app: &mut Self::App,
+        _message: &Event,
+        _error_manager: &mut ErrorManager,
+    ) {
+    }
+
+    /// Search providers of the panel (see [`crate::search`])
+    fn search_providers(&self) -> Vec<Box<dyn SearchProvider<Self::App>>> {
+        vec![]
+    }
+
+    /// Help topic of the panel - opened by the "?" button of its header (see [`crate::help`])
+    fn help_topic(&self) -> Option<HelpTopic> {
+        None
+    }
+}
+
+/// Size constraints of a panel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanelLayout {
+    /// Minimum size
+    pub min_size: Option<egui::Vec2>,
+    /// Preferred size when opened
+    pub default_size: Option<egui::Vec2>,
+    /// Can the user resize the window
+    pub resizable: bool,
+}
+
+impl Default for PanelLayout {
+    fn default() -> Self {
+        Self {
+            min_size: None,
+            default_size: None,
+            resizable: true,
+        }
+    }
+}
+
+impl PanelLayout {
+    /// Set the minimum size
+    #[must_use]
+    pub fn with_min_size(mut self, min_size: egui::Vec2) -> Self {
+        self.min_size = Some(min_size);
+        self
+    }
+
+    /// Set the preferred size
+    #[must_use]
+    pub fn with_default_size(mut self, default_size: egui::Vec2) -> Self {
+        self.default_size = Some(default_size);
+        self
+    }
+
+    /// Set if the user can resize the window
+    #[must_use]
+    pub fn with_resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    /// Apply the constraints to a window
+    pub(crate) fn window<'a>(&self, mut window: egui::Window<'a>) -> egui::Window<'a> {
+        if let Some(min_size) = self.min_size {
+            window = window.min_size(min_size);
+        }
+        if let Some(default_size) = self.default_size {
+            window = window.default_size(default_size);
+        }
+        window.resizable(self.resizable)
+    }
+}
+
+/// Position of a panel in the sidebar and the settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PanelOrder {
+    /// The lower priorities first - the panels of the same priority keep the order of
+    /// [`BladvakApp::panel_list`]
+    Priority(i32),
+    /// Right after a panel (see [`BladvakPanel::name`]) - at the end if there is no such panel
+    After(String),
+}
+
+impl Default for PanelOrder {
+    fn default() -> Self {
+        Self::Priority(0)
+    }
+}
+
+/// Priority of the panels of the library (e.g. the logs) - after the panels of the app by
+/// default
+pub const LIBRARY_PANEL_PRIORITY: i32 = 100;
+
+/// Check if a panel is placed after `target`, directly or through other panels
+fn is_placed_after(anchors: &BTreeMap<String, String>, name: &str, target: &str) -> bool {
+    let mut name = name;
+    while let Some(anchor) = anchors.get(name) {
+        if anchor == target {
+            return true;
+        }
+        name = anchor;
+    }
+    false
+}
+
+/// Sort the panels by their [`BladvakPanel::order`]
+fn sort_panels<App>(
+    panels: Vec<Box<dyn BladvakPanel<App = App>>>,
+) -> Vec<Box<dyn BladvakPanel<App = App>>> {
+    let mut sorted = Vec::new();
+    let mut pending = Vec::new();
+    for panel in panels {
+        match panel.order() {
+            PanelOrder::Priority(priority) => sorted.push((priority, panel)),
+            PanelOrder::After(target) => pending.push((target, panel)),
+        }
+    }
+    sorted.sort_by_key(|(priority, _)| *priority);
+    let mut sorted: Vec<_> = sorted.into_iter().map(|(_, panel)| panel).collect();
+    // panel placed after a panel -> that panel
+    let mut anchors: BTreeMap<String, String> = BTreeMap::new();
+    // the panels placed after the same panel keep the order of the panel list
+    while let Some((pending_index, target_index)) =
+        pending
+            .iter()
+            .enumerate()
+            .find_map(|(pending_index, (target, _))| {
+                let target_index = sorted.iter().position(|panel| panel.name() == target)?;
+                Some((pending_index, target_index))
+            })
+    {
+        let (target, panel) = pending.remove(pending_index);
+        let mut index = target_index + 1;
+        while sorted
+            .get(index)
+            .is_some_and(|next| is_placed_after(&anchors, next.name(), &target))
+        {
+            index += 1;
+        }
+        anchors.insert(panel.name().to_string(), target);
+        sorted.insert(index, panel);
+    }
+    for (target, panel) in pending {
+        log::warn!(
+            "Panel {}: no panel {target} to be placed after",
+            panel.name()
+        );
+        sorted.push(panel);
+    }
+    sorted
 }
 
 /// Panel open state
@@ -173,6 +623,21 @@ pub struct BladvakSavedState {
     pub(crate) settings: Settings,
     /// Panel state
     pub(crate) panel_state: BTreeMap<String, PanelState>,
+    /// Visibility of the app regions
+    #[serde(default)]
+    pub(crate) region_state: BTreeMap<String, bool>,
+    /// Previous session
+    #[serde(default)]
+    pub(crate) session: Session,
+    /// Version of the app at the last run
+    #[serde(default)]
+    pub(crate) last_version: Option<String>,
+    /// Recently opened files, most recent first
+    #[serde(default)]
+    pub(crate) recent_files: Vec<PathBuf>,
+    /// Style changes made in the style editor
+    #[serde(default)]
+    pub(crate) style_overrides: StyleOverrides,
 }
 
 /// App wrapper
@@ -195,9 +660,106 @@ pub struct Bladvak<App> {
     #[serde(skip)]
     pub(crate) file_handler: FileHandler,
 
+    /// Files waiting for the user to choose a format - asked one at a time
+    #[serde(skip)]
+    pub(crate) pending_open_as: VecDeque<PendingOpenAs>,
+
+    /// Files from an external source waiting for the user to trust them - prompted one at a
+    /// time
+    #[serde(skip)]
+    pub(crate) pending_trust: VecDeque<PendingTrust>,
+
+    /// Background tasks
+    #[serde(skip)]
+    pub(crate) tasks: TaskManager,
+
+    /// Batch export state
+    #[serde(skip)]
+    pub(crate) batch_export: BatchExport,
+
+    /// Performance statistics
+    #[serde(skip)]
+    pub(crate) perf: PerfStats,
+
+    /// Handoff endpoint
+    #[serde(skip)]
+    pub(crate) handoff: Option<Handoff>,
+
+    /// Commands of the app
+    #[serde(skip)]
+    pub(crate) commands: Vec<Command<App>>,
+
+    /// Command palette
+    #[serde(skip)]
+    pub(crate) command_palette: CommandPalette,
+
+    /// Global search box
+    #[serde(skip)]
+    pub(crate) search: SearchBox<App>,
+
+    /// Help window
+    #[serde(skip)]
+    pub(crate) help: HelpBrowser,
+
+    /// Menus of the app
+    #[serde(skip)]
+    pub(crate) menu_model: MenuModel,
+
+    /// Large saved state loaded after the start
+    #[serde(skip)]
+    pub(crate) pending_restore: Option<PendingRestore>,
+
+    /// Geometry cache of the panels
+    #[serde(skip)]
+    pub(crate) panel_cache: PanelCache,
+
+    /// Session overrides of the panel states
+    #[serde(skip)]
+    pub(crate) panel_overrides: PanelOverrides,
+
+    /// Startup profiler
+    #[serde(skip)]
+    pub(crate) startup: StartupProfiler,
+
+    /// Messages sent by the background work
+    #[serde(skip)]
+    pub(crate) inbox: Option<Inbox>,
+
+    /// Errors sent by the background work
+    #[serde(skip)]
+    pub(crate) error_receiver: Option<ErrorReceiver>,
+
+    /// Events waiting to be dispatched to the app and the panels
+    #[serde(skip)]
+    pub(crate) events: EventBus,
+
+    /// Profiles of the saved state
+    #[serde(skip)]
+    pub(crate) profiles: Profiles,
+
+    /// Regions of the app
+    #[serde(skip)]
+    pub(crate) regions: Vec<RegionDescriptor<App>>,
+
     /// panel list
     #[serde(skip)]
     pub(crate) panel_list: Vec<Box<dyn BladvakPanel<App = App>>>,
+
+    /// Handlers of the file formats
+    #[serde(skip)]
+    pub(crate) file_handlers: FileHandlers<App>,
+
+    /// Update check
+    #[serde(skip)]
+    pub(crate) updater: Updater,
+
+    /// Feedback dialog
+    #[serde(skip)]
+    pub(crate) feedback: Feedback,
+
+    /// Scratch files removed on exit
+    #[serde(skip)]
+    pub(crate) temp_files: TempFileRegistry,
 }
 
 /// Return type for [`Bladvak::bladvak_main`]
@@ -215,26 +777,171 @@ where
     /// Try to create a new app with args
     /// # Errors
     /// Can return an error if fails to create new app
-    fn try_new_with_args(cc: &CreationContext<'_>, vec_args: &[String]) -> Self {
-        let (saved_state_app, saved_internal) = if let Some(saved) = Self::get_saved_app_state(cc) {
-            if saved.ignore_saved_state {
-                log::info!("Explicitly ignoring saved state");
-                (M::default(), None)
-            } else {
-                log::info!("Using saved state");
-                (saved.app, Some(saved.internal))
-            }
-        } else {
+    pub(crate) fn try_new_with_args(cc: &CreationContext<'_>, vec_args: &[String]) -> Self {
+        let mut startup = StartupProfiler::new(vec_args).with_progress(M::startup_progress);
+        let profiles = Profiles::load(cc.storage, &M::storage_key(), vec_args);
+        let storage_key = profiles.storage_key(&M::storage_key());
+        let pending_restore = Self::get_deferred_state(cc, &storage_key);
+        let (saved_state_app, saved_internal) = if pending_restore.is_some() {
             (M::default(), None)
+        } else {
+            Self::load_saved_state(cc, &storage_key)
         };
-        let mut error_manager = ErrorManager::default();
+        startup.phase("state load");
+        let restore_session = saved_internal
+            .as_ref()
+            .is_none_or(|internal| internal.settings.restore_session);
+        if !restore_session {
+            Self::discard_egui_session(&cc.egui_ctx);
+        }
+        let mut error_manager = ErrorManager::with_capacity(M::error_capacity());
         let (app, creation_error) =
             match M::try_new_with_args(saved_state_app, cc, vec_args, &mut error_manager) {
                 Ok(app) => (app, None),
                 Err(err) => (M::default(), Some(err)),
             };
-        let panel_list = app.panel_list();
-        let bladvak_internal = if let Some(saved_state) = saved_internal {
+        startup.phase("app construction");
+        let panel_list = sort_panels(app.panel_list());
+        let commands = app.commands();
+        let menu_model = app.menu_model();
+        let regions = app.regions();
+        let contributions = app.context_menus().into_iter();
+        for (context_id, entry) in
+            contributions.chain(panel_list.iter().flat_map(|p| p.context_menus()))
+        {
+            crate::context_menu::register_context_menu(&cc.egui_ctx, context_id, entry);
+        }
+        let mut tasks = TaskManager::default();
+        for job in app.recurring_jobs() {
+            tasks.schedule(job);
+        }
+        let file_handlers = app.file_handlers();
+        let file_handler = FileHandler::for_app(&app, &file_handlers);
+        Self::install_services(&cc.egui_ctx, &app, &file_handlers);
+        let mut bladvak_internal = Self::merge_saved_state(saved_internal, &panel_list);
+        if !restore_session {
+            bladvak_internal.discard_session();
+        }
+        Self::merge_region_state(&mut bladvak_internal, &regions);
+        let (mut panel_overrides, override_errors) = PanelOverrides::from_args(vec_args);
+        for err in override_errors
+            .into_iter()
+            .chain(panel_overrides.apply(&mut bladvak_internal.panel_state))
+        {
+            error_manager.add_error(err);
+        }
+        if let Some(err) = creation_error {
+            error_manager.add_error(err);
+        }
+        let handoff = Self::start_handoff(&app, &cc.egui_ctx, &mut error_manager);
+        let mut bladvak = Self {
+            app,
+            internal: bladvak_internal,
+            ignore_saved_state: false,
+            error_manager,
+            file_handler,
+            pending_open_as: VecDeque::new(),
+            pending_trust: VecDeque::new(),
+            tasks,
+            batch_export: BatchExport::default(),
+            perf: PerfStats::default(),
+            handoff,
+            commands,
+            command_palette: CommandPalette::default(),
+            search: SearchBox::default(),
+            help: HelpBrowser::default(),
+            menu_model,
+            pending_restore,
+            panel_cache: PanelCache::default(),
+            panel_overrides,
+            startup,
+            inbox: Some(Inbox::new(&cc.egui_ctx, "bladvak_app")),
+            error_receiver: Some(ErrorReceiver::new(&cc.egui_ctx)),
+            events: EventBus::new(&cc.egui_ctx),
+            profiles,
+            regions,
+            panel_list,
+            file_handlers,
+            updater: Updater::default(),
+            feedback: Feedback::default(),
+            temp_files: crate::temp_files::install(&cc.egui_ctx, &M::name()),
+        };
+        bladvak.restore_session(&cc.egui_ctx);
+        bladvak.apply_window_settings(&cc.egui_ctx);
+        bladvak.check_version(&cc.egui_ctx);
+        bladvak.startup.phase("bladvak setup");
+        bladvak
+    }
+
+    /// Start listening for the handoffs if the app opts in
+    fn start_handoff(
+        app: &M,
+        ctx: &egui::Context,
+        error_manager: &mut ErrorManager,
+    ) -> Option<Handoff> {
+        if !app.handoff() {
+            return None;
+        }
+        match Handoff::start(&M::name(), ctx) {
+            Ok(handoff) => Some(handoff),
+            Err(err) => {
+                error_manager.add_error(err);
+                None
+            }
+        }
+    }
+
+    /// Install the image loaders, the assets, the blob store and the web listeners in the egui
+    /// context
+    fn install_services(ctx: &egui::Context, app: &M, file_handlers: &FileHandlers<M>) {
+        egui_extras::install_image_loaders(ctx);
+        #[cfg(target_arch = "wasm32")]
+        {
+            crate::web_drop::install(
+                ctx,
+                crate::file_handler::FolderDrop::from_formats(
+                    app.recursive_folder_drop(),
+                    &crate::file_registry::app_formats(app, file_handlers),
+                ),
+            );
+            crate::pwa::install(ctx);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        let _ = file_handlers;
+        app.assets().install(ctx);
+        crate::blob_store::install(ctx, &M::name());
+    }
+
+    /// Load the saved app state and internal state - the internal state is used alone when the
+    /// app state was not saved
+    fn load_saved_state(
+        cc: &CreationContext<'_>,
+        storage_key: &str,
+    ) -> (M, Option<BladvakSavedState>) {
+        match Self::get_saved_app_state(cc, storage_key) {
+            Some(saved) if saved.ignore_saved_state => {
+                log::info!("Explicitly ignoring saved state");
+                (M::default(), None)
+            }
+            Some(saved) => {
+                log::info!("Using saved state");
+                (saved.app, Some(saved.internal))
+            }
+            None => {
+                let internal = cc
+                    .storage
+                    .and_then(|storage| Self::saved_internal_state(storage, storage_key));
+                (M::default(), internal)
+            }
+        }
+    }
+
+    /// Merge the saved internal state with the current panels
+    pub(crate) fn merge_saved_state(
+        saved_internal: Option<BladvakSavedState>,
+        panel_list: &[Box<dyn BladvakPanel<App = M>>],
+    ) -> BladvakSavedState {
+        if let Some(saved_state) = saved_internal {
             let hashet_saved = saved_state
                 .panel_state
                 .iter()
@@ -253,10 +960,15 @@ where
                 let BladvakSavedState {
                     settings: old_settings,
                     panel_state: old_panel_state,
+                    region_state,
+                    session,
+                    last_version,
+                    recent_files,
+                    style_overrides,
                 } = saved_state;
                 log::info!("Trying to use old saved panels state");
                 let mut new_panel_state = BTreeMap::new();
-                for one_panel in &panel_list {
+                for one_panel in panel_list {
                     let is_panel_present = old_panel_state
                         .iter()
                         .find(|panel| panel.0 == one_panel.name());
@@ -270,33 +982,46 @@ where
                 BladvakSavedState {
                     settings: old_settings,
                     panel_state: new_panel_state,
+                    region_state,
+                    session,
+                    last_version,
+                    recent_files,
+                    style_overrides,
                 }
             }
         } else {
             let mut panel_state = BTreeMap::new();
-            for one_panel in &panel_list {
+            for one_panel in panel_list {
                 panel_state.insert(one_panel.name().to_string(), PanelState::default());
             }
             BladvakSavedState {
                 settings: Settings::default(),
                 panel_state,
+                region_state: BTreeMap::new(),
+                session: Session::default(),
+                last_version: None,
+                recent_files: Vec::new(),
+                style_overrides: StyleOverrides::default(),
             }
-        };
-        if let Some(err) = creation_error {
-            error_manager.add_error(err);
         }
-        Self {
-            app,
-            internal: bladvak_internal,
-            ignore_saved_state: false,
-            error_manager,
-            file_handler: FileHandler::default(),
-            panel_list,
+    }
+
+    /// Add the visibility of the regions missing from the saved state
+    pub(crate) fn merge_region_state(
+        internal: &mut BladvakSavedState,
+        regions: &[RegionDescriptor<M>],
+    ) {
+        for region in regions {
+            internal
+                .region_state
+                .entry(region.id.clone())
+                .or_insert(region.visible_by_default);
         }
     }
 
     /// Show the central panel
     pub(crate) fn central_panel(&mut self, ui: &mut egui::Ui) {
+        trace_span!("central_panel");
         egui::CentralPanel::default()
             .frame(
                 egui::Frame::central_panel(&ui.ctx().global_style())
@@ -310,12 +1035,31 @@ where
                     if let Some(panel_state) = self.internal.panel_state.get_mut(panel_name)
                         && let PanelOpen::AsWindows = panel_state.open
                     {
+                        trace_span!("panel", name = panel_name);
                         let mut open = true;
-                        egui::Window::new(panel_name)
+                        let help_topic = one_panel.help_topic().map(|topic| topic.id);
+                        one_panel
+                            .layout()
+                            .window(egui::Window::new(panel_name))
                             .open(&mut open)
                             .frame(self.app.window_panel_frame(ui))
                             .show(ui.ctx(), |window_ui| {
-                                one_panel.ui(&mut self.app, window_ui, &mut self.error_manager);
+                                crate::a11y::annotate(
+                                    window_ui,
+                                    egui::accesskit::Role::Region,
+                                    panel_name,
+                                );
+                                if let Some(topic) = &help_topic {
+                                    crate::help::panel_button(window_ui, topic);
+                                }
+                                let start = Instant::now();
+                                self.panel_cache.show(
+                                    one_panel.as_ref(),
+                                    &mut self.app,
+                                    window_ui,
+                                    &mut self.error_manager,
+                                );
+                                self.perf.panel(panel_name, start.elapsed());
                             });
                         if !open {
                             panel_state.open = PanelOpen::AsSideBar;
@@ -327,84 +1071,297 @@ where
 
     /// Show the top panel
     pub(crate) fn top_panel(&mut self, ui: &mut egui::Ui) {
+        trace_span!("top_panel");
         egui::Panel::top("top_panel").show(ui, |ui| {
             // The top panel is often a good place for a menu bar:
 
             egui::MenuBar::new().ui(ui, |ui| {
+                crate::a11y::annotate(ui, egui::accesskit::Role::MenuBar, "Menu bar");
                 ui.menu_button("File", |ui| {
+                    if let Some(file_menu) = self.menu_model.file_menu() {
+                        file_menu.entries_ui(ui);
+                    }
                     self.app.menu_file(ui, &mut self.error_manager);
-                    if self.app.is_open_button() && ui.button("Open").clicked() {
+                    if self.app.is_open_button()
+                        && ui
+                            .add(shortcut_button(ui, "Open", Some(&OPEN_SHORTCUT)))
+                            .clicked()
+                    {
                         ui.close();
                         self.file_handler.handle_file_open();
                     }
-                    if self.app.panel_options_as_menu() && !self.internal.panel_state.is_empty() {
-                        ui.menu_button("Panels", |ui| {
-                            ui.menu_button("All", |ui| {
-                                if ui.button(PanelOpen::AsSideBar.to_string()).clicked() {
-                                    for one_panel in &mut self.internal.panel_state {
-                                        one_panel.1.open = PanelOpen::AsSideBar;
-                                    }
-                                }
-                                if ui.button(PanelOpen::AsWindows.to_string()).clicked() {
-                                    for one_panel in &mut self.internal.panel_state {
-                                        one_panel.1.open = PanelOpen::AsWindows;
-                                    }
-                                }
-                                if ui.button(PanelOpen::None.to_string()).clicked() {
-                                    for one_panel in &mut self.internal.panel_state {
-                                        one_panel.1.open = PanelOpen::None;
-                                    }
-                                }
-                            });
-                            for one_panel in &mut self.internal.panel_state {
-                                if let Some(panel) =
-                                    self.panel_list.iter().find(|p| p.name() == one_panel.0)
-                                {
-                                    // Check if plugin has a UI
-                                    if !panel.has_ui() {
-                                        continue;
-                                    }
-                                } else {
-                                    // Plugin not found - weird
-                                    continue;
+                    let formats = crate::file_registry::app_formats(&self.app, &self.file_handlers);
+                    if self.app.is_open_button() && formats.len() > 1 {
+                        ui.menu_button("Open As…", |ui| {
+                            for format in formats {
+                                if ui.button(&format.name).clicked() {
+                                    ui.close();
+                                    self.file_handler.handle_file_open_as(format.name);
                                 }
-                                ui.menu_button(one_panel.0, |ui| {
-                                    let value = &mut one_panel.1.open;
-                                    ui.selectable_value(
-                                        value,
-                                        PanelOpen::AsSideBar,
-                                        PanelOpen::AsSideBar.to_string(),
-                                    );
-                                    ui.selectable_value(
-                                        value,
-                                        PanelOpen::AsWindows,
-                                        PanelOpen::AsWindows.to_string(),
-                                    );
-                                    ui.selectable_value(
-                                        value,
-                                        PanelOpen::None,
-                                        PanelOpen::None.to_string(),
-                                    );
-                                });
                             }
                         });
                     }
-                    if ui.button("Settings").clicked() {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if self.app.is_open_button() {
+                        self.menu_recent(ui);
+                    }
+                    if !self.app.converters().is_empty() && ui.button("Batch export…").clicked() {
+                        ui.close();
+                        self.batch_export.open = true;
+                    }
+                    self.menu_export(ui);
+                    if self.app.panel_options_as_menu()
+                        && !(self.internal.panel_state.is_empty() && self.regions.is_empty())
+                    {
+                        ui.menu_button("Panels", |ui| self.menu_panels(ui));
+                    }
+                    if !self.commands.is_empty()
+                        && ui
+                            .add(shortcut_button(
+                                ui,
+                                "Command palette",
+                                Some(&PALETTE_SHORTCUT),
+                            ))
+                            .clicked()
+                    {
+                        ui.close();
+                        self.command_palette.open = true;
+                    }
+                    ui.menu_button(format!("Profile ({})", self.profiles.active()), |ui| {
+                        self.profiles.menu(ui);
+                    });
+                    if ui
+                        .add(shortcut_button(ui, "Settings", Some(&SETTINGS_SHORTCUT)))
+                        .clicked()
+                    {
                         self.internal.settings.open = true;
                     }
+                    #[cfg(target_arch = "wasm32")]
+                    crate::pwa::menu_entry(ui);
                     let is_web = cfg!(target_arch = "wasm32");
-                    if !is_web && ui.button("Quit").clicked() {
+                    if !is_web
+                        && ui
+                            .add(shortcut_button(ui, "Quit", Some(&QUIT_SHORTCUT)))
+                            .clicked()
+                    {
                         ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
                     }
                     egui::warn_if_debug_build(ui);
                 });
+                ui.menu_button("View", |ui| self.menu_view(ui));
+                self.menu_model.menu_bar_ui(ui);
+                if self.menu_model.help_menu().is_some()
+                    || M::issue_url().is_some()
+                    || self.help_available()
+                {
+                    ui.menu_button("Help", |ui| self.menu_help(ui));
+                }
+                self.menu_bar_extras(ui);
+            });
+        });
+    }
+
+    /// Show the View menu
+    fn menu_view(&mut self, ui: &mut egui::Ui) {
+        if let Some(view_menu) = self.menu_model.view_menu() {
+            view_menu.entries_ui(ui);
+            ui.separator();
+        }
+        if !cfg!(target_arch = "wasm32")
+            && ui
+                .checkbox(&mut self.internal.settings.always_on_top, "Always on top")
+                .clicked()
+        {
+            self.apply_always_on_top(ui);
+            ui.close();
+        }
+        let mut fullscreen = crate::fullscreen::is_fullscreen(ui);
+        if ui
+            .checkbox(&mut fullscreen, "Fullscreen")
+            .on_hover_text(ui.ctx().format_shortcut(&FULLSCREEN_SHORTCUT))
+            .clicked()
+        {
+            crate::fullscreen::set_fullscreen(ui, fullscreen);
+            ui.close();
+        }
+    }
+
+    /// Show the Help menu
+    fn menu_help(&mut self, ui: &mut egui::Ui) {
+        let help_available = self.help_available();
+        if help_available
+            && ui
+                .add(shortcut_button(ui, "Help topics", Some(&HELP_SHORTCUT)))
+                .clicked()
+        {
+            ui.close();
+            crate::help::open(ui.ctx(), None);
+        }
+        if let Some(help_menu) = self.menu_model.help_menu() {
+            if help_available {
+                ui.separator();
+            }
+            help_menu.entries_ui(ui);
+        }
+        if M::issue_url().is_some() {
+            if self.menu_model.help_menu().is_some() || help_available {
+                ui.separator();
+            }
+            if ui.button("Send feedback…").clicked() {
+                self.feedback.open = true;
+            }
+        }
+    }
+
+    /// Show the widgets after the File menu - collapsed in an overflow menu when the window is
+    /// too narrow for them
+    fn menu_bar_extras(&mut self, ui: &mut egui::Ui) {
+        let width_id = egui::Id::new("bladvak_menu_bar_extras_width");
+        let needed = ui.data(|data| data.get_temp::<f32>(width_id));
+        if needed.is_some_and(|needed| ui.available_width() < needed) {
+            let more = ui.menu_button("⋯", |ui| {
+                self.search_button(ui);
+                self.update_button(ui);
+                self.messages_button(ui);
+                crate::progress::menu_spinner(ui);
                 self.app.top_panel(ui, &mut self.error_manager);
             });
+            crate::a11y::label(&more.response, "More");
+        } else {
+            let width = ui
+                .scope(|ui| {
+                    self.search_button(ui);
+                    self.update_button(ui);
+                    self.messages_button(ui);
+                    crate::progress::menu_spinner(ui);
+                    self.app.top_panel(ui, &mut self.error_manager);
+                })
+                .response
+                .rect
+                .width();
+            ui.data_mut(|data| data.insert_temp(width_id, width));
+        }
+    }
+
+    /// Show the button of the messages window - hidden without message
+    fn messages_button(&mut self, ui: &mut egui::Ui) {
+        let count = self.error_manager.messages().len();
+        if count == 0 {
+            return;
+        }
+        let unread = self.error_manager.unread_messages;
+        let text = if unread > 0 {
+            egui::RichText::new(format!("🗨 {unread}")).color(ui.visuals().warn_fg_color)
+        } else {
+            egui::RichText::new(format!("🗨 {count}"))
+        };
+        let button = ui.button(text);
+        crate::a11y::label(&button, "Messages");
+        if button.on_hover_text("Messages").clicked() {
+            self.error_manager.messages_open = !self.error_manager.messages_open;
+        }
+    }
+
+    /// Measured startup phases
+    #[must_use]
+    pub fn startup_report(&self) -> &StartupReport {
+        &self.startup.report
+    }
+
+    /// Start a progress shown in the status area and the menu bar
+    #[must_use]
+    pub fn progress<S: Into<String>>(ctx: &egui::Context, label: S) -> ProgressHandle {
+        crate::progress::progress(ctx, label)
+    }
+
+    /// Show the visible regions of the app
+    fn show_regions(&mut self, ui: &mut egui::Ui) {
+        for region in &self.regions {
+            if self
+                .internal
+                .region_state
+                .get(&region.id)
+                .copied()
+                .unwrap_or(region.visible_by_default)
+            {
+                trace_span!("region", id = region.id.as_str());
+                region.show(&mut self.app, ui, &mut self.error_manager);
+            }
+        }
+    }
+
+    /// Show the panels menu
+    fn menu_panels(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("All", |ui| {
+            if ui.button(PanelOpen::AsSideBar.to_string()).clicked() {
+                for one_panel in &mut self.internal.panel_state {
+                    one_panel.1.open = PanelOpen::AsSideBar;
+                }
+            }
+            if ui.button(PanelOpen::AsWindows.to_string()).clicked() {
+                for one_panel in &mut self.internal.panel_state {
+                    one_panel.1.open = PanelOpen::AsWindows;
+                }
+            }
+            if ui.button(PanelOpen::None.to_string()).clicked() {
+                for one_panel in &mut self.internal.panel_state {
+                    one_panel.1.open = PanelOpen::None;
+                }
+            }
         });
+        if !self.regions.is_empty() {
+            ui.menu_button("Regions", |ui| self.region_toggles(ui));
+        }
+        for one_panel in &mut self.internal.panel_state {
+            if let Some(panel) = self.panel_list.iter().find(|p| p.name() == one_panel.0) {
+                // Check if plugin has a UI
+                if !panel.has_ui() {
+                    continue;
+                }
+            } else {
+                // Plugin not found - weird
+                continue;
+            }
+            ui.menu_button(one_panel.0, |ui| {
+                let value = &mut one_panel.1.open;
+                let value_changed = ui
+                    .selectable_value(
+                        value,
+                        PanelOpen::AsSideBar,
+                        PanelOpen::AsSideBar.to_string(),
+                    )
+                    .changed()
+                    | ui.selectable_value(
+                        value,
+                        PanelOpen::AsWindows,
+                        PanelOpen::AsWindows.to_string(),
+                    )
+                    .changed();
+                if ui
+                    .selectable_value(value, PanelOpen::None, PanelOpen::None.to_string())
+                    .changed()
+                    | value_changed
+                {
+                    crate::journal::record(
+                        crate::journal::JournalKind::Settings,
+                        format!("Panel {}: {value}", one_panel.0),
+                    );
+                    crate::telemetry::panel_toggled(one_panel.0, value);
+                    crate::events::publish(
+                        ui.ctx(),
+                        PanelToggled {
+                            panel: one_panel.0.clone(),
+                            state: value.clone(),
+                        },
+                    );
+                }
+            });
+        }
     }
 
     /// Show the side panel
     pub(crate) fn side_panel(&mut self, ui: &mut egui::Ui) {
+        trace_span!("side_panel");
         let is_panels_in_sidebar = self.panel_list.iter().any(|p| {
             p.has_ui()
                 && self
@@ -416,37 +1373,261 @@ where
         if !is_panels_in_sidebar {
             return;
         }
-        egui::Panel::right("my_panel")
+        let (min_width, default_width) = self
+            .panel_list
+            .iter()
+            .filter(|p| {
+                p.has_ui()
+                    && self
+                        .internal
+                        .panel_state
+                        .get(p.name())
+                        .is_some_and(|p_state| p_state.open == PanelOpen::AsSideBar)
+            })
+            .map(|p| p.layout())
+            .fold(
+                (self.internal.settings.min_width_sidebar, None::<f32>),
+                |(min_width, default_width), layout| {
+                    (
+                        layout
+                            .min_size
+                            .map_or(min_width, |size| min_width.max(size.x)),
+                        layout.default_size.map_or(default_width, |size| {
+                            Some(default_width.unwrap_or(0.0).max(size.x))
+                        }),
+                    )
+                },
+            );
+        let mut side_panel = egui::Panel::right("my_panel")
             .frame(self.app.side_panel_frame(ui))
-            .min_size(self.internal.settings.min_width_sidebar)
-            .show(ui, |side_panel_ui| {
-                for (idx, one_panel) in self
-                    .panel_list
-                    .iter()
-                    .filter(|p| {
-                        p.has_ui()
-                            && self
-                                .internal
-                                .panel_state
-                                .get(p.name())
-                                .is_some_and(|p_state| p_state.open == PanelOpen::AsSideBar)
-                    })
-                    .enumerate()
-                {
-                    if idx != 0 {
-                        side_panel_ui.separator();
+            .min_size(min_width);
+        if let Some(default_width) = default_width {
+            side_panel = side_panel.default_size(default_width.max(min_width));
+        }
+        side_panel.show(ui, |side_panel_ui| {
+            for (idx, one_panel) in self
+                .panel_list
+                .iter()
+                .filter(|p| {
+                    p.has_ui()
+                        && self
+                            .internal
+                            .panel_state
+                            .get(p.name())
+                            .is_some_and(|p_state| p_state.open == PanelOpen::AsSideBar)
+                })
+                .enumerate()
+            {
+                if idx != 0 {
+                    side_panel_ui.separator();
+                }
+                trace_span!("panel", name = one_panel.name());
+                let min_height = one_panel.layout().min_size.map_or(0.0, |size| size.y);
+                let help_topic = one_panel.help_topic().map(|topic| topic.id);
+                self.app.side_panel(side_panel_ui, |ui, app| {
+                    crate::a11y::annotate(ui, egui::accesskit::Role::Region, one_panel.name());
+                    if let Some(topic) = &help_topic {
+                        crate::help::panel_button(ui, topic);
                     }
-                    self.app.side_panel(side_panel_ui, |ui, app| {
-                        one_panel.ui(app, ui, &mut self.error_manager);
-                    });
+                    ui.set_min_height(min_height);
+                    self.panel_cache
+                        .show(one_panel.as_ref(), app, ui, &mut self.error_manager);
+                });
+            }
+            side_panel_ui.with_layout(
+                egui::Layout::bottom_up(egui::Align::RIGHT),
+                |ui: &mut egui::Ui| {
+                    egui::warn_if_debug_build(ui);
+                },
+            );
+        });
+    }
+
+    /// Pause and resume the services when the visibility of the window changes
+    fn process_lifecycle(&mut self, ctx: &egui::Context) {
+        let Some(visibility) = crate::lifecycle::update(ctx) else {
+            return;
+        };
+        self.tasks.set_paused(
+            self.app
+                .suspend_policy(Service::RecurringJobs)
+                .is_suspended(visibility),
+        );
+        self.app.on_visibility_changed(visibility);
+    }
+
+    /// Check if a built-in service is paused
+    fn is_suspended(&self, ctx: &egui::Context, service: Service) -> bool {
+        crate::lifecycle::is_suspended(ctx, self.app.suspend_policy(service))
+    }
+
+    /// Handle the opened, dropped and submitted files
+    fn process_files(&mut self, ctx: &egui::Context) {
+        match self.file_handler.handle_files(ctx) {
+            Ok(Some(file)) => {
+                let forced_format = self.file_handler.forced_format.take();
+                self.handle_incoming_file(file, forced_format);
+                // repaint with the file
+                ctx.request_repaint();
+            }
+            Ok(None) => {
+                // nothing to do
+            }
+            Err(err) => {
+                self.error_manager.add_error(err);
+            }
+        }
+    }
+
+    /// Pass the inbox messages to the app and add the errors sent by the background work
+    fn process_inbox(&mut self) {
+        if let Some(error_receiver) = &self.error_receiver {
+            error_receiver.drain_into(&mut self.error_manager);
+        }
+        let Some(inbox) = &self.inbox else {
+            return;
+        };
+        for msg in inbox.drain() {
+            self.app.handle_message(msg, &mut self.error_manager);
+        }
+    }
+
+    /// Report the files saved with [`crate::dialog::save_file_dialog`] and the failed writes of
+    /// the blob store
+    fn process_saved_files(&mut self, ctx: &egui::Context) {
+        for outcome in crate::dialog::take_saved(ctx) {
+            match outcome {
+                Ok((path, size)) => crate::journal::record(
+                    crate::journal::JournalKind::Export,
+                    format!(
+                        "Saved to {} ({})",
+                        path.display(),
+                        crate::utils::format_bytes(size as u64)
+                    ),
+                ),
+                Err(err) => self.error_manager.add_error(err),
+            }
+        }
+        for err in crate::BlobStore::get(ctx).take_failures() {
+            self.error_manager.add_error(err);
+        }
+    }
+
+    /// Collect the finished background tasks
+    fn process_tasks(&mut self, ctx: &egui::Context) {
+        for finished in self.tasks.poll(ctx) {
+            #[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+            if self.updater.installer.owns(finished.id) {
+                self.finish_self_update(finished);
+                continue;
+            }
+            if self.batch_export.owns(finished.id) {
+                self.batch_export.record(finished);
+            } else {
+                match finished.result {
+                    Ok(message) if finished.job.is_some() => {
+                        self.app.handle_job_result(
+                            &finished.name,
+                            message,
+                            &mut self.error_manager,
+                        );
+                    }
+                    Ok(message) => log::info!(
+                        "Task {} finished in {}: {message}",
+                        finished.name,
+                        crate::utils::format_duration(finished.duration)
+                    ),
+                    Err(err) => self.error_manager.add_error(err),
                 }
-                side_panel_ui.with_layout(
-                    egui::Layout::bottom_up(egui::Align::RIGHT),
-                    |ui: &mut egui::Ui| {
-                        egui::warn_if_debug_build(ui);
-                    },
-                );
-            });
+            }
+        }
+    }
+
+    /// Publish the commands state, handle the shortcuts and run the requested commands
+    fn process_commands(&mut self, ctx: &egui::Context) {
+        self.process_builtin_shortcuts(ctx);
+        self.process_search_shortcut(ctx);
+        self.process_help_shortcut(ctx);
+        if self.commands.is_empty() {
+            return;
+        }
+        crate::command::publish(
+            ctx,
+            self.commands
+                .iter()
+                .map(|command| {
+                    let mut info = command.info(&self.app);
+                    info.menu_path = self.menu_model.path_of(&command.id);
+                    info
+                })
+                .collect(),
+        );
+        if ctx.input_mut(|i| i.consume_shortcut(&PALETTE_SHORTCUT)) {
+            self.command_palette.open = true;
+        }
+        let mut to_run = crate::command::take_queue(ctx);
+        for command in &self.commands {
+            if let Some(shortcut) = &command.shortcut
+                && command.is_enabled(&self.app)
+                && ctx.input_mut(|i| i.consume_shortcut(shortcut))
+            {
+                to_run.push(command.id.clone());
+            }
+        }
+        for id in to_run {
+            match self.commands.iter().find(|command| command.id == id) {
+                Some(command) if command.is_enabled(&self.app) => {
+                    command.run(&mut self.app, &mut self.error_manager);
+                }
+                Some(_) => log::warn!("Command {id} is disabled"),
+                None => log::warn!("Unknown command {id}"),
+            }
+        }
+    }
+
+    /// Handle the shortcuts of the File menu
+    fn process_builtin_shortcuts(&mut self, ctx: &egui::Context) {
+        if self.app.is_open_button() && ctx.input_mut(|i| i.consume_shortcut(&OPEN_SHORTCUT)) {
+            self.file_handler.handle_file_open();
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&SETTINGS_SHORTCUT)) {
+            self.internal.settings.open = !self.internal.settings.open;
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&FULLSCREEN_SHORTCUT)) {
+            crate::fullscreen::toggle_fullscreen(ctx);
+        }
+        if !cfg!(target_arch = "wasm32") && ctx.input_mut(|i| i.consume_shortcut(&QUIT_SHORTCUT)) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+    }
+
+    /// Send and receive the handoff messages
+    fn process_handoff(&mut self, ctx: &egui::Context) {
+        if self.is_suspended(ctx, Service::Handoff) {
+            // the payloads wait in the channel until the resume
+            return;
+        }
+        let Some(handoff) = &mut self.handoff else {
+            return;
+        };
+        for err in handoff.process(ctx) {
+            self.error_manager.add_error(err);
+        }
+        for received in handoff.received() {
+            log::info!("Received handoff from {}", received.from);
+            match received.message {
+                HandoffMessage::File { path, data } => {
+                    let file =
+                        File::new(data, path).with_origin(FileOrigin::Handoff(received.from));
+                    self.handle_incoming_file(file, None);
+                }
+                HandoffMessage::Payload { kind, data } => {
+                    if let Err(err) = self.app.handle_handoff(&received.from, &kind, data) {
+                        self.error_manager.add_error(err);
+                    }
+                }
+            }
+        }
     }
 
     /// When compiling natively
@@ -458,7 +1639,47 @@ where
 
         use crate::app::Bladvak;
 
-        env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
+        crate::startup::mark_process_start();
+
+        let log_file = M::log_file().and_then(|config| {
+            crate::logger::FileLogSink::new(&M::name(), config)
+                .inspect_err(|e| eprintln!("Cannot open log file: {e}"))
+                .ok()
+        });
+
+        // Log to stderr (if you run with `RUST_LOG=debug`) and capture the events for the log
+        // panel and the log file - the `log` records are forwarded to tracing
+        #[cfg(feature = "tracing")]
+        {
+            use tracing_subscriber::{Layer, layer::SubscriberExt, util::SubscriberInitExt};
+
+            let layer = crate::logger::BladvakLayer::new(log_file);
+            let level_filter = layer.level_filter();
+            if let Err(e) = tracing_subscriber::registry()
+                .with(
+                    tracing_subscriber::fmt::layer()
+                        .with_filter(tracing_subscriber::EnvFilter::from_default_env()),
+                )
+                .with(layer.with_filter(level_filter))
+                .try_init()
+            {
+                eprintln!("Cannot init tracing: {e}");
+            }
+        }
+
+        // Log to stderr (if you run with `RUST_LOG=debug`) and capture logs for the log panel
+        #[cfg(not(feature = "tracing"))]
+        {
+            let env_logger = env_logger::Builder::from_default_env().build();
+            let env_filter = env_logger.filter();
+            if let Err(e) = crate::logger::BladvakLogger::init_with_file(
+                Box::new(env_logger),
+                env_filter,
+                log_file,
+            ) {
+                eprintln!("Cannot init logger: {e}");
+            }
+        }
 
         let viewport = egui::ViewportBuilder::default()
             .with_drag_and_drop(true)
@@ -481,6 +1702,8 @@ where
         };
         let native_options = eframe::NativeOptions {
             viewport,
+            // the window geometry is restored with the session
+            persist_window: false,
             ..Default::default()
         };
         let args: Vec<String> = env::args().collect();
@@ -497,8 +1720,14 @@ where
     pub fn bladvak_main() {
         use eframe::wasm_bindgen::JsCast as _;
 
-        // Redirect `log` message to `console.log` and friends:
-        eframe::WebLogger::init(log::LevelFilter::Debug).ok();
+        crate::startup::mark_process_start();
+
+        // Redirect `log` message to `console.log` and friends and capture logs for the log panel
+        crate::logger::BladvakLogger::init(
+            Box::new(eframe::WebLogger::new(log::LevelFilter::Debug)),
+            log::LevelFilter::Debug,
+        )
+        .ok();
 
         let web_options = eframe::WebOptions::default();
 
@@ -518,22 +1747,27 @@ where
                 .start(
                     canvas,
                     web_options,
-                    Box::new(|cc| Ok(Box::new(Bladvak::<M>::try_new_with_args(cc, &[])))),
+                    Box::new(|cc| {
+                        let args = crate::overrides::url_args();
+                        Ok(Box::new(Bladvak::<M>::try_new_with_args(cc, &args)))
+                    }),
                 )
                 .await;
 
             // Remove the loading text and spinner:
-            if let Some(loading_text) = document.get_element_by_id("loading_text") {
-                match start_result {
-                    Ok(_) => {
-                        loading_text.remove();
+            let loading = document.get_element_by_id(&M::loading_element_id());
+            match start_result {
+                Ok(()) => {
+                    if let Some(loading) = loading {
+                        loading.remove();
                     }
-                    Err(e) => {
-                        loading_text.set_inner_html(
-                            "<p> The app has crashed. See the developer console for details. </p>",
-                        );
-                        panic!("Failed to start app: {e:?}");
+                }
+                Err(e) => {
+                    let error = e.as_string().unwrap_or_else(|| format!("{e:?}"));
+                    if let Some(loading) = loading {
+                        loading.set_inner_html(&M::startup_error_page(&error));
                     }
+                    panic!("Failed to start app: {error}");
                 }
             }
         });
@@ -542,14 +1776,43 @@ where
     /// Load previous app state (if any)
     // eframe: Note that you must enable the `persistence` feature for this to work.
     #[must_use]
-    pub(crate) fn get_saved_app_state(cc: &eframe::CreationContext<'_>) -> Option<Bladvak<M>> {
-        if let Some(storage) = cc.storage
-            && let Some(saved_app_state) = eframe::get_value::<Bladvak<M>>(storage, eframe::APP_KEY)
-        {
-            log::info!("Loading saved app state");
-            return Some(saved_app_state);
+    pub(crate) fn get_saved_app_state(
+        cc: &eframe::CreationContext<'_>,
+        key: &str,
+    ) -> Option<Bladvak<M>> {
+        let data = Self::saved_state_string(cc.storage?, key)?;
+        if data.is_empty() {
+            // only the internal state was saved
+            return None;
         }
-        None
+        match ron::from_str::<Bladvak<M>>(&data) {
+            Ok(saved_app_state) => {
+                log::info!("Loading saved app state");
+                Some(saved_app_state)
+            }
+            Err(err) => {
+                log::warn!("Failed to decode the saved state: {err}");
+                None
+            }
+        }
+    }
+
+    /// Raw saved state under a key - the state saved under [`eframe::APP_KEY`] by the previous
+    /// versions is used if there is no state under [`BladvakApp::storage_key`]
+    pub(crate) fn saved_state_string(storage: &dyn eframe::Storage, key: &str) -> Option<String> {
+        storage.get_string(key).or_else(|| {
+            if key != M::storage_key() {
+                return None;
+            }
+            let legacy = storage.get_string(eframe::APP_KEY)?;
+            log::info!("Migrating the saved state to the key {}", M::storage_key());
+            Some(legacy)
+        })
+    }
+
+    /// Storage key of the state of the active profile
+    pub(crate) fn storage_key(&self) -> String {
+        self.profiles.storage_key(&M::storage_key())
     }
 }
 
@@ -559,37 +1822,106 @@ where
 {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        if self.pending_restore.is_some() {
+            // do not overwrite the saved state before it is loaded
+            return;
+        }
         self.app.on_save();
-        eframe::set_value(storage, eframe::APP_KEY, self);
+        self.capture_session();
+        self.profiles.save(storage, &M::storage_key());
+        let key = self.storage_key();
+        if self.panel_overrides.is_empty() {
+            self.write_state(storage, &key);
+        } else {
+            // the overrides are not persisted
+            let persisted = self.panel_overrides.persisted(&self.internal.panel_state);
+            let session = std::mem::replace(&mut self.internal.panel_state, persisted);
+            self.write_state(storage, &key);
+            self.internal.panel_state = session;
+        }
+    }
+
+    /// Called once on shutdown, after [`Self::save`].
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.app.on_exit();
+        self.temp_files.cleanup();
     }
 
     /// Called each time the UI needs repainting, which may be many times per second.
     fn ui(&mut self, ui: &mut egui::Ui, frame: &mut eframe::Frame) {
+        trace_span!("update");
+        self.startup.frame(ui, self.pending_restore.is_some());
+        if let Some(inbox) = &self.inbox {
+            inbox.enter(ui);
+        }
+        if self.restore_ui(ui) {
+            return;
+        }
+        self.process_profile_switch(ui, frame);
+        self.perf.begin_frame(ui);
+        self.process_lifecycle(ui);
+        self.track_session(ui);
+        self.process_commands(ui);
         self.top_panel(ui);
+        self.show_toolbar(ui);
+        if crate::progress::status_panel(ui, self.error_manager.status_errors) {
+            self.error_manager.is_open = true;
+        }
+        self.perf.section("top panel");
+
+        self.show_regions(ui);
+        self.perf.section("regions");
 
         if self.app.is_side_panel() {
             self.side_panel(ui);
+            self.perf.section("side panel");
         }
 
         self.central_panel(ui);
+        self.perf.section("central panel");
 
-        match self.file_handler.handle_files(ui) {
-            Ok(Some(file)) => {
-                if let Err(err) = self.app.handle_file(file) {
-                    self.error_manager.add_error(err);
-                }
-                // repaint with the file
-                ui.ctx().request_repaint();
-            }
-            Ok(None) => {
-                // nothing to do
-            }
-            Err(err) => {
-                self.error_manager.add_error(err);
-            }
-        }
+        self.process_files(ui);
+        self.process_opened_file(ui);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.process_recent_reads();
+        self.process_handoff(ui);
+        self.process_tasks(ui);
+        self.process_saved_files(ui);
+        self.process_inbox();
+        self.process_events();
+        self.process_updates(ui);
+        self.process_telemetry(ui);
+        #[cfg(target_arch = "wasm32")]
+        crate::web_unload::set_dirty(self.app.is_dirty());
+        self.perf.section("files and tasks");
 
+        self.show_trust_modal(ui);
+        self.show_open_as_modal(ui);
+        self.show_batch_export(ui);
         self.show_error_manager(ui);
+        self.show_messages(ui);
+        self.command_palette.show(ui);
+        self.show_search(ui);
+        self.show_help(ui);
+        self.show_link_confirmation(ui);
         self.show_setting(ui, frame);
+        self.show_style_editor(ui);
+        self.show_clear_storage(ui, frame);
+        self.show_whats_new(ui);
+        #[cfg(target_arch = "wasm32")]
+        crate::pwa::show_update_toast(ui);
+        self.show_drop_overlay(ui);
+        self.show_feedback(ui);
+        self.show_telemetry_consent(ui);
+        self.perf.section("windows");
+        self.perf.end_frame();
+        self.perf.publish(ui, self.tasks.pending_count());
+
+        if self.internal.settings.show_perf_overlay {
+            self.perf.show_overlay(ui);
+            if !self.is_suspended(ui, Service::PerfOverlay) {
+                ui.ctx().request_repaint();
+            }
+        }
     }
 }
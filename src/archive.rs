@@ -0,0 +1,283 @@
+//! Zip archives - `zip` feature
+//!
+//! An opened or dropped zip file matching none of the [`crate::BladvakApp::file_formats`] is
+//! given to [`crate::BladvakApp::handle_archive`] as an [`Archive`]. The entries are read within
+//! the [`ParseLimits`] of the archive, so that a zip bomb fails with
+//! [`LimitError::SizeExceeded`] instead of using all the memory. [`save_zip`] saves several
+//! files as one zip with the save dialog.
+
+use eframe::egui;
+use std::{
+    borrow::Cow,
+    fmt::Debug,
+    io::{Cursor, Read, Write},
+    path::Path,
+};
+use zip::{CompressionMethod, ZipArchive, ZipWriter, result::ZipError, write::SimpleFileOptions};
+
+use crate::{
+    AppError, File,
+    file_handler::FileOrigin,
+    limits::{LimitError, LimitedReader, ParseLimits},
+};
+
+impl From<ZipError> for AppError {
+    fn from(err: ZipError) -> Self {
+        Self::new(format!("Zip error: {err}"))
+    }
+}
+
+/// Magic bytes of a zip archive - a local file header or the end of an empty archive
+const ZIP_MAGIC: [&[u8]; 2] = [b"PK\x03\x04", b"PK\x05\x06"];
+
+/// Check if a file is a zip archive
+#[must_use]
+pub fn is_zip(file: &File) -> bool {
+    ZIP_MAGIC.iter().any(|magic| file.data.starts_with(magic))
+}
+
+/// Zip archive opened in memory
+pub struct Archive {
+    /// Path of the archive
+    path: std::path::PathBuf,
+    /// Origin of the archive - given to the files of the archive
+    origin: FileOrigin,
+    /// Reader
+    zip: ZipArchive<Cursor<Vec<u8>>>,
+    /// Limits of the decompressed entries - `max_size` caps the total of the entries read
+    limits: ParseLimits,
+    /// Number of decompressed bytes read
+    read_total: u64,
+}
+
+impl Debug for Archive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Archive")
+            .field("path", &self.path)
+            .field("entries", &self.zip.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Archive {
+    /// Open an archive
+    /// # Errors
+    /// Error if the file is not a valid zip archive
+    pub fn new(file: File) -> Result<Self, AppError> {
+        Ok(Self {
+            zip: ZipArchive::new(Cursor::new(file.data))?,
+            path: file.path,
+            origin: file.origin,
+            limits: ParseLimits::default(),
+            read_total: 0,
+        })
+    }
+
+    /// Set the limits of the decompressed entries
+    #[must_use]
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Path of the archive
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of entries (files and folders)
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.zip.len()
+    }
+
+    /// Check if the archive is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.zip.is_empty()
+    }
+
+    /// Names of the entries - the names which cannot be decoded are skipped
+    pub fn names(&self) -> impl Iterator<Item = String> {
+        self.zip
+            .file_names()
+            .filter_map(|name| name.ok().map(Cow::into_owned))
+    }
+
+    /// Read an entry
+    /// # Errors
+    /// Error if the entry does not exist, cannot be decompressed or if the entries read exceed
+    /// the size limit
+    pub fn read(&mut self, name: &str) -> Result<Vec<u8>, AppError> {
+        let entry = self.zip.by_name(name)?;
+        let size = entry.size();
+        read_entry(entry, size, &self.limits, &mut self.read_total)
+    }
+
+    /// File of an entry - `None` for a folder
+    fn read_file(&mut self, index: usize) -> Result<Option<File>, AppError> {
+        let entry = self.zip.by_index(index)?;
+        if entry.is_dir() {
+            return Ok(None);
+        }
+        // the name is sanitized: no absolute path nor ".."
+        let Some(name) = entry.enclosed_name() else {
+            return Err(AppError::new(format!(
+                "Invalid entry name: {}",
+                entry.name().unwrap_or_default()
+            )));
+        };
+        let path = self.path.join(name);
+        let size = entry.size();
+        let data = read_entry(entry, size, &self.limits, &mut self.read_total)?;
+        Ok(Some(File::new(data, path).with_origin(self.origin.clone())))
+    }
+
+    /// Files of the archive, in the order of the archive - the folders are skipped and the path
+    /// of a file is the path of the archive joined with its name
+    pub fn files(&mut self) -> impl Iterator<Item = Result<File, AppError>> + '_ {
+        (0..self.zip.len()).filter_map(|index| self.read_file(index).transpose())
+    }
+}
+
+/// Decompress an entry of `size` bytes - `total` is the number of bytes already read from the
+/// archive, checked against the maximum size before and while reading
+fn read_entry<R: Read>(
+    entry: R,
+    size: u64,
+    limits: &ParseLimits,
+    total: &mut u64,
+) -> Result<Vec<u8>, AppError> {
+    let exceeded = LimitError::SizeExceeded {
+        limit: limits.max_size,
+    };
+    let remaining = limits.max_size.saturating_sub(*total);
+    if size > remaining {
+        return Err(exceeded.into());
+    }
+    let mut data = Vec::new();
+    // the size of the header can lie: the reader stops at the remaining size
+    if let Err(err) = LimitedReader::new(entry, remaining).read_to_end(&mut data) {
+        return Err(
+            match err
+                .get_ref()
+                .and_then(|err| err.downcast_ref::<LimitError>())
+            {
+                Some(_) => exceeded.into(),
+                None => err.into(),
+            },
+        );
+    }
+    *total += data.len() as u64;
+    Ok(data)
+}
+
+/// File of a zip archive to write
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    /// Name in the archive (e.g. "images/a.png")
+    pub name: String,
+    /// Content
+    pub data: Vec<u8>,
+}
+
+impl ArchiveEntry {
+    /// Create an entry
+    pub fn new<S: Into<String>>(name: S, data: Vec<u8>) -> Self {
+        Self {
+            name: name.into(),
+            data,
+        }
+    }
+}
+
+/// Write entries as a zip archive
+/// # Errors
+/// Error if an entry cannot be written (e.g. a duplicated name)
+pub fn zip_entries(entries: &[ArchiveEntry]) -> Result<Vec<u8>, AppError> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for entry in entries {
+        zip.start_file(entry.name.as_str(), options)?;
+        zip.write_all(&entry.data)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Ask where to save the entries as a zip archive and save it - the result is reported by the
+/// wrapper like the other saves
+pub fn save_zip(ctx: &egui::Context, entries: &[ArchiveEntry], suggested: &Path) {
+    match zip_entries(entries) {
+        Ok(data) => crate::dialog::save_file_dialog(ctx, data, suggested),
+        Err(err) => crate::dialog::save_failed(ctx, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Size limits of the entries
+
+    use super::{Archive, ArchiveEntry, read_entry, zip_entries};
+    use crate::{File, limits::ParseLimits};
+    use std::io::Cursor;
+
+    /// Archive of the entries read with a maximum size
+    fn archive(entries: &[ArchiveEntry], max_size: u64) -> Archive {
+        let Ok(data) = zip_entries(entries) else {
+            panic!("cannot write the archive");
+        };
+        let Ok(archive) = Archive::new(File::new(data, "test.zip")) else {
+            panic!("cannot open the archive");
+        };
+        archive.with_limits(ParseLimits {
+            max_size,
+            ..ParseLimits::default()
+        })
+    }
+
+    /// An entry over the maximum size fails
+    #[test]
+    fn entry_over_max_size() {
+        let mut archive = archive(&[ArchiveEntry::new("big.bin", vec![0; 100])], 50);
+        let Some(Err(err)) = archive.files().next() else {
+            panic!("the entry over the maximum size was read");
+        };
+        assert!(err.message.contains("Size limit exceeded"));
+    }
+
+    /// The entries read are counted together against the maximum size
+    #[test]
+    fn total_over_max_size() {
+        let entries = [
+            ArchiveEntry::new("a.bin", vec![1; 30]),
+            ArchiveEntry::new("b.bin", vec![2; 30]),
+        ];
+        let mut archive = archive(&entries, 50);
+        let results: Vec<_> = archive.files().collect();
+        assert_eq!(results.len(), 2);
+        assert!(
+            results[0]
+                .as_ref()
+                .is_ok_and(|file| file.data == vec![1; 30])
+        );
+        assert!(results[1].is_err());
+    }
+
+    /// A header reporting less than the real size is caught while reading
+    #[test]
+    fn lying_header() {
+        let limits = ParseLimits {
+            max_size: 50,
+            ..ParseLimits::default()
+        };
+        let mut total = 0;
+        let result = read_entry(Cursor::new(vec![0; 100]), 10, &limits, &mut total);
+        assert!(result.is_err_and(|err| err.message.contains("Size limit exceeded")));
+        assert_eq!(total, 0);
+
+        let result = read_entry(Cursor::new(vec![0; 40]), 10, &limits, &mut total);
+        assert!(result.is_ok_and(|data| data.len() == 40));
+        assert_eq!(total, 40);
+    }
+}
@@ -0,0 +1,96 @@
+//! Theme-dependent assets
+//!
+//! The app registers its assets with [`crate::BladvakApp::assets`], the variant matching the
+//! current theme is resolved every time the asset is used
+
+use eframe::egui::{self, ImageSource, Theme};
+use std::collections::BTreeMap;
+
+/// Name of the logo displayed on the welcome screen
+pub const LOGO: &str = "logo";
+
+/// Asset with a light and a dark variant
+#[derive(Debug, Clone)]
+pub struct ThemedAsset {
+    /// Variant used with the light theme
+    pub light: ImageSource<'static>,
+    /// Variant used with the dark theme
+    pub dark: ImageSource<'static>,
+}
+
+impl ThemedAsset {
+    /// Create a new asset from its light and dark variants
+    #[must_use]
+    pub fn new(light: ImageSource<'static>, dark: ImageSource<'static>) -> Self {
+        Self { light, dark }
+    }
+
+    /// Create an asset using the same source for both themes
+    #[must_use]
+    pub fn single(source: ImageSource<'static>) -> Self {
+        Self {
+            light: source.clone(),
+            dark: source,
+        }
+    }
+
+    /// Get the variant of a theme
+    #[must_use]
+    pub fn for_theme(&self, theme: Theme) -> &ImageSource<'static> {
+        match theme {
+            Theme::Light => &self.light,
+            Theme::Dark => &self.dark,
+        }
+    }
+}
+
+/// Assets of the app
+#[derive(Debug, Clone, Default)]
+pub struct Assets(BTreeMap<String, ThemedAsset>);
+
+impl Assets {
+    /// Add an asset
+    #[must_use]
+    pub fn with<S: Into<String>>(mut self, name: S, asset: ThemedAsset) -> Self {
+        self.0.insert(name.into(), asset);
+        self
+    }
+
+    /// Get an asset
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&ThemedAsset> {
+        self.0.get(name)
+    }
+
+    /// Check if there is no asset
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Id of the assets in the egui context
+    fn id() -> egui::Id {
+        egui::Id::new("bladvak_assets")
+    }
+
+    /// Store the assets in the egui context
+    pub(crate) fn install(self, ctx: &egui::Context) {
+        ctx.data_mut(|data| data.insert_temp(Self::id(), self));
+    }
+}
+
+/// Get the source of an asset for the current theme
+#[must_use]
+pub fn asset(ctx: &egui::Context, name: &str) -> Option<ImageSource<'static>> {
+    let theme = ctx.theme();
+    ctx.data(|data| {
+        data.get_temp::<Assets>(Assets::id())
+            .and_then(|assets| assets.get(name).map(|asset| asset.for_theme(theme).clone()))
+    })
+}
+
+/// Get an image of an asset for the current theme
+#[must_use]
+pub fn themed_image(ctx: &egui::Context, name: &str) -> Option<egui::Image<'static>> {
+    asset(ctx, name).map(egui::Image::new)
+}
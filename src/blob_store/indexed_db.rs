@@ -0,0 +1,217 @@
+//! `IndexedDB` storage on web
+//!
+//! The database is opened for each operation: the handles of the browser cannot be shared
+//! through the egui context.
+
+use eframe::{
+    wasm_bindgen::{JsCast, JsValue, closure::Closure},
+    web_sys::{self, IdbDatabase, IdbObjectStore, IdbRequest, IdbTransaction, IdbTransactionMode},
+};
+use js_sys::{Function, Promise, Uint8Array};
+use wasm_bindgen_futures::JsFuture;
+
+use super::{StateStore, StoreFuture, quota_exceeded};
+use crate::{AppError, ErrorCategory};
+
+/// Object store of the blobs
+const OBJECT_STORE: &str = "blobs";
+
+/// Version of the database
+const VERSION: u32 = 1;
+
+/// Store of blobs in an `IndexedDB` database
+#[derive(Debug, Clone)]
+pub struct IndexedDbStore {
+    /// Name of the database
+    name: String,
+}
+
+impl IndexedDbStore {
+    /// Create a store in a database - the database is created on the first operation
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+}
+
+/// Name of a JS error (e.g. `QuotaExceededError`)
+fn error_name(err: &JsValue) -> Option<String> {
+    err.dyn_ref::<web_sys::DomException>()
+        .map(web_sys::DomException::name)
+}
+
+/// Error of an operation on a key
+fn store_error(key: &str, err: &JsValue) -> AppError {
+    if error_name(err).as_deref() == Some("QuotaExceededError") {
+        return quota_exceeded(key);
+    }
+    let message = err
+        .dyn_ref::<web_sys::DomException>()
+        .map_or_else(|| format!("{err:?}"), web_sys::DomException::message);
+    AppError::new(format!("Cannot access the blob '{key}': {message}"))
+        .with_category(ErrorCategory::Io)
+}
+
+/// Wait for the success or the error of a request or a transaction - `result` gives the value
+/// of a success and `error` the error
+async fn wait<S, E>(
+    set_handlers: impl FnOnce(Option<&Function>, Option<&Function>),
+    result: S,
+    error: E,
+) -> Result<JsValue, JsValue>
+where
+    S: Fn() -> JsValue + 'static,
+    E: Fn() -> JsValue + 'static,
+{
+    let mut handlers = None;
+    let promise = Promise::new(&mut |resolve: Function, reject: Function| {
+        handlers = Some((resolve, reject));
+    });
+    let Some((resolve, reject)) = handlers else {
+        return Err(JsValue::from_str("Cannot create the promise"));
+    };
+    let on_success = Closure::<dyn FnMut()>::new(move || {
+        let _ = resolve.call1(&JsValue::NULL, &result());
+    });
+    let on_error = Closure::<dyn FnMut()>::new(move || {
+        let _ = reject.call1(&JsValue::NULL, &error());
+    });
+    set_handlers(
+        Some(on_success.as_ref().unchecked_ref()),
+        Some(on_error.as_ref().unchecked_ref()),
+    );
+    // the closures live until the promise is settled
+    JsFuture::from(promise).await
+}
+
+/// Wait for a request
+async fn wait_request(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let (on_result, on_error) = (request.clone(), request.clone());
+    let result = wait(
+        |success, error| {
+            request.set_onsuccess(success);
+            request.set_onerror(error);
+        },
+        move || on_result.result().unwrap_or_default(),
+        move || {
+            on_error
+                .error()
+                .ok()
+                .flatten()
+                .map(JsValue::from)
+                .unwrap_or_default()
+        },
+    )
+    .await;
+    request.set_onsuccess(None);
+    request.set_onerror(None);
+    result
+}
+
+/// Wait for a transaction to be committed - a full storage aborts the transaction
+async fn wait_transaction(transaction: &IdbTransaction) -> Result<(), JsValue> {
+    let on_error = transaction.clone();
+    let result = wait(
+        |success, error| {
+            transaction.set_oncomplete(success);
+            transaction.set_onerror(error);
+            transaction.set_onabort(error);
+        },
+        || JsValue::UNDEFINED,
+        move || on_error.error().map(JsValue::from).unwrap_or_default(),
+    )
+    .await;
+    transaction.set_oncomplete(None);
+    transaction.set_onerror(None);
+    transaction.set_onabort(None);
+    result.map(|_| ())
+}
+
+/// Open the database - the object store is created with the database
+async fn open(name: &str) -> Result<IdbDatabase, JsValue> {
+    let factory = web_sys::window()
+        .ok_or_else(|| JsValue::from_str("Cannot get the website window"))?
+        .indexed_db()?
+        .ok_or_else(|| JsValue::from_str("IndexedDB is not available"))?;
+    let request = factory.open_with_u32(name, VERSION)?;
+    let upgraded = request.clone();
+    let on_upgrade = Closure::<dyn FnMut()>::new(move || {
+        if let Ok(database) = upgraded.result().and_then(JsCast::dyn_into::<IdbDatabase>)
+            && !database.object_store_names().contains(OBJECT_STORE)
+            && let Err(err) = database.create_object_store(OBJECT_STORE)
+        {
+            log::error!("Cannot create the blob store: {err:?}");
+        }
+    });
+    request.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+    let database = wait_request(&request).await;
+    request.set_onupgradeneeded(None);
+    database?.dyn_into()
+}
+
+/// Start a transaction on the object store
+fn object_store(
+    database: &IdbDatabase,
+    mode: IdbTransactionMode,
+) -> Result<(IdbTransaction, IdbObjectStore), JsValue> {
+    let transaction = database.transaction_with_str_and_mode(OBJECT_STORE, mode)?;
+    let store = transaction.object_store(OBJECT_STORE)?;
+    Ok((transaction, store))
+}
+
+/// Read a blob
+async fn get(name: &str, key: &str) -> Result<Option<Vec<u8>>, JsValue> {
+    let database = open(name).await?;
+    let (_, store) = object_store(&database, IdbTransactionMode::Readonly)?;
+    let value = wait_request(&store.get(&key.into())?).await;
+    database.close();
+    let value = value?;
+    if value.is_undefined() {
+        return Ok(None);
+    }
+    Ok(Some(value.dyn_into::<Uint8Array>()?.to_vec()))
+}
+
+/// Write or remove a blob - the operation is done when the transaction is committed
+async fn write(name: &str, key: &str, data: Option<Vec<u8>>) -> Result<(), JsValue> {
+    let database = open(name).await?;
+    let result = async {
+        let (transaction, store) = object_store(&database, IdbTransactionMode::Readwrite)?;
+        match data {
+            Some(data) => store.put_with_key(&Uint8Array::from(data.as_slice()), &key.into())?,
+            None => store.delete(&key.into())?,
+        };
+        wait_transaction(&transaction).await
+    }
+    .await;
+    database.close();
+    result
+}
+
+impl StateStore for IndexedDbStore {
+    fn get(&self, key: &str) -> StoreFuture<Option<Vec<u8>>> {
+        let (name, key) = (self.name.clone(), key.to_string());
+        Box::pin(async move {
+            get(&name, &key)
+                .await
+                .map_err(|err| store_error(&key, &err))
+        })
+    }
+
+    fn set(&self, key: &str, data: Vec<u8>) -> StoreFuture<()> {
+        let (name, key) = (self.name.clone(), key.to_string());
+        Box::pin(async move {
+            write(&name, &key, Some(data))
+                .await
+                .map_err(|err| store_error(&key, &err))
+        })
+    }
+
+    fn remove(&self, key: &str) -> StoreFuture<()> {
+        let (name, key) = (self.name.clone(), key.to_string());
+        Box::pin(async move {
+            write(&name, &key, None)
+                .await
+                .map_err(|err| store_error(&key, &err))
+        })
+    }
+}
@@ -0,0 +1,266 @@
+//! Storage of large blobs (documents, caches...)
+//!
+//! The eframe persistence is backed by `localStorage` on web, which holds a few megabytes at
+//! most. The [`BlobStore`] of the app keeps large data in `IndexedDB` on web and in files under
+//! the storage directory of the app on native, both behind the [`StateStore`] trait. A failed
+//! write (e.g. the quota of the browser is exceeded) is reported in the error manager.
+
+use eframe::egui;
+use std::{
+    fmt::{Debug, Write},
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use crate::{AppError, ErrorCategory, task::TaskHandle};
+
+#[cfg(target_arch = "wasm32")]
+mod indexed_db;
+#[cfg(target_arch = "wasm32")]
+pub use indexed_db::IndexedDbStore;
+
+/// Code of the error of a full storage
+pub const QUOTA_EXCEEDED_CODE: &str = "quota-exceeded";
+
+/// Future of a [`StateStore`] operation
+#[cfg(not(target_arch = "wasm32"))]
+pub type StoreFuture<T> = Pin<Box<dyn Future<Output = Result<T, AppError>> + Send>>;
+
+/// Future of a [`StateStore`] operation - runs on the browser event loop
+#[cfg(target_arch = "wasm32")]
+pub type StoreFuture<T> = Pin<Box<dyn Future<Output = Result<T, AppError>>>>;
+
+/// Asynchronous key-value storage of blobs
+pub trait StateStore: Send + Sync {
+    /// Read a blob - `None` if there is no blob for the key
+    fn get(&self, key: &str) -> StoreFuture<Option<Vec<u8>>>;
+
+    /// Write a blob, replacing the previous one
+    fn set(&self, key: &str, data: Vec<u8>) -> StoreFuture<()>;
+
+    /// Remove a blob - no error if there is no blob for the key
+    fn remove(&self, key: &str) -> StoreFuture<()>;
+}
+
+/// Error of a full storage
+#[must_use]
+pub fn quota_exceeded(key: &str) -> AppError {
+    AppError::new(format!(
+        "Storage quota exceeded: cannot save '{key}' - free some space or clear the storage"
+    ))
+    .with_category(ErrorCategory::Io)
+    .with_code(QUOTA_EXCEEDED_CODE)
+}
+
+/// Store of blobs in the files of a folder - one file per key
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    /// Folder of the files
+    folder: PathBuf,
+}
+
+impl FileStore {
+    /// Create a store in a folder - the folder is created on the first write
+    #[must_use]
+    pub fn new(folder: impl Into<PathBuf>) -> Self {
+        Self {
+            folder: folder.into(),
+        }
+    }
+
+    /// Folder of the files
+    #[must_use]
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    /// Path of the file of a key - the characters which are not allowed in a file name are
+    /// escaped
+    fn path(&self, key: &str) -> PathBuf {
+        let mut name = String::with_capacity(key.len());
+        for c in key.chars() {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '.') && !name.is_empty() {
+                name.push(c);
+            } else {
+                let mut buffer = [0; 4];
+                for byte in c.encode_utf8(&mut buffer).bytes() {
+                    let _ = write!(name, "_{byte:02x}");
+                }
+            }
+        }
+        self.folder.join(name)
+    }
+}
+
+/// Error of a file operation - a full disk is reported like a full browser storage
+fn file_error(key: &str, err: &std::io::Error) -> AppError {
+    if err.kind() == std::io::ErrorKind::StorageFull {
+        quota_exceeded(key)
+    } else {
+        AppError::new(format!("Cannot access the blob '{key}': {err}"))
+            .with_category(ErrorCategory::Io)
+    }
+}
+
+/// Write a file through a temporary file renamed over it, so that a crash never leaves a
+/// partially written blob - unlike [`crate::utils::save_file`], no backup is kept
+fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    // `~` is escaped in the file names of the keys
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push("~tmp");
+    let temp_path = path.with_file_name(temp_name);
+    let write = || -> std::io::Result<()> {
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)
+    };
+    write().inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp_path);
+    })
+}
+
+impl StateStore for FileStore {
+    fn get(&self, key: &str) -> StoreFuture<Option<Vec<u8>>> {
+        let (path, key) = (self.path(key), key.to_string());
+        Box::pin(async move {
+            match std::fs::read(&path) {
+                Ok(data) => Ok(Some(data)),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(err) => Err(file_error(&key, &err)),
+            }
+        })
+    }
+
+    fn set(&self, key: &str, data: Vec<u8>) -> StoreFuture<()> {
+        let (path, key) = (self.path(key), key.to_string());
+        let folder = self.folder.clone();
+        Box::pin(async move {
+            std::fs::create_dir_all(&folder).map_err(|err| file_error(&key, &err))?;
+            write_atomic(&path, &data).map_err(|err| file_error(&key, &err))
+        })
+    }
+
+    fn remove(&self, key: &str) -> StoreFuture<()> {
+        let (path, key) = (self.path(key), key.to_string());
+        Box::pin(async move {
+            match std::fs::remove_file(&path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    Err(file_error(&key, &err))
+                }
+                _ => Ok(()),
+            }
+        })
+    }
+}
+
+/// Blob store of the app - cheap to clone
+#[derive(Clone)]
+pub struct BlobStore {
+    /// Storage
+    store: Arc<dyn StateStore>,
+    /// Errors of the writes, reported by the wrapper
+    failures: Arc<Mutex<Vec<AppError>>>,
+}
+
+impl Debug for BlobStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlobStore").finish_non_exhaustive()
+    }
+}
+
+impl Default for BlobStore {
+    /// Store named "bladvak" - the wrapper installs the store of the app
+    fn default() -> Self {
+        app_store("bladvak")
+    }
+}
+
+impl BlobStore {
+    /// Create a blob store
+    pub fn new(store: impl StateStore + 'static) -> Self {
+        Self {
+            store: Arc::new(store),
+            failures: Arc::default(),
+        }
+    }
+
+    /// Id of the store in the egui context
+    fn id() -> egui::Id {
+        egui::Id::new("bladvak_blob_store")
+    }
+
+    /// Get the blob store of the app
+    #[must_use]
+    pub fn get(ctx: &egui::Context) -> Self {
+        ctx.data_mut(|data| data.get_temp_mut_or_default::<Self>(Self::id()).clone())
+    }
+
+    /// Replace the blob store of the app - e.g. with a custom [`StateStore`]
+    pub fn install(self, ctx: &egui::Context) {
+        ctx.data_mut(|data| data.insert_temp(Self::id(), self));
+    }
+
+    /// Read a blob in the background
+    #[must_use]
+    pub fn load(&self, key: &str) -> TaskHandle<Result<Option<Vec<u8>>, AppError>> {
+        crate::task::spawn(self.store.get(key))
+    }
+
+    /// Write a blob in the background - an error is reported in the error manager
+    pub fn save(&self, ctx: &egui::Context, key: &str, data: Vec<u8>) {
+        self.report(ctx, self.store.set(key, data));
+    }
+
+    /// Remove a blob in the background - an error is reported in the error manager
+    pub fn remove(&self, ctx: &egui::Context, key: &str) {
+        self.report(ctx, self.store.remove(key));
+    }
+
+    /// Run an operation and keep its error
+    fn report(&self, ctx: &egui::Context, operation: StoreFuture<()>) {
+        let failures = Arc::clone(&self.failures);
+        let ctx = ctx.clone();
+        let _ = crate::task::spawn(async move {
+            if let Err(err) = operation.await {
+                if let Ok(mut failures) = failures.lock() {
+                    failures.push(err);
+                }
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    /// Take the errors of the writes
+    pub(crate) fn take_failures(&self) -> Vec<AppError> {
+        self.failures
+            .lock()
+            .map(|mut failures| std::mem::take(&mut *failures))
+            .unwrap_or_default()
+    }
+}
+
+/// Blob store of an app on native - in the storage directory of the app, or in the system
+/// temporary folder if there is none
+#[cfg(not(target_arch = "wasm32"))]
+fn app_store(app_name: &str) -> BlobStore {
+    let folder = eframe::storage_dir(app_name)
+        .unwrap_or_else(|| std::env::temp_dir().join(app_name))
+        .join("blobs");
+    BlobStore::new(FileStore::new(folder))
+}
+
+/// Blob store of an app on web - in an `IndexedDB` database named after the app
+#[cfg(target_arch = "wasm32")]
+fn app_store(app_name: &str) -> BlobStore {
+    BlobStore::new(IndexedDbStore::new(app_name))
+}
+
+/// Create the blob store of the app
+pub(crate) fn install(ctx: &egui::Context, app_name: &str) {
+    app_store(app_name).install(ctx);
+}
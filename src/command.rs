@@ -0,0 +1,329 @@
+//! Commands of the app - shared by the menus, the toolbar, the palette and the shortcuts
+
+use eframe::egui::{self, KeyboardShortcut};
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use crate::errors::ErrorManager;
+
+/// Action of a command
+pub type CommandAction<App> = dyn Fn(&mut App, &mut ErrorManager);
+
+/// Enablement predicate of a command
+pub type CommandPredicate<App> = dyn Fn(&App) -> bool;
+
+/// Shortcut opening the command palette
+pub const PALETTE_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(
+    egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT),
+    egui::Key::P,
+);
+
+/// Shortcut of the "Open" entry of the File menu
+pub const OPEN_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::O);
+
+/// Shortcut opening the settings
+pub const SETTINGS_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Comma);
+
+/// Shortcut toggling fullscreen
+pub const FULLSCREEN_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(egui::Modifiers::NONE, egui::Key::F11);
+
+/// Shortcut closing the app - not on web
+pub const QUIT_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Q);
+
+/// Command registered by the app
+pub struct Command<App> {
+    /// Unique id
+    pub id: String,
+    /// Label displayed in the menus and the palette
+    pub label: String,
+    /// Keyboard shortcut
+    pub shortcut: Option<KeyboardShortcut>,
+    /// Icon - an emoji or a short text - the command is shown in the toolbar if set
+    pub icon: Option<String>,
+    /// Enablement predicate - always enabled if `None`
+    enabled: Option<Box<CommandPredicate<App>>>,
+    /// Checked state - for the commands toggling an option
+    checked: Option<Box<CommandPredicate<App>>>,
+    /// Action
+    action: Box<CommandAction<App>>,
+}
+
+impl<App> Debug for Command<App> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Command")
+            .field("id", &self.id)
+            .field("label", &self.label)
+            .field("shortcut", &self.shortcut)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<App> Command<App> {
+    /// Create a new command
+    pub fn new<I, L, F>(id: I, label: L, action: F) -> Self
+    where
+        I: Into<String>,
+        L: Into<String>,
+        F: Fn(&mut App, &mut ErrorManager) + 'static,
+    {
+        Self {
+            id: id.into(),
+            label: label.into(),
+            shortcut: None,
+            icon: None,
+            enabled: None,
+            checked: None,
+            action: Box::new(action),
+        }
+    }
+
+    /// Set the keyboard shortcut
+    #[must_use]
+    pub fn with_shortcut(mut self, shortcut: KeyboardShortcut) -> Self {
+        self.shortcut = Some(shortcut);
+        self
+    }
+
+    /// Set the icon - shown in the toolbar with the label as tooltip
+    #[must_use]
+    pub fn with_icon<S: Into<String>>(mut self, icon: S) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+
+    /// Set the enablement predicate - a disabled command is greyed out everywhere
+    #[must_use]
+    pub fn with_enabled<F: Fn(&App) -> bool + 'static>(mut self, enabled: F) -> Self {
+        self.enabled = Some(Box::new(enabled));
+        self
+    }
+
+    /// Set the checked state - the command is shown as a checkbox in the menus
+    #[must_use]
+    pub fn with_checked<F: Fn(&App) -> bool + 'static>(mut self, checked: F) -> Self {
+        self.checked = Some(Box::new(checked));
+        self
+    }
+
+    /// Checked state - `None` if the command does not toggle an option
+    pub fn is_checked(&self, app: &App) -> Option<bool> {
+        self.checked.as_ref().map(|checked| checked(app))
+    }
+
+    /// Check if the command is enabled
+    pub fn is_enabled(&self, app: &App) -> bool {
+        self.enabled.as_ref().is_none_or(|enabled| enabled(app))
+    }
+
+    /// Run the command
+    pub fn run(&self, app: &mut App, error_manager: &mut ErrorManager) {
+        (self.action)(app, error_manager);
+    }
+
+    /// Get the command information
+    pub fn info(&self, app: &App) -> CommandInfo {
+        CommandInfo {
+            id: self.id.clone(),
+            label: self.label.clone(),
+            shortcut: self.shortcut,
+            icon: self.icon.clone(),
+            enabled: self.is_enabled(app),
+            checked: self.is_checked(app),
+            menu_path: None,
+        }
+    }
+}
+
+/// Command information of the current frame
+#[derive(Debug, Clone)]
+pub struct CommandInfo {
+    /// Unique id
+    pub id: String,
+    /// Label
+    pub label: String,
+    /// Keyboard shortcut
+    pub shortcut: Option<KeyboardShortcut>,
+    /// Icon
+    pub icon: Option<String>,
+    /// Is the command enabled
+    pub enabled: bool,
+    /// Checked state - `None` if the command does not toggle an option
+    pub checked: Option<bool>,
+    /// Menu path of the command (see [`crate::menu::MenuModel`])
+    pub menu_path: Option<String>,
+}
+
+/// Commands state shared through the egui context
+#[derive(Debug, Default)]
+struct CommandShared {
+    /// Commands of the current frame
+    infos: Vec<CommandInfo>,
+    /// Commands waiting to be run
+    queue: Vec<String>,
+}
+
+/// Handle to the shared commands state
+#[derive(Clone, Default)]
+struct SharedHandle(Arc<Mutex<CommandShared>>);
+
+impl SharedHandle {
+    /// Id of the shared state in the egui context
+    fn id() -> egui::Id {
+        egui::Id::new("bladvak_commands")
+    }
+
+    /// Get the shared state from the egui context
+    fn get(ctx: &egui::Context) -> Self {
+        ctx.data_mut(|data| data.get_temp_mut_or_default::<Self>(Self::id()).clone())
+    }
+}
+
+/// Publish the commands of the current frame
+pub(crate) fn publish(ctx: &egui::Context, infos: Vec<CommandInfo>) {
+    if let Ok(mut shared) = SharedHandle::get(ctx).0.lock() {
+        shared.infos = infos;
+    }
+}
+
+/// Take the commands waiting to be run
+pub(crate) fn take_queue(ctx: &egui::Context) -> Vec<String> {
+    SharedHandle::get(ctx)
+        .0
+        .lock()
+        .map(|mut shared| std::mem::take(&mut shared.queue))
+        .unwrap_or_default()
+}
+
+/// Run a command at the next frame
+pub fn run_command<S: Into<String>>(ctx: &egui::Context, id: S) {
+    if let Ok(mut shared) = SharedHandle::get(ctx).0.lock() {
+        shared.queue.push(id.into());
+    }
+    ctx.request_repaint();
+}
+
+/// Get the registered commands
+#[must_use]
+pub fn commands(ctx: &egui::Context) -> Vec<CommandInfo> {
+    SharedHandle::get(ctx)
+        .0
+        .lock()
+        .map(|shared| shared.infos.clone())
+        .unwrap_or_default()
+}
+
+/// Get a registered command
+#[must_use]
+pub fn command_info(ctx: &egui::Context, id: &str) -> Option<CommandInfo> {
+    SharedHandle::get(ctx)
+        .0
+        .lock()
+        .ok()
+        .and_then(|shared| shared.infos.iter().find(|info| info.id == id).cloned())
+}
+
+/// Button with its shortcut right-aligned
+pub(crate) fn shortcut_button<'a>(
+    ui: &egui::Ui,
+    label: impl Into<egui::WidgetText>,
+    shortcut: Option<&KeyboardShortcut>,
+) -> egui::Button<'a> {
+    let button = egui::Button::new(label);
+    match shortcut {
+        Some(shortcut) => button.shortcut_text(ui.ctx().format_shortcut(shortcut)),
+        None => button,
+    }
+}
+
+/// Show a button running a command - greyed out if the command is disabled
+///
+/// Returns `None` if the command is not registered
+pub fn command_button(ui: &mut egui::Ui, id: &str) -> Option<egui::Response> {
+    let info = command_info(ui.ctx(), id)?;
+    let button = shortcut_button(ui, &info.label, info.shortcut.as_ref());
+    let response = ui.add_enabled(info.enabled, button);
+    if response.clicked() {
+        run_command(ui.ctx(), id);
+        ui.close();
+    }
+    Some(response)
+}
+
+/// Show a menu entry running a command, with its shortcut right-aligned - the menu is closed
+/// when clicked
+///
+/// Returns `None` if the command is not registered
+pub fn menu_item(ui: &mut egui::Ui, id: &str) -> Option<egui::Response> {
+    command_button(ui, id)
+}
+
+/// Command palette
+#[derive(Debug, Default)]
+pub(crate) struct CommandPalette {
+    /// Is the palette open
+    pub(crate) open: bool,
+    /// Search text
+    search: String,
+}
+
+impl CommandPalette {
+    /// Show the palette
+    pub(crate) fn show(&mut self, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+        let search = self.search.to_lowercase();
+        let matching = commands(ctx)
+            .into_iter()
+            .filter(|info| search.is_empty() || info.label.to_lowercase().contains(&search))
+            .collect::<Vec<_>>();
+        let modal = egui::Modal::new(egui::Id::new("bladvak_command_palette")).show(ctx, |ui| {
+            ui.set_width(360.0);
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.search)
+                    .hint_text("Type a command")
+                    .desired_width(f32::INFINITY),
+            );
+            response.request_focus();
+            if response.lost_focus()
+                && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                && let Some(info) = matching.iter().find(|info| info.enabled)
+            {
+                run_command(ui.ctx(), &info.id);
+                self.open = false;
+            }
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    if matching.is_empty() {
+                        ui.label("No command");
+                    }
+                    for info in &matching {
+                        let label = match &info.menu_path {
+                            Some(path) => format!("{path} › {}", info.label),
+                            None => info.label.clone(),
+                        };
+                        let button =
+                            shortcut_button(ui, label, info.shortcut.as_ref()).frame(false);
+                        if ui.add_enabled(info.enabled, button).clicked() {
+                            run_command(ui.ctx(), &info.id);
+                            self.open = false;
+                        }
+                    }
+                });
+        });
+        if modal.should_close() {
+            self.open = false;
+        }
+        if !self.open {
+            self.search.clear();
+        }
+    }
+}
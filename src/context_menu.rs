@@ -0,0 +1,87 @@
+//! Context menu contributions
+//!
+//! A shared surface (the error list, a canvas of the app...) has a context id. The app
+//! ([`crate::BladvakApp::context_menus`]), the panels ([`crate::app::BladvakPanel::context_menus`])
+//! or any code with the egui context ([`register_context_menu`]) contribute [`MenuEntry`] to a
+//! context id, and the surface shows them with [`show_context_menu`] - neither side knows the
+//! other. The entries are command ids, like in the [`crate::MenuModel`].
+
+use eframe::egui;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::menu::{MenuEntry, entries_ui};
+
+/// Context id of the entries of the error list
+pub const ERROR_LIST_CONTEXT: &str = "bladvak.errors";
+
+/// Context id of the entries of the sortable tables (see [`crate::utils::grid::SortableTable`])
+pub const GRID_CONTEXT: &str = "bladvak.grid";
+
+/// Handle to the contributions shared through the egui context
+#[derive(Clone, Default)]
+struct SharedHandle(Arc<Mutex<BTreeMap<String, Vec<MenuEntry>>>>);
+
+impl SharedHandle {
+    /// Id of the contributions in the egui context
+    fn id() -> egui::Id {
+        egui::Id::new("bladvak_context_menus")
+    }
+
+    /// Get the contributions from the egui context
+    fn get(ctx: &egui::Context) -> Self {
+        ctx.data_mut(|data| data.get_temp_mut_or_default::<Self>(Self::id()).clone())
+    }
+}
+
+/// Add an entry to the context menu of a context id
+pub fn register_context_menu<S: Into<String>>(
+    ctx: &egui::Context,
+    context_id: S,
+    entry: MenuEntry,
+) {
+    if let Ok(mut shared) = SharedHandle::get(ctx).0.lock() {
+        shared.entry(context_id.into()).or_default().push(entry);
+    }
+}
+
+/// Remove the entries of a context id
+pub fn clear_context_menu(ctx: &egui::Context, context_id: &str) {
+    if let Ok(mut shared) = SharedHandle::get(ctx).0.lock() {
+        shared.remove(context_id);
+    }
+}
+
+/// Get the entries of a context id
+#[must_use]
+pub fn context_menu_entries(ctx: &egui::Context, context_id: &str) -> Vec<MenuEntry> {
+    SharedHandle::get(ctx)
+        .0
+        .lock()
+        .ok()
+        .and_then(|shared| shared.get(context_id).cloned())
+        .unwrap_or_default()
+}
+
+/// Show the context menu of a response - the local entries first, then the contributions
+/// to the context id
+pub fn show_context_menu(
+    response: &egui::Response,
+    context_id: &str,
+    add_contents: impl FnOnce(&mut egui::Ui),
+) {
+    let entries = context_menu_entries(&response.ctx, context_id);
+    response.context_menu(|ui| {
+        let start = ui.cursor().min;
+        add_contents(ui);
+        if entries.is_empty() {
+            return;
+        }
+        if ui.cursor().min != start {
+            ui.separator();
+        }
+        entries_ui(ui, &entries);
+    });
+}
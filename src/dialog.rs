@@ -0,0 +1,218 @@
+//! Non-blocking system dialogs
+//!
+//! Every dialog runs as a [`Promise`] polled by the ui: on native the `rfd` async dialog is
+//! driven from a background thread, on web it runs on the browser event loop. The ui thread
+//! is never blocked and several dialogs can be pending at the same time.
+
+use eframe::egui;
+use poll_promise::Promise;
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::errors::AppError;
+
+/// Run a dialog future in the background - see [`crate::task::spawn`]
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn_dialog<T, F>(future: F) -> Promise<T>
+where
+    T: Send + 'static,
+    F: Future<Output = T> + Send + 'static,
+{
+    crate::task::spawn(future).into_promise()
+}
+
+/// Run a dialog future in the background - see [`crate::task::spawn`]
+#[cfg(target_arch = "wasm32")]
+pub fn spawn_dialog<T, F>(future: F) -> Promise<T>
+where
+    T: Send + 'static,
+    F: Future<Output = T> + 'static,
+{
+    crate::task::spawn(future).into_promise()
+}
+
+/// Save dialog future - the chosen path, `None` if cancelled
+fn save_path_future(suggested: Option<&Path>) -> impl Future<Output = Option<PathBuf>> + use<> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let mut dialog = rfd::AsyncFileDialog::new();
+        if let Some(directory) = suggested.and_then(Path::parent) {
+            dialog = dialog.set_directory(directory);
+        }
+        let file_name = suggested.and_then(Path::file_name).map_or_else(
+            || "file".to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        dialog = dialog.set_file_name(file_name);
+        async move {
+            dialog
+                .save_file()
+                .await
+                .map(|handle| handle.path().to_path_buf())
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let path = suggested.map_or_else(|| PathBuf::from("file"), Path::to_path_buf);
+        async move {
+            // a file already chosen with the picker is saved again without asking
+            if crate::web_save::has_handle(&path) || !crate::web_save::is_supported() {
+                return Some(path);
+            }
+            match crate::web_save::pick(&path).await {
+                Ok(picked) => picked,
+                Err(err) => {
+                    log::warn!("{err}, downloading the file instead");
+                    Some(path)
+                }
+            }
+        }
+    }
+}
+
+/// Write the data of a save
+#[cfg_attr(
+    not(target_arch = "wasm32"),
+    allow(clippy::unused_async, reason = "awaits the file handle on web")
+)]
+async fn write_file(data: &[u8], path: &Path) -> Result<(), AppError> {
+    #[cfg(target_arch = "wasm32")]
+    if crate::web_save::has_handle(path) {
+        return crate::web_save::write(path, data).await;
+    }
+    crate::utils::save_file(data, path).map_err(AppError::from)
+}
+
+/// Save dialog - resolves to the chosen path, `None` if cancelled
+///
+/// On web the browser asks with the File System Access API where available, the suggested path
+/// is used directly otherwise (the browser downloads the file)
+pub fn save_path(suggested: Option<&Path>) -> Promise<Option<PathBuf>> {
+    spawn_dialog(save_path_future(suggested))
+}
+
+/// Folder dialog - resolves to the chosen folder, `None` if cancelled or on web
+pub fn pick_folder() -> Promise<Option<PathBuf>> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        spawn_dialog(async {
+            rfd::AsyncFileDialog::new()
+                .pick_folder()
+                .await
+                .map(|handle| handle.path().to_path_buf())
+        })
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        Promise::from_ready(None)
+    }
+}
+
+/// Outcome of a save started with [`save_file_dialog`] - the saved path or an error
+type SaveOutcome = Result<(PathBuf, usize), AppError>;
+
+/// Saves started with [`save_file_dialog`], shared through the egui context
+#[derive(Clone, Default)]
+struct SharedHandle(Arc<Mutex<Vec<Promise<Option<SaveOutcome>>>>>);
+
+impl SharedHandle {
+    /// Id of the shared state in the egui context
+    fn id() -> egui::Id {
+        egui::Id::new("bladvak_dialogs")
+    }
+
+    /// Get the shared state from the egui context
+    fn get(ctx: &egui::Context) -> Self {
+        ctx.data_mut(|data| data.get_temp_mut_or_default::<Self>(Self::id()).clone())
+    }
+}
+
+/// Ask where to save the data and save it - the result is reported by the wrapper
+pub fn save_file_dialog(ctx: &egui::Context, data: Vec<u8>, suggested: &Path) {
+    let path = save_path_future(Some(suggested));
+    let repaint_ctx = ctx.clone();
+    let promise = spawn_dialog(async move {
+        let path = path.await?;
+        let outcome = write_file(&data, &path).await.map(|()| (path, data.len()));
+        repaint_ctx.request_repaint();
+        Some(outcome)
+    });
+    if let Ok(mut pending) = SharedHandle::get(ctx).0.lock() {
+        pending.push(promise);
+    }
+}
+
+/// Report a save which failed before the dialog - e.g. the data could not be produced
+pub(crate) fn save_failed(ctx: &egui::Context, err: AppError) {
+    if let Ok(mut pending) = SharedHandle::get(ctx).0.lock() {
+        pending.push(Promise::from_ready(Some(Err(err))));
+    }
+    ctx.request_repaint();
+}
+
+/// Take the finished saves
+pub(crate) fn take_saved(ctx: &egui::Context) -> Vec<SaveOutcome> {
+    let shared = SharedHandle::get(ctx);
+    let Ok(mut pending) = shared.0.lock() else {
+        return Vec::new();
+    };
+    let mut finished = Vec::new();
+    let mut running = Vec::with_capacity(pending.len());
+    for promise in pending.drain(..) {
+        match promise.try_take() {
+            Ok(Some(outcome)) => finished.push(outcome),
+            Ok(None) => {}
+            Err(promise) => running.push(promise),
+        }
+    }
+    *pending = running;
+    finished
+}
+
+/// Dialog waiting for the user - polled by the ui
+pub struct PendingDialog<T: Send + 'static>(Option<Promise<T>>);
+
+impl<T: Send + 'static> Default for PendingDialog<T> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<T: Send + 'static> std::fmt::Debug for PendingDialog<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PendingDialog")
+            .field(&self.is_pending())
+            .finish()
+    }
+}
+
+impl<T: Send + 'static> PendingDialog<T> {
+    /// Start waiting for a dialog - ignored if a dialog is already pending
+    pub fn start(&mut self, dialog: impl FnOnce() -> Promise<T>) {
+        if self.is_pending() {
+            log::info!("A dialog is already open");
+        } else {
+            self.0 = Some(dialog());
+        }
+    }
+
+    /// Check if a dialog is pending
+    #[must_use]
+    pub fn is_pending(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Take the result of the dialog once the user answered
+    pub fn poll(&mut self) -> Option<T> {
+        match self.0.take()?.try_take() {
+            Ok(value) => Some(value),
+            Err(promise) => {
+                self.0 = Some(promise);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Embedding of a [`BladvakApp`] in another app
+//!
+//! An [`EmbeddedApp`] hosts a child app as a [`BladvakPanel`] of the host app, so that small
+//! tools built on this crate can be combined in an umbrella app. The state of the child is a
+//! field of the host state (saved with it); the child gets its own error manager, forwarded to
+//! the host with the child name, its own inbox (see [`crate::inbox`]) and its own egui id
+//! namespace. The messages of the child inbox are handled when the child is shown. The files,
+//! the commands and the background services stay the ones of the host.
+
+use eframe::egui;
+use std::{
+    cell::{OnceCell, RefCell},
+    fmt::Debug,
+};
+
+use crate::{
+    app::{BladvakApp, BladvakPanel},
+    errors::ErrorManager,
+    inbox::Inbox,
+};
+
+/// Access to the child state in the host state
+pub type ChildAccess<Host, Child> = dyn Fn(&mut Host) -> &mut Child;
+
+/// Child app hosted as a panel
+pub struct EmbeddedApp<Host, Child> {
+    /// Panel name - the child name by default
+    name: String,
+    /// Access to the child state
+    access: Box<ChildAccess<Host, Child>>,
+    /// Panels of the child
+    panels: Vec<Box<dyn BladvakPanel<App = Child>>>,
+    /// Error manager of the child
+    errors: RefCell<ErrorManager>,
+    /// Inbox of the child - created when the child is first shown
+    inbox: OnceCell<Inbox>,
+}
+
+impl<Host, Child> Debug for EmbeddedApp<Host, Child> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmbeddedApp")
+            .field("name", &self.name)
+            .field("panels", &self.panels)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Host, Child> EmbeddedApp<Host, Child>
+where
+    Child: for<'a> BladvakApp<'a>,
+{
+    /// Create the adapter of a child app - `access` gets the child state from the host state
+    pub fn new<F>(access: F) -> Self
+    where
+        F: Fn(&mut Host) -> &mut Child + 'static,
+    {
+        Self {
+            name: Child::name(),
+            access: Box::new(access),
+            panels: Vec::new(),
+            errors: RefCell::new(ErrorManager::with_capacity(Child::error_capacity())),
+            inbox: OnceCell::new(),
+        }
+    }
+
+    /// Set the panel name
+    #[must_use]
+    pub fn with_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Show the panels of the child (see [`BladvakApp::panel_list`]) below its central panel
+    #[must_use]
+    pub fn with_panels(mut self, panels: Vec<Box<dyn BladvakPanel<App = Child>>>) -> Self {
+        self.panels = panels;
+        self
+    }
+
+    /// Run a ui of the child with its error manager and its inbox, in its id namespace
+    fn scoped(
+        &self,
+        host: &mut Host,
+        ui: &mut egui::Ui,
+        error_manager: &mut ErrorManager,
+        func: impl FnOnce(&mut Child, &mut egui::Ui, &mut ErrorManager),
+    ) {
+        let child = (self.access)(host);
+        let Ok(mut errors) = self.errors.try_borrow_mut() else {
+            return;
+        };
+        let inbox = self
+            .inbox
+            .get_or_init(|| Inbox::new(ui.ctx(), ("bladvak_embedded", &self.name)));
+        for msg in inbox.drain() {
+            child.handle_message(msg, &mut errors);
+        }
+        let previous = inbox.enter(ui.ctx());
+        ui.push_id(("bladvak_embedded", &self.name), |ui| {
+            func(child, ui, &mut errors);
+        });
+        Inbox::leave(ui.ctx(), previous);
+        errors.forward_to(error_manager, &self.name);
+    }
+}
+
+impl<Host, Child> BladvakPanel for EmbeddedApp<Host, Child>
+where
+    Child: for<'a> BladvakApp<'a>,
+{
+    type App = Host;
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn has_settings(&self) -> bool {
+        self.panels.iter().any(|panel| panel.has_settings())
+    }
+
+    fn ui_settings(&self, app: &mut Host, ui: &mut egui::Ui, error_manager: &mut ErrorManager) {
+        self.scoped(app, ui, error_manager, |child, ui, errors| {
+            for panel in self.panels.iter().filter(|panel| panel.has_settings()) {
+                ui.heading(panel.name());
+                panel.ui_settings(child, ui, errors);
+            }
+        });
+    }
+
+    fn has_ui(&self) -> bool {
+        true
+    }
+
+    fn ui(&self, app: &mut Host, ui: &mut egui::Ui, error_manager: &mut ErrorManager) {
+        self.scoped(app, ui, error_manager, |child, ui, errors| {
+            egui::MenuBar::new().ui(ui, |ui| child.top_panel(ui, errors));
+            child.central_panel(ui, errors);
+            for panel in self.panels.iter().filter(|panel| panel.has_ui()) {
+                egui::CollapsingHeader::new(panel.name())
+                    .default_open(true)
+                    .show(ui, |ui| panel.ui(child, ui, errors));
+            }
+        });
+    }
+}
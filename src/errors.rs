@@ -1,6 +1,112 @@
 //! Error handling
+//!
+//! The errors are added to the [`ErrorManager`] given to the app. The background work reports
+//! its errors with an [`ErrorSink`] (see [`error_sink`]), drained into the error manager each
+//! frame.
 
-use std::{error::Error, fmt, io, string::FromUtf8Error, sync::Arc};
+use eframe::egui;
+use std::{
+    error::Error,
+    fmt, io,
+    string::FromUtf8Error,
+    sync::{
+        Arc,
+        mpsc::{Receiver, Sender, channel},
+    },
+};
+
+use crate::utils::time::{SystemTime, now};
+
+/// Severity of an error
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum Severity {
+    /// Information
+    Info,
+    /// Warning - the action partially succeeded
+    Warning,
+    /// Error - the action failed
+    #[default]
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Info => write!(f, "Info"),
+            Self::Warning => write!(f, "Warning"),
+            Self::Error => write!(f, "Error"),
+        }
+    }
+}
+
+/// Time (in seconds) the toast of an error is shown
+pub const TOAST_DURATION: f64 = 6.0;
+
+/// How the new errors of a severity are presented
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ErrorPresentation {
+    /// Open the error window and take the keyboard focus
+    #[default]
+    Window,
+    /// Show a toast for a few seconds - the error window opens on click
+    Toast,
+    /// Count the errors in the status bar - the error window opens on click
+    StatusBar,
+}
+
+impl ErrorPresentation {
+    /// All the presentations
+    pub const ALL: [Self; 3] = [Self::Window, Self::Toast, Self::StatusBar];
+}
+
+impl fmt::Display for ErrorPresentation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Window => write!(f, "Window"),
+            Self::Toast => write!(f, "Toast"),
+            Self::StatusBar => write!(f, "Status bar"),
+        }
+    }
+}
+
+/// Category of an error - lets the tooling aggregate the errors without matching the messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorCategory {
+    /// Input/output error (files, storage...)
+    Io,
+    /// Parsing error
+    Parse,
+    /// Network error
+    Network,
+    /// Internal error (bug)
+    Internal,
+    /// Category defined by the app
+    App(&'static str),
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io => write!(f, "io"),
+            Self::Parse => write!(f, "parse"),
+            Self::Network => write!(f, "network"),
+            Self::Internal => write!(f, "internal"),
+            Self::App(category) => write!(f, "{category}"),
+        }
+    }
+}
 
 /// `AppError` object
 #[derive(Default, Debug, Clone)]
@@ -9,6 +115,16 @@ pub struct AppError {
     pub message: String,
     /// Error source
     pub source: Option<Arc<dyn std::error::Error + Send + Sync>>,
+    /// Severity
+    pub severity: Severity,
+    /// Context or backtrace
+    pub details: Option<Box<str>>,
+    /// Time of the error - set when added to the [`ErrorManager`]
+    pub timestamp: Option<SystemTime>,
+    /// Category
+    pub category: Option<ErrorCategory>,
+    /// Machine-readable code
+    pub code: Option<&'static str>,
 }
 
 impl fmt::Display for AppError {
@@ -29,6 +145,11 @@ impl AppError {
         Self {
             message,
             source: None,
+            severity: Severity::default(),
+            details: None,
+            timestamp: None,
+            category: None,
+            code: None,
         }
     }
 
@@ -40,8 +161,92 @@ impl AppError {
         Self {
             message: message.into(),
             source: Some(source),
+            severity: Severity::default(),
+            details: None,
+            timestamp: None,
+            category: None,
+            code: None,
+        }
+    }
+
+    /// Create an error converted from its source
+    fn from_source(
+        message: String,
+        source: Arc<dyn Error + Send + Sync>,
+        category: Option<ErrorCategory>,
+    ) -> Self {
+        Self {
+            category,
+            ..Self::new_with_source(message, source)
+        }
+    }
+
+    /// Set the severity
+    #[must_use]
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Set the category
+    #[must_use]
+    pub fn with_category(mut self, category: ErrorCategory) -> Self {
+        self.category = Some(category);
+        self
+    }
+
+    /// Set the machine-readable code
+    #[must_use]
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// Category and code as `category:code` - `None` if both are unset
+    #[must_use]
+    pub fn tag(&self) -> Option<String> {
+        match (&self.category, &self.code) {
+            (None, None) => None,
+            (Some(category), None) => Some(category.to_string()),
+            (None, Some(code)) => Some((*code).to_string()),
+            (Some(category), Some(code)) => Some(format!("{category}:{code}")),
         }
     }
+
+    /// Set the details (context or backtrace)
+    #[must_use]
+    pub fn with_details<S: Into<String>>(mut self, details: S) -> Self {
+        self.details = Some(details.into().into_boxed_str());
+        self
+    }
+
+    /// Full description of the error: severity, category, message, sources and details
+    #[must_use]
+    pub fn report(&self) -> String {
+        let header = match self.tag() {
+            Some(tag) => format!("[{}] [{tag}] {}", self.severity, self.message),
+            None => format!("[{}] {}", self.severity, self.message),
+        };
+        let report = self.chain().iter().skip(1).fold(header, |report, source| {
+            format!("{report}\n  caused by: {source}")
+        });
+        match &self.details {
+            Some(details) => format!("{report}\n{details}"),
+            None => report,
+        }
+    }
+
+    /// Messages of the error and of its sources
+    #[must_use]
+    pub fn chain(&self) -> Vec<String> {
+        let mut chain = vec![self.message.clone()];
+        let mut source = Error::source(self);
+        while let Some(error) = source {
+            chain.push(error.to_string());
+            source = error.source();
+        }
+        chain
+    }
 }
 
 impl From<String> for AppError {
@@ -58,28 +263,47 @@ impl From<&str> for AppError {
 
 impl From<io::Error> for AppError {
     fn from(error: io::Error) -> Self {
-        Self {
-            message: error.to_string(),
-            source: Some(Arc::new(error)),
-        }
+        Self::from_source(error.to_string(), Arc::new(error), Some(ErrorCategory::Io))
     }
 }
 
 impl From<FromUtf8Error> for AppError {
     fn from(error: FromUtf8Error) -> Self {
-        Self {
-            message: error.to_string(),
-            source: Some(Arc::new(error)),
-        }
+        Self::from_source(
+            error.to_string(),
+            Arc::new(error),
+            Some(ErrorCategory::Parse),
+        )
     }
 }
 
 impl From<std::num::ParseFloatError> for AppError {
     fn from(error: std::num::ParseFloatError) -> Self {
-        Self {
-            message: error.to_string(),
-            source: Some(Arc::new(error)),
-        }
+        Self::from_source(
+            error.to_string(),
+            Arc::new(error),
+            Some(ErrorCategory::Parse),
+        )
+    }
+}
+
+impl From<Box<dyn Error + Send + Sync>> for AppError {
+    fn from(error: Box<dyn Error + Send + Sync>) -> Self {
+        Self::from_source(error.to_string(), Arc::from(error), None)
+    }
+}
+
+#[cfg(feature = "anyhow")]
+impl From<anyhow::Error> for AppError {
+    fn from(error: anyhow::Error) -> Self {
+        Box::<dyn Error + Send + Sync>::from(error).into()
+    }
+}
+
+#[cfg(feature = "eyre")]
+impl From<eyre::Report> for AppError {
+    fn from(error: eyre::Report) -> Self {
+        Box::<dyn Error + Send + Sync>::from(error).into()
     }
 }
 
@@ -105,8 +329,13 @@ where
     }
 }
 
+/// Error callback
+pub type ErrorCallback = dyn FnMut(&AppError);
+
+/// Default number of errors kept by the [`ErrorManager`]
+pub const DEFAULT_ERROR_CAPACITY: usize = 200;
+
 /// Error handler
-#[derive(Debug, Default)]
 pub struct ErrorManager {
     /// List of errors
     pub(crate) errors: Vec<AppError>,
@@ -114,8 +343,83 @@ pub struct ErrorManager {
     /// Check if it is open
     pub(crate) is_open: bool,
 
-    /// Check if it was open
-    pub(crate) was_open: bool,
+    /// Number of errors already shown - the window opens when new errors arrive
+    pub(crate) seen: usize,
+
+    /// Search text of the error window
+    pub(crate) search: String,
+
+    /// Severity shown in the error window - all if `None`
+    pub(crate) severity_filter: Option<Severity>,
+
+    /// Group the errors by category in the error window
+    pub(crate) group_by_category: bool,
+
+    /// Callbacks called for each error
+    callbacks: Vec<Box<ErrorCallback>>,
+
+    /// Warnings and information - shown in the messages window without interrupting the user
+    messages: Vec<AppError>,
+
+    /// Number of messages not reviewed yet
+    pub(crate) unread_messages: usize,
+
+    /// Check if the messages window is open
+    pub(crate) messages_open: bool,
+
+    /// Toasts of the new errors and the time they expire
+    pub(crate) toasts: Vec<(AppError, f64)>,
+
+    /// Number of errors counted in the status bar and not reviewed yet
+    pub(crate) status_errors: usize,
+
+    /// Maximum number of errors kept - the oldest are dropped first
+    capacity: usize,
+
+    /// Number of dropped errors
+    dropped: usize,
+}
+
+impl Default for ErrorManager {
+    fn default() -> Self {
+        Self {
+            errors: Vec::new(),
+            is_open: false,
+            seen: 0,
+            search: String::new(),
+            severity_filter: None,
+            group_by_category: false,
+            callbacks: Vec::new(),
+            messages: Vec::new(),
+            unread_messages: 0,
+            messages_open: false,
+            toasts: Vec::new(),
+            status_errors: 0,
+            capacity: DEFAULT_ERROR_CAPACITY,
+            dropped: 0,
+        }
+    }
+}
+
+impl fmt::Debug for ErrorManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorManager")
+            .field("errors", &self.errors)
+            .field("is_open", &self.is_open)
+            .field("seen", &self.seen)
+            .field("search", &self.search)
+            .field("severity_filter", &self.severity_filter)
+            .field("group_by_category", &self.group_by_category)
+            .field("callbacks", &self.callbacks.len())
+            .field("messages", &self.messages)
+            .field("unread_messages", &self.unread_messages)
+            .field("messages_open", &self.messages_open)
+            .field("toasts", &self.toasts)
+            .field("status_errors", &self.status_errors)
+            .field("capacity", &self.capacity)
+            .field("dropped", &self.dropped)
+            .finish()
+    }
 }
 
 impl ErrorManager {
@@ -127,9 +431,138 @@ impl ErrorManager {
         }
     }
 
+    /// New Error manager keeping at most `capacity` errors
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            ..Default::default()
+        }
+    }
+
+    /// Maximum number of errors kept
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of errors dropped since the last clear
+    #[must_use]
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Drop the oldest errors above the capacity
+    fn evict(&mut self) {
+        let excess = self.errors.len().saturating_sub(self.capacity);
+        if excess > 0 {
+            self.errors.drain(..excess);
+            self.dropped += excess;
+            self.seen = self.seen.saturating_sub(excess);
+        }
+    }
+
     /// Add an error
     pub fn add_error<E: Into<AppError>>(&mut self, error: E) {
-        self.errors.push(error.into());
+        let mut error = error.into();
+        error.timestamp.get_or_insert_with(now);
+        crate::journal::record(crate::journal::JournalKind::Error, error.to_string());
+        crate::telemetry::emit(crate::telemetry::TelemetryEvent::ErrorOccurred {
+            severity: error.severity,
+            tag: error.tag(),
+        });
+        self.push_error(error);
+    }
+
+    /// Store an error already recorded in the journal and the telemetry - the callbacks are
+    /// called
+    fn push_error(&mut self, error: AppError) {
+        for callback in &mut self.callbacks {
+            callback(&error);
+        }
+        self.errors.push(error);
+        self.evict();
+    }
+
+    /// Add a warning - shown in the messages window, the error window is not opened
+    pub fn add_warning<E: Into<AppError>>(&mut self, warning: E) {
+        self.add_message(warning.into().with_severity(Severity::Warning));
+    }
+
+    /// Add an information - shown in the messages window, the error window is not opened
+    pub fn add_info<E: Into<AppError>>(&mut self, info: E) {
+        self.add_message(info.into().with_severity(Severity::Info));
+    }
+
+    /// Add a message - the oldest are dropped above the capacity
+    fn add_message(&mut self, mut message: AppError) {
+        message.timestamp.get_or_insert_with(now);
+        match message.severity {
+            Severity::Info => log::info!("{message}"),
+            Severity::Warning | Severity::Error => log::warn!("{message}"),
+        }
+        let kind = match message.severity {
+            Severity::Error => crate::journal::JournalKind::Error,
+            Severity::Warning | Severity::Info => crate::journal::JournalKind::Message,
+        };
+        crate::journal::record(kind, message.to_string());
+        crate::telemetry::emit(crate::telemetry::TelemetryEvent::ErrorOccurred {
+            severity: message.severity,
+            tag: message.tag(),
+        });
+        self.push_message(message);
+    }
+
+    /// Store a message already recorded in the journal and the telemetry
+    fn push_message(&mut self, message: AppError) {
+        self.messages.push(message);
+        self.unread_messages += 1;
+        let excess = self.messages.len().saturating_sub(self.capacity);
+        self.messages.drain(..excess);
+        self.unread_messages = self.unread_messages.min(self.messages.len());
+    }
+
+    /// Warnings and information
+    #[must_use]
+    pub fn messages(&self) -> &[AppError] {
+        &self.messages
+    }
+
+    /// Remove a message
+    pub fn dismiss_message(&mut self, index: usize) {
+        if index < self.messages.len() {
+            // the unread messages are the last ones
+            if index >= self.messages.len() - self.unread_messages {
+                self.unread_messages -= 1;
+            }
+            self.messages.remove(index);
+        }
+    }
+
+    /// Clears the messages
+    pub fn clear_messages(&mut self) {
+        self.messages.clear();
+        self.unread_messages = 0;
+    }
+
+    /// Move the errors and the messages to another manager, prefixed with a scope name - they
+    /// are already in the journal and the telemetry, only the callbacks of `other` are called
+    pub(crate) fn forward_to(&mut self, other: &mut Self, scope: &str) {
+        for mut error in self.errors.drain(..) {
+            error.message = format!("{scope}: {}", error.message);
+            other.push_error(error);
+        }
+        self.seen = 0;
+        for mut message in self.messages.drain(..) {
+            message.message = format!("{scope}: {}", message.message);
+            other.push_message(message);
+        }
+        self.unread_messages = 0;
+    }
+
+    /// Register a callback called for each error (e.g. to log remotely or mark a document invalid)
+    pub fn on_error<F: FnMut(&AppError) + 'static>(&mut self, callback: F) {
+        self.callbacks.push(Box::new(callback));
     }
 
     /// Errors Title
@@ -147,5 +580,166 @@ impl ErrorManager {
     /// Clears errors
     pub fn clear(&mut self) {
         self.errors.clear();
+        self.toasts.clear();
+        self.status_errors = 0;
+        self.seen = 0;
+        self.dropped = 0;
+    }
+
+    /// Remove an error
+    pub fn dismiss(&mut self, index: usize) {
+        if index < self.errors.len() {
+            self.errors.remove(index);
+            // the seen errors are the first ones
+            if index < self.seen {
+                self.seen -= 1;
+            }
+        }
+    }
+
+    /// Check if an error matches the search and the severity filter of the error window
+    pub(crate) fn is_shown(&self, error: &AppError) -> bool {
+        let search = self.search.to_lowercase();
+        self.severity_filter
+            .is_none_or(|severity| error.severity == severity)
+            && (search.is_empty() || error.report().to_lowercase().contains(&search))
+    }
+
+    /// Reset the errors and the window state - the callbacks are kept
+    pub(crate) fn reset(&mut self) {
+        self.errors.clear();
+        self.is_open = false;
+        self.seen = 0;
+        self.dropped = 0;
+        self.messages.clear();
+        self.unread_messages = 0;
+        self.messages_open = false;
+        self.toasts.clear();
+        self.status_errors = 0;
+        self.search.clear();
+        self.severity_filter = None;
+    }
+}
+
+/// Error sent by an [`ErrorSink`]
+enum SinkEntry {
+    /// Added with [`ErrorManager::add_error`]
+    Error(AppError),
+    /// Added to the messages (warnings and information)
+    Message(AppError),
+}
+
+/// Thread-safe handle of the [`ErrorManager`] - can be cloned and sent to a worker thread or an
+/// async task, the errors are added to the error manager on the next frame
+#[derive(Clone)]
+pub struct ErrorSink {
+    /// Channel to the error manager
+    sender: Sender<SinkEntry>,
+    /// Context repainted when an error is sent
+    ctx: egui::Context,
+}
+
+impl fmt::Debug for ErrorSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorSink").finish_non_exhaustive()
+    }
+}
+
+impl ErrorSink {
+    /// Send an entry - returns `false` if the app is closed
+    fn send(&self, entry: SinkEntry) -> bool {
+        let sent = self.sender.send(entry).is_ok();
+        self.ctx.request_repaint();
+        sent
+    }
+
+    /// Add an error - see [`ErrorManager::add_error`]
+    pub fn add_error<E: Into<AppError>>(&self, error: E) -> bool {
+        self.send(SinkEntry::Error(error.into()))
+    }
+
+    /// Add a warning - see [`ErrorManager::add_warning`]
+    pub fn add_warning<E: Into<AppError>>(&self, warning: E) -> bool {
+        self.send(SinkEntry::Message(
+            warning.into().with_severity(Severity::Warning),
+        ))
+    }
+
+    /// Add an information - see [`ErrorManager::add_info`]
+    pub fn add_info<E: Into<AppError>>(&self, info: E) -> bool {
+        self.send(SinkEntry::Message(
+            info.into().with_severity(Severity::Info),
+        ))
+    }
+}
+
+/// Id of the sink in the egui context
+fn sink_id() -> egui::Id {
+    egui::Id::new("bladvak_error_sink")
+}
+
+/// Get a thread-safe handle of the error manager of the app
+#[must_use]
+pub fn error_sink(ctx: &egui::Context) -> Option<ErrorSink> {
+    ctx.data(|data| data.get_temp::<ErrorSink>(sink_id()))
+}
+
+/// Receiving side of the [`ErrorSink`]s
+pub(crate) struct ErrorReceiver(Receiver<SinkEntry>);
+
+impl fmt::Debug for ErrorReceiver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ErrorReceiver").finish_non_exhaustive()
+    }
+}
+
+impl ErrorReceiver {
+    /// Create the receiver and store its sink in the egui context
+    pub(crate) fn new(ctx: &egui::Context) -> Self {
+        let (sender, receiver) = channel();
+        let sink = ErrorSink {
+            sender,
+            ctx: ctx.clone(),
+        };
+        ctx.data_mut(|data| data.insert_temp(sink_id(), sink));
+        Self(receiver)
+    }
+
+    /// Add the errors sent since the last call to the error manager
+    pub(crate) fn drain_into(&self, error_manager: &mut ErrorManager) {
+        for entry in self.0.try_iter() {
+            match entry {
+                SinkEntry::Error(error) => error_manager.add_error(error),
+                SinkEntry::Message(message) => error_manager.add_message(message),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Errors forwarded from an embedded app
+
+    use super::{AppError, ErrorManager};
+    use std::{cell::Cell, rc::Rc};
+
+    /// A forwarded error is journaled once and reaches the callbacks of the host
+    #[test]
+    fn forward_once() {
+        let mut child = ErrorManager::default();
+        let mut host = ErrorManager::default();
+        let called = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&called);
+        host.on_error(move |_| counter.set(counter.get() + 1));
+        child.add_error(AppError::new("forwarded once".to_string()));
+        child.forward_to(&mut host, "child");
+        assert_eq!(called.get(), 1);
+        assert_eq!(host.errors.len(), 1);
+        assert_eq!(host.errors[0].message, "child: forwarded once");
+        let journaled = crate::journal::journal_entries()
+            .iter()
+            .filter(|entry| entry.message.contains("forwarded once"))
+            .count();
+        assert_eq!(journaled, 1);
     }
 }
@@ -0,0 +1,221 @@
+//! Event bus
+//!
+//! The wrapper publishes a few events ([`FileOpened`], [`ThemeChanged`], [`PanelToggled`]) and
+//! the app and its panels publish their own types with [`publish`]. Each frame, the events
+//! published since the last frame are dispatched to [`crate::BladvakApp::on_event`], then to
+//! [`crate::app::BladvakPanel::on_event`] of every panel, so that the panels react to the app
+//! without referencing each other. An event published while dispatching is delivered at the
+//! next frame.
+//!
+//! A panel can also send a message to one panel with [`Panels::send`] (see [`panels`]): it is
+//! delivered to [`crate::app::BladvakPanel::on_message`] of the panel at the next frame - e.g.
+//! a list panel sends the selected item to its detail panel.
+
+use eframe::egui::{self, ThemePreference};
+use serde::{Deserialize, Serialize};
+use std::{
+    any::{Any, type_name},
+    fmt::Debug,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use crate::app::{Bladvak, BladvakApp, PanelOpen};
+
+/// A file was opened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileOpened {
+    /// Path of the file
+    pub path: PathBuf,
+}
+
+/// The theme was changed in the settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThemeChanged {
+    /// New theme
+    pub theme: ThemePreference,
+}
+
+/// The state of a panel was changed by the user
+#[derive(Debug, Clone, PartialEq)]
+pub struct PanelToggled {
+    /// Panel name
+    pub panel: String,
+    /// New state
+    pub state: PanelOpen,
+}
+
+/// Published event - get its value with [`Event::get`]
+pub struct Event {
+    /// Value of the event
+    value: Box<dyn Any + Send>,
+    /// Name of the type of the value
+    type_name: &'static str,
+}
+
+impl Debug for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Event").field(&self.type_name).finish()
+    }
+}
+
+impl Event {
+    /// Create an event
+    pub fn new<T: Any + Send>(value: T) -> Self {
+        Self {
+            value: Box::new(value),
+            type_name: type_name::<T>(),
+        }
+    }
+
+    /// Check if the event is a `T`
+    #[must_use]
+    pub fn is<T: Any>(&self) -> bool {
+        self.value.is::<T>()
+    }
+
+    /// Get the event as a `T`
+    #[must_use]
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.value.downcast_ref::<T>()
+    }
+
+    /// Name of the type of the event
+    #[must_use]
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+/// Events waiting to be dispatched
+type EventQueue = Arc<Mutex<Vec<Event>>>;
+
+/// Messages waiting to be delivered and the names of their panels
+type MessageQueue = Arc<Mutex<Vec<(String, Event)>>>;
+
+/// Id of the queue in the egui context
+fn queue_id() -> egui::Id {
+    egui::Id::new("bladvak_event_bus")
+}
+
+/// Id of the message queue in the egui context
+fn messages_id() -> egui::Id {
+    egui::Id::new("bladvak_panel_messages")
+}
+
+/// Sender of the messages to the panels - can be cloned and kept
+#[derive(Clone)]
+pub struct Panels {
+    /// Messages waiting to be delivered
+    queue: MessageQueue,
+    /// Context repainted when a message is sent
+    ctx: egui::Context,
+}
+
+impl Debug for Panels {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Panels").finish_non_exhaustive()
+    }
+}
+
+impl Panels {
+    /// Send a message to a panel (see [`crate::app::BladvakPanel::name`]) - delivered at the
+    /// next frame
+    pub fn send<T: Any + Send>(&self, panel: &str, message: T) {
+        if let Ok(mut messages) = self.queue.lock() {
+            messages.push((panel.to_string(), Event::new(message)));
+        }
+        self.ctx.request_repaint();
+    }
+}
+
+/// Get the sender of the messages to the panels
+#[must_use]
+pub fn panels(ctx: &egui::Context) -> Option<Panels> {
+    let queue = ctx.data(|data| data.get_temp::<MessageQueue>(messages_id()))?;
+    Some(Panels {
+        queue,
+        ctx: ctx.clone(),
+    })
+}
+
+/// Publish an event - dispatched at the next frame
+pub fn publish<T: Any + Send>(ctx: &egui::Context, event: T) {
+    let queue = ctx.data(|data| data.get_temp::<EventQueue>(queue_id()));
+    match queue {
+        Some(queue) => {
+            if let Ok(mut events) = queue.lock() {
+                events.push(Event::new(event));
+            }
+            ctx.request_repaint();
+        }
+        None => log::warn!("No event bus to publish {}", type_name::<T>()),
+    }
+}
+
+/// Event bus of the app
+#[derive(Debug, Default)]
+pub(crate) struct EventBus {
+    /// Events waiting to be dispatched - shared with the egui context
+    queue: EventQueue,
+    /// Messages waiting to be delivered - shared with the egui context
+    messages: MessageQueue,
+}
+
+impl EventBus {
+    /// Create the bus and store its queue in the egui context
+    pub(crate) fn new(ctx: &egui::Context) -> Self {
+        let bus = Self::default();
+        ctx.data_mut(|data| {
+            data.insert_temp(queue_id(), bus.queue.clone());
+            data.insert_temp(messages_id(), bus.messages.clone());
+        });
+        bus
+    }
+
+    /// Publish an event - dispatched at the next frame
+    pub(crate) fn publish<T: Any + Send>(&self, event: T) {
+        if let Ok(mut events) = self.queue.lock() {
+            events.push(Event::new(event));
+        }
+    }
+
+    /// Events published since the last call
+    fn take(&self) -> Vec<Event> {
+        self.queue
+            .lock()
+            .map(|mut events| std::mem::take(&mut *events))
+            .unwrap_or_default()
+    }
+
+    /// Messages sent since the last call
+    fn take_messages(&self) -> Vec<(String, Event)> {
+        self.messages
+            .lock()
+            .map(|mut messages| std::mem::take(&mut *messages))
+            .unwrap_or_default()
+    }
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Dispatch the published events to the app and the panels, and deliver the messages to
+    /// their panels
+    pub(crate) fn process_events(&mut self) {
+        for (target, message) in self.events.take_messages() {
+            match self.panel_list.iter().find(|panel| panel.name() == target) {
+                Some(panel) => panel.on_message(&mut self.app, &message, &mut self.error_manager),
+                None => log::warn!("No panel {target} for the message {message:?}"),
+            }
+        }
+        for event in self.events.take() {
+            log::trace!("Dispatching {event:?}");
+            self.app.on_event(&event, &mut self.error_manager);
+            for panel in &self.panel_list {
+                panel.on_event(&mut self.app, &event, &mut self.error_manager);
+            }
+        }
+    }
+}
@@ -0,0 +1,397 @@
+//! Export and batch conversion
+//!
+//! The [`Exporter`]s of [`crate::BladvakApp::exporters`] are listed in File > Export: the
+//! exported data is saved with the save dialog, and the result is reported like the other saves.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    app::{Bladvak, BladvakApp},
+    dialog::PendingDialog,
+    errors::{AppError, ErrorManager},
+    file_handler::File,
+    limits::{LimitError, ParseLimits},
+    task::{FinishedTask, TaskId},
+};
+
+/// Conversion function of a [`Converter`]
+pub type ConvertFn = dyn Fn(&File) -> Result<Vec<u8>, AppError> + Send + Sync;
+
+/// Converter used by the batch export - converts an input file into the output bytes
+#[derive(Clone)]
+pub struct Converter {
+    /// Name of the converter
+    pub name: String,
+    /// Extension of the output files (without the dot)
+    pub extension: String,
+    /// Conversion function
+    pub convert: Arc<ConvertFn>,
+}
+
+impl Converter {
+    /// Create a new converter
+    pub fn new<S, E, F>(name: S, extension: E, convert: F) -> Self
+    where
+        S: Into<String>,
+        E: Into<String>,
+        F: Fn(&File) -> Result<Vec<u8>, AppError> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            extension: extension.into(),
+            convert: Arc::new(convert),
+        }
+    }
+}
+
+impl Debug for Converter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Converter")
+            .field("name", &self.name)
+            .field("extension", &self.extension)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Export of the app state to a file format (e.g. "PNG image")
+pub trait Exporter<App> {
+    /// Name shown in the Export menu
+    fn name(&self) -> String;
+
+    /// Extension of the exported file (without the dot)
+    fn extension(&self) -> String;
+
+    /// Suggested file name
+    fn file_name(&self) -> String {
+        format!("export.{}", self.extension())
+    }
+
+    /// Export the app state
+    /// # Errors
+    /// Error if the state cannot be exported
+    fn export(&self, app: &App) -> Result<Vec<u8>, AppError>;
+}
+
+/// Batch export state
+#[derive(Debug, Default)]
+pub(crate) struct BatchExport {
+    /// Is the batch export window open
+    pub(crate) open: bool,
+    /// Inputs from a folder - read by the conversion tasks
+    folder_inputs: Vec<PathBuf>,
+    /// Selected app inputs (index)
+    selected: BTreeSet<usize>,
+    /// Selected converter
+    converter: usize,
+    /// Output directory
+    output_dir: Option<PathBuf>,
+    /// Dialog choosing an input folder
+    folder_dialog: PendingDialog<Option<PathBuf>>,
+    /// Dialog choosing the output directory
+    #[cfg(not(target_arch = "wasm32"))]
+    output_dialog: PendingDialog<Option<PathBuf>>,
+    /// Running conversions
+    running: BTreeSet<TaskId>,
+    /// Successful conversions
+    successes: Vec<String>,
+    /// Failed conversions
+    failures: Vec<(String, AppError)>,
+}
+
+impl BatchExport {
+    /// Inputs selection ui
+    fn inputs_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        app_inputs: &[&File],
+        error_manager: &mut ErrorManager,
+    ) {
+        ui.heading("Inputs");
+        for (idx, input) in app_inputs.iter().enumerate() {
+            let mut checked = self.selected.contains(&idx);
+            if ui
+                .checkbox(&mut checked, input.path.display().to_string())
+                .changed()
+            {
+                if checked {
+                    self.selected.insert(idx);
+                } else {
+                    self.selected.remove(&idx);
+                }
+            }
+        }
+        for path in &self.folder_inputs {
+            ui.label(path.display().to_string());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if ui
+            .add_enabled(
+                !self.folder_dialog.is_pending(),
+                egui::Button::new("Add folder…"),
+            )
+            .clicked()
+        {
+            self.folder_dialog.start(crate::dialog::pick_folder);
+        }
+        if let Some(Some(folder)) = self.folder_dialog.poll() {
+            match crate::file_handler::list_files(&folder, false) {
+                Ok(paths) => self.folder_inputs.extend(paths),
+                Err(err) => error_manager.add_error(err),
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = error_manager;
+        if !self.folder_inputs.is_empty() && ui.button("Clear folder inputs").clicked() {
+            self.folder_inputs.clear();
+        }
+    }
+
+    /// Running conversions and summary ui
+    fn summary_ui(&self, ui: &mut egui::Ui) {
+        if !self.running.is_empty() {
+            ui.horizontal(|ui| {
+                crate::a11y::busy_indicator(ui);
+                ui.label(format!("{} conversion(s) running", self.running.len()));
+            });
+        }
+        if !self.successes.is_empty() || !self.failures.is_empty() {
+            ui.separator();
+            ui.heading("Summary");
+            ui.label(format!(
+                "{} succeeded, {} failed",
+                self.successes.len(),
+                self.failures.len()
+            ));
+            for (name, err) in &self.failures {
+                ui.colored_label(ui.visuals().error_fg_color, format!("{name}: {err}"));
+            }
+        }
+    }
+
+    /// Check if the task belongs to the batch export
+    pub(crate) fn owns(&self, id: TaskId) -> bool {
+        self.running.contains(&id)
+    }
+
+    /// Record a finished conversion
+    pub(crate) fn record(&mut self, finished: FinishedTask) {
+        self.running.remove(&finished.id);
+        match finished.result {
+            Ok(message) => self.successes.push(message),
+            Err(err) => self.failures.push((finished.name, err)),
+        }
+    }
+}
+
+/// Input of the batch export
+#[derive(Debug, Clone)]
+enum BatchInput {
+    /// File given by the app
+    Loaded(File),
+    /// File of a folder - read by the conversion task
+    Path(PathBuf),
+}
+
+impl BatchInput {
+    /// Path of the input
+    fn path(&self) -> &Path {
+        match self {
+            Self::Loaded(file) => &file.path,
+            Self::Path(path) => path,
+        }
+    }
+
+    /// Get the file, reading it within the limits if needed
+    fn into_file(self, limits: &ParseLimits) -> Result<File, AppError> {
+        match self {
+            Self::Loaded(file) => Ok(file),
+            Self::Path(path) => read_file(&path, limits),
+        }
+    }
+}
+
+/// Read a file failing if it is bigger than the maximum size of the limits
+/// # Errors
+/// Fails if the file cannot be read or is too big
+pub fn read_file(path: &Path, limits: &ParseLimits) -> Result<File, AppError> {
+    use std::io::Read;
+
+    let file = std::fs::File::open(path)?;
+    let metadata = file.metadata()?;
+    if metadata.len() > limits.max_size {
+        return Err(LimitError::SizeExceeded {
+            limit: limits.max_size,
+        }
+        .into());
+    }
+    // the file may grow while it is read
+    let mut data = Vec::new();
+    limits.reader(file).read_to_end(&mut data)?;
+    let file = File::new(data, path);
+    #[cfg(not(target_arch = "wasm32"))]
+    let file = file.with_metadata(&metadata);
+    Ok(file)
+}
+
+/// Output path of a converted file
+fn output_path(output_dir: Option<&Path>, input: &Path, extension: &str) -> PathBuf {
+    let stem = input
+        .file_stem()
+        .map_or_else(|| "file".into(), |stem| stem.to_string_lossy());
+    let filename = format!("{stem}.{extension}");
+    match output_dir {
+        Some(dir) => dir.join(filename),
+        None => PathBuf::from(filename),
+    }
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Export the app state and ask where to save it
+    fn run_export(&self, ctx: &egui::Context, exporter: &dyn Exporter<M>) {
+        trace_span!("export", name = %exporter.name());
+        match exporter.export(&self.app) {
+            Ok(data) => {
+                crate::dialog::save_file_dialog(ctx, data, Path::new(&exporter.file_name()));
+            }
+            Err(mut err) => {
+                err.message = format!("Cannot export {}: {}", exporter.name(), err.message);
+                crate::dialog::save_failed(ctx, err);
+            }
+        }
+    }
+
+    /// Show the Export menu - hidden without exporter
+    pub(crate) fn menu_export(&mut self, ui: &mut egui::Ui) {
+        let exporters = self.app.exporters();
+        if exporters.is_empty() {
+            return;
+        }
+        ui.menu_button("Export", |ui| {
+            for exporter in &exporters {
+                let label = format!("{} (.{})…", exporter.name(), exporter.extension());
+                if ui.button(label).clicked() {
+                    ui.close();
+                    self.run_export(ui.ctx(), exporter.as_ref());
+                }
+            }
+        });
+    }
+
+    /// Start the conversions of the selected inputs
+    fn run_batch_export(&mut self, inputs: Vec<BatchInput>, converter: &Converter) {
+        crate::journal::record(
+            crate::journal::JournalKind::Export,
+            format!(
+                "Batch export of {} file(s) with {}",
+                inputs.len(),
+                converter.name
+            ),
+        );
+        let batch = &mut self.batch_export;
+        batch.successes.clear();
+        batch.failures.clear();
+        for input in inputs {
+            let convert = Arc::clone(&converter.convert);
+            let path = output_path(
+                batch.output_dir.as_deref(),
+                input.path(),
+                &converter.extension,
+            );
+            let name = input.path().display().to_string();
+            let id = self.tasks.spawn(name, move || {
+                let input = input.into_file(&ParseLimits::default())?;
+                let data = convert(&input)?;
+                crate::utils::save_file(&data, &path)?;
+                Ok(path.display().to_string())
+            });
+            batch.running.insert(id);
+        }
+    }
+
+    /// Show the batch export window
+    pub(crate) fn show_batch_export(&mut self, ctx: &egui::Context) {
+        if !self.batch_export.open {
+            return;
+        }
+        let converters = self.app.converters();
+        let app_inputs = self.app.batch_inputs();
+        let mut open = true;
+        let mut to_run = None;
+        egui::Window::new("Batch export")
+            .id("bladvak_batch_export_window".into())
+            .open(&mut open)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                let batch = &mut self.batch_export;
+                batch.inputs_ui(ui, &app_inputs, &mut self.error_manager);
+                ui.separator();
+                ui.heading("Output");
+                egui::ComboBox::from_label("Converter")
+                    .selected_text(
+                        converters
+                            .get(batch.converter)
+                            .map_or("", |converter| converter.name.as_str()),
+                    )
+                    .show_ui(ui, |ui| {
+                        for (idx, converter) in converters.iter().enumerate() {
+                            ui.selectable_value(&mut batch.converter, idx, &converter.name);
+                        }
+                    });
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.horizontal(|ui| {
+                    let label = batch
+                        .output_dir
+                        .as_ref()
+                        .map_or("No output directory".to_string(), |dir| {
+                            dir.display().to_string()
+                        });
+                    ui.label(label);
+                    if ui
+                        .add_enabled(
+                            !batch.output_dialog.is_pending(),
+                            egui::Button::new("Choose…"),
+                        )
+                        .clicked()
+                    {
+                        batch.output_dialog.start(crate::dialog::pick_folder);
+                    }
+                    if let Some(Some(folder)) = batch.output_dialog.poll() {
+                        batch.output_dir = Some(folder);
+                    }
+                });
+                ui.separator();
+                let has_inputs = batch.selected.iter().any(|idx| *idx < app_inputs.len())
+                    || !batch.folder_inputs.is_empty();
+                let can_run = has_inputs
+                    && batch.running.is_empty()
+                    && (cfg!(target_arch = "wasm32") || batch.output_dir.is_some());
+                if ui.add_enabled(can_run, egui::Button::new("Run")).clicked()
+                    && let Some(converter) = converters.get(batch.converter)
+                {
+                    let inputs = app_inputs
+                        .iter()
+                        .enumerate()
+                        .filter(|(idx, _)| batch.selected.contains(idx))
+                        .map(|(_, input)| BatchInput::Loaded((*input).clone()))
+                        .chain(batch.folder_inputs.iter().cloned().map(BatchInput::Path))
+                        .collect::<Vec<_>>();
+                    to_run = Some((inputs, converter.clone()));
+                }
+                batch.summary_ui(ui);
+            });
+        if let Some((inputs, converter)) = to_run {
+            self.run_batch_export(inputs, &converter);
+        }
+        self.batch_export.open = open;
+    }
+}
@@ -0,0 +1,163 @@
+//! Feedback dialog - "Help > Send feedback…"
+//!
+//! The user types a title and a description; the new issue page of
+//! [`crate::BladvakApp::issue_url`] is opened with the description, the versions, the target
+//! and optionally the recent errors. The body is shortened to keep the URL under the length
+//! accepted by GitHub.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{Bladvak, BladvakApp},
+    utils::{self, percent_encode},
+};
+
+/// Maximum length of the issue URL
+pub const MAX_ISSUE_URL_LENGTH: usize = 8000;
+
+/// Number of recent errors added to the feedback
+const RECENT_ERRORS: usize = 5;
+
+/// Marker added to a shortened body
+const TRUNCATED: &str = "\n\n[truncated]";
+
+/// Cut a text to at most `len` bytes, at a char boundary
+fn cut(text: &str, len: usize) -> &str {
+    let mut end = len.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// URL of a new issue with a title and a body - the body, then the title, are shortened if the
+/// URL is too long
+///
+/// ```
+/// use bladvak::feedback::{MAX_ISSUE_URL_LENGTH, issue_link};
+///
+/// let url = "https://github.com/Its-Just-Nans/bladvak/issues/new";
+/// let long = "é".repeat(MAX_ISSUE_URL_LENGTH);
+/// assert!(issue_link(url, "Title", &long).len() <= MAX_ISSUE_URL_LENGTH);
+/// assert!(issue_link(url, &long, "Body").len() <= MAX_ISSUE_URL_LENGTH);
+/// ```
+#[must_use]
+pub fn issue_link(issue_url: &str, title: &str, body: &str) -> String {
+    // each encoded byte takes at most 3 characters
+    let fixed = format!("{issue_url}?title=&body=").len() + percent_encode(TRUNCATED).len();
+    let title_space = MAX_ISSUE_URL_LENGTH.saturating_sub(fixed);
+    let title = if percent_encode(title).len() > title_space {
+        cut(title, title_space / 3)
+    } else {
+        title
+    };
+    let link = |body: &str| {
+        format!(
+            "{issue_url}?title={}&body={}",
+            percent_encode(title),
+            percent_encode(body)
+        )
+    };
+    let full = link(body);
+    if full.len() <= MAX_ISSUE_URL_LENGTH {
+        return full;
+    }
+    let base = link("").len() + percent_encode(TRUNCATED).len();
+    let body = cut(body, MAX_ISSUE_URL_LENGTH.saturating_sub(base) / 3);
+    link(&format!("{body}{TRUNCATED}"))
+}
+
+/// State of the feedback dialog
+#[derive(Debug, Default)]
+pub(crate) struct Feedback {
+    /// Is the dialog open
+    pub(crate) open: bool,
+    /// Issue title
+    title: String,
+    /// Description typed by the user
+    description: String,
+    /// Add the recent errors to the body
+    include_errors: bool,
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Body of the feedback issue
+    fn feedback_body(&self) -> String {
+        let mut body = format!(
+            "{}\n\n---\n{}@{} (bladvak@{}, {})\n",
+            self.feedback.description.trim(),
+            M::name(),
+            M::version(),
+            env!("CARGO_PKG_VERSION"),
+            utils::target_info()
+        );
+        if self.feedback.include_errors {
+            let errors = &self.error_manager.errors;
+            if !errors.is_empty() {
+                body.push_str("\nRecent errors:\n");
+            }
+            for error in errors.iter().rev().take(RECENT_ERRORS) {
+                body.push_str(&error.report());
+                body.push('\n');
+            }
+        }
+        body
+    }
+
+    /// Show the feedback dialog
+    pub(crate) fn show_feedback(&mut self, ctx: &egui::Context) {
+        if !self.feedback.open {
+            return;
+        }
+        let Some(issue_url) = M::issue_url() else {
+            return;
+        };
+        let mut send = false;
+        let mut open = true;
+        egui::Window::new("Send feedback")
+            .id("bladvak_feedback".into())
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                crate::a11y::annotate(ui, egui::accesskit::Role::Dialog, "Send feedback");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.feedback.title)
+                        .hint_text("Title")
+                        .desired_width(f32::INFINITY),
+                );
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.feedback.description)
+                        .hint_text("Describe the problem or the idea")
+                        .desired_rows(8)
+                        .desired_width(f32::INFINITY),
+                );
+                let has_errors = !self.error_manager.errors.is_empty();
+                ui.add_enabled(
+                    has_errors,
+                    egui::Checkbox::new(&mut self.feedback.include_errors, "Add the recent errors"),
+                );
+                ui.label(format!(
+                    "The version of {} and the platform are added.",
+                    M::name()
+                ));
+                let ready = !self.feedback.description.trim().is_empty();
+                if ui
+                    .add_enabled(ready, egui::Button::new("Open the issue page"))
+                    .clicked()
+                {
+                    send = true;
+                }
+            });
+        if send {
+            let link = issue_link(&issue_url, &self.feedback.title, &self.feedback_body());
+            crate::widgets::open_link(ctx, &link);
+            self.feedback = Feedback::default();
+        } else {
+            self.feedback.open = open;
+        }
+    }
+}
@@ -2,9 +2,71 @@
 
 use eframe::egui;
 use poll_promise::Promise;
-use std::{fmt::Debug, fs::read, path::PathBuf};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
-use crate::errors::AppError;
+#[cfg(target_arch = "wasm32")]
+use crate::utils::time::{Duration, UNIX_EPOCH};
+use crate::{
+    app::{Bladvak, BladvakApp},
+    errors::AppError,
+    file_kind::FileKind,
+    file_registry,
+    journal::{self, JournalKind},
+    progress::ProgressHandle,
+    utils::time::SystemTime,
+};
+
+/// Origin of a file
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum FileOrigin {
+    /// Picked with the file dialog
+    #[default]
+    Dialog,
+    /// Dropped from the local file system
+    LocalDrop,
+    /// Dropped from the browser
+    BrowserDrop,
+    /// Pasted from the clipboard
+    Clipboard,
+    /// Downloaded from an URL
+    Url(String),
+    /// Sent by another Bladvak app
+    Handoff(String),
+}
+
+impl FileOrigin {
+    /// Check if the file comes from an external source
+    #[must_use]
+    pub fn is_external(&self) -> bool {
+        matches!(
+            self,
+            Self::BrowserDrop | Self::Clipboard | Self::Url(_) | Self::Handoff(_)
+        )
+    }
+
+    /// Key used to remember the trust decision of this origin
+    #[must_use]
+    pub fn key(&self) -> String {
+        match self {
+            Self::Dialog => "dialog".to_string(),
+            Self::LocalDrop => "local-drop".to_string(),
+            Self::BrowserDrop => "browser-drop".to_string(),
+            Self::Clipboard => "clipboard".to_string(),
+            Self::Url(url) => {
+                let without_scheme = url.split_once("://").map_or(url.as_str(), |(_, rest)| rest);
+                let host = without_scheme.split('/').next().unwrap_or(without_scheme);
+                format!("url:{host}")
+            }
+            Self::Handoff(app) => format!("handoff:{app}"),
+        }
+    }
+}
 
 /// File object
 #[derive(Default, Clone)]
@@ -13,11 +75,390 @@ pub struct File {
     pub data: Vec<u8>,
     /// Path or filename
     pub path: PathBuf,
+    /// Origin of the file
+    pub origin: FileOrigin,
+    /// Content type - detected again before the file is given to the app
+    pub kind: FileKind,
+    /// MIME type given by the source (e.g. the browser) - see [`File::content_type`]
+    pub mime: Option<String>,
+    /// Size in bytes given by the source (e.g. the file system) - see [`File::len`]
+    pub size: Option<u64>,
+    /// Last modification time given by the source
+    pub modified: Option<SystemTime>,
+}
+
+impl Debug for File {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("File")
+            .field("data", &self.data.len())
+            .field("path", &self.path)
+            .field("origin", &self.origin)
+            .field("kind", &self.kind)
+            .field("mime", &self.mime)
+            .field("size", &self.size)
+            .field("modified", &self.modified)
+            .finish()
+    }
+}
+
+impl File {
+    /// Create a new file
+    #[must_use]
+    pub fn new<P: Into<PathBuf>>(data: Vec<u8>, path: P) -> Self {
+        let path = path.into();
+        Self {
+            kind: FileKind::detect(&data, &path),
+            data,
+            path,
+            origin: FileOrigin::default(),
+            mime: None,
+            size: None,
+            modified: None,
+        }
+    }
+
+    /// Read a file and its metadata
+    /// # Errors
+    /// Error if the file cannot be read
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_path<P: Into<PathBuf>>(path: P) -> Result<Self, AppError> {
+        let path = path.into();
+        let metadata = std::fs::metadata(&path)?;
+        Ok(Self::new(std::fs::read(&path)?, path).with_metadata(&metadata))
+    }
+
+    /// Size in bytes - the size given by the source, the length of the data otherwise
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.size.unwrap_or(self.data.len() as u64)
+    }
+
+    /// Check if the file is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// MIME type - the type given by the source, the type of the detected kind otherwise
+    #[must_use]
+    pub fn content_type(&self) -> &str {
+        self.mime.as_deref().unwrap_or(self.kind.mime())
+    }
+
+    /// Check the kind of the file
+    /// # Errors
+    /// Error if the kind is not in the list - the same error as for
+    /// [`crate::BladvakApp::accepted_kinds`]
+    pub fn require_kind(&self, kinds: &[FileKind]) -> Result<(), AppError> {
+        if kinds.contains(&self.kind) {
+            Ok(())
+        } else {
+            Err(FileKind::unsupported(self))
+        }
+    }
+
+    /// Set the origin of the file
+    #[must_use]
+    pub fn with_origin(mut self, origin: FileOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Set the MIME type - ignored if empty
+    #[must_use]
+    pub fn with_mime<S: Into<String>>(mut self, mime: S) -> Self {
+        self.mime = Some(mime.into()).filter(|mime| !mime.is_empty());
+        self
+    }
+
+    /// Set the size in bytes
+    #[must_use]
+    pub fn with_size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Set the last modification time
+    #[must_use]
+    pub fn with_modified(mut self, modified: SystemTime) -> Self {
+        self.modified = Some(modified);
+        self
+    }
+
+    /// Set the size and the last modification time from the file system
+    #[cfg(not(target_arch = "wasm32"))]
+    #[must_use]
+    pub fn with_metadata(mut self, metadata: &std::fs::Metadata) -> Self {
+        self.size = Some(metadata.len());
+        self.modified = metadata.modified().ok();
+        self
+    }
+
+    /// Set the MIME type, the size and the last modification time from a browser file
+    #[cfg(target_arch = "wasm32")]
+    #[must_use]
+    pub fn with_web_file(self, file: &eframe::web_sys::File) -> Self {
+        let modified = UNIX_EPOCH + Duration::from_secs_f64(file.last_modified() / 1000.0);
+        // sizes are integers stored in a f64
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let size = file.size() as u64;
+        self.with_mime(file.type_())
+            .with_size(size)
+            .with_modified(modified)
+    }
+}
+
+/// Queue of files submitted by the app
+#[derive(Clone, Default)]
+struct FileQueue(Arc<Mutex<Vec<File>>>);
+
+impl FileQueue {
+    /// Id of the queue in the egui context
+    fn id() -> egui::Id {
+        egui::Id::new("bladvak_file_queue")
+    }
+
+    /// Get the queue from the egui context
+    fn get(ctx: &egui::Context) -> Self {
+        ctx.data_mut(|data| data.get_temp_mut_or_default::<Self>(Self::id()).clone())
+    }
+}
+
+/// List the files of a folder and of its sub-folders, sorted by path
+/// # Errors
+/// Fails if a folder cannot be read
+pub fn list_files_recursive(folder: &Path) -> Result<Vec<PathBuf>, AppError> {
+    list_files(folder, true)
+}
+
+/// List the files of a folder, and of its sub-folders if `recursive`, sorted by path
+///
+/// The symbolic links to folders are not followed and the unreadable sub-folders are skipped
+/// # Errors
+/// Fails if the folder cannot be read
+pub fn list_files(folder: &Path, recursive: bool) -> Result<Vec<PathBuf>, AppError> {
+    let mut files = Vec::new();
+    let mut folders = Vec::new();
+    list_folder(folder, std::fs::read_dir(folder)?, &mut files, &mut folders);
+    while recursive && let Some(folder) = folders.pop() {
+        match std::fs::read_dir(&folder) {
+            Ok(entries) => list_folder(&folder, entries, &mut files, &mut folders),
+            Err(err) => log::warn!("Skipping the folder {}: {err}", folder.display()),
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Sort the entries of a folder into its files and its sub-folders
+fn list_folder(
+    folder: &Path,
+    entries: std::fs::ReadDir,
+    files: &mut Vec<PathBuf>,
+    folders: &mut Vec<PathBuf>,
+) {
+    for entry in entries {
+        let (path, file_type) = match entry.and_then(|entry| Ok((entry.path(), entry.file_type()?)))
+        {
+            Ok(entry) => entry,
+            Err(err) => {
+                log::warn!("Skipping an entry of {}: {err}", folder.display());
+                continue;
+            }
+        };
+        if file_type.is_dir() {
+            folders.push(path);
+        } else if file_type.is_file() || (file_type.is_symlink() && path.is_file()) {
+            // a link to a folder is skipped - it may point to a parent folder
+            files.push(path);
+        }
+    }
+}
+
+/// Files opened from a dropped folder
+#[derive(Debug, Clone, Default)]
+pub struct FolderDrop {
+    /// Open the files of the sub-folders
+    pub recursive: bool,
+    /// Extensions of the opened files - every file if `None`
+    pub extensions: Option<Vec<String>>,
+}
+
+impl FolderDrop {
+    /// Open the files matching the extensions of the formats - every file if there is no
+    /// format or if a format is only detected by its magic bytes
+    #[must_use]
+    pub fn from_formats(recursive: bool, formats: &[FileFormat]) -> Self {
+        let extensions = (!formats.is_empty()
+            && formats.iter().all(|format| !format.extensions.is_empty()))
+        .then(|| {
+            formats
+                .iter()
+                .flat_map(|format| format.extensions.iter().cloned())
+                .collect()
+        });
+        Self {
+            recursive,
+            extensions,
+        }
+    }
+
+    /// Check if a file of the folder is opened
+    #[must_use]
+    pub fn accepts(&self, path: &Path) -> bool {
+        let Some(extensions) = &self.extensions else {
+            return true;
+        };
+        path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .is_some_and(|ext| extensions.contains(&ext))
+    }
+}
+
+/// Overlay shown while files are dragged over the window
+#[derive(Debug, Clone)]
+pub struct DropHint {
+    /// Text of the overlay
+    pub text: String,
+    /// The files would be opened - shown in the error color otherwise
+    pub accept: bool,
+}
+
+impl DropHint {
+    /// Overlay of files which would be opened
+    #[must_use]
+    pub fn accept<S: Into<String>>(text: S) -> Self {
+        Self {
+            text: text.into(),
+            accept: true,
+        }
+    }
+
+    /// Overlay of files which would be rejected
+    #[must_use]
+    pub fn reject<S: Into<String>>(text: S) -> Self {
+        Self {
+            text: text.into(),
+            accept: false,
+        }
+    }
+
+    /// Default overlay - "Drop file to open"
+    #[must_use]
+    pub fn for_files(hovered: &[egui::HoveredFile]) -> Self {
+        match hovered.len() {
+            0 | 1 => Self::accept("Drop file to open"),
+            count => Self::accept(format!("Drop {count} files to open")),
+        }
+    }
+}
+
+/// Submit a file to the file pipeline (e.g. a file from the clipboard or an URL)
+///
+/// The file is handled at the next frame like an opened or dropped file
+pub fn submit_file(ctx: &egui::Context, file: File) {
+    if let Ok(mut queue) = FileQueue::get(ctx).0.lock() {
+        queue.push(file);
+    }
+    ctx.request_repaint();
+}
+
+/// File format handled by the app
+#[derive(Debug, Clone, Default)]
+pub struct FileFormat {
+    /// Name of the format
+    pub name: String,
+    /// Extensions of the format (without the dot)
+    pub extensions: Vec<String>,
+    /// Magic bytes at the start of the file
+    pub magic: Vec<Vec<u8>>,
+}
+
+impl FileFormat {
+    /// Create a new file format
+    #[must_use]
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Add an extension (without the dot)
+    #[must_use]
+    pub fn with_extension<S: Into<String>>(mut self, extension: S) -> Self {
+        self.extensions.push(extension.into().to_lowercase());
+        self
+    }
+
+    /// Add magic bytes
+    #[must_use]
+    pub fn with_magic<B: Into<Vec<u8>>>(mut self, magic: B) -> Self {
+        self.magic.push(magic.into());
+        self
+    }
+
+    /// Check if the file starts with one of the magic bytes
+    #[must_use]
+    pub fn matches_magic(&self, file: &File) -> bool {
+        self.magic
+            .iter()
+            .any(|magic| !magic.is_empty() && file.data.starts_with(magic))
+    }
+
+    /// Check if the file has one of the extensions
+    #[must_use]
+    pub fn matches_extension(&self, file: &File) -> bool {
+        file.path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .is_some_and(|ext| self.extensions.contains(&ext))
+    }
+}
+
+/// Detect the formats matching a file - magic bytes first, then extensions
+#[must_use]
+pub fn detect_formats<'f>(formats: &'f [FileFormat], file: &File) -> Vec<&'f FileFormat> {
+    let by_magic = formats
+        .iter()
+        .filter(|format| format.matches_magic(file))
+        .collect::<Vec<_>>();
+    if !by_magic.is_empty() {
+        return by_magic;
+    }
+    formats
+        .iter()
+        .filter(|format| format.matches_extension(file))
+        .collect()
+}
+
+/// File from an external source waiting for the user to trust it
+#[derive(Debug)]
+pub(crate) struct PendingTrust {
+    /// The file
+    pub(crate) file: File,
+    /// Format forced for the file
+    pub(crate) forced_format: Option<String>,
+    /// Remember the decision for the origin
+    pub(crate) remember: bool,
+}
+
+/// File waiting for the user to choose a format
+#[derive(Debug)]
+pub(crate) struct PendingOpenAs {
+    /// The file
+    pub(crate) file: File,
+    /// Candidate formats
+    pub(crate) formats: Vec<FileFormat>,
 }
 
 /// File Handler
-#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[derive(serde::Deserialize, serde::Serialize)]
 pub struct FileHandler {
+    /// File picker
+    #[serde(skip, default = "default_picker")]
+    picker: Box<dyn FilePicker>,
+
     /// Dropped files handler
     #[serde(skip)]
     pub dropped_files: Vec<egui::DroppedFile>,
@@ -25,6 +466,31 @@ pub struct FileHandler {
     /// File upload handling
     #[serde(skip)]
     pub file_upload: Option<Promise<Result<FileState, AppError>>>,
+
+    /// Format requested with "Open As…" for the file being selected
+    #[serde(skip)]
+    pub(crate) open_as: Option<String>,
+
+    /// Format forced for the last returned file
+    #[serde(skip)]
+    pub(crate) forced_format: Option<String>,
+
+    /// Files opened from a dropped folder
+    #[serde(skip)]
+    folder_drop: FolderDrop,
+
+    /// Progress of the files of the dropped folders, with their number
+    #[serde(skip)]
+    folder_progress: Option<(ProgressHandle, usize)>,
+
+    /// Path and size of the last opened file - shown by the wrapper
+    #[serde(skip)]
+    pub(crate) last_opened: Option<(PathBuf, usize)>,
+
+    /// Recent files being read again, with their path
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    pub(crate) recent_reads: Vec<(PathBuf, crate::task::TaskHandle<Result<(), AppError>>)>,
 }
 
 /// File state
@@ -40,6 +506,26 @@ pub enum FileState {
     Ready(File),
 }
 
+impl Default for FileHandler {
+    fn default() -> Self {
+        Self {
+            picker: default_picker(),
+            dropped_files: Vec::new(),
+            file_upload: None,
+            open_as: None,
+            forced_format: None,
+            folder_drop: FolderDrop {
+                recursive: true,
+                extensions: None,
+            },
+            folder_progress: None,
+            last_opened: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            recent_reads: Vec::new(),
+        }
+    }
+}
+
 impl Debug for FileHandler {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut debug_fmt = f.debug_struct("FileHandler");
@@ -48,55 +534,141 @@ impl Debug for FileHandler {
             let val = String::new();
             debug_fmt.field("file_upload", &val);
         }
-        debug_fmt.finish()
+        debug_fmt.field("open_as", &self.open_as);
+        debug_fmt.field("forced_format", &self.forced_format);
+        debug_fmt.field("folder_drop", &self.folder_drop);
+        debug_fmt.finish_non_exhaustive()
     }
 }
 
-impl FileHandler {
-    /// Handle the file
+/// Dialog side of the file handler - can be replaced (e.g. in tests or headless builds)
+pub trait FilePicker {
+    /// Ask the user to pick a file
+    fn pick_file(&self) -> Promise<Result<FileState, AppError>>;
+}
+
+/// File picker using the system dialogs (`rfd`)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RfdPicker;
+
+impl FilePicker for RfdPicker {
     #[cfg(target_arch = "wasm32")]
-    pub fn handle_file_open(&mut self) {
-        self.file_upload = Some(Promise::spawn_local(async {
+    fn pick_file(&self) -> Promise<Result<FileState, AppError>> {
+        Promise::spawn_local(async {
             log::info!("rfd start");
             let file_selected = rfd::AsyncFileDialog::new().pick_file().await;
             log::info!("rfd result {:?}", file_selected);
             if let Some(curr_file) = file_selected {
                 let buf = curr_file.read().await;
-                return Ok(FileState::Ready(File {
-                    data: buf,
-                    path: PathBuf::from(curr_file.file_name()),
-                }));
+                return Ok(FileState::Ready(
+                    File::new(buf, curr_file.file_name()).with_web_file(curr_file.inner()),
+                ));
             }
             // no file selected
             Ok(FileState::NotSelected)
-        }));
+        })
     }
 
-    /// Handle the file
     #[cfg(not(target_arch = "wasm32"))]
-    pub fn handle_file_open(&mut self) {
-        self.file_upload = Some(Promise::spawn_thread("slow", move || {
-            if let Some(path_buf) = rfd::FileDialog::new().pick_file() {
-                // read file as string
-                if let Some(path) = path_buf.to_str() {
-                    let buf = std::fs::read(path);
-                    let buf = match buf {
-                        Ok(v) => v,
-                        Err(e) => {
-                            log::warn!("{e:?}");
-                            return Err(AppError::new(e.to_string()));
-                        }
-                    };
-                    return Ok(FileState::Ready(File {
-                        data: buf,
-                        path: path_buf,
-                    }));
-                }
-                return Err(AppError::new("Invalid file path".to_string()));
+    fn pick_file(&self) -> Promise<Result<FileState, AppError>> {
+        crate::dialog::spawn_dialog(async {
+            if let Some(handle) = rfd::AsyncFileDialog::new().pick_file().await {
+                return match File::from_path(handle.path()) {
+                    Ok(file) => Ok(FileState::Ready(file)),
+                    Err(e) => {
+                        log::warn!("{e:?}");
+                        Err(e)
+                    }
+                };
             }
             // no file selected
             Ok(FileState::NotSelected)
-        }));
+        })
+    }
+}
+
+/// File picker returning canned files - the dialog is cancelled when there are no more files
+#[derive(Debug, Default)]
+pub struct CannedPicker {
+    /// Files returned by the next picks
+    files: Mutex<VecDeque<File>>,
+}
+
+impl CannedPicker {
+    /// Create a picker returning these files, one per pick
+    #[must_use]
+    pub fn new<I: IntoIterator<Item = File>>(files: I) -> Self {
+        Self {
+            files: Mutex::new(files.into_iter().collect()),
+        }
+    }
+
+    /// Add a file returned by a next pick
+    pub fn push(&self, file: File) {
+        if let Ok(mut files) = self.files.lock() {
+            files.push_back(file);
+        }
+    }
+}
+
+impl FilePicker for CannedPicker {
+    fn pick_file(&self) -> Promise<Result<FileState, AppError>> {
+        let file = self
+            .files
+            .lock()
+            .ok()
+            .and_then(|mut files| files.pop_front());
+        Promise::from_ready(Ok(file.map_or(FileState::NotSelected, FileState::Ready)))
+    }
+}
+
+/// Default file picker
+fn default_picker() -> Box<dyn FilePicker> {
+    Box::new(RfdPicker)
+}
+
+impl FileHandler {
+    /// Handle the file
+    pub fn handle_file_open(&mut self) {
+        if self
+            .file_upload
+            .as_ref()
+            .is_some_and(|upload| upload.ready().is_none())
+        {
+            log::info!("The file dialog is already open");
+            return;
+        }
+        self.file_upload = Some(self.picker.pick_file());
+    }
+
+    /// Replace the file picker
+    pub fn set_picker(&mut self, picker: Box<dyn FilePicker>) {
+        self.picker = picker;
+    }
+
+    /// Set the files opened from a dropped folder
+    pub fn set_folder_drop(&mut self, folder_drop: FolderDrop) {
+        self.folder_drop = folder_drop;
+    }
+
+    /// File handler with the picker and the formats of the app
+    pub(crate) fn for_app<M: for<'a> BladvakApp<'a>>(
+        app: &M,
+        file_handlers: &file_registry::FileHandlers<M>,
+    ) -> Self {
+        let mut file_handler = Self::default();
+        file_handler.set_picker(app.file_picker());
+        file_handler.set_folder_drop(FolderDrop::from_formats(
+            app.recursive_folder_drop(),
+            &file_registry::app_formats(app, file_handlers),
+        ));
+        file_handler
+    }
+
+    /// Open a file and force the format used to handle it
+    pub fn handle_file_open_as<S: Into<String>>(&mut self, format_name: S) {
+        self.handle_file_open();
+        self.open_as = Some(format_name.into());
     }
 
     /// Reset the `file_handler`
@@ -104,27 +676,86 @@ impl FileHandler {
         self.file_upload = None;
     }
 
+    /// Queue the files of a dropped folder - they are handled one by one like dropped files
+    #[cfg(not(target_arch = "wasm32"))]
+    fn expand_folder(&mut self, ctx: &egui::Context, folder: &Path) -> Result<(), AppError> {
+        let files: Vec<_> = list_files(folder, self.folder_drop.recursive)?
+            .into_iter()
+            .filter(|path| self.folder_drop.accepts(path))
+            .collect();
+        log::info!("Opening {} files of {}", files.len(), folder.display());
+        if files.is_empty() {
+            return Ok(());
+        }
+        let total = files.len() + self.dropped_files.len();
+        self.dropped_files.splice(
+            0..0,
+            files.into_iter().map(|path| egui::DroppedFile {
+                path: Some(path),
+                ..Default::default()
+            }),
+        );
+        let label = format!("Opening {}", folder.display());
+        match &mut self.folder_progress {
+            Some((progress, count)) => {
+                progress.set_label(label);
+                *count = total;
+            }
+            None => self.folder_progress = Some((crate::progress::progress(ctx, label), total)),
+        }
+        Ok(())
+    }
+
+    /// Update the progress of the files of the dropped folders
+    fn update_folder_progress(&mut self) {
+        let Some((progress, total)) = &self.folder_progress else {
+            return;
+        };
+        if self.dropped_files.is_empty() {
+            progress.finish();
+            self.folder_progress = None;
+        } else {
+            #[allow(clippy::cast_precision_loss)]
+            let fraction = (total - self.dropped_files.len()) as f32 / *total as f32;
+            progress.set_fraction(fraction);
+        }
+    }
+
     /// Handle file dropped
-    fn handle_file_dropped(&mut self) -> Result<Option<File>, AppError> {
+    fn handle_file_dropped(&mut self, ctx: &egui::Context) -> Result<Option<File>, AppError> {
         if self.dropped_files.is_empty() {
             return Ok(None);
         }
         let file = self.dropped_files.remove(0);
-        if cfg!(not(target_arch = "wasm32")) {
-            if let Some(path) = file.path.as_deref() {
-                let file = read(path)?;
-                return Ok(Some(File {
-                    data: file,
-                    path: path.to_path_buf(),
-                }));
-            }
-        } else if cfg!(target_arch = "wasm32")
-            && let Some(bytes) = file.bytes.as_deref()
-        {
-            return Ok(Some(File {
-                data: bytes.to_vec(),
-                path: file.path.unwrap_or(PathBuf::from(file.name)),
-            }));
+        self.update_folder_progress();
+        #[cfg(target_arch = "wasm32")]
+        let _ = ctx;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = file.path.as_deref() {
+            if path.is_dir() {
+                self.expand_folder(ctx, path)?;
+                return self.handle_file_dropped(ctx);
+            }
+            return Ok(Some(
+                File::from_path(path)?.with_origin(FileOrigin::LocalDrop),
+            ));
+        }
+        #[cfg(target_arch = "wasm32")]
+        if let Some(bytes) = file.bytes.as_deref() {
+            let mut dropped = File::new(
+                bytes.to_vec(),
+                file.path.unwrap_or(PathBuf::from(file.name)),
+            )
+            .with_origin(FileOrigin::BrowserDrop)
+            .with_mime(file.mime);
+            // the browser gives the time as a std time
+            if let Some(modified) = file
+                .last_modified
+                .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            {
+                dropped = dropped.with_modified(UNIX_EPOCH + modified);
+            }
+            return Ok(Some(dropped));
         }
         Ok(None)
     }
@@ -135,8 +766,12 @@ impl FileHandler {
     pub fn handle_files(&mut self, ctx: &egui::Context) -> Result<Option<File>, AppError> {
         ctx.input(|i| {
             if !i.raw.dropped_files.is_empty() {
-                // read the first file
-                self.dropped_files.clone_from(&i.raw.dropped_files);
+                // queued after the files still pending from a previous drop
+                self.dropped_files
+                    .extend(i.raw.dropped_files.iter().cloned());
+                if let Some((_, total)) = &mut self.folder_progress {
+                    *total += i.raw.dropped_files.len();
+                }
             }
         });
         let file_upload_state = match &self.file_upload {
@@ -151,6 +786,7 @@ impl FileHandler {
             Ok(state) => match state {
                 FileState::NotSelected => {
                     log::info!("No file selected");
+                    self.open_as = None;
                     self.reset();
                 }
                 FileState::UploadedOrSelected => {
@@ -160,6 +796,7 @@ impl FileHandler {
                 }
                 FileState::Ready(data) => {
                     log::info!("File uploaded successfully");
+                    self.forced_format = self.open_as.take();
                     self.reset();
                     return Ok(Some(data));
                 }
@@ -168,13 +805,263 @@ impl FileHandler {
                 }
             },
             Err(e) => {
+                self.open_as = None;
                 self.reset();
                 return Err(e);
             }
         }
-        if let Some(file_dropped) = self.handle_file_dropped()? {
+        self.forced_format = None;
+        if let Some(file_dropped) = self.handle_file_dropped(ctx)? {
             return Ok(Some(file_dropped));
         }
+        if let Ok(mut queue) = FileQueue::get(ctx).0.lock()
+            && !queue.is_empty()
+        {
+            return Ok(Some(queue.remove(0)));
+        }
         Ok(None)
     }
 }
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Check if the file can be opened - ask the user if it comes from an external source
+    pub(crate) fn handle_incoming_file(&mut self, file: File, forced_format: Option<String>) {
+        if !self.app.require_trust() || !file.origin.is_external() {
+            self.handle_file_input(file, forced_format);
+            return;
+        }
+        match self
+            .internal
+            .settings
+            .trusted_origins
+            .get(&file.origin.key())
+        {
+            Some(true) => self.handle_file_input(file, forced_format),
+            Some(false) => self.error_manager.add_error(format!(
+                "{} comes from an untrusted source ({})",
+                file.path.display(),
+                file.origin.key()
+            )),
+            None => {
+                self.pending_trust.push_back(PendingTrust {
+                    file,
+                    forced_format,
+                    remember: false,
+                });
+            }
+        }
+    }
+
+    /// Show the overlay while files are dragged over the window
+    pub(crate) fn show_drop_overlay(&self, ctx: &egui::Context) {
+        let hovered = ctx.input(|i| i.raw.hovered_files.clone());
+        if hovered.is_empty() {
+            return;
+        }
+        let Some(hint) = self.app.drop_hint(&hovered) else {
+            return;
+        };
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("bladvak_drop_overlay"),
+        ));
+        let rect = ctx.content_rect();
+        let visuals = ctx.global_style().visuals.clone();
+        let color = if hint.accept {
+            visuals.strong_text_color()
+        } else {
+            visuals.error_fg_color
+        };
+        painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(160));
+        painter.rect_stroke(
+            rect.shrink(8.0),
+            8.0,
+            egui::Stroke::new(2.0, color),
+            egui::StrokeKind::Inside,
+        );
+        painter.text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            hint.text,
+            egui::FontId::proportional(24.0),
+            color,
+        );
+    }
+
+    /// Show the trust prompt of the first file from an external source - the other files wait
+    /// for their turn
+    pub(crate) fn show_trust_modal(&mut self, ctx: &egui::Context) {
+        let waiting = self.pending_trust.len().saturating_sub(1);
+        let Some(pending) = self.pending_trust.front_mut() else {
+            return;
+        };
+        let mut decision = None;
+        let modal = egui::Modal::new(egui::Id::new("bladvak_trust_modal")).show(ctx, |ui| {
+            ui.heading("External file");
+            ui.label(format!(
+                "{} came from an external source ({}) — open anyway?",
+                pending.file.path.display(),
+                pending.file.origin.key()
+            ));
+            ui.checkbox(
+                &mut pending.remember,
+                "Remember my decision for this source",
+            );
+            if waiting > 0 {
+                ui.weak(format!("{waiting} more file(s) waiting"));
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Open").clicked() {
+                    decision = Some(true);
+                }
+                if ui.button("Cancel").clicked() {
+                    decision = Some(false);
+                }
+            });
+        });
+        if decision.is_none() && modal.should_close() {
+            decision = Some(false);
+        }
+        if let Some(trusted) = decision
+            && let Some(pending) = self.pending_trust.pop_front()
+        {
+            let origin_key = pending.file.origin.key();
+            let remember = pending.remember;
+            let mut decided = vec![pending];
+            if remember {
+                self.internal
+                    .settings
+                    .trusted_origins
+                    .insert(origin_key.clone(), trusted);
+                // the remembered decision applies to the waiting files of the same source
+                let (same, others): (VecDeque<_>, _) = std::mem::take(&mut self.pending_trust)
+                    .into_iter()
+                    .partition(|pending| pending.file.origin.key() == origin_key);
+                self.pending_trust = others;
+                decided.extend(same);
+            }
+            journal::record(
+                JournalKind::Settings,
+                format!("Trust {origin_key}: {trusted}"),
+            );
+            if trusted {
+                for pending in decided {
+                    self.handle_file_input(pending.file, pending.forced_format);
+                }
+            }
+        }
+    }
+
+    /// Dispatch a file matching none of the formats - a zip archive is opened with the `zip`
+    /// feature
+    fn handle_unknown_file(&mut self, file: File) -> Result<(), AppError> {
+        #[cfg(feature = "zip")]
+        if crate::archive::is_zip(&file) {
+            let archive = crate::archive::Archive::new(file)?;
+            return self.app.handle_archive(archive);
+        }
+        self.app.handle_file(file)
+    }
+
+    /// Dispatch a file to the app, using the format detection
+    pub(crate) fn handle_file_input(&mut self, mut file: File, forced_format: Option<String>) {
+        trace_span!("handle_file", path = %file.path.display());
+        file.kind = FileKind::of(&file);
+        let path = file.path.clone();
+        let size = file.data.len();
+        let origin = file.origin.clone();
+        let mut deferred = false;
+        let accepted = self.app.accepted_kinds();
+        let formats = file_registry::app_formats(&self.app, &self.file_handlers);
+        let result = if !accepted.is_empty() && !accepted.contains(&file.kind) {
+            Err(FileKind::unsupported(&file))
+        } else if let Some(format_name) = forced_format {
+            match formats.iter().find(|format| format.name == format_name) {
+                Some(format) => {
+                    file_registry::dispatch(&mut self.app, &self.file_handlers, file, format)
+                }
+                None => Err(AppError::new(format!("Unknown format: {format_name}"))),
+            }
+        } else {
+            let detected = detect_formats(&formats, &file);
+            match detected.as_slice() {
+                [] => self.handle_unknown_file(file),
+                [format] => {
+                    file_registry::dispatch(&mut self.app, &self.file_handlers, file, format)
+                }
+                _ => {
+                    let formats = detected.into_iter().cloned().collect();
+                    self.pending_open_as
+                        .push_back(PendingOpenAs { file, formats });
+                    deferred = true;
+                    Ok(())
+                }
+            }
+        };
+        match result {
+            Ok(()) if deferred => {}
+            Ok(()) => {
+                self.file_opened(&path, size, &origin);
+                journal::record(
+                    JournalKind::FileOpened,
+                    format!(
+                        "{} ({})",
+                        path.display(),
+                        crate::utils::format_bytes(size as u64)
+                    ),
+                );
+                self.events
+                    .publish(crate::events::FileOpened { path: path.clone() });
+                crate::telemetry::emit(crate::telemetry::TelemetryEvent::FileOpened {
+                    extension: path
+                        .extension()
+                        .map(|extension| extension.to_string_lossy().to_lowercase()),
+                });
+            }
+            Err(err) => self
+                .error_manager
+                .add_error(crate::recent::with_file_name(err, &path)),
+        }
+    }
+
+    /// Show the format chooser of the first file with an ambiguous format detection - the
+    /// other files wait for their turn
+    pub(crate) fn show_open_as_modal(&mut self, ctx: &egui::Context) {
+        let waiting = self.pending_open_as.len().saturating_sub(1);
+        let Some(pending) = self.pending_open_as.front() else {
+            return;
+        };
+        let mut selected = None;
+        let modal = egui::Modal::new(egui::Id::new("bladvak_open_as_modal")).show(ctx, |ui| {
+            ui.heading("Open As…");
+            ui.label(format!(
+                "Multiple formats match {}",
+                pending.file.path.display()
+            ));
+            ui.separator();
+            for format in &pending.formats {
+                if ui.button(&format.name).clicked() {
+                    selected = Some(format.name.clone());
+                }
+            }
+            if waiting > 0 {
+                ui.weak(format!("{waiting} more file(s) waiting"));
+            }
+            ui.separator();
+            if ui.button("Cancel").clicked() {
+                ui.close();
+            }
+        });
+        if let Some(format_name) = selected {
+            if let Some(pending) = self.pending_open_as.pop_front() {
+                self.handle_file_input(pending.file, Some(format_name));
+            }
+        } else if modal.should_close() {
+            self.pending_open_as.pop_front();
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Content type of the files
+//!
+//! The kind is detected from the magic bytes, then from the content and the extension, when a
+//! [`crate::File`] is created and again before it is given to the app. The app can list the
+//! kinds it supports with [`crate::BladvakApp::accepted_kinds`]: the other files are rejected
+//! with the same error as [`crate::File::require_kind`].
+
+use std::{fmt::Display, path::Path};
+
+use crate::{AppError, ErrorCategory, File};
+
+/// Number of bytes checked to recognize a text
+const TEXT_SAMPLE: usize = 8 * 1024;
+
+/// Code of the error of an unsupported file
+pub const UNSUPPORTED_KIND_CODE: &str = "unsupported-kind";
+
+/// Content type of a file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FileKind {
+    /// No data
+    Empty,
+    /// PNG image
+    Png,
+    /// JPEG image
+    Jpeg,
+    /// GIF image
+    Gif,
+    /// WebP image
+    Webp,
+    /// BMP image
+    Bmp,
+    /// SVG image
+    Svg,
+    /// PDF document
+    Pdf,
+    /// Zip archive (also docx, xlsx, jar…)
+    Zip,
+    /// Gzip archive
+    Gzip,
+    /// WebAssembly module
+    Wasm,
+    /// JSON text
+    Json,
+    /// XML text
+    Xml,
+    /// UTF-8 text
+    Text,
+    /// Unknown binary data
+    #[default]
+    Binary,
+}
+
+/// Magic bytes of the kinds
+const MAGIC: [(&[u8], FileKind); 9] = [
+    (b"\x89PNG\r\n\x1a\n", FileKind::Png),
+    (b"\xff\xd8\xff", FileKind::Jpeg),
+    (b"GIF87a", FileKind::Gif),
+    (b"GIF89a", FileKind::Gif),
+    (b"%PDF-", FileKind::Pdf),
+    (b"PK\x03\x04", FileKind::Zip),
+    (b"PK\x05\x06", FileKind::Zip),
+    (b"\x1f\x8b", FileKind::Gzip),
+    (b"\0asm", FileKind::Wasm),
+];
+
+/// Check if the start of the data is UTF-8 - a character cut at the end of the sample is
+/// accepted
+fn is_text(data: &[u8]) -> bool {
+    let sample = &data[..data.len().min(TEXT_SAMPLE)];
+    match std::str::from_utf8(sample) {
+        Ok(text) => !text.contains('\0'),
+        Err(err) => err.error_len().is_none() && sample.len() == TEXT_SAMPLE,
+    }
+}
+
+impl FileKind {
+    /// Detect the kind of some data - the extension is used for the texts
+    #[must_use]
+    pub fn detect(data: &[u8], path: &Path) -> Self {
+        if data.is_empty() {
+            return Self::Empty;
+        }
+        if let Some((_, kind)) = MAGIC.iter().find(|(magic, _)| data.starts_with(magic)) {
+            return *kind;
+        }
+        if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+            return Self::Webp;
+        }
+        // "BM" alone could start a text: the reserved bytes of the header are zero
+        if data.starts_with(b"BM") && data.get(6..10) == Some(&[0; 4]) {
+            return Self::Bmp;
+        }
+        if !is_text(data) {
+            return Self::Binary;
+        }
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        let sample = String::from_utf8_lossy(&data[..data.len().min(TEXT_SAMPLE)]);
+        let start = sample.trim_start().trim_start_matches('\u{feff}');
+        match extension.as_deref() {
+            Some("json") => Self::Json,
+            Some("svg") => Self::Svg,
+            Some("xml") => Self::Xml,
+            _ if start.starts_with("<svg") => Self::Svg,
+            _ if start.starts_with("<?xml") => {
+                if sample.contains("<svg") {
+                    Self::Svg
+                } else {
+                    Self::Xml
+                }
+            }
+            _ if start.starts_with(['{', '['])
+                && data.trim_ascii_end().ends_with(if start.starts_with('{') {
+                    b"}"
+                } else {
+                    b"]"
+                }) =>
+            {
+                Self::Json
+            }
+            _ => Self::Text,
+        }
+    }
+
+    /// Detect the kind of a file
+    #[must_use]
+    pub fn of(file: &File) -> Self {
+        Self::detect(&file.data, &file.path)
+    }
+
+    /// MIME type
+    #[must_use]
+    pub fn mime(self) -> &'static str {
+        match self {
+            Self::Empty | Self::Binary => "application/octet-stream",
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Gif => "image/gif",
+            Self::Webp => "image/webp",
+            Self::Bmp => "image/bmp",
+            Self::Svg => "image/svg+xml",
+            Self::Pdf => "application/pdf",
+            Self::Zip => "application/zip",
+            Self::Gzip => "application/gzip",
+            Self::Wasm => "application/wasm",
+            Self::Json => "application/json",
+            Self::Xml => "application/xml",
+            Self::Text => "text/plain",
+        }
+    }
+
+    /// Is an image
+    #[must_use]
+    pub fn is_image(self) -> bool {
+        matches!(
+            self,
+            Self::Png | Self::Jpeg | Self::Gif | Self::Webp | Self::Bmp | Self::Svg
+        )
+    }
+
+    /// Is a text (JSON, XML, SVG and plain text)
+    #[must_use]
+    pub fn is_text(self) -> bool {
+        matches!(self, Self::Json | Self::Xml | Self::Svg | Self::Text)
+    }
+
+    /// Error of a file of an unsupported kind
+    #[must_use]
+    pub fn unsupported(file: &File) -> AppError {
+        let name = file.path.file_name().map_or_else(
+            || file.path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        AppError::new(format!("Unsupported file type: {} ({name})", file.kind))
+            .with_category(ErrorCategory::Parse)
+            .with_code(UNSUPPORTED_KIND_CODE)
+    }
+}
+
+impl Display for FileKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Empty => "empty file",
+            Self::Png => "PNG image",
+            Self::Jpeg => "JPEG image",
+            Self::Gif => "GIF image",
+            Self::Webp => "WebP image",
+            Self::Bmp => "BMP image",
+            Self::Svg => "SVG image",
+            Self::Pdf => "PDF document",
+            Self::Zip => "zip archive",
+            Self::Gzip => "gzip archive",
+            Self::Wasm => "WebAssembly module",
+            Self::Json => "JSON",
+            Self::Xml => "XML",
+            Self::Text => "text",
+            Self::Binary => "binary data",
+        };
+        write!(f, "{name}")
+    }
+}
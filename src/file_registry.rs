@@ -0,0 +1,115 @@
+//! Handlers of the file formats
+//!
+//! Instead of matching the formats in [`crate::BladvakApp::handle_file_as`], an app can register
+//! one handler per format with [`crate::BladvakApp::file_handlers`] (e.g. `.csv` to a CSV
+//! importer and `.json` to a JSON importer). The formats of the handlers are detected like the
+//! [`crate::BladvakApp::file_formats`]: the user chooses the handler when several formats match.
+
+use std::fmt::Debug;
+
+use crate::{AppError, File, FileFormat, app::BladvakApp};
+
+/// Handler of the files of a format
+pub type FileHandlerFn<App> = Box<dyn Fn(&mut App, File) -> Result<(), AppError>>;
+
+/// Handlers of the file formats of an app
+pub struct FileHandlers<App> {
+    /// Formats and their handlers, in the order of registration
+    handlers: Vec<(FileFormat, FileHandlerFn<App>)>,
+}
+
+impl<App> Default for FileHandlers<App> {
+    fn default() -> Self {
+        Self {
+            handlers: Vec::new(),
+        }
+    }
+}
+
+impl<App> Debug for FileHandlers<App> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.handlers.iter().map(|(format, _)| &format.name))
+            .finish()
+    }
+}
+
+impl<App> FileHandlers<App> {
+    /// Create an empty registry
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the handler of a format - a handler registered for the same format name
+    /// is replaced
+    #[must_use]
+    pub fn with<F>(mut self, format: FileFormat, handler: F) -> Self
+    where
+        F: Fn(&mut App, File) -> Result<(), AppError> + 'static,
+    {
+        self.handlers
+            .retain(|(registered, _)| registered.name != format.name);
+        self.handlers.push((format, Box::new(handler)));
+        self
+    }
+
+    /// Register the handler of an extension (without the dot) - the format is named after the
+    /// extension (e.g. "CSV")
+    #[must_use]
+    pub fn with_extension<F>(self, extension: &str, handler: F) -> Self
+    where
+        F: Fn(&mut App, File) -> Result<(), AppError> + 'static,
+    {
+        let format = FileFormat::new(extension.to_uppercase()).with_extension(extension);
+        self.with(format, handler)
+    }
+
+    /// Formats of the handlers
+    pub fn formats(&self) -> impl Iterator<Item = &FileFormat> {
+        self.handlers.iter().map(|(format, _)| format)
+    }
+
+    /// Check if there is no handler
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// Handler of a format
+    pub(crate) fn get(&self, format_name: &str) -> Option<&FileHandlerFn<App>> {
+        self.handlers
+            .iter()
+            .find(|(format, _)| format.name == format_name)
+            .map(|(_, handler)| handler)
+    }
+}
+
+/// Formats of an app: its file formats, then the formats of its handlers (see
+/// [`crate::BladvakApp::file_handlers`], built once at start)
+pub(crate) fn app_formats<M: for<'a> BladvakApp<'a>>(
+    app: &M,
+    handlers: &FileHandlers<M>,
+) -> Vec<FileFormat> {
+    let mut formats = app.file_formats();
+    for format in handlers.formats() {
+        if !formats.iter().any(|known| known.name == format.name) {
+            formats.push(format.clone());
+        }
+    }
+    formats
+}
+
+/// Give a file to the handler of its format - to `handle_file_as` if no handler is registered
+/// for the format
+pub(crate) fn dispatch<M: for<'a> BladvakApp<'a>>(
+    app: &mut M,
+    handlers: &FileHandlers<M>,
+    file: File,
+    format: &FileFormat,
+) -> Result<(), AppError> {
+    match handlers.get(&format.name) {
+        Some(handler) => handler(app, file),
+        None => app.handle_file_as(file, format),
+    }
+}
@@ -0,0 +1,46 @@
+//! Fullscreen - the "View > Fullscreen" entry and the F11 shortcut of the wrapper
+//!
+//! On native, the window is toggled with [`egui::ViewportCommand::Fullscreen`]. On web, the
+//! page is toggled with the Fullscreen API of the browser, which only accepts a request made
+//! from a user input (a click or a key press).
+
+use eframe::egui;
+
+/// Check if the window (or the page on web) is fullscreen
+#[must_use]
+pub fn is_fullscreen(ctx: &egui::Context) -> bool {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = ctx;
+        eframe::web_sys::window()
+            .and_then(|window| window.document())
+            .is_some_and(|document| document.fullscreen_element().is_some())
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    ctx.input(|i| i.viewport().fullscreen.unwrap_or(false))
+}
+
+/// Enter or leave fullscreen
+pub fn set_fullscreen(ctx: &egui::Context, fullscreen: bool) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let Some(document) = eframe::web_sys::window().and_then(|window| window.document()) else {
+            return;
+        };
+        if !fullscreen {
+            document.exit_fullscreen();
+        } else if let Some(element) = document.document_element()
+            && let Err(err) = element.request_fullscreen()
+        {
+            log::warn!("Cannot enter fullscreen: {err:?}");
+        }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(fullscreen));
+    ctx.request_repaint();
+}
+
+/// Toggle fullscreen
+pub fn toggle_fullscreen(ctx: &egui::Context) {
+    set_fullscreen(ctx, !is_fullscreen(ctx));
+}
@@ -0,0 +1,676 @@
+//! Data handoff between Bladvak apps
+//!
+//! On native each app listens on a local endpoint registered in a directory of the user
+//! (a unix socket, or a loopback TCP port on other platforms) - the endpoint also
+//! prevents two instances of the same app from listening at the same time. The messages are
+//! sent and received on background threads, so that a slow peer does not freeze the UI.
+//! On web the apps share a `BroadcastChannel`.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex, mpsc},
+};
+
+use crate::{errors::AppError, file_handler::File};
+
+/// Message sent to another app
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum HandoffMessage {
+    /// A file - handled by the receiver like an opened file
+    File {
+        /// Path or filename
+        path: PathBuf,
+        /// File data
+        data: Vec<u8>,
+    },
+    /// App-defined payload
+    Payload {
+        /// Kind of payload, used by the receiver to decode the data
+        kind: String,
+        /// Payload data
+        data: Vec<u8>,
+    },
+}
+
+impl From<File> for HandoffMessage {
+    fn from(file: File) -> Self {
+        Self::File {
+            path: file.path,
+            data: file.data,
+        }
+    }
+}
+
+/// Message received from another app
+#[derive(Debug, Clone)]
+pub struct ReceivedHandoff {
+    /// Name of the sender
+    pub from: String,
+    /// Message
+    pub message: HandoffMessage,
+}
+
+/// Message on the wire
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    /// Name of the sender
+    from: String,
+    /// Name of the receiver - `None` for a broadcast
+    to: Option<String>,
+    /// Message - `None` for an announcement
+    message: Option<HandoffMessage>,
+}
+
+/// State shared with the app through the egui context
+#[derive(Debug, Default)]
+struct HandoffShared {
+    /// Messages waiting to be sent
+    outbox: Vec<(String, HandoffMessage)>,
+    /// Known peers
+    peers: Vec<String>,
+}
+
+/// Handle to the shared state
+#[derive(Clone, Default)]
+struct SharedHandle(Arc<Mutex<HandoffShared>>);
+
+impl SharedHandle {
+    /// Id of the shared state in the egui context
+    fn id() -> egui::Id {
+        egui::Id::new("bladvak_handoff")
+    }
+
+    /// Get the shared state from the egui context
+    fn get(ctx: &egui::Context) -> Self {
+        ctx.data_mut(|data| data.get_temp_mut_or_default::<Self>(Self::id()).clone())
+    }
+}
+
+/// Send a message to another Bladvak app
+///
+/// The message is sent at the next frame - errors are reported in the error manager
+pub fn send_handoff<S: Into<String>>(ctx: &egui::Context, peer: S, message: HandoffMessage) {
+    if let Ok(mut shared) = SharedHandle::get(ctx).0.lock() {
+        shared.outbox.push((peer.into(), message));
+    }
+    ctx.request_repaint();
+}
+
+/// Names of the other Bladvak apps reachable with [`send_handoff`]
+#[must_use]
+pub fn handoff_peers(ctx: &egui::Context) -> Vec<String> {
+    SharedHandle::get(ctx)
+        .0
+        .lock()
+        .map(|shared| shared.peers.clone())
+        .unwrap_or_default()
+}
+
+/// Make an app name usable as an endpoint name
+fn endpoint_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Messages given to the sending thread, with the names of their peers
+#[cfg(not(target_arch = "wasm32"))]
+type Outbox = mpsc::Sender<(String, Envelope)>;
+
+/// Handoff endpoint of the running app
+pub(crate) struct Handoff {
+    /// Endpoint name of the app
+    name: String,
+    /// Received messages
+    receiver: mpsc::Receiver<ReceivedHandoff>,
+    /// Last refresh of the peers
+    last_refresh: Option<crate::utils::time::Instant>,
+    /// Endpoint file
+    #[cfg(not(target_arch = "wasm32"))]
+    endpoint: PathBuf,
+    /// Messages given to the sending thread
+    #[cfg(not(target_arch = "wasm32"))]
+    outbox: Outbox,
+    /// Errors of the sending thread
+    #[cfg(not(target_arch = "wasm32"))]
+    send_errors: mpsc::Receiver<AppError>,
+    /// Broadcast channel
+    #[cfg(target_arch = "wasm32")]
+    channel: eframe::web_sys::BroadcastChannel,
+    /// Peers that announced themselves
+    #[cfg(target_arch = "wasm32")]
+    peers: std::rc::Rc<std::cell::RefCell<std::collections::BTreeSet<String>>>,
+    /// Message callback - kept alive with the channel
+    #[cfg(target_arch = "wasm32")]
+    _on_message: eframe::wasm_bindgen::closure::Closure<dyn FnMut(eframe::web_sys::MessageEvent)>,
+}
+
+impl std::fmt::Debug for Handoff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Handoff")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Handoff {
+    /// Name of the app endpoint
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Take the received messages
+    pub(crate) fn received(&self) -> Vec<ReceivedHandoff> {
+        self.receiver.try_iter().collect()
+    }
+
+    /// Send the messages submitted by the app and refresh the peers
+    pub(crate) fn process(&mut self, ctx: &egui::Context) -> Vec<AppError> {
+        let shared = SharedHandle::get(ctx);
+        let outbox = shared
+            .0
+            .lock()
+            .map(|mut shared| std::mem::take(&mut shared.outbox))
+            .unwrap_or_default();
+        #[allow(unused_mut)]
+        let mut errors: Vec<AppError> = outbox
+            .into_iter()
+            .filter_map(|(peer, message)| self.send(&peer, message).err())
+            .collect();
+        #[cfg(not(target_arch = "wasm32"))]
+        errors.extend(self.send_errors.try_iter());
+        let now = crate::utils::time::Instant::now();
+        let refresh = self.last_refresh.is_none_or(|last| {
+            now.duration_since(last) > crate::utils::time::Duration::from_secs(1)
+        });
+        if refresh {
+            self.last_refresh = Some(now);
+            let peers = self.peers();
+            if let Ok(mut shared) = shared.0.lock() {
+                shared.peers = peers;
+            }
+        }
+        errors
+    }
+}
+
+/// Native implementation
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use eframe::egui;
+    use std::{
+        io::{self, Read, Write},
+        path::{Path, PathBuf},
+        sync::mpsc::{self, Sender},
+        time::Duration,
+    };
+
+    use super::{Envelope, Handoff, HandoffMessage, Outbox, ReceivedHandoff, endpoint_name};
+    use crate::{
+        errors::AppError,
+        limits::{LimitError, ParseLimits},
+    };
+
+    /// Extension of the endpoint files
+    #[cfg(unix)]
+    const EXTENSION: &str = "sock";
+
+    /// Extension of the endpoint files
+    #[cfg(not(unix))]
+    const EXTENSION: &str = "port";
+
+    /// Timeout of the reads and writes on a connection - a peer that does not write stops
+    /// only its own connection
+    const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Directory of the endpoints - in the runtime or cache directory of the user, so that the
+    /// other users cannot send messages
+    fn endpoint_dir() -> PathBuf {
+        std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .or_else(crate::temp_files::os_cache_dir)
+            .unwrap_or_else(std::env::temp_dir)
+            .join("bladvak-handoff")
+    }
+
+    /// Create the directory of the endpoints - only accessible by the user on unix
+    fn create_endpoint_dir() -> io::Result<()> {
+        let dir = endpoint_dir();
+        std::fs::create_dir_all(&dir)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+        }
+        Ok(())
+    }
+
+    /// Endpoint file of an app
+    fn endpoint_path(name: &str) -> PathBuf {
+        endpoint_dir().join(format!("{name}.{EXTENSION}"))
+    }
+
+    /// Connect to an endpoint
+    #[cfg(unix)]
+    fn connect(path: &Path) -> io::Result<std::os::unix::net::UnixStream> {
+        let stream = std::os::unix::net::UnixStream::connect(path)?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        Ok(stream)
+    }
+
+    /// Connect to an endpoint
+    #[cfg(not(unix))]
+    fn connect(path: &Path) -> io::Result<std::net::TcpStream> {
+        let port = std::fs::read_to_string(path)?
+            .trim()
+            .parse::<u16>()
+            .map_err(io::Error::other)?;
+        let stream =
+            std::net::TcpStream::connect_timeout(&([127, 0, 0, 1], port).into(), IO_TIMEOUT)?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        Ok(stream)
+    }
+
+    /// Listen on an endpoint
+    #[cfg(unix)]
+    fn listen(path: &Path, sender: Sender<ReceivedHandoff>, ctx: egui::Context) -> io::Result<()> {
+        if path.exists() {
+            // stale endpoint of a previous instance
+            std::fs::remove_file(path)?;
+        }
+        let listener = std::os::unix::net::UnixListener::bind(path)?;
+        std::thread::Builder::new()
+            .name("bladvak_handoff".to_string())
+            .spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    if let Err(err) = stream.set_read_timeout(Some(IO_TIMEOUT)) {
+                        log::warn!("Cannot set the handoff read timeout: {err}");
+                        continue;
+                    }
+                    accept(stream, &sender, &ctx);
+                }
+            })?;
+        Ok(())
+    }
+
+    /// Listen on an endpoint
+    #[cfg(not(unix))]
+    fn listen(path: &Path, sender: Sender<ReceivedHandoff>, ctx: egui::Context) -> io::Result<()> {
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+        std::fs::write(path, listener.local_addr()?.port().to_string())?;
+        std::thread::Builder::new()
+            .name("bladvak_handoff".to_string())
+            .spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    if let Err(err) = stream.set_read_timeout(Some(IO_TIMEOUT)) {
+                        log::warn!("Cannot set the handoff read timeout: {err}");
+                        continue;
+                    }
+                    accept(stream, &sender, &ctx);
+                }
+            })?;
+        Ok(())
+    }
+
+    /// Read the message of a connection on its own thread
+    fn accept<R: Read + Send + 'static>(
+        stream: R,
+        sender: &Sender<ReceivedHandoff>,
+        ctx: &egui::Context,
+    ) {
+        let sender = sender.clone();
+        let ctx = ctx.clone();
+        if let Err(err) = std::thread::Builder::new()
+            .name("bladvak_handoff_receive".to_string())
+            .spawn(move || receive(stream, &sender, &ctx))
+        {
+            log::warn!("Cannot receive the handoff: {err}");
+        }
+    }
+
+    /// Send the messages given to the outbox, one at a time - the errors are given back to
+    /// [`Handoff::process`]
+    fn start_sender(ctx: egui::Context) -> io::Result<(Outbox, mpsc::Receiver<AppError>)> {
+        let (outbox, queue) = mpsc::channel::<(String, Envelope)>();
+        let (error_sender, errors) = mpsc::channel();
+        // the thread stops when the outbox is dropped with the handoff
+        std::thread::Builder::new()
+            .name("bladvak_handoff_send".to_string())
+            .spawn(move || {
+                for (peer, envelope) in queue {
+                    if let Err(err) = deliver(&peer, &envelope) {
+                        let _ = error_sender.send(err);
+                        ctx.request_repaint();
+                    }
+                }
+            })?;
+        Ok((outbox, errors))
+    }
+
+    /// Send a message to a peer
+    fn deliver(peer: &str, envelope: &Envelope) -> Result<(), AppError> {
+        let path = endpoint_path(&endpoint_name(peer));
+        let mut stream = match connect(&path) {
+            Ok(stream) => stream,
+            Err(err) => {
+                if err.kind() == io::ErrorKind::ConnectionRefused {
+                    // the peer is gone
+                    let _ = std::fs::remove_file(&path);
+                }
+                return Err(AppError::from((format!("Cannot reach {peer}"), err)));
+            }
+        };
+        write_envelope(&mut stream, envelope)
+    }
+
+    /// Read one message from a connection
+    fn receive<R: Read>(mut stream: R, sender: &Sender<ReceivedHandoff>, ctx: &egui::Context) {
+        match read_envelope(&mut stream) {
+            Ok(Some(Envelope {
+                from,
+                message: Some(message),
+                ..
+            })) => {
+                let _ = sender.send(ReceivedHandoff { from, message });
+                ctx.request_repaint();
+            }
+            Ok(_) => {}
+            Err(err) => log::warn!("Invalid handoff message: {err}"),
+        }
+    }
+
+    /// Read a length-prefixed message - `None` if the connection is closed without message
+    fn read_envelope<R: Read>(stream: &mut R) -> Result<Option<Envelope>, AppError> {
+        let mut len = [0; 4];
+        if let Err(err) = stream.read_exact(&mut len) {
+            if err.kind() == io::ErrorKind::UnexpectedEof {
+                // e.g. the instance check of another app
+                return Ok(None);
+            }
+            return Err(err.into());
+        }
+        let len = u64::from(u32::from_le_bytes(len));
+        let limits = ParseLimits::default();
+        if len > limits.max_size {
+            return Err(LimitError::SizeExceeded {
+                limit: limits.max_size,
+            }
+            .into());
+        }
+        let mut data = Vec::new();
+        stream.take(len).read_to_end(&mut data)?;
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| AppError::from(("Invalid handoff message", e)))
+    }
+
+    /// Write a length-prefixed message
+    fn write_envelope<W: Write>(stream: &mut W, envelope: &Envelope) -> Result<(), AppError> {
+        let data = serde_json::to_vec(envelope)
+            .map_err(|e| AppError::from(("Cannot serialize the handoff message", e)))?;
+        let len = u32::try_from(data.len()).map_err(|_| LimitError::SizeExceeded {
+            limit: u64::from(u32::MAX),
+        })?;
+        stream.write_all(&len.to_le_bytes())?;
+        stream.write_all(&data)?;
+        stream.flush()?;
+        Ok(())
+    }
+
+    impl Handoff {
+        /// Start listening for messages of other apps
+        /// # Errors
+        /// Fails if the endpoint cannot be created or if another instance of the app is listening
+        pub(crate) fn start(name: &str, ctx: &egui::Context) -> Result<Self, AppError> {
+            let name = endpoint_name(name);
+            create_endpoint_dir()?;
+            let endpoint = endpoint_path(&name);
+            if endpoint.exists() && connect(&endpoint).is_ok() {
+                return Err(AppError::new(format!(
+                    "Another instance of {name} is already listening for handoffs"
+                )));
+            }
+            let (sender, receiver) = mpsc::channel();
+            listen(&endpoint, sender, ctx.clone())?;
+            let (outbox, send_errors) = start_sender(ctx.clone())?;
+            log::info!("Listening for handoffs on {}", endpoint.display());
+            Ok(Self {
+                name,
+                receiver,
+                last_refresh: None,
+                endpoint,
+                outbox,
+                send_errors,
+            })
+        }
+
+        /// Give a message to the sending thread - the delivery errors are returned by a later
+        /// [`Handoff::process`]
+        /// # Errors
+        /// Fails if the sending thread is stopped
+        pub(crate) fn send(&self, peer: &str, message: HandoffMessage) -> Result<(), AppError> {
+            let envelope = Envelope {
+                from: self.name.clone(),
+                to: Some(peer.to_string()),
+                message: Some(message),
+            };
+            self.outbox
+                .send((peer.to_string(), envelope))
+                .map_err(|_| AppError::new(format!("Cannot send to {peer}: no handoff thread")))
+        }
+
+        /// Names of the other apps with an endpoint
+        pub(crate) fn peers(&self) -> Vec<String> {
+            let Ok(entries) = std::fs::read_dir(endpoint_dir()) else {
+                return Vec::new();
+            };
+            let mut peers = entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+                .filter_map(|path| {
+                    path.file_stem()
+                        .map(|stem| stem.to_string_lossy().to_string())
+                })
+                .filter(|peer| *peer != self.name)
+                .collect::<Vec<_>>();
+            peers.sort();
+            peers
+        }
+    }
+
+    impl Drop for Handoff {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.endpoint);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        //! Length-prefixed framing of the messages
+
+        use super::{Envelope, HandoffMessage, read_envelope, write_envelope};
+        use std::io::Cursor;
+
+        /// A written message is read back
+        #[test]
+        fn framing_round_trip() {
+            let envelope = Envelope {
+                from: "sender".to_string(),
+                to: Some("receiver".to_string()),
+                message: Some(HandoffMessage::Payload {
+                    kind: "kind".to_string(),
+                    data: vec![0, 1, 2, 255],
+                }),
+            };
+            let mut wire = Vec::new();
+            assert!(write_envelope(&mut wire, &envelope).is_ok());
+            assert_eq!(
+                wire[..4],
+                u32::try_from(wire.len() - 4)
+                    .unwrap_or_default()
+                    .to_le_bytes()
+            );
+            let Ok(Some(read)) = read_envelope(&mut Cursor::new(wire)) else {
+                panic!("the message was not read back");
+            };
+            assert_eq!(read.from, "sender");
+            assert_eq!(read.to.as_deref(), Some("receiver"));
+            assert!(matches!(
+                read.message,
+                Some(HandoffMessage::Payload { kind, data }) if kind == "kind" && data == [0, 1, 2, 255]
+            ));
+        }
+
+        /// A closed connection gives no message, a length over the limit fails before reading
+        #[test]
+        fn framing_limits() {
+            assert!(matches!(
+                read_envelope(&mut Cursor::new(Vec::new())),
+                Ok(None)
+            ));
+            let mut wire = u32::MAX.to_le_bytes().to_vec();
+            wire.extend_from_slice(b"{}");
+            let result = read_envelope(&mut Cursor::new(wire));
+            assert!(result.is_err_and(|err| err.message.contains("Size limit exceeded")));
+            // a message shorter than its length is invalid
+            let mut wire = 100_u32.to_le_bytes().to_vec();
+            wire.extend_from_slice(b"{}");
+            assert!(read_envelope(&mut Cursor::new(wire)).is_err());
+        }
+    }
+}
+
+/// Web implementation
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use eframe::{
+        egui,
+        wasm_bindgen::{JsCast as _, closure::Closure},
+        web_sys,
+    };
+    use std::{cell::RefCell, collections::BTreeSet, rc::Rc, sync::mpsc};
+
+    use super::{Envelope, Handoff, HandoffMessage, ReceivedHandoff, endpoint_name};
+    use crate::errors::AppError;
+
+    /// Name of the broadcast channel
+    const CHANNEL: &str = "bladvak-handoff";
+
+    /// Post a message on the channel
+    fn post(channel: &web_sys::BroadcastChannel, envelope: &Envelope) -> Result<(), AppError> {
+        let data = serde_json::to_string(envelope)
+            .map_err(|e| AppError::from(("Cannot serialize the handoff message", e)))?;
+        channel
+            .post_message(&data.into())
+            .map_err(|e| AppError::new(format!("Cannot post the handoff message: {e:?}")))
+    }
+
+    impl Handoff {
+        /// Start listening for messages of other apps
+        /// # Errors
+        /// Fails if the broadcast channel cannot be created
+        pub(crate) fn start(name: &str, ctx: &egui::Context) -> Result<Self, AppError> {
+            let name = endpoint_name(name);
+            let channel = web_sys::BroadcastChannel::new(CHANNEL)
+                .map_err(|e| AppError::new(format!("Cannot open the handoff channel: {e:?}")))?;
+            let (sender, receiver) = mpsc::channel();
+            let peers = Rc::new(RefCell::new(BTreeSet::new()));
+            let on_message = {
+                let name = name.clone();
+                let channel = channel.clone();
+                let peers = Rc::clone(&peers);
+                let ctx = ctx.clone();
+                Closure::<dyn FnMut(web_sys::MessageEvent)>::new(
+                    move |event: web_sys::MessageEvent| {
+                        let Some(text) = event.data().as_string() else {
+                            return;
+                        };
+                        let Ok(envelope) = serde_json::from_str::<Envelope>(&text) else {
+                            log::warn!("Invalid handoff message");
+                            return;
+                        };
+                        if envelope.from == name
+                            || envelope.to.as_ref().is_some_and(|to| *to != name)
+                        {
+                            return;
+                        }
+                        peers.borrow_mut().insert(envelope.from.clone());
+                        match envelope.message {
+                            Some(message) => {
+                                let _ = sender.send(ReceivedHandoff {
+                                    from: envelope.from,
+                                    message,
+                                });
+                                ctx.request_repaint();
+                            }
+                            None if envelope.to.is_none() => {
+                                // answer the announcement of a new peer
+                                let answer = Envelope {
+                                    from: name.clone(),
+                                    to: Some(envelope.from),
+                                    message: None,
+                                };
+                                if let Err(err) = post(&channel, &answer) {
+                                    log::warn!("{err}");
+                                }
+                            }
+                            None => {}
+                        }
+                    },
+                )
+            };
+            channel.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+            post(
+                &channel,
+                &Envelope {
+                    from: name.clone(),
+                    to: None,
+                    message: None,
+                },
+            )?;
+            Ok(Self {
+                name,
+                receiver,
+                last_refresh: None,
+                channel,
+                peers,
+                _on_message: on_message,
+            })
+        }
+
+        /// Send a message to a peer
+        /// # Errors
+        /// Fails if the message cannot be posted
+        pub(crate) fn send(&self, peer: &str, message: HandoffMessage) -> Result<(), AppError> {
+            post(
+                &self.channel,
+                &Envelope {
+                    from: self.name.clone(),
+                    to: Some(endpoint_name(peer)),
+                    message: Some(message),
+                },
+            )
+        }
+
+        /// Names of the other apps that announced themselves
+        pub(crate) fn peers(&self) -> Vec<String> {
+            self.peers.borrow().iter().cloned().collect()
+        }
+    }
+
+    impl Drop for Handoff {
+        fn drop(&mut self) {
+            self.channel.set_onmessage(None);
+            self.channel.close();
+        }
+    }
+}
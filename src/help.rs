@@ -0,0 +1,242 @@
+//! In-app help
+//!
+//! The app ([`crate::BladvakApp::help_topics`]) and its panels
+//! ([`crate::app::BladvakPanel::help_topic`]) register help topics. The "Help" window (F1, or
+//! "Help topics" in the Help menu) shows them as a tree with a search, and the panels with a
+//! topic get a "?" button in their header opening it. The text of a topic is markdown (see
+//! [`crate::widgets::markdown`]).
+
+use eframe::egui::{self, KeyboardShortcut};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{Bladvak, BladvakApp};
+
+/// Shortcut opening the help
+pub const HELP_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(egui::Modifiers::NONE, egui::Key::F1);
+
+/// Help topic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelpTopic {
+    /// Id of the topic - used to open it (see [`open`])
+    pub id: String,
+    /// Title shown in the tree
+    pub title: String,
+    /// Text of the topic
+    pub text: String,
+    /// Sub topics
+    pub children: Vec<HelpTopic>,
+}
+
+impl HelpTopic {
+    /// Create a topic
+    pub fn new<I: Into<String>, T: Into<String>, S: Into<String>>(
+        id: I,
+        title: T,
+        text: S,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            title: title.into(),
+            text: text.into(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Add a sub topic
+    #[must_use]
+    pub fn with_child(mut self, child: Self) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Find a topic in this topic and its sub topics
+    fn find(&self, id: &str) -> Option<&Self> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(id))
+    }
+
+    /// Topics of this topic and its sub topics matching a lowercase query
+    fn matching<'a>(&'a self, query: &str, found: &mut Vec<&'a Self>) {
+        if self.title.to_lowercase().contains(query) || self.text.to_lowercase().contains(query) {
+            found.push(self);
+        }
+        for child in &self.children {
+            child.matching(query, found);
+        }
+    }
+}
+
+/// Open state of the help, shared with the "?" buttons
+#[derive(Debug, Clone, Default)]
+struct HelpState {
+    /// Is the help window open
+    open: bool,
+    /// Id of the shown topic
+    topic: Option<String>,
+}
+
+/// Id of the help state
+fn help_id() -> egui::Id {
+    egui::Id::new("bladvak_help")
+}
+
+/// Open the help window - on a topic if given (see [`HelpTopic::id`])
+pub fn open(ctx: &egui::Context, topic: Option<&str>) {
+    ctx.data_mut(|data| {
+        let state = data.get_temp_mut_or_default::<HelpState>(help_id());
+        state.open = true;
+        if let Some(topic) = topic {
+            state.topic = Some(topic.to_string());
+        }
+    });
+}
+
+/// Show the "?" header of a panel opening its topic
+pub(crate) fn panel_button(ui: &mut egui::Ui, topic: &str) {
+    ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+        let button = ui.small_button("?").on_hover_text("Help");
+        crate::a11y::label(&button, "Help");
+        if button.clicked() {
+            open(ui.ctx(), Some(topic));
+        }
+    });
+}
+
+/// Help window state
+#[derive(Debug, Default)]
+pub(crate) struct HelpBrowser {
+    /// Search query
+    query: String,
+    /// The app or its panels have topics - checked on the first frame
+    available: Option<bool>,
+}
+
+/// Show the tree of the topics - returns the clicked topic
+fn topic_tree(ui: &mut egui::Ui, topics: &[HelpTopic], selected: Option<&str>) -> Option<String> {
+    let mut clicked = None;
+    for topic in topics {
+        let is_selected = selected == Some(topic.id.as_str());
+        if topic.children.is_empty() {
+            if ui.selectable_label(is_selected, &topic.title).clicked() {
+                clicked = Some(topic.id.clone());
+            }
+        } else {
+            let header = egui::CollapsingHeader::new(&topic.title)
+                .id_salt(&topic.id)
+                .default_open(true)
+                .show(ui, |ui| topic_tree(ui, &topic.children, selected));
+            if header.header_response.clicked() {
+                clicked = Some(topic.id.clone());
+            }
+            clicked = clicked.or(header.body_returned.flatten());
+        }
+    }
+    clicked
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Topics of the app, then the topics of its panels
+    fn help_topics(&self) -> Vec<HelpTopic> {
+        let mut topics = self.app.help_topics();
+        topics.extend(
+            self.panel_list
+                .iter()
+                .filter_map(|panel| panel.help_topic()),
+        );
+        topics
+    }
+
+    /// Check if the help is available - the app or one of its panels has a topic
+    pub(crate) fn help_available(&mut self) -> bool {
+        if let Some(available) = self.help.available {
+            return available;
+        }
+        let available = !self.help_topics().is_empty();
+        self.help.available = Some(available);
+        available
+    }
+
+    /// Open the help with its shortcut
+    pub(crate) fn process_help_shortcut(&mut self, ctx: &egui::Context) {
+        if ctx.input_mut(|i| i.consume_shortcut(&HELP_SHORTCUT)) && self.help_available() {
+            open(ctx, None);
+        }
+    }
+
+    /// Show the help window
+    pub(crate) fn show_help(&mut self, ctx: &egui::Context) {
+        let mut state = ctx.data(|data| data.get_temp::<HelpState>(help_id()).unwrap_or_default());
+        if !state.open {
+            return;
+        }
+        let topics = self.help_topics();
+        let query = &mut self.help.query;
+        egui::Window::new("Help")
+            .id("bladvak_help".into())
+            .open(&mut state.open)
+            .default_size([640.0, 420.0])
+            .show(ctx, |ui| {
+                egui::Panel::left("bladvak_help_topics")
+                    .resizable(true)
+                    .default_size(180.0)
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(query)
+                                .hint_text("Search")
+                                .desired_width(f32::INFINITY),
+                        );
+                        ui.separator();
+                        egui::ScrollArea::vertical().show(ui, |ui| {
+                            let selected = state.topic.as_deref();
+                            let needle = query.trim().to_lowercase();
+                            let clicked = if needle.is_empty() {
+                                topic_tree(ui, &topics, selected)
+                            } else {
+                                let mut found = Vec::new();
+                                for topic in &topics {
+                                    topic.matching(&needle, &mut found);
+                                }
+                                if found.is_empty() {
+                                    ui.label("No topic");
+                                }
+                                let mut clicked = None;
+                                for topic in found {
+                                    let is_selected = selected == Some(topic.id.as_str());
+                                    if ui.selectable_label(is_selected, &topic.title).clicked() {
+                                        clicked = Some(topic.id.clone());
+                                    }
+                                }
+                                clicked
+                            };
+                            if clicked.is_some() {
+                                state.topic = clicked;
+                            }
+                        });
+                    });
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    let topic = state
+                        .topic
+                        .as_deref()
+                        .and_then(|id| topics.iter().find_map(|topic| topic.find(id)))
+                        .or_else(|| topics.first());
+                    match topic {
+                        Some(topic) => {
+                            ui.heading(&topic.title);
+                            ui.separator();
+                            crate::widgets::markdown(ui, &topic.text);
+                        }
+                        None => {
+                            ui.label("No help topic");
+                        }
+                    }
+                });
+            });
+        ctx.data_mut(|data| data.insert_temp(help_id(), state));
+    }
+}
@@ -0,0 +1,161 @@
+//! App inbox - messages sent by the background work to the app
+//!
+//! A background task sends messages with an [`InboxSender`] (see [`sender`]), each frame the
+//! wrapper passes them to [`crate::BladvakApp::handle_message`]. Each app has its own inbox - an
+//! app embedded with [`crate::embed::EmbeddedApp`] gets the messages sent from its ui, not the
+//! host.
+
+use eframe::egui;
+use std::{
+    any::Any,
+    fmt::Debug,
+    sync::mpsc::{Receiver, Sender, channel},
+};
+
+/// Message of the inbox
+pub struct InboxMessage(Box<dyn Any + Send>);
+
+impl Debug for InboxMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("InboxMessage").finish_non_exhaustive()
+    }
+}
+
+impl InboxMessage {
+    /// Check if the message is a `T`
+    #[must_use]
+    pub fn is<T: Any>(&self) -> bool {
+        self.0.is::<T>()
+    }
+
+    /// Get the message as a `T`
+    /// # Errors
+    /// Returns the message if it is not a `T`
+    pub fn downcast<T: Any>(self) -> Result<T, Self> {
+        self.0.downcast::<T>().map(|msg| *msg).map_err(Self)
+    }
+}
+
+/// Sender of the inbox - can be cloned and sent to a background task
+#[derive(Clone)]
+pub struct InboxSender {
+    /// Channel to the inbox
+    sender: Sender<InboxMessage>,
+    /// Context repainted when a message is sent
+    ctx: egui::Context,
+}
+
+impl Debug for InboxSender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InboxSender").finish_non_exhaustive()
+    }
+}
+
+impl InboxSender {
+    /// Send a message to the app - returns `false` if the app is closed
+    pub fn send<T: Any + Send>(&self, msg: T) -> bool {
+        let sent = self.sender.send(InboxMessage(Box::new(msg))).is_ok();
+        self.ctx.request_repaint();
+        sent
+    }
+}
+
+/// Id of the inbox of the app being run in the egui context
+fn current_id() -> egui::Id {
+    egui::Id::new("bladvak_inbox_current")
+}
+
+/// Get a sender of the inbox of the app being run - to call from the ui of the app
+#[must_use]
+pub fn sender(ctx: &egui::Context) -> Option<InboxSender> {
+    ctx.data(|data| {
+        let id = data.get_temp::<egui::Id>(current_id())?;
+        data.get_temp::<InboxSender>(id)
+    })
+}
+
+/// Receiving side of the inbox
+#[derive(Debug)]
+pub(crate) struct Inbox {
+    /// Id of the sender in the egui context
+    id: egui::Id,
+    /// Channel of the messages
+    receiver: Receiver<InboxMessage>,
+}
+
+impl Inbox {
+    /// Create the inbox of an app and store its sender in the egui context - `salt` is unique
+    /// per app of the context. The first inbox of the context is the one of [`sender`].
+    pub(crate) fn new(ctx: &egui::Context, salt: impl egui::AsIdSalt) -> Self {
+        let (sender, receiver) = channel();
+        let sender = InboxSender {
+            sender,
+            ctx: ctx.clone(),
+        };
+        let id = egui::Id::new("bladvak_inbox").with(salt);
+        ctx.data_mut(|data| {
+            data.insert_temp(id, sender);
+            if data.get_temp::<egui::Id>(current_id()).is_none() {
+                data.insert_temp(current_id(), id);
+            }
+        });
+        Self { id, receiver }
+    }
+
+    /// Make this inbox the one of [`sender`] - returns the previous one, given back to
+    /// [`Inbox::leave`] once the app is run
+    pub(crate) fn enter(&self, ctx: &egui::Context) -> Option<egui::Id> {
+        ctx.data_mut(|data| {
+            let previous = data.get_temp::<egui::Id>(current_id());
+            data.insert_temp(current_id(), self.id);
+            previous
+        })
+    }
+
+    /// Give back the inbox of [`sender`] replaced by [`Inbox::enter`]
+    pub(crate) fn leave(ctx: &egui::Context, previous: Option<egui::Id>) {
+        ctx.data_mut(|data| match previous {
+            Some(previous) => {
+                data.insert_temp(current_id(), previous);
+            }
+            None => data.remove::<egui::Id>(current_id()),
+        });
+    }
+
+    /// Messages received since the last call
+    pub(crate) fn drain(&self) -> Vec<InboxMessage> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Inboxes of a host and of an embedded app
+
+    use super::{Inbox, sender};
+    use eframe::egui;
+
+    /// The messages sent from the ui of an embedded app reach its inbox, not the host one
+    #[test]
+    fn embedded_inbox() {
+        let ctx = egui::Context::default();
+        let host = Inbox::new(&ctx, "host");
+        let child = Inbox::new(&ctx, "child");
+        let previous = child.enter(&ctx);
+        let Some(child_sender) = sender(&ctx) else {
+            panic!("no inbox for the embedded app");
+        };
+        Inbox::leave(&ctx, previous);
+        let Some(host_sender) = sender(&ctx) else {
+            panic!("no inbox for the host");
+        };
+        assert!(child_sender.send(1_u8));
+        assert!(host_sender.send("host"));
+        let messages = child.drain();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].is::<u8>());
+        let messages = host.drain();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].is::<&str>());
+    }
+}
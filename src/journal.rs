@@ -0,0 +1,221 @@
+//! Session activity journal
+
+use eframe::egui::{self, RichText};
+use serde::Serialize;
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fmt::{Debug, Display},
+    marker::PhantomData,
+    path::Path,
+    sync::Mutex,
+};
+
+use crate::{
+    AppError, ErrorManager, LocaleFormat,
+    app::{BladvakPanel, LIBRARY_PANEL_PRIORITY, PanelOrder},
+    utils::time::{SystemTime, format_time, now, unix_millis},
+};
+
+/// Maximum number of journal entries kept in memory
+pub const JOURNAL_CAPACITY: usize = 1000;
+
+/// Journal entries
+static JOURNAL: Mutex<VecDeque<JournalEntry>> = Mutex::new(VecDeque::new());
+
+/// Kind of journal entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum JournalKind {
+    /// A file was opened
+    FileOpened,
+    /// An export was run
+    Export,
+    /// A setting was changed
+    Settings,
+    /// An error occurred
+    Error,
+    /// A warning or an information was reported
+    Message,
+    /// App-defined action
+    Other,
+}
+
+impl Display for JournalKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FileOpened => write!(f, "File"),
+            Self::Export => write!(f, "Export"),
+            Self::Settings => write!(f, "Settings"),
+            Self::Error => write!(f, "Error"),
+            Self::Message => write!(f, "Message"),
+            Self::Other => write!(f, "Other"),
+        }
+    }
+}
+
+/// One journal entry
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    /// Time of the action
+    pub timestamp: SystemTime,
+    /// Kind of action
+    pub kind: JournalKind,
+    /// Description
+    pub message: String,
+}
+
+impl Display for JournalEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} [{}] {}",
+            format_time(self.timestamp),
+            self.kind,
+            self.message
+        )
+    }
+}
+
+impl JournalEntry {
+    /// Line of the entry with the time in the formats of a locale
+    #[must_use]
+    pub fn line(&self, format: &LocaleFormat) -> String {
+        format!(
+            "{} [{}] {}",
+            format.format_time(self.timestamp),
+            self.kind,
+            self.message
+        )
+    }
+}
+
+impl Serialize for JournalEntry {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("JournalEntry", 3)?;
+        state.serialize_field("timestamp", &unix_millis(self.timestamp))?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("message", &self.message)?;
+        state.end()
+    }
+}
+
+/// Record an action in the journal
+pub fn record<S: Into<String>>(kind: JournalKind, message: S) {
+    if let Ok(mut journal) = JOURNAL.lock() {
+        if journal.len() >= JOURNAL_CAPACITY {
+            journal.pop_front();
+        }
+        journal.push_back(JournalEntry {
+            timestamp: now(),
+            kind,
+            message: message.into(),
+        });
+    }
+}
+
+/// Get a copy of the journal entries
+#[must_use]
+pub fn journal_entries() -> Vec<JournalEntry> {
+    JOURNAL
+        .lock()
+        .map(|journal| journal.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Export the journal as JSON
+/// # Errors
+/// Fails if the journal cannot be serialized or saved
+pub fn export_journal(path: &Path) -> Result<(), AppError> {
+    crate::utils::save_file(&journal_json()?, path)?;
+    Ok(())
+}
+
+/// Journal as JSON
+/// # Errors
+/// Fails if the journal cannot be serialized
+fn journal_json() -> Result<Vec<u8>, AppError> {
+    serde_json::to_vec_pretty(&journal_entries())
+        .map_err(|e| AppError::from(("Cannot serialize the journal", e)))
+}
+
+/// Built-in panel displaying the journal
+pub struct JournalPanel<App> {
+    /// Search text
+    search: RefCell<String>,
+    /// App type
+    _app: PhantomData<App>,
+}
+
+impl<App> Default for JournalPanel<App> {
+    fn default() -> Self {
+        Self {
+            search: RefCell::new(String::new()),
+            _app: PhantomData,
+        }
+    }
+}
+
+impl<App> Debug for JournalPanel<App> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JournalPanel")
+            .field("search", &self.search)
+            .finish()
+    }
+}
+
+impl<App> BladvakPanel for JournalPanel<App> {
+    type App = App;
+
+    fn name(&self) -> &'static str {
+        "Journal"
+    }
+
+    fn order(&self) -> PanelOrder {
+        PanelOrder::Priority(LIBRARY_PANEL_PRIORITY)
+    }
+
+    fn has_settings(&self) -> bool {
+        false
+    }
+
+    fn ui_settings(&self, _app: &mut App, _ui: &mut egui::Ui, _error_manager: &mut ErrorManager) {}
+
+    fn has_ui(&self) -> bool {
+        true
+    }
+
+    fn ui(&self, _app: &mut App, ui: &mut egui::Ui, error_manager: &mut ErrorManager) {
+        let mut search = self.search.borrow_mut();
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut *search)
+                    .hint_text("Search")
+                    .desired_width(120.0),
+            );
+            if ui.button("Export").clicked() {
+                match journal_json() {
+                    Ok(data) => {
+                        crate::dialog::save_file_dialog(ui.ctx(), data, Path::new("journal.json"));
+                    }
+                    Err(err) => error_manager.add_error(err),
+                }
+            }
+        });
+        ui.separator();
+        let search = search.to_lowercase();
+        let locale = LocaleFormat::get(ui.ctx());
+        egui::ScrollArea::vertical()
+            .id_salt("bladvak_journal_scroll")
+            .stick_to_bottom(true)
+            .auto_shrink([false, true])
+            .max_height(300.0)
+            .show(ui, |ui| {
+                for entry in journal_entries().iter().filter(|entry| {
+                    search.is_empty() || entry.message.to_lowercase().contains(&search)
+                }) {
+                    ui.label(RichText::new(entry.line(&locale)).monospace());
+                }
+            });
+    }
+}
@@ -15,23 +15,164 @@
 #![allow(clippy::multiple_crate_versions)]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
+/// Enter a tracing span until the end of the current scope (when the `tracing` feature is enabled)
+macro_rules! trace_span {
+    ($($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!($($args)*).entered();
+    };
+}
+
+/// Implement [`BladvakApp::name`], [`BladvakApp::version`] and [`BladvakApp::repo_url`] with the
+/// `name`, `version` and `repository` of the `Cargo.toml` of the app
+///
+/// With the defaults of the other methods, a small tool only implements its UI:
+///
+/// ```
+/// use bladvak::{BladvakApp, ErrorManager, eframe::egui, serde};
+///
+/// #[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+/// #[serde(crate = "bladvak::serde")]
+/// struct MyApp {}
+///
+/// impl BladvakApp<'_> for MyApp {
+///     bladvak::package_metadata!();
+///
+///     fn central_panel(&mut self, ui: &mut egui::Ui, _error_manager: &mut ErrorManager) {
+///         ui.label("Hello");
+///     }
+/// }
+///
+/// assert_eq!(MyApp::name(), env!("CARGO_PKG_NAME"));
+/// assert_eq!(MyApp::version(), env!("CARGO_PKG_VERSION"));
+/// ```
+#[macro_export]
+macro_rules! package_metadata {
+    () => {
+        fn name() -> String {
+            env!("CARGO_PKG_NAME").to_string()
+        }
+
+        fn version() -> String {
+            env!("CARGO_PKG_VERSION").to_string()
+        }
+
+        fn repo_url() -> String {
+            env!("CARGO_PKG_REPOSITORY").to_string()
+        }
+    };
+}
+
+pub mod a11y;
 pub mod app;
+#[cfg(feature = "zip")]
+pub mod archive;
+pub mod assets;
+pub mod blob_store;
+pub mod command;
+pub mod context_menu;
+pub mod dialog;
+pub mod embed;
 pub mod errors;
+pub mod events;
+pub mod export;
+pub mod feedback;
 pub mod file_handler;
+pub mod file_kind;
+pub mod file_registry;
+pub mod fullscreen;
+pub mod handoff;
+pub mod help;
+pub mod inbox;
+pub mod journal;
+pub mod lifecycle;
+pub mod limits;
+pub mod locale;
+pub mod logger;
+pub mod menu;
+pub mod observable;
+pub(crate) mod overrides;
+pub(crate) mod panel_cache;
+pub mod perf;
+pub mod persist;
+pub mod profile;
+pub mod progress;
+#[cfg(target_arch = "wasm32")]
+pub mod pwa;
+pub mod recent;
+pub mod region;
+pub(crate) mod restore;
+pub mod search;
+#[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+pub mod self_update;
+pub mod session;
 pub mod settings;
+pub mod snapshot;
+pub mod splash;
+pub mod startup;
+pub(crate) mod style_editor;
+pub mod task;
+pub mod telemetry;
+pub mod temp_files;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub(crate) mod toolbar;
+pub mod updater;
 pub mod utils;
+pub mod vector_export;
+#[cfg(target_arch = "wasm32")]
+pub(crate) mod web_drop;
+#[cfg(target_arch = "wasm32")]
+pub(crate) mod web_save;
+#[cfg(target_arch = "wasm32")]
+pub(crate) mod web_unload;
+pub mod whats_new;
+pub mod widgets;
 
 pub use app::{Bladvak, BladvakApp, MainResult};
-pub use errors::{AppError, ErrorManager};
-pub use file_handler::File;
+pub use assets::{Assets, ThemedAsset};
+pub use blob_store::{BlobStore, StateStore};
+pub use command::{Command, command_button, menu_item, run_command};
+pub use context_menu::{register_context_menu, show_context_menu};
+pub use embed::EmbeddedApp;
+pub use errors::{
+    AppError, ErrorCategory, ErrorManager, ErrorPresentation, ErrorSink, Severity, error_sink,
+};
+pub use events::{Event, Panels, panels, publish};
+pub use file_handler::{
+    CannedPicker, DropHint, File, FileFormat, FileOrigin, FilePicker, FolderDrop,
+};
+pub use file_kind::FileKind;
+pub use file_registry::FileHandlers;
+pub use handoff::{HandoffMessage, handoff_peers, send_handoff};
+pub use help::HelpTopic;
+pub use inbox::{InboxMessage, InboxSender};
+pub use journal::JournalPanel;
+pub use lifecycle::{Service, SuspendPolicy, Visibility};
+pub use locale::LocaleFormat;
+pub use logger::LogPanel;
+pub use menu::{Menu, MenuEntry, MenuModel};
+pub use observable::{Observable, Subscription};
+pub use perf::{Metrics, metrics};
+pub use persist::SessionOnly;
+pub use progress::{ProgressHandle, progress, show_status};
+pub use region::{RegionDescriptor, RegionSide};
+pub use search::{SearchProvider, SearchResult};
+pub use session::{AppSession, DocumentSession};
+pub use task::{OverlapPolicy, RecurringJob, Schedule, TaskHandle};
+pub use temp_files::TempFileRegistry;
 
 /// re-export
 pub use eframe;
+#[cfg(feature = "markdown")]
+pub use egui_commonmark;
 pub use egui_extras;
 pub use egui_plot;
 pub use log;
 pub use rfd;
 pub use serde;
+#[cfg(feature = "tracing")]
+pub use tracing;
 
 /// re-export wasm utils
 #[cfg(target_arch = "wasm32")]
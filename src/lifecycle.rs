@@ -0,0 +1,123 @@
+//! Window lifecycle - suspend the background services while the window is hidden
+//!
+//! Each frame the wrapper computes the [`Visibility`] of the window. The built-in services (see
+//! [`Service`]) are paused according to [`crate::BladvakApp::suspend_policy`] and resumed when
+//! the window is visible again. The services of the app can use [`is_suspended`].
+
+use eframe::egui;
+use std::fmt::Display;
+
+/// Visibility of the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Visibility {
+    /// Visible with the keyboard focus
+    #[default]
+    Focused,
+    /// Visible without the keyboard focus
+    Unfocused,
+    /// Minimized or in a background tab
+    Hidden,
+}
+
+impl Display for Visibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Focused => write!(f, "Focused"),
+            Self::Unfocused => write!(f, "Unfocused"),
+            Self::Hidden => write!(f, "Hidden"),
+        }
+    }
+}
+
+/// When a service is paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SuspendPolicy {
+    /// Never paused
+    #[default]
+    KeepRunning,
+    /// Paused while the window is hidden
+    PauseWhenHidden,
+    /// Paused while the window has not the focus
+    PauseWhenUnfocused,
+}
+
+impl SuspendPolicy {
+    /// Check if a service with this policy is paused
+    #[must_use]
+    pub fn is_suspended(self, visibility: Visibility) -> bool {
+        match self {
+            Self::KeepRunning => false,
+            Self::PauseWhenHidden => visibility == Visibility::Hidden,
+            Self::PauseWhenUnfocused => visibility != Visibility::Focused,
+        }
+    }
+}
+
+/// Built-in service
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Service {
+    /// Recurring jobs (see [`crate::task::RecurringJob`])
+    RecurringJobs,
+    /// Payloads sent by other apps (see [`crate::handoff`])
+    Handoff,
+    /// Continuous repaint of the performance overlay
+    PerfOverlay,
+}
+
+/// Id of the visibility in the egui context
+fn visibility_id() -> egui::Id {
+    egui::Id::new("bladvak_visibility")
+}
+
+/// Visibility of the window in the last frame
+#[must_use]
+pub fn visibility(ctx: &egui::Context) -> Visibility {
+    ctx.data(|data| data.get_temp(visibility_id()))
+        .unwrap_or_default()
+}
+
+/// Check if a service with this policy is paused in the last frame
+#[must_use]
+pub fn is_suspended(ctx: &egui::Context, policy: SuspendPolicy) -> bool {
+    policy.is_suspended(visibility(ctx))
+}
+
+/// Check if the page is in a background tab
+#[cfg(target_arch = "wasm32")]
+fn is_page_hidden() -> bool {
+    eframe::web_sys::window()
+        .and_then(|window| window.document())
+        .is_some_and(|document| document.hidden())
+}
+
+/// Compute the visibility of the window
+fn current_visibility(ctx: &egui::Context) -> Visibility {
+    let (minimized, focused) = ctx.input(|i| {
+        let viewport = i.viewport();
+        (
+            viewport.minimized.unwrap_or(false),
+            viewport.focused.unwrap_or(true),
+        )
+    });
+    #[cfg(target_arch = "wasm32")]
+    let minimized = minimized || is_page_hidden();
+    if minimized {
+        Visibility::Hidden
+    } else if focused {
+        Visibility::Focused
+    } else {
+        Visibility::Unfocused
+    }
+}
+
+/// Update the visibility of the window - returns the new visibility if it changed
+pub(crate) fn update(ctx: &egui::Context) -> Option<Visibility> {
+    let current = current_visibility(ctx);
+    let previous = visibility(ctx);
+    if current == previous {
+        return None;
+    }
+    log::info!("Window {current}");
+    ctx.data_mut(|data| data.insert_temp(visibility_id(), current));
+    Some(current)
+}
@@ -0,0 +1,207 @@
+//! Limits for parsing untrusted files
+
+use eframe::egui;
+use std::{cell::Cell, error::Error, fmt, io::Read, sync::Arc};
+
+use crate::{
+    errors::{AppError, ErrorCategory},
+    task::TaskId,
+    utils::time::Duration,
+};
+
+/// Limit violation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitError {
+    /// The data is bigger than the maximum size
+    SizeExceeded {
+        /// Maximum size in bytes
+        limit: u64,
+    },
+    /// The data is nested deeper than the maximum depth
+    DepthExceeded {
+        /// Maximum depth
+        limit: usize,
+    },
+    /// The parsing took longer than the timeout
+    Timeout {
+        /// Timeout
+        limit: Duration,
+    },
+    /// The data is not supported
+    Unsupported(String),
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SizeExceeded { limit } => write!(f, "Size limit exceeded ({limit} bytes)"),
+            Self::DepthExceeded { limit } => write!(f, "Depth limit exceeded ({limit})"),
+            Self::Timeout { limit } => write!(f, "Timeout exceeded ({limit:?})"),
+            Self::Unsupported(reason) => write!(f, "Unsupported data: {reason}"),
+        }
+    }
+}
+
+impl Error for LimitError {}
+
+impl From<LimitError> for AppError {
+    fn from(error: LimitError) -> Self {
+        Self::new_with_source(error.to_string(), Arc::new(error))
+            .with_category(ErrorCategory::Parse)
+    }
+}
+
+/// Parsing limits
+#[derive(Debug, Clone)]
+pub struct ParseLimits {
+    /// Maximum size (in bytes) of the data, decompressed data included
+    pub max_size: u64,
+    /// Maximum recursion depth
+    pub max_depth: usize,
+    /// Maximum parsing duration
+    pub timeout: Option<Duration>,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_size: 512 * 1024 * 1024,
+            max_depth: 128,
+            timeout: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+impl ParseLimits {
+    /// Check the size of some data
+    /// # Errors
+    /// Fails if the data is bigger than the maximum size
+    pub fn check_size(&self, data: &[u8]) -> Result<(), LimitError> {
+        if data.len() as u64 > self.max_size {
+            return Err(LimitError::SizeExceeded {
+                limit: self.max_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Wrap a reader (e.g. a decompressor) to limit the number of bytes read
+    pub fn reader<R: Read>(&self, inner: R) -> LimitedReader<R> {
+        LimitedReader::new(inner, self.max_size)
+    }
+
+    /// Create a depth counter
+    #[must_use]
+    pub fn depth(&self) -> DepthCounter {
+        DepthCounter::new(self.max_depth)
+    }
+
+    /// Run the parsing function as a background task with the timeout - returns at once
+    ///
+    /// The task is collected by the task manager of the app owning `ctx`: the parsed value is
+    /// sent to the inbox of the app calling `spawn` (see [`crate::BladvakApp::handle_message`]
+    /// and [`crate::inbox::sender`]) and a failure, a panic or a timeout
+    /// is shown as an error. The function is not cancelled at the timeout: it runs until it
+    /// finishes, listed as timed out in the task list, and its result is ignored. On web there
+    /// are no threads: the function runs directly and the timeout is not enforced.
+    pub fn spawn<T, F>(&self, ctx: &egui::Context, name: impl Into<String>, func: F) -> TaskId
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T, AppError> + Send + 'static,
+    {
+        crate::task::spawn_detached(ctx, name.into(), self.timeout, func)
+    }
+}
+
+/// Reader failing when more than `limit` bytes are read
+#[derive(Debug)]
+pub struct LimitedReader<R> {
+    /// Inner reader
+    inner: R,
+    /// Number of bytes read
+    read: u64,
+    /// Maximum number of bytes
+    limit: u64,
+}
+
+impl<R> LimitedReader<R> {
+    /// Create a new limited reader
+    pub fn new(inner: R, limit: u64) -> Self {
+        Self {
+            inner,
+            read: 0,
+            limit,
+        }
+    }
+
+    /// Get the inner reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        self.read += count as u64;
+        if self.read > self.limit {
+            return Err(std::io::Error::other(LimitError::SizeExceeded {
+                limit: self.limit,
+            }));
+        }
+        Ok(count)
+    }
+}
+
+/// Recursion depth counter
+#[derive(Debug)]
+pub struct DepthCounter {
+    /// Current depth
+    current: Cell<usize>,
+    /// Maximum depth
+    max: usize,
+}
+
+impl DepthCounter {
+    /// Create a new depth counter
+    #[must_use]
+    pub fn new(max: usize) -> Self {
+        Self {
+            current: Cell::new(0),
+            max,
+        }
+    }
+
+    /// Enter one level - the level is left when the guard is dropped
+    /// # Errors
+    /// Fails if the maximum depth is exceeded
+    pub fn enter(&self) -> Result<DepthGuard<'_>, LimitError> {
+        let depth = self.current.get() + 1;
+        if depth > self.max {
+            return Err(LimitError::DepthExceeded { limit: self.max });
+        }
+        self.current.set(depth);
+        Ok(DepthGuard { counter: self })
+    }
+
+    /// Current depth
+    #[must_use]
+    pub fn current(&self) -> usize {
+        self.current.get()
+    }
+}
+
+/// Guard of one recursion level
+#[derive(Debug)]
+pub struct DepthGuard<'a> {
+    /// Depth counter
+    counter: &'a DepthCounter,
+}
+
+impl Drop for DepthGuard<'_> {
+    fn drop(&mut self) {
+        self.counter
+            .current
+            .set(self.counter.current.get().saturating_sub(1));
+    }
+}
@@ -0,0 +1,294 @@
+//! Locale formatting
+//!
+//! [`LocaleFormat`] formats the numbers, the dates and the times like the locale chosen in the
+//! settings - the locale of the system by default. The wrapper uses it for its timestamps
+//! (errors, journal, scheduled jobs) and apps get it with [`LocaleFormat::get`]. The times are
+//! shown in the local time zone - of the system on native, of the browser on web - and the zone
+//! is written after the time. UTC is used when the time zone cannot be read.
+
+use eframe::egui;
+use std::fmt::Display;
+
+use crate::utils::{
+    format::NumberFormat,
+    time::{SystemTime, civil_from_days, unix_millis},
+};
+
+/// Locales proposed in the settings
+pub const KNOWN_LOCALES: [&str; 14] = [
+    "en-US", "en-GB", "de-DE", "fr-FR", "es-ES", "it-IT", "nl-NL", "pt-BR", "pl-PL", "sv-SE",
+    "ru-RU", "ja-JP", "zh-CN", "ko-KR",
+];
+
+/// Order of the day, the month and the year in a date
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateOrder {
+    /// e.g. 31/12/2024
+    DayMonthYear,
+    /// e.g. 12/31/2024
+    MonthDayYear,
+    /// e.g. 2024-12-31
+    YearMonthDay,
+}
+
+/// Day of the week
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weekday {
+    /// Monday
+    Monday,
+    /// Tuesday
+    Tuesday,
+    /// Wednesday
+    Wednesday,
+    /// Thursday
+    Thursday,
+    /// Friday
+    Friday,
+    /// Saturday
+    Saturday,
+    /// Sunday
+    Sunday,
+}
+
+impl Display for Weekday {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Formats of a locale
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocaleFormat {
+    /// Locale (e.g. "en-US")
+    pub locale: String,
+    /// Separators of the numbers
+    pub number: NumberFormat,
+    /// Order of the parts of a date
+    pub date_order: DateOrder,
+    /// Separator of the parts of a date
+    pub date_separator: char,
+    /// Times with AM/PM instead of 24 hours
+    pub hour12: bool,
+    /// First day of the week of the calendars
+    pub first_day_of_week: Weekday,
+    /// Offset of the times from UTC in minutes - `None` for the local time zone
+    pub utc_offset: Option<i32>,
+}
+
+impl Default for LocaleFormat {
+    /// Formats of the locale of the user
+    fn default() -> Self {
+        Self::current()
+    }
+}
+
+/// Offset of the local time zone from UTC at a time, in minutes - the time zone is given by the
+/// browser on web and by the system on native (0 if it cannot be read)
+fn local_utc_offset(time: SystemTime) -> i64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        // the browser gives the milliseconds as a f64
+        #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+        {
+            let date = js_sys::Date::new(&(unix_millis(time) as f64).into());
+            -(date.get_timezone_offset() as i64)
+        }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use chrono::{Offset, TimeZone};
+
+        let seconds = i64::try_from(unix_millis(time) / 1000).unwrap_or_default();
+        chrono::DateTime::from_timestamp(seconds, 0).map_or(0, |utc| {
+            let offset = chrono::Local.offset_from_utc_datetime(&utc.naive_utc());
+            i64::from(offset.fix().local_minus_utc()) / 60
+        })
+    }
+}
+
+/// Name of a time zone from its offset (e.g. "UTC" or "UTC+02:00")
+fn zone_name(offset: i64) -> String {
+    if offset == 0 {
+        return "UTC".to_string();
+    }
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.unsigned_abs();
+    format!("UTC{sign}{:02}:{:02}", offset / 60, offset % 60)
+}
+
+/// Seconds since the unix epoch of a time shifted by an offset in minutes
+fn shifted_seconds(time: SystemTime, offset: i64) -> u64 {
+    let seconds = i64::try_from(unix_millis(time) / 1000).unwrap_or(i64::MAX);
+    u64::try_from(seconds.saturating_add(offset * 60)).unwrap_or_default()
+}
+
+impl LocaleFormat {
+    /// Formats of a locale (e.g. `de-DE`, `fr_FR` or `ja`) - the formats of the language are
+    /// used for an unknown region
+    ///
+    /// ```
+    /// use bladvak::locale::{DateOrder, LocaleFormat, Weekday};
+    ///
+    /// let us = LocaleFormat::for_locale("en-US");
+    /// assert_eq!(us.date_order, DateOrder::MonthDayYear);
+    /// assert!(us.hour12);
+    /// assert_eq!(us.first_day_of_week, Weekday::Sunday);
+    ///
+    /// let german = LocaleFormat::for_locale("de_DE");
+    /// assert_eq!(german.date_order, DateOrder::DayMonthYear);
+    /// assert_eq!(german.date_separator, '.');
+    /// assert_eq!(german.first_day_of_week, Weekday::Monday);
+    ///
+    /// assert_eq!(LocaleFormat::for_locale("ja").date_order, DateOrder::YearMonthDay);
+    /// ```
+    #[must_use]
+    pub fn for_locale(locale: &str) -> Self {
+        let mut parts = locale.split(['-', '_']);
+        let language = parts.next().unwrap_or_default().to_lowercase();
+        let region = parts.next().unwrap_or_default().to_uppercase();
+        let (date_order, date_separator) = match (language.as_str(), region.as_str()) {
+            ("en", "" | "US" | "PH") => (DateOrder::MonthDayYear, '/'),
+            ("en", "CA") | ("ja" | "zh" | "ko" | "hu" | "lt" | "sv", _) => {
+                let separator = if matches!(language.as_str(), "ja" | "zh") {
+                    '/'
+                } else if matches!(language.as_str(), "ko" | "hu") {
+                    '.'
+                } else {
+                    '-'
+                };
+                (DateOrder::YearMonthDay, separator)
+            }
+            (
+                "de" | "ru" | "pl" | "cs" | "sk" | "fi" | "nb" | "nn" | "no" | "tr" | "uk" | "ro"
+                | "hr" | "sl" | "sr" | "bg" | "lv" | "et" | "da",
+                _,
+            ) => (DateOrder::DayMonthYear, '.'),
+            ("nl", _) => (DateOrder::DayMonthYear, '-'),
+            _ => (DateOrder::DayMonthYear, '/'),
+        };
+        let hour12 = matches!(
+            (language.as_str(), region.as_str()),
+            ("en", "" | "US" | "CA" | "AU" | "NZ" | "IN" | "PH")
+        );
+        let first_day_of_week = match (language.as_str(), region.as_str()) {
+            ("en", "" | "US" | "CA" | "PH") | ("ja" | "he" | "ko", _) | ("pt", "BR") => {
+                Weekday::Sunday
+            }
+            ("ar" | "fa", _) => Weekday::Saturday,
+            _ => Weekday::Monday,
+        };
+        Self {
+            locale: locale.to_string(),
+            number: NumberFormat::for_locale(locale),
+            date_order,
+            date_separator,
+            hour12,
+            first_day_of_week,
+            utc_offset: None,
+        }
+    }
+
+    /// Show the times with a fixed offset from UTC in minutes instead of the local time zone
+    #[must_use]
+    pub fn with_utc_offset(mut self, minutes: i32) -> Self {
+        self.utc_offset = Some(minutes);
+        self
+    }
+
+    /// Offset of the times from UTC at a time, in minutes
+    fn offset_at(&self, time: SystemTime) -> i64 {
+        self.utc_offset
+            .map_or_else(|| local_utc_offset(time), i64::from)
+    }
+
+    /// Formats of the locale of the user (see [`crate::utils::format::locale`])
+    #[must_use]
+    pub fn current() -> Self {
+        Self::for_locale(&crate::utils::format::locale())
+    }
+
+    /// Id of the formats in the egui context
+    fn id() -> egui::Id {
+        egui::Id::new("bladvak_locale_format")
+    }
+
+    /// Get the formats of the locale chosen in the settings
+    #[must_use]
+    pub fn get(ctx: &egui::Context) -> Self {
+        ctx.data_mut(|data| data.get_temp_mut_or_default::<Self>(Self::id()).clone())
+    }
+
+    /// Replace the formats of the app
+    pub fn install(self, ctx: &egui::Context) {
+        ctx.data_mut(|data| data.insert_temp(Self::id(), self));
+    }
+
+    /// Format a number with a fixed number of decimals
+    #[must_use]
+    pub fn format_number(&self, value: f64, decimals: usize) -> String {
+        self.number.format(value, decimals)
+    }
+
+    /// Format the date of a time in the local time zone (see [`LocaleFormat::format_time`])
+    ///
+    /// ```
+    /// use bladvak::{locale::LocaleFormat, utils::time::{Duration, UNIX_EPOCH}};
+    ///
+    /// let time = UNIX_EPOCH + Duration::from_secs(1_735_689_599); // 2024-12-31 23:59:59 UTC
+    /// let format = |locale| LocaleFormat::for_locale(locale).with_utc_offset(0);
+    /// assert_eq!(format("en-US").format_date(time), "12/31/2024");
+    /// assert_eq!(format("de-DE").format_date(time), "31.12.2024");
+    /// assert_eq!(format("sv-SE").format_date(time), "2024-12-31");
+    /// assert_eq!(format("sv-SE").with_utc_offset(60).format_date(time), "2025-01-01");
+    /// ```
+    #[must_use]
+    pub fn format_date(&self, time: SystemTime) -> String {
+        let seconds = shifted_seconds(time, self.offset_at(time));
+        let days = i64::try_from(seconds / 86_400).unwrap_or_default();
+        let (year, month, day) = civil_from_days(days);
+        let separator = self.date_separator;
+        match self.date_order {
+            DateOrder::DayMonthYear => format!("{day:02}{separator}{month:02}{separator}{year}"),
+            DateOrder::MonthDayYear => format!("{month:02}{separator}{day:02}{separator}{year}"),
+            DateOrder::YearMonthDay => format!("{year}{separator}{month:02}{separator}{day:02}"),
+        }
+    }
+
+    /// Format the time of the day of a time followed by its time zone - the local time zone
+    /// unless a fixed offset is set with [`LocaleFormat::with_utc_offset`]
+    ///
+    /// ```
+    /// use bladvak::{locale::LocaleFormat, utils::time::{Duration, UNIX_EPOCH}};
+    ///
+    /// let time = UNIX_EPOCH + Duration::from_secs(13 * 3600 + 5 * 60 + 9);
+    /// let us = LocaleFormat::for_locale("en-US").with_utc_offset(0);
+    /// assert_eq!(us.format_time(time), "1:05:09 PM UTC");
+    /// assert_eq!(us.format_time(UNIX_EPOCH), "12:00:00 AM UTC");
+    /// let french = LocaleFormat::for_locale("fr-FR");
+    /// assert_eq!(french.clone().with_utc_offset(0).format_time(time), "13:05:09 UTC");
+    /// assert_eq!(french.with_utc_offset(120).format_time(time), "15:05:09 UTC+02:00");
+    /// ```
+    #[must_use]
+    pub fn format_time(&self, time: SystemTime) -> String {
+        let offset = self.offset_at(time);
+        let seconds = shifted_seconds(time, offset);
+        let (hours, minutes, seconds) = ((seconds / 3600) % 24, (seconds / 60) % 60, seconds % 60);
+        let zone = zone_name(offset);
+        if self.hour12 {
+            let period = if hours < 12 { "AM" } else { "PM" };
+            let hours = match hours % 12 {
+                0 => 12,
+                hours => hours,
+            };
+            format!("{hours}:{minutes:02}:{seconds:02} {period} {zone}")
+        } else {
+            format!("{hours:02}:{minutes:02}:{seconds:02} {zone}")
+        }
+    }
+
+    /// Format the date and the time of the day of a time followed by its time zone
+    #[must_use]
+    pub fn format_date_time(&self, time: SystemTime) -> String {
+        format!("{} {}", self.format_date(time), self.format_time(time))
+    }
+}
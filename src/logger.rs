@@ -0,0 +1,516 @@
+//! Logger and in-app log viewer
+
+use eframe::egui::{self, Color32, RichText};
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fmt::{Debug, Display},
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use crate::{
+    AppError, ErrorManager,
+    app::{BladvakPanel, LIBRARY_PANEL_PRIORITY, PanelOrder},
+};
+
+/// Maximum number of log records kept in memory
+pub const LOG_BUFFER_CAPACITY: usize = 1000;
+
+/// Captured log records
+static LOG_BUFFER: Mutex<VecDeque<LogEntry>> = Mutex::new(VecDeque::new());
+
+/// Path of the log file (if enabled)
+static LOG_FILE_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// One captured log record
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    /// Level of the record
+    pub level: log::Level,
+    /// Target of the record (usually the module path)
+    pub target: String,
+    /// Formatted message
+    pub message: String,
+}
+
+impl Display for LogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{} {}] {}", self.level, self.target, self.message)
+    }
+}
+
+/// Log file configuration
+#[derive(Debug, Clone)]
+pub struct LogFileConfig {
+    /// Most verbose level written in the log file
+    pub level: log::LevelFilter,
+    /// Maximum size of a log file (in bytes) before rotation
+    pub max_size: u64,
+    /// Number of rotated log files kept
+    pub max_files: usize,
+    /// Log directory - defaults to the `logs` folder of the app storage directory
+    pub directory: Option<PathBuf>,
+}
+
+impl Default for LogFileConfig {
+    fn default() -> Self {
+        Self {
+            level: log::LevelFilter::Info,
+            max_size: 1024 * 1024,
+            max_files: 3,
+            directory: None,
+        }
+    }
+}
+
+/// Get the path of the log file (if enabled)
+#[must_use]
+pub fn log_file_path() -> Option<&'static Path> {
+    LOG_FILE_PATH.get().map(PathBuf::as_path)
+}
+
+/// File sink with size-based rotation
+#[derive(Debug)]
+pub struct FileLogSink {
+    /// Configuration
+    config: LogFileConfig,
+    /// Path of the current log file
+    path: PathBuf,
+    /// Opened file and its size
+    file: Mutex<(std::fs::File, u64)>,
+}
+
+impl FileLogSink {
+    /// Open the log file of the app
+    /// # Errors
+    /// Fails if the log directory or file cannot be created
+    pub fn new(app_name: &str, config: LogFileConfig) -> Result<Self, AppError> {
+        let directory = match &config.directory {
+            Some(directory) => directory.clone(),
+            None => Self::default_directory(app_name)
+                .ok_or_else(|| AppError::new("Cannot find the log directory".to_string()))?,
+        };
+        std::fs::create_dir_all(&directory)?;
+        let path = directory.join(format!("{}.log", app_name.to_lowercase()));
+        let file = Self::open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            config,
+            path,
+            file: Mutex::new((file, size)),
+        })
+    }
+
+    /// Default log directory
+    #[cfg(not(target_arch = "wasm32"))]
+    fn default_directory(app_name: &str) -> Option<PathBuf> {
+        eframe::storage_dir(app_name).map(|dir| dir.join("logs"))
+    }
+
+    /// Default log directory - no log directory on web
+    #[cfg(target_arch = "wasm32")]
+    fn default_directory(_app_name: &str) -> Option<PathBuf> {
+        None
+    }
+
+    /// Path of the current log file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Open the log file in append mode
+    fn open(path: &Path) -> std::io::Result<std::fs::File> {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+    }
+
+    /// Path of a rotated log file
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        PathBuf::from(path)
+    }
+
+    /// Rotate the log files: `app.log` -> `app.log.1` -> `app.log.2`...
+    fn rotate(&self) -> std::io::Result<std::fs::File> {
+        for index in (1..self.config.max_files).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                std::fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        if self.config.max_files > 0 {
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        } else {
+            std::fs::remove_file(&self.path)?;
+        }
+        Self::open(&self.path)
+    }
+
+    /// Write a record in the log file
+    fn write(&self, level: log::Level, target: &str, message: &dyn Display) {
+        use std::io::Write;
+
+        if level > self.config.level {
+            return;
+        }
+        let time = crate::utils::time::format_rfc3339(crate::utils::time::now());
+        let line = format!("{time} [{level} {target}] {message}\n");
+        let Ok(mut guard) = self.file.lock() else {
+            return;
+        };
+        let (file, size) = &mut *guard;
+        if *size + line.len() as u64 > self.config.max_size && *size > 0 {
+            match self.rotate() {
+                Ok(new_file) => {
+                    *file = new_file;
+                    *size = 0;
+                }
+                Err(e) => eprintln!("Cannot rotate log file: {e}"),
+            }
+        }
+        if file.write_all(line.as_bytes()).is_ok() {
+            *size += line.len() as u64;
+        }
+    }
+}
+
+/// Most verbose level captured in the log buffer
+const CAPTURE_LEVEL: log::LevelFilter = log::LevelFilter::Info;
+
+/// Capture a record in the log buffer
+fn capture(level: log::Level, target: &str, message: String) {
+    if let Ok(mut buffer) = LOG_BUFFER.lock() {
+        if buffer.len() >= LOG_BUFFER_CAPACITY {
+            buffer.pop_front();
+        }
+        buffer.push_back(LogEntry {
+            level,
+            target: target.to_string(),
+            message,
+        });
+    }
+}
+
+/// Logger capturing records into the log buffer before forwarding them to an inner logger
+pub struct BladvakLogger {
+    /// Inner logger (`env_logger` or `WebLogger`)
+    inner: Box<dyn log::Log>,
+    /// Optional log file
+    file: Option<FileLogSink>,
+}
+
+impl BladvakLogger {
+    /// Install the logger, chaining `inner` after the capture
+    /// # Errors
+    /// Fails if a logger is already installed
+    pub fn init(
+        inner: Box<dyn log::Log>,
+        inner_filter: log::LevelFilter,
+    ) -> Result<(), log::SetLoggerError> {
+        Self::init_with_file(inner, inner_filter, None)
+    }
+
+    /// Install the logger, chaining `inner` and the log file after the capture
+    /// # Errors
+    /// Fails if a logger is already installed
+    pub fn init_with_file(
+        inner: Box<dyn log::Log>,
+        inner_filter: log::LevelFilter,
+        file: Option<FileLogSink>,
+    ) -> Result<(), log::SetLoggerError> {
+        let mut max_level = inner_filter.max(CAPTURE_LEVEL);
+        if let Some(file) = &file {
+            max_level = max_level.max(file.config.level);
+            LOG_FILE_PATH.get_or_init(|| file.path.clone());
+        }
+        log::set_max_level(max_level);
+        log::set_boxed_logger(Box::new(Self { inner, file }))
+    }
+}
+
+impl log::Log for BladvakLogger {
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        metadata.level() <= CAPTURE_LEVEL
+            || self.inner.enabled(metadata)
+            || self
+                .file
+                .as_ref()
+                .is_some_and(|file| metadata.level() <= file.config.level)
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        if record.level() <= CAPTURE_LEVEL {
+            capture(record.level(), record.target(), record.args().to_string());
+        }
+        if let Some(file) = &self.file {
+            file.write(record.level(), record.target(), record.args());
+        }
+        if self.inner.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file
+            && let Ok(mut guard) = file.file.lock()
+        {
+            use std::io::Write;
+            let _ = guard.0.flush();
+        }
+        self.inner.flush();
+    }
+}
+
+/// Tracing layer capturing the events into the log buffer and writing them in the log file -
+/// used instead of [`BladvakLogger`] with the `tracing` feature
+#[cfg(feature = "tracing")]
+#[derive(Debug)]
+pub struct BladvakLayer {
+    /// Optional log file
+    file: Option<FileLogSink>,
+}
+
+#[cfg(feature = "tracing")]
+impl BladvakLayer {
+    /// Create the layer, writing in the log file if any
+    #[must_use]
+    pub fn new(file: Option<FileLogSink>) -> Self {
+        if let Some(file) = &file {
+            LOG_FILE_PATH.get_or_init(|| file.path.clone());
+        }
+        Self { file }
+    }
+
+    /// Most verbose level used by the layer - to filter the events given to the layer
+    #[must_use]
+    pub fn level_filter(&self) -> tracing_subscriber::filter::LevelFilter {
+        let max_level = self
+            .file
+            .as_ref()
+            .map_or(CAPTURE_LEVEL, |file| CAPTURE_LEVEL.max(file.config.level));
+        match max_level.to_level() {
+            None => tracing_subscriber::filter::LevelFilter::OFF,
+            Some(log::Level::Error) => tracing_subscriber::filter::LevelFilter::ERROR,
+            Some(log::Level::Warn) => tracing_subscriber::filter::LevelFilter::WARN,
+            Some(log::Level::Info) => tracing_subscriber::filter::LevelFilter::INFO,
+            Some(log::Level::Debug) => tracing_subscriber::filter::LevelFilter::DEBUG,
+            Some(log::Level::Trace) => tracing_subscriber::filter::LevelFilter::TRACE,
+        }
+    }
+}
+
+/// Message and target of a tracing event - the `log` records forwarded to tracing have their
+/// target in the `log.target` field
+#[cfg(feature = "tracing")]
+#[derive(Default)]
+struct EventVisitor {
+    /// Formatted message and fields
+    message: String,
+    /// Target of a forwarded `log` record
+    target: Option<String>,
+}
+
+#[cfg(feature = "tracing")]
+impl tracing::field::Visit for EventVisitor {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        if field.name() == "log.target" {
+            self.target = Some(value.to_string());
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn Debug) {
+        use std::fmt::Write;
+
+        match field.name() {
+            "message" if self.message.is_empty() => {
+                let _ = write!(self.message, "{value:?}");
+            }
+            "message" => {
+                let _ = write!(self.message, " {value:?}");
+            }
+            name if name.starts_with("log.") => {}
+            name => {
+                let _ = write!(self.message, " {name}={value:?}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for BladvakLayer {
+    fn on_event(
+        &self,
+        event: &tracing::Event<'_>,
+        _ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let level = match *event.metadata().level() {
+            tracing::Level::ERROR => log::Level::Error,
+            tracing::Level::WARN => log::Level::Warn,
+            tracing::Level::INFO => log::Level::Info,
+            tracing::Level::DEBUG => log::Level::Debug,
+            tracing::Level::TRACE => log::Level::Trace,
+        };
+        let mut visitor = EventVisitor::default();
+        event.record(&mut visitor);
+        let target = visitor
+            .target
+            .unwrap_or_else(|| event.metadata().target().to_string());
+        if let Some(file) = &self.file {
+            file.write(level, &target, &visitor.message);
+        }
+        if level <= CAPTURE_LEVEL {
+            capture(level, &target, visitor.message);
+        }
+    }
+}
+
+/// Get a copy of the captured log records
+#[must_use]
+pub fn log_entries() -> Vec<LogEntry> {
+    LOG_BUFFER
+        .lock()
+        .map(|buffer| buffer.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Clear the captured log records
+pub fn clear_log_entries() {
+    if let Ok(mut buffer) = LOG_BUFFER.lock() {
+        buffer.clear();
+    }
+}
+
+/// Log panel ui state
+#[derive(Debug)]
+struct LogPanelState {
+    /// Most verbose level displayed
+    level: log::LevelFilter,
+    /// Search text
+    search: String,
+}
+
+/// Built-in panel displaying the captured log records
+pub struct LogPanel<App> {
+    /// ui state
+    state: RefCell<LogPanelState>,
+    /// App type
+    _app: PhantomData<App>,
+}
+
+impl<App> Default for LogPanel<App> {
+    fn default() -> Self {
+        Self {
+            state: RefCell::new(LogPanelState {
+                level: log::LevelFilter::Info,
+                search: String::new(),
+            }),
+            _app: PhantomData,
+        }
+    }
+}
+
+impl<App> Debug for LogPanel<App> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogPanel")
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+/// Color of a log level
+fn level_color(ui: &egui::Ui, level: log::Level) -> Color32 {
+    match level {
+        log::Level::Error => ui.visuals().error_fg_color,
+        log::Level::Warn => ui.visuals().warn_fg_color,
+        log::Level::Info => ui.visuals().text_color(),
+        log::Level::Debug | log::Level::Trace => ui.visuals().weak_text_color(),
+    }
+}
+
+impl<App> BladvakPanel for LogPanel<App> {
+    type App = App;
+
+    fn name(&self) -> &'static str {
+        "Logs"
+    }
+
+    fn order(&self) -> PanelOrder {
+        PanelOrder::Priority(LIBRARY_PANEL_PRIORITY)
+    }
+
+    fn has_settings(&self) -> bool {
+        false
+    }
+
+    fn ui_settings(&self, _app: &mut App, _ui: &mut egui::Ui, _error_manager: &mut ErrorManager) {}
+
+    fn has_ui(&self) -> bool {
+        true
+    }
+
+    fn ui(&self, _app: &mut App, ui: &mut egui::Ui, _error_manager: &mut ErrorManager) {
+        let mut state = self.state.borrow_mut();
+        let search = state.search.to_lowercase();
+        let entries = log_entries()
+            .into_iter()
+            .filter(|entry| entry.level <= state.level)
+            .filter(|entry| {
+                search.is_empty()
+                    || entry.message.to_lowercase().contains(&search)
+                    || entry.target.to_lowercase().contains(&search)
+            })
+            .collect::<Vec<_>>();
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("bladvak_log_level")
+                .selected_text(state.level.to_string())
+                .show_ui(ui, |ui| {
+                    for level in [
+                        log::LevelFilter::Error,
+                        log::LevelFilter::Warn,
+                        log::LevelFilter::Info,
+                    ] {
+                        ui.selectable_value(&mut state.level, level, level.to_string());
+                    }
+                });
+            ui.add(
+                egui::TextEdit::singleline(&mut state.search)
+                    .hint_text("Search")
+                    .desired_width(120.0),
+            );
+            if ui.button("Copy").clicked() {
+                let text = entries
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                ui.ctx().copy_text(text);
+            }
+            if ui.button("Clear").clicked() {
+                clear_log_entries();
+            }
+        });
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .id_salt("bladvak_log_scroll")
+            .stick_to_bottom(true)
+            .auto_shrink([false, true])
+            .max_height(300.0)
+            .show(ui, |ui| {
+                for entry in &entries {
+                    ui.label(
+                        RichText::new(entry.to_string())
+                            .monospace()
+                            .color(level_color(ui, entry.level)),
+                    );
+                }
+            });
+    }
+}
@@ -0,0 +1,173 @@
+//! Declarative menus
+//!
+//! The app describes its menus with a [`MenuModel`] (see [`crate::BladvakApp::menu_model`]):
+//! the entries are ids of [`crate::Command`], so the label, the shortcut, the enablement and
+//! the checked state come from the command. The wrapper renders the model in the menu bar - the
+//! menus named "File", "View" and "Help" are merged in the menus of the wrapper - and shows the
+//! menu path of the commands in the palette. The model is plain data and can also feed native
+//! menus.
+
+use eframe::egui;
+
+use crate::command::{command_info, run_command, shortcut_button};
+
+/// Entry of a menu
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuEntry {
+    /// Command id
+    Command(String),
+    /// Separator
+    Separator,
+    /// Sub-menu
+    Menu(Menu),
+}
+
+/// Menu
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Menu {
+    /// Label
+    pub label: String,
+    /// Entries in order
+    pub entries: Vec<MenuEntry>,
+}
+
+impl Menu {
+    /// Create an empty menu
+    pub fn new<S: Into<String>>(label: S) -> Self {
+        Self {
+            label: label.into(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Add a command
+    #[must_use]
+    pub fn command<S: Into<String>>(mut self, id: S) -> Self {
+        self.entries.push(MenuEntry::Command(id.into()));
+        self
+    }
+
+    /// Add a separator
+    #[must_use]
+    pub fn separator(mut self) -> Self {
+        self.entries.push(MenuEntry::Separator);
+        self
+    }
+
+    /// Add a sub-menu
+    #[must_use]
+    pub fn submenu(mut self, menu: Self) -> Self {
+        self.entries.push(MenuEntry::Menu(menu));
+        self
+    }
+
+    /// Menu path of a command in this menu (e.g. "File › Export")
+    fn path_of(&self, id: &str) -> Option<String> {
+        self.entries.iter().find_map(|entry| match entry {
+            MenuEntry::Command(command) if command == id => Some(self.label.clone()),
+            MenuEntry::Menu(menu) => menu
+                .path_of(id)
+                .map(|path| format!("{} › {path}", self.label)),
+            MenuEntry::Command(_) | MenuEntry::Separator => None,
+        })
+    }
+
+    /// Show the entries of the menu
+    pub(crate) fn entries_ui(&self, ui: &mut egui::Ui) {
+        entries_ui(ui, &self.entries);
+    }
+}
+
+/// Show menu entries - also used by the context menus
+pub(crate) fn entries_ui(ui: &mut egui::Ui, entries: &[MenuEntry]) {
+    for entry in entries {
+        match entry {
+            MenuEntry::Command(id) => command_entry(ui, id),
+            MenuEntry::Separator => {
+                ui.separator();
+            }
+            MenuEntry::Menu(menu) => {
+                ui.menu_button(&menu.label, |ui| menu.entries_ui(ui));
+            }
+        }
+    }
+}
+
+/// Show a command entry - a checkbox for a command with a checked state
+fn command_entry(ui: &mut egui::Ui, id: &str) {
+    let Some(info) = command_info(ui.ctx(), id) else {
+        log::warn!("Unknown command {id} in the menu");
+        return;
+    };
+    let clicked = if let Some(mut checked) = info.checked {
+        ui.add_enabled(info.enabled, egui::Checkbox::new(&mut checked, &info.label))
+            .clicked()
+    } else {
+        let button = shortcut_button(ui, &info.label, info.shortcut.as_ref());
+        ui.add_enabled(info.enabled, button).clicked()
+    };
+    if clicked {
+        run_command(ui.ctx(), id);
+        ui.close();
+    }
+}
+
+/// Menus of the app
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MenuModel {
+    /// Menus of the menu bar in order
+    pub menus: Vec<Menu>,
+}
+
+impl MenuModel {
+    /// Create an empty model
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a menu
+    #[must_use]
+    pub fn menu(mut self, menu: Menu) -> Self {
+        self.menus.push(menu);
+        self
+    }
+
+    /// Check if the model has no menu
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.menus.is_empty()
+    }
+
+    /// Menu path of a command (e.g. "File › Export")
+    #[must_use]
+    pub fn path_of(&self, id: &str) -> Option<String> {
+        self.menus.iter().find_map(|menu| menu.path_of(id))
+    }
+
+    /// The "File" menu - merged in the File menu of the wrapper
+    pub(crate) fn file_menu(&self) -> Option<&Menu> {
+        self.menus.iter().find(|menu| menu.label == "File")
+    }
+
+    /// The "View" menu - merged in the View menu of the wrapper
+    pub(crate) fn view_menu(&self) -> Option<&Menu> {
+        self.menus.iter().find(|menu| menu.label == "View")
+    }
+
+    /// The "Help" menu - merged in the Help menu of the wrapper
+    pub(crate) fn help_menu(&self) -> Option<&Menu> {
+        self.menus.iter().find(|menu| menu.label == "Help")
+    }
+
+    /// Show the menus between the View menu and the Help menu
+    pub(crate) fn menu_bar_ui(&self, ui: &mut egui::Ui) {
+        for menu in self
+            .menus
+            .iter()
+            .filter(|menu| !matches!(menu.label.as_str(), "File" | "View" | "Help"))
+        {
+            ui.menu_button(&menu.label, |ui| menu.entries_ui(ui));
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Observable app state
+//!
+//! An [`Observable`] wraps a value of the app state and counts its changes, a change requests
+//! a repaint once the observable is bound to the egui context. Panels keep a [`Subscription`]
+//! to know if the value changed since they last looked at it - e.g. to implement
+//! [`crate::app::BladvakPanel::changed_since_last_frame`].
+
+use eframe::egui;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{cell::Cell, fmt::Debug, ops::Deref};
+
+/// Value notifying its changes
+#[derive(Clone, Default)]
+pub struct Observable<T> {
+    /// Value
+    value: T,
+    /// Number of changes
+    version: u64,
+    /// Context repainted on change
+    ctx: Option<egui::Context>,
+}
+
+impl<T: Debug> Debug for Observable<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Observable")
+            .field("value", &self.value)
+            .field("version", &self.version)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Observable<T> {
+    /// Create a new observable
+    #[must_use]
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            version: 0,
+            ctx: None,
+        }
+    }
+
+    /// Request a repaint of `ctx` on each change
+    pub fn bind(&mut self, ctx: &egui::Context) {
+        self.ctx = Some(ctx.clone());
+    }
+
+    /// Get the value
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Number of changes of the value
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Modify the value - always notifies a change
+    pub fn modify<R>(&mut self, func: impl FnOnce(&mut T) -> R) -> R {
+        let result = func(&mut self.value);
+        self.notify();
+        result
+    }
+
+    /// Edit the value - notifies a change only if `func` returns `true`
+    ///
+    /// Fits the egui widgets: `value.edit(|v| ui.checkbox(v, "label").changed())`
+    pub fn edit(&mut self, func: impl FnOnce(&mut T) -> bool) -> bool {
+        let changed = func(&mut self.value);
+        if changed {
+            self.notify();
+        }
+        changed
+    }
+
+    /// Subscribe to the changes - the subscription starts up to date
+    #[must_use]
+    pub fn subscribe(&self) -> Subscription {
+        Subscription {
+            seen: Cell::new(Some(self.version)),
+        }
+    }
+
+    /// Notify a change
+    fn notify(&mut self) {
+        self.version = self.version.wrapping_add(1);
+        if let Some(ctx) = &self.ctx {
+            ctx.request_repaint();
+        }
+    }
+}
+
+impl<T: PartialEq> Observable<T> {
+    /// Set the value - notifies a change only if the value is different
+    pub fn set(&mut self, value: T) -> bool {
+        self.edit(|current| {
+            if *current == value {
+                false
+            } else {
+                *current = value;
+                true
+            }
+        })
+    }
+}
+
+impl<T> Deref for Observable<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Serialize> Serialize for Observable<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Observable<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
+
+/// Subscription to the changes of an [`Observable`]
+#[derive(Debug, Clone, Default)]
+pub struct Subscription {
+    /// Last version seen - `None` if never checked
+    seen: Cell<Option<u64>>,
+}
+
+impl Subscription {
+    /// Check if the observable changed since the last check - a new default subscription
+    /// reports a change at the first check
+    pub fn changed<T>(&self, observable: &Observable<T>) -> bool {
+        let version = Some(observable.version());
+        self.seen.replace(version) != version
+    }
+}
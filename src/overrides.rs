@@ -0,0 +1,146 @@
+//! Session overrides of the panel states
+//!
+//! `--panel "Histogram=window" --panel "Log=hidden"` (or `?panel=Histogram=window&panel=Log=hidden` on web)
+//! overrides the persisted panel states for the session only
+
+use std::{collections::BTreeMap, str::FromStr};
+
+use crate::{
+    app::{PanelOpen, PanelState},
+    errors::{AppError, Severity},
+};
+
+/// Flag of the panel overrides
+const PANEL_FLAG: &str = "--panel";
+
+impl FromStr for PanelOpen {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "window" | "windows" => Ok(Self::AsWindows),
+            "sidebar" | "side" => Ok(Self::AsSideBar),
+            "hidden" | "none" => Ok(Self::None),
+            _ => Err(AppError::new(format!(
+                "Invalid panel state '{value}' (expected window, sidebar or hidden)"
+            ))),
+        }
+    }
+}
+
+/// Panel states overridden for the session
+#[derive(Debug, Default)]
+pub(crate) struct PanelOverrides {
+    /// Requested panel states
+    requested: Vec<(String, PanelOpen)>,
+    /// Overridden state and persisted state of the panels
+    applied: BTreeMap<String, (PanelOpen, PanelOpen)>,
+}
+
+impl PanelOverrides {
+    /// Parse the `--panel` flags
+    pub(crate) fn from_args(args: &[String]) -> (Self, Vec<AppError>) {
+        let mut requested = Vec::new();
+        let mut errors = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let value = if arg == PANEL_FLAG {
+                args.next().map(String::as_str)
+            } else if let Some(value) = arg
+                .strip_prefix(PANEL_FLAG)
+                .and_then(|v| v.strip_prefix('='))
+            {
+                Some(value)
+            } else {
+                continue;
+            };
+            let Some((name, state)) = value.and_then(|value| value.rsplit_once('=')) else {
+                errors.push(
+                    AppError::new(format!(
+                        "Invalid {PANEL_FLAG} flag (expected \"Name=state\")"
+                    ))
+                    .with_severity(Severity::Warning),
+                );
+                continue;
+            };
+            match state.parse::<PanelOpen>() {
+                Ok(state) => requested.push((name.to_string(), state)),
+                Err(err) => errors.push(err.with_severity(Severity::Warning)),
+            }
+        }
+        (
+            Self {
+                requested,
+                applied: BTreeMap::new(),
+            },
+            errors,
+        )
+    }
+
+    /// Apply the overrides to the panel states
+    pub(crate) fn apply(
+        &mut self,
+        panel_state: &mut BTreeMap<String, PanelState>,
+    ) -> Vec<AppError> {
+        let mut errors = Vec::new();
+        for (name, state) in &self.requested {
+            let Some(current) = panel_state.get_mut(name) else {
+                errors.push(
+                    AppError::new(format!("Unknown panel '{name}'"))
+                        .with_severity(Severity::Warning),
+                );
+                continue;
+            };
+            log::info!("Panel {name} overridden: {state}");
+            self.applied
+                .insert(name.clone(), (state.clone(), current.open.clone()));
+            current.open = state.clone();
+        }
+        errors
+    }
+
+    /// Panel states to persist - the overridden panels left unchanged get their persisted state back
+    pub(crate) fn persisted(
+        &self,
+        panel_state: &BTreeMap<String, PanelState>,
+    ) -> BTreeMap<String, PanelState> {
+        let mut persisted = panel_state.clone();
+        for (name, (overridden, original)) in &self.applied {
+            if let Some(state) = persisted.get_mut(name)
+                && state.open == *overridden
+            {
+                state.open = original.clone();
+            }
+        }
+        persisted
+    }
+
+    /// Check if there is no override
+    pub(crate) fn is_empty(&self) -> bool {
+        self.applied.is_empty()
+    }
+}
+
+/// Convert the `panel` and `profile` parameters of the page URL to `--panel` and `--profile` flags
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn url_args() -> Vec<String> {
+    let Some(search) = eframe::web_sys::window().and_then(|window| window.location().search().ok())
+    else {
+        return Vec::new();
+    };
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .filter_map(|param| {
+            let (flag, value) = param.split_once('=')?;
+            let flag = match flag {
+                "panel" => PANEL_FLAG,
+                "profile" => crate::profile::PROFILE_FLAG,
+                _ => return None,
+            };
+            let value = js_sys::decode_uri_component(&value.replace('+', " ")).ok()?;
+            Some([flag.to_string(), value.as_string()?])
+        })
+        .flatten()
+        .collect()
+}
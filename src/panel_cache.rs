@@ -0,0 +1,230 @@
+//! Geometry cache of the panels
+//!
+//! A panel opting in with [`BladvakPanel::cache_ui`] is not rendered when
+//! [`BladvakPanel::changed_since_last_frame`] returns `false`: the shapes painted at the last
+//! render are painted again instead, and its interactive widgets are registered again so that
+//! egui still finds them under the pointer. The panel is rendered again, so that its widgets
+//! get the input, when the pointer is over it, when one of its widgets has the focus or when
+//! there are input events in the frame.
+
+use eframe::egui::{self, Id, LayerId, Rect, Sense, epaint::ClippedShape};
+use std::collections::BTreeMap;
+
+use crate::{app::BladvakPanel, errors::ErrorManager};
+
+/// Geometry painted by a panel
+#[derive(Debug, Clone)]
+struct CachedGeometry {
+    /// Layer of the panel
+    layer: LayerId,
+    /// Rect used by the panel
+    rect: Rect,
+    /// Available width when rendered
+    width: f32,
+    /// Theme when rendered
+    dark_mode: bool,
+    /// Painted shapes
+    shapes: Vec<ClippedShape>,
+    /// Interactive widgets of the panel
+    widgets: Vec<(Id, Rect, Sense)>,
+    /// One of the widgets of the panel had the focus
+    focused: bool,
+}
+
+/// Geometry cache of the panels
+#[derive(Debug, Default)]
+pub(crate) struct PanelCache(BTreeMap<String, CachedGeometry>);
+
+impl PanelCache {
+    /// Show a panel - reuse the cached geometry when the panel did not change
+    pub(crate) fn show<App>(
+        &mut self,
+        panel: &dyn BladvakPanel<App = App>,
+        app: &mut App,
+        ui: &mut egui::Ui,
+        error_manager: &mut ErrorManager,
+    ) {
+        if !panel.cache_ui() {
+            panel.ui(app, ui, error_manager);
+            return;
+        }
+        let layer = ui.layer_id();
+        let width = ui.available_width();
+        let dark_mode = ui.visuals().dark_mode;
+        if !panel.changed_since_last_frame(app)
+            && let Some(cached) = self.0.get(panel.name())
+            && cached.layer == layer
+            && cached.dark_mode == dark_mode
+            && (cached.width - width).abs() < 0.5
+            && !cached.focused
+            && !has_input(ui, Rect::from_min_size(ui.cursor().min, cached.rect.size()))
+        {
+            let (rect, _) = ui.allocate_exact_size(cached.rect.size(), egui::Sense::hover());
+            let delta = rect.min - cached.rect.min;
+            ui.ctx().graphics_mut(|graphics| {
+                let list = graphics.entry(layer);
+                for ClippedShape { clip_rect, shape } in &cached.shapes {
+                    let mut shape = shape.clone();
+                    shape.translate(delta);
+                    list.add(clip_rect.translate(delta), shape);
+                }
+            });
+            for (id, widget_rect, sense) in &cached.widgets {
+                ui.interact(widget_rect.translate(delta), *id, *sense);
+            }
+            return;
+        }
+        let start = ui
+            .ctx()
+            .graphics(|graphics| graphics.get(layer).map_or(0, |list| list.next_idx().0));
+        let rect = ui
+            .scope(|ui| panel.ui(app, ui, error_manager))
+            .response
+            .rect;
+        let shapes = ui.ctx().graphics(|graphics| {
+            graphics
+                .get(layer)
+                .map(|list| list.all_entries().skip(start).cloned().collect())
+                .unwrap_or_default()
+        });
+        let ctx = ui.ctx();
+        let focused = ctx
+            .memory(egui::Memory::focused)
+            .and_then(|id| ctx.read_response(id))
+            .is_some_and(|response| rect.intersects(response.rect));
+        let widgets = ctx.viewport(|viewport| {
+            viewport
+                .this_pass
+                .widgets
+                .get_layer(layer)
+                .filter(|widget| widget.sense.interactive() && rect.contains_rect(widget.rect))
+                .map(|widget| (widget.id, widget.rect, widget.sense))
+                .collect()
+        });
+        self.0.insert(
+            panel.name().to_string(),
+            CachedGeometry {
+                layer,
+                rect,
+                width,
+                dark_mode,
+                shapes,
+                widgets,
+                focused,
+            },
+        );
+    }
+}
+
+/// Is there some input for a panel shown in `rect` - an input event or the pointer over it
+fn has_input(ui: &egui::Ui, rect: Rect) -> bool {
+    ui.input(|input| {
+        !input.events.is_empty()
+            || input
+                .pointer
+                .hover_pos()
+                .is_some_and(|pos| rect.contains(pos))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    //! Input of the cached panels
+
+    use super::PanelCache;
+    use crate::{app::BladvakPanel, errors::ErrorManager};
+    use eframe::egui;
+
+    /// Panel with a button counting its renders and its clicks
+    #[derive(Debug)]
+    struct ButtonPanel;
+
+    /// State of the test app
+    #[derive(Debug, Default)]
+    struct Counts {
+        /// Number of renders of the panel
+        renders: usize,
+        /// Number of clicks on the button
+        clicks: usize,
+    }
+
+    impl BladvakPanel for ButtonPanel {
+        type App = Counts;
+
+        fn name(&self) -> &'static str {
+            "Button"
+        }
+
+        fn has_settings(&self) -> bool {
+            false
+        }
+
+        fn ui_settings(&self, _app: &mut Counts, _ui: &mut egui::Ui, _errors: &mut ErrorManager) {}
+
+        fn has_ui(&self) -> bool {
+            true
+        }
+
+        fn ui(&self, app: &mut Counts, ui: &mut egui::Ui, _errors: &mut ErrorManager) {
+            app.renders += 1;
+            if ui.button("Click me").clicked() {
+                app.clicks += 1;
+            }
+        }
+
+        fn cache_ui(&self) -> bool {
+            true
+        }
+
+        fn changed_since_last_frame(&self, _app: &Counts) -> bool {
+            false
+        }
+    }
+
+    /// Run a frame with the given input events
+    fn frame(
+        ctx: &egui::Context,
+        cache: &mut PanelCache,
+        app: &mut Counts,
+        events: Vec<egui::Event>,
+    ) {
+        let input = egui::RawInput {
+            events,
+            screen_rect: Some(egui::Rect::from_min_size(
+                egui::Pos2::ZERO,
+                egui::vec2(400.0, 300.0),
+            )),
+            ..egui::RawInput::default()
+        };
+        let _ = ctx.run_ui(input, |ui| {
+            cache.show(&ButtonPanel, app, ui, &mut ErrorManager::default());
+        });
+    }
+
+    /// A cached panel is not rendered without input and still gets a click
+    #[test]
+    fn cached_panel_click() {
+        let ctx = egui::Context::default();
+        let mut cache = PanelCache::default();
+        let mut app = Counts::default();
+        frame(&ctx, &mut cache, &mut app, Vec::new());
+        frame(&ctx, &mut cache, &mut app, Vec::new());
+        assert_eq!(app.renders, 1);
+
+        let pos = egui::pos2(20.0, 15.0);
+        let button = |pressed| egui::Event::PointerButton {
+            pos,
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: egui::Modifiers::default(),
+        };
+        frame(
+            &ctx,
+            &mut cache,
+            &mut app,
+            vec![egui::Event::PointerMoved(pos), button(true)],
+        );
+        frame(&ctx, &mut cache, &mut app, vec![button(false)]);
+        assert_eq!(app.clicks, 1);
+    }
+}
@@ -0,0 +1,196 @@
+//! Frame-time performance overlay
+
+use eframe::egui::{self, Color32, Pos2, Stroke};
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::utils::time::{Duration, Instant};
+
+/// Number of frames kept in the history
+const FRAME_HISTORY: usize = 120;
+
+/// Performance statistics of the wrapper update loop
+#[derive(Debug, Default)]
+pub struct PerfStats {
+    /// Start of the current frame
+    frame_start: Option<Instant>,
+    /// Start of the current section
+    section_start: Option<Instant>,
+    /// Timings of the current frame sections
+    current_sections: Vec<(&'static str, Duration)>,
+    /// Timings of the last frame sections
+    last_sections: Vec<(&'static str, Duration)>,
+    /// Last frame durations (in seconds)
+    frame_times: VecDeque<f32>,
+    /// Render durations of the panels of the current frame
+    current_panels: BTreeMap<String, Duration>,
+    /// Render durations of the panels of the last frame
+    last_panels: BTreeMap<String, Duration>,
+    /// Does the current frame handle input events
+    has_input: bool,
+    /// Processing time of the last frame with input events
+    input_latency: Option<Duration>,
+}
+
+/// Performance metrics of the last frame - see [`metrics`]
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    /// Processing time of the last frame
+    pub frame_time: Duration,
+    /// Mean processing time of the last frames
+    pub mean_frame_time: Duration,
+    /// Processing time of the last frame with input events, i.e. the delay before the input is shown
+    pub input_latency: Option<Duration>,
+    /// Render durations of the panels
+    pub panel_costs: BTreeMap<String, Duration>,
+    /// Durations of the sections of the update loop
+    pub sections: Vec<(&'static str, Duration)>,
+    /// Number of running background tasks
+    pub pending_tasks: usize,
+}
+
+/// Id of the metrics in the egui context
+fn metrics_id() -> egui::Id {
+    egui::Id::new("bladvak_metrics")
+}
+
+/// Get the performance metrics of the last frame (e.g. to reduce the quality when the frame time spikes)
+#[must_use]
+pub fn metrics(ctx: &egui::Context) -> Metrics {
+    ctx.data(|data| data.get_temp::<Metrics>(metrics_id()))
+        .unwrap_or_default()
+}
+
+impl PerfStats {
+    /// Start a new frame
+    pub(crate) fn begin_frame(&mut self, ctx: &egui::Context) {
+        let now = Instant::now();
+        self.frame_start = Some(now);
+        self.section_start = Some(now);
+        self.current_sections.clear();
+        self.current_panels.clear();
+        self.has_input = ctx.input(|i| !i.events.is_empty() || i.pointer.any_down());
+    }
+
+    /// Record the render duration of a panel
+    pub(crate) fn panel(&mut self, name: &str, duration: Duration) {
+        *self.current_panels.entry(name.to_string()).or_default() += duration;
+    }
+
+    /// End the current section
+    pub(crate) fn section(&mut self, name: &'static str) {
+        let now = Instant::now();
+        if let Some(start) = self.section_start {
+            self.current_sections.push((name, now - start));
+        }
+        self.section_start = Some(now);
+    }
+
+    /// End the current frame
+    pub(crate) fn end_frame(&mut self) {
+        if let Some(start) = self.frame_start.take() {
+            let elapsed = start.elapsed();
+            if self.frame_times.len() >= FRAME_HISTORY {
+                self.frame_times.pop_front();
+            }
+            self.frame_times.push_back(elapsed.as_secs_f32());
+            if self.has_input {
+                self.input_latency = Some(elapsed);
+            }
+        }
+        std::mem::swap(&mut self.last_sections, &mut self.current_sections);
+        std::mem::swap(&mut self.last_panels, &mut self.current_panels);
+    }
+
+    /// Publish the metrics of the last frame in the egui context
+    pub(crate) fn publish(&self, ctx: &egui::Context, pending_tasks: usize) {
+        let metrics = Metrics {
+            frame_time: Duration::from_secs_f32(self.last_frame_time()),
+            mean_frame_time: Duration::from_secs_f32(self.mean_frame_time()),
+            input_latency: self.input_latency,
+            panel_costs: self.last_panels.clone(),
+            sections: self.last_sections.clone(),
+            pending_tasks,
+        };
+        ctx.data_mut(|data| data.insert_temp(metrics_id(), metrics));
+    }
+
+    /// Duration of the last frame (in seconds)
+    #[must_use]
+    pub fn last_frame_time(&self) -> f32 {
+        self.frame_times.back().copied().unwrap_or_default()
+    }
+
+    /// Mean duration of the last frames (in seconds)
+    #[must_use]
+    pub fn mean_frame_time(&self) -> f32 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let count = self.frame_times.len() as f32;
+        self.frame_times.iter().sum::<f32>() / count
+    }
+
+    /// Timings of the last frame sections
+    #[must_use]
+    pub fn sections(&self) -> &[(&'static str, Duration)] {
+        &self.last_sections
+    }
+
+    /// Draw the frame time graph
+    fn graph_ui(&self, ui: &mut egui::Ui) {
+        #[allow(clippy::cast_precision_loss)]
+        let width = FRAME_HISTORY as f32;
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(width, 40.0), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+        let max = self.frame_times.iter().copied().fold(1.0 / 60.0, f32::max);
+        let points = self
+            .frame_times
+            .iter()
+            .enumerate()
+            .map(|(idx, time)| {
+                #[allow(clippy::cast_precision_loss)]
+                let x = rect.left() + idx as f32;
+                Pos2::new(x, rect.bottom() - rect.height() * time / max)
+            })
+            .collect::<Vec<_>>();
+        painter.add(egui::Shape::line(
+            points,
+            Stroke::new(1.0, ui.visuals().text_color()),
+        ));
+        // 60 fps budget
+        let budget_y = rect.bottom() - rect.height() * (1.0 / 60.0) / max;
+        painter.hline(
+            rect.x_range(),
+            budget_y,
+            Stroke::new(1.0, Color32::from_rgb(0, 160, 0)),
+        );
+    }
+
+    /// Show the overlay in the top right corner
+    pub(crate) fn show_overlay(&self, ctx: &egui::Context) {
+        egui::Area::new(egui::Id::new("bladvak_perf_overlay"))
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-8.0, 32.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    let mean = self.mean_frame_time();
+                    let fps = if mean > 0.0 { 1.0 / mean } else { 0.0 };
+                    ui.monospace(format!("FPS (cpu): {fps:.0}"));
+                    ui.monospace(format!("Frame: {:.2} ms", self.last_frame_time() * 1000.0));
+                    self.graph_ui(ui);
+                    for (name, duration) in self.sections() {
+                        ui.monospace(format!("{name}: {:.2} ms", duration.as_secs_f32() * 1000.0));
+                    }
+                    for (name, duration) in &self.last_panels {
+                        ui.monospace(format!(
+                            "  {name}: {:.2} ms",
+                            duration.as_secs_f32() * 1000.0
+                        ));
+                    }
+                });
+            });
+    }
+}
@@ -0,0 +1,174 @@
+//! Partial persistence of the state
+//!
+//! The internal state of the wrapper (settings, panels...) is saved on its own, so it is kept
+//! even when the state of the app cannot be serialized. A field of the app wrapped in a
+//! [`SessionOnly`] is not saved, and [`crate::BladvakApp::persist_state`] opts out of saving
+//! the app state at all. [`Bladvak::clear_storage`] deletes the saved state.
+
+use eframe::egui;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::IgnoredAny};
+use std::{
+    fmt::Debug,
+    ops::{Deref, DerefMut},
+};
+
+use crate::app::{Bladvak, BladvakApp, BladvakSavedState};
+
+/// Value kept for the session only - saved as nothing and restored with its default
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionOnly<T>(pub T);
+
+impl<T> SessionOnly<T> {
+    /// Create a session-only value
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Get the value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for SessionOnly<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for SessionOnly<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> Serialize for SessionOnly<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_unit()
+    }
+}
+
+impl<'de, T: Default> Deserialize<'de> for SessionOnly<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        IgnoredAny::deserialize(deserializer)?;
+        Ok(Self::default())
+    }
+}
+
+/// Storage key of the internal state
+fn internal_key(key: &str) -> String {
+    format!("{key}_internal")
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Save the state under a key - the internal state is saved on its own
+    pub(crate) fn write_state(&self, storage: &mut dyn eframe::Storage, key: &str) {
+        if self.ignore_saved_state {
+            storage.set_string(&internal_key(key), String::new());
+        } else {
+            eframe::set_value(storage, &internal_key(key), &self.internal);
+        }
+        let state = if self.app.persist_state() || self.ignore_saved_state {
+            ron::to_string(self)
+                .inspect_err(|err| log::warn!("Cannot serialize the app state: {err}"))
+                .ok()
+        } else {
+            None
+        };
+        // an empty state makes the next start use the internal state only
+        storage.set_string(key, state.unwrap_or_default());
+    }
+
+    /// Internal state saved under a key - used when the full state is missing or invalid
+    pub(crate) fn saved_internal_state(
+        storage: &dyn eframe::Storage,
+        key: &str,
+    ) -> Option<BladvakSavedState> {
+        let data = storage.get_string(&internal_key(key))?;
+        if data.is_empty() {
+            return None;
+        }
+        match ron::from_str::<BladvakSavedState>(&data) {
+            Ok(internal) => {
+                log::info!("Using the saved internal state");
+                Some(internal)
+            }
+            Err(err) => {
+                log::warn!("Failed to decode the saved internal state: {err}");
+                None
+            }
+        }
+    }
+}
+
+/// Id of the confirmation of the storage clearing
+fn confirm_clear_id() -> egui::Id {
+    egui::Id::new("bladvak_confirm_clear_storage")
+}
+
+/// Ask to clear the saved state - the confirmation is shown by the wrapper
+pub(crate) fn request_clear_storage(ctx: &egui::Context) {
+    ctx.data_mut(|data| data.insert_temp(confirm_clear_id(), true));
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Debug + Default + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Delete the saved state of the active profile, reset the state to the defaults and undo
+    /// the effects of the settings
+    pub fn clear_storage(&mut self, ctx: &egui::Context, storage: &mut dyn eframe::Storage) {
+        let key = self.storage_key();
+        storage.remove_string(&key);
+        storage.remove_string(&internal_key(&key));
+        if key == M::storage_key() {
+            storage.remove_string(eframe::APP_KEY);
+        }
+        storage.flush();
+        log::info!("Cleared the saved state {key}");
+        crate::journal::record(crate::journal::JournalKind::Settings, "Cleared the storage");
+        let open = self.internal.settings.open;
+        self.app = M::default();
+        self.internal = Self::merge_saved_state(None, &self.panel_list);
+        Self::merge_region_state(&mut self.internal, &self.regions);
+        self.internal.settings.open = open;
+        self.ignore_saved_state = false;
+        self.reapply_general_settings(ctx);
+    }
+
+    /// Ask the confirmation of [`Bladvak::clear_storage`]
+    pub(crate) fn show_clear_storage(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if !ctx.data(|data| data.get_temp::<bool>(confirm_clear_id()).unwrap_or(false)) {
+            return;
+        }
+        let mut answered = false;
+        let modal = egui::Modal::new(egui::Id::new("Modal clear storage")).show(ctx, |ui| {
+            crate::a11y::annotate(ui, egui::accesskit::Role::AlertDialog, "Clear storage");
+            ui.heading("Clear storage");
+            ui.label(format!(
+                "The saved state of {} is deleted and the app is reset to its defaults.",
+                M::name()
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("Clear").clicked() {
+                    answered = true;
+                    match frame.storage_mut() {
+                        Some(storage) => self.clear_storage(ctx, storage),
+                        None => log::warn!("No storage to clear"),
+                    }
+                }
+                if ui.button("Cancel").clicked() {
+                    answered = true;
+                }
+            });
+        });
+        if answered || modal.should_close() {
+            ctx.data_mut(|data| data.remove::<bool>(confirm_clear_id()));
+        }
+    }
+}
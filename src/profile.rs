@@ -0,0 +1,198 @@
+//! Named profiles of the saved state
+//!
+//! Each profile (e.g. "work", "demo") saves the state under its own storage key, derived from
+//! [`crate::BladvakApp::storage_key`]. The profile is chosen with `--profile NAME` (or
+//! `?profile=NAME` on web), the last used profile otherwise, and can be switched from the File
+//! menu: the state of the current profile is saved and the state of the new one loaded.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+use crate::app::{Bladvak, BladvakApp};
+
+/// Flag of the profile
+pub(crate) const PROFILE_FLAG: &str = "--profile";
+
+/// Name of the profile using the storage key of the app
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Profiles saved next to the state
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProfilesIndex {
+    /// Last used profile
+    last: Option<String>,
+    /// Known profiles
+    known: Vec<String>,
+}
+
+/// Profiles of the app
+#[derive(Debug)]
+pub(crate) struct Profiles {
+    /// Active profile
+    active: String,
+    /// Known profiles - the default profile is not listed
+    known: Vec<String>,
+    /// Profile to switch to at the next frame
+    pending_switch: Option<String>,
+    /// Name of the profile being created in the menu
+    new_name: String,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_PROFILE.to_string(),
+            known: Vec::new(),
+            pending_switch: None,
+            new_name: String::new(),
+        }
+    }
+}
+
+/// Parse the `--profile` flag
+fn profile_from_args(args: &[String]) -> Option<String> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = if arg == PROFILE_FLAG {
+            args.next().cloned()
+        } else if let Some(value) = arg
+            .strip_prefix(PROFILE_FLAG)
+            .and_then(|v| v.strip_prefix('='))
+        {
+            Some(value.to_string())
+        } else {
+            continue;
+        };
+        if let Some(value) = value.map(|value| value.trim().to_string())
+            && !value.is_empty()
+        {
+            return Some(value);
+        }
+        log::warn!("Invalid {PROFILE_FLAG} flag (expected a name)");
+    }
+    None
+}
+
+impl Profiles {
+    /// Storage key of the profiles index
+    fn index_key(base: &str) -> String {
+        format!("{base}_profiles")
+    }
+
+    /// Load the profiles - the `--profile` flag wins over the last used profile
+    pub(crate) fn load(storage: Option<&dyn eframe::Storage>, base: &str, args: &[String]) -> Self {
+        let index = storage
+            .and_then(|storage| storage.get_string(&Self::index_key(base)))
+            .and_then(|data| ron::from_str::<ProfilesIndex>(&data).ok())
+            .unwrap_or_default();
+        let mut profiles = Self {
+            active: profile_from_args(args)
+                .or(index.last)
+                .unwrap_or_else(|| DEFAULT_PROFILE.to_string()),
+            known: index.known,
+            ..Self::default()
+        };
+        profiles.remember_active();
+        log::info!("Using the profile {}", profiles.active);
+        profiles
+    }
+
+    /// Add the active profile to the known profiles
+    fn remember_active(&mut self) {
+        if self.active != DEFAULT_PROFILE && !self.known.contains(&self.active) {
+            self.known.push(self.active.clone());
+            self.known.sort();
+        }
+    }
+
+    /// Active profile
+    pub(crate) fn active(&self) -> &str {
+        &self.active
+    }
+
+    /// Storage key of the state of the active profile
+    pub(crate) fn storage_key(&self, base: &str) -> String {
+        if self.active == DEFAULT_PROFILE {
+            base.to_string()
+        } else {
+            format!("{base}@{}", self.active)
+        }
+    }
+
+    /// Save the profiles index
+    pub(crate) fn save(&self, storage: &mut dyn eframe::Storage, base: &str) {
+        eframe::set_value(
+            storage,
+            &Self::index_key(base),
+            &ProfilesIndex {
+                last: Some(self.active.clone()),
+                known: self.known.clone(),
+            },
+        );
+    }
+
+    /// Show the profiles menu
+    pub(crate) fn menu(&mut self, ui: &mut egui::Ui) {
+        for name in std::iter::once(DEFAULT_PROFILE).chain(self.known.iter().map(String::as_str)) {
+            if ui.radio(self.active == name, name).clicked() && self.active != name {
+                ui.close();
+                self.pending_switch = Some(name.to_string());
+            }
+        }
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.new_name).hint_text("New profile"));
+            let name = self.new_name.trim();
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("Create"))
+                .clicked()
+            {
+                ui.close();
+                self.pending_switch = Some(name.to_string());
+                self.new_name.clear();
+            }
+        });
+    }
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Debug + Default + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Switch to the requested profile - the current state is saved first
+    pub(crate) fn process_profile_switch(
+        &mut self,
+        ctx: &egui::Context,
+        frame: &mut eframe::Frame,
+    ) {
+        let Some(profile) = self.profiles.pending_switch.take() else {
+            return;
+        };
+        let Some(storage) = frame.storage_mut() else {
+            log::warn!("Cannot switch profile without storage");
+            return;
+        };
+        eframe::App::save(self, storage);
+        self.profiles.active = profile;
+        self.profiles.remember_active();
+        self.profiles.save(storage, &M::storage_key());
+        log::info!("Switching to the profile {}", self.profiles.active);
+        crate::journal::record(
+            crate::journal::JournalKind::Settings,
+            format!("Profile {}", self.profiles.active),
+        );
+        let saved = Self::saved_state_string(storage, &self.storage_key())
+            .and_then(|data| ron::from_str::<Self>(&data).ok())
+            .filter(|saved| !saved.ignore_saved_state);
+        if let Some(saved) = saved {
+            self.apply_saved_state(ctx, saved);
+        } else {
+            // new profile or app state not saved
+            self.app = M::default();
+            let internal = Self::saved_internal_state(storage, &self.storage_key());
+            self.internal = Self::merge_saved_state(internal, &self.panel_list);
+            Self::merge_region_state(&mut self.internal, &self.regions);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Progress indicators
+//!
+//! A [`ProgressHandle`] is created with [`progress`] and updated by the app or by a background
+//! task. While a progress is active, the wrapper shows a progress bar in the status area and a
+//! spinner in the menu bar. The progress ends with [`ProgressHandle::finish`] or when every
+//! clone of the handle is dropped. A short message set with [`show_status`] is shown in the
+//! status area for [`STATUS_DURATION`] seconds.
+
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+
+/// Time (in seconds) a status message is shown
+pub const STATUS_DURATION: f64 = 4.0;
+
+/// State of a progress
+#[derive(Debug, Clone)]
+pub struct ProgressState {
+    /// Label
+    pub label: String,
+    /// Fraction done (0.0 - 1.0) - `None` if indeterminate
+    pub fraction: Option<f32>,
+    /// Is the progress finished
+    finished: bool,
+}
+
+/// Handle to update a progress - can be sent to a background task
+#[derive(Debug, Clone)]
+pub struct ProgressHandle {
+    /// Shared state
+    state: Arc<Mutex<ProgressState>>,
+    /// Context repainted on update
+    ctx: egui::Context,
+}
+
+impl ProgressHandle {
+    /// Update the state and request a repaint
+    fn update(&self, func: impl FnOnce(&mut ProgressState)) {
+        if let Ok(mut state) = self.state.lock() {
+            func(&mut state);
+        }
+        self.ctx.request_repaint();
+    }
+
+    /// Set the fraction done (0.0 - 1.0)
+    pub fn set_fraction(&self, fraction: f32) {
+        self.update(|state| state.fraction = Some(fraction.clamp(0.0, 1.0)));
+    }
+
+    /// Set the label
+    pub fn set_label<S: Into<String>>(&self, label: S) {
+        let label = label.into();
+        self.update(|state| state.label = label);
+    }
+
+    /// Make the progress indeterminate
+    pub fn set_indeterminate(&self) {
+        self.update(|state| state.fraction = None);
+    }
+
+    /// Finish the progress
+    pub fn finish(&self) {
+        self.update(|state| state.finished = true);
+    }
+}
+
+/// Active progresses shared through the egui context
+#[derive(Clone, Default)]
+struct SharedHandle(Arc<Mutex<Vec<Arc<Mutex<ProgressState>>>>>);
+
+impl SharedHandle {
+    /// Id of the shared state in the egui context
+    fn id() -> egui::Id {
+        egui::Id::new("bladvak_progress")
+    }
+
+    /// Get the shared state from the egui context
+    fn get(ctx: &egui::Context) -> Self {
+        ctx.data_mut(|data| data.get_temp_mut_or_default::<Self>(Self::id()).clone())
+    }
+}
+
+/// Start an indeterminate progress
+#[must_use]
+pub fn progress<S: Into<String>>(ctx: &egui::Context, label: S) -> ProgressHandle {
+    let state = Arc::new(Mutex::new(ProgressState {
+        label: label.into(),
+        fraction: None,
+        finished: false,
+    }));
+    if let Ok(mut progresses) = SharedHandle::get(ctx).0.lock() {
+        progresses.push(state.clone());
+    }
+    ctx.request_repaint();
+    ProgressHandle {
+        state,
+        ctx: ctx.clone(),
+    }
+}
+
+/// Active progresses - the finished and dropped progresses are removed
+#[must_use]
+pub fn active_progresses(ctx: &egui::Context) -> Vec<ProgressState> {
+    let shared = SharedHandle::get(ctx);
+    let Ok(mut progresses) = shared.0.lock() else {
+        return Vec::new();
+    };
+    progresses.retain(|state| {
+        Arc::strong_count(state) > 1 && state.lock().is_ok_and(|state| !state.finished)
+    });
+    progresses
+        .iter()
+        .filter_map(|state| state.lock().ok().map(|state| state.clone()))
+        .collect()
+}
+
+/// Id of the status message in the egui context
+fn status_id() -> egui::Id {
+    egui::Id::new("bladvak_status_message")
+}
+
+/// Show a message in the status area (e.g. "Opened image.png") - it replaces the previous one
+pub fn show_status<S: Into<String>>(ctx: &egui::Context, message: S) {
+    let expires = ctx.input(|i| i.time) + STATUS_DURATION;
+    ctx.data_mut(|data| data.insert_temp(status_id(), (message.into(), expires)));
+    ctx.request_repaint();
+}
+
+/// Current status message - `None` once expired
+fn status_message(ctx: &egui::Context) -> Option<String> {
+    let (message, expires) = ctx.data(|data| data.get_temp::<(String, f64)>(status_id()))?;
+    let remaining = expires - ctx.input(|i| i.time);
+    if remaining <= 0.0 {
+        ctx.data_mut(|data| data.remove::<(String, f64)>(status_id()));
+        return None;
+    }
+    ctx.request_repaint_after(std::time::Duration::from_secs_f64(remaining));
+    Some(message)
+}
+
+/// Show the status area with the status message, the active progresses and the number of
+/// errors not reviewed yet - returns `true` when the errors indicator is clicked
+pub(crate) fn status_panel(ui: &mut egui::Ui, errors: usize) -> bool {
+    let progresses = active_progresses(ui);
+    let message = status_message(ui);
+    if progresses.is_empty() && message.is_none() && errors == 0 {
+        return false;
+    }
+    let mut errors_clicked = false;
+    egui::Panel::bottom("bladvak_status").show(ui, |ui| {
+        if errors > 0 {
+            let text = egui::RichText::new(format!("⚠ {errors} error(s)"))
+                .color(ui.visuals().error_fg_color);
+            let indicator = ui
+                .add(egui::Button::new(text).frame(false))
+                .on_hover_text("Show the errors");
+            crate::a11y::label(&indicator, &format!("{errors} errors"));
+            errors_clicked = indicator.clicked();
+        }
+        if let Some(message) = message {
+            ui.label(message);
+        }
+        for state in progresses {
+            ui.horizontal(|ui| {
+                ui.label(&state.label);
+                let bar = match state.fraction {
+                    Some(fraction) => egui::ProgressBar::new(fraction).show_percentage(),
+                    None => egui::ProgressBar::new(0.0)
+                        .animate(!crate::a11y::is_reduced_motion(ui.ctx())),
+                };
+                ui.add(bar);
+            });
+        }
+    });
+    errors_clicked
+}
+
+/// Show a spinner while a progress is active
+pub(crate) fn menu_spinner(ui: &mut egui::Ui) {
+    let progresses = active_progresses(ui);
+    if progresses.is_empty() {
+        return;
+    }
+    let labels = progresses
+        .iter()
+        .map(|state| state.label.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let spinner = crate::a11y::busy_indicator(ui);
+    crate::a11y::label(&spinner, &labels);
+    spinner.on_hover_text(labels);
+}
@@ -0,0 +1,194 @@
+//! Progressive web app helpers - web only
+//!
+//! The `beforeinstallprompt` event of the browser is kept to show an "Install app" entry in the
+//! File menu, and a toast proposes to reload when the service worker has installed a new
+//! version. The service worker of the template (`sw.js`) activates the new version when the
+//! page asks for it.
+
+use std::cell::{Cell, RefCell};
+
+use eframe::{
+    egui,
+    wasm_bindgen::{JsCast, JsValue, closure::Closure},
+    web_sys,
+};
+use js_sys::{Function, Promise, Reflect};
+use wasm_bindgen_futures::JsFuture;
+
+thread_local! {
+    /// Deferred install prompt - `None` if the app cannot be installed
+    static INSTALL_PROMPT: RefCell<Option<JsValue>> = const { RefCell::new(None) };
+    /// Service worker of the new version, waiting to be activated
+    static WAITING_WORKER: RefCell<Option<JsValue>> = const { RefCell::new(None) };
+    /// The update toast was dismissed
+    static DISMISSED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Get a property of a JS object
+fn get(target: &JsValue, name: &str) -> Option<JsValue> {
+    Reflect::get(target, &name.into())
+        .ok()
+        .filter(|value| !value.is_undefined() && !value.is_null())
+}
+
+/// Call a method of a JS object
+fn call(target: &JsValue, method: &str, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let function: Function = Reflect::get(target, &method.into())?.dyn_into()?;
+    function.apply(target, &args.iter().collect())
+}
+
+/// Listen to an event of a JS object - the listener lives as long as the page
+fn listen(target: &JsValue, event: &str, listener: impl FnMut(JsValue) + 'static) {
+    let closure = Closure::<dyn FnMut(JsValue)>::new(listener);
+    if let Err(err) = call(
+        target,
+        "addEventListener",
+        &[event.into(), closure.as_ref().clone()],
+    ) {
+        log::warn!("Cannot listen to {event}: {err:?}");
+    }
+    closure.forget();
+}
+
+/// Check if the app can be installed - the browser proposed it
+#[must_use]
+pub fn can_install() -> bool {
+    INSTALL_PROMPT.with(|prompt| prompt.borrow().is_some())
+}
+
+/// Show the install prompt of the browser - the prompt can only be shown once
+pub fn prompt_install() {
+    let Some(prompt) = INSTALL_PROMPT.with(RefCell::take) else {
+        return;
+    };
+    if let Err(err) = call(&prompt, "prompt", &[]) {
+        log::warn!("Cannot show the install prompt: {err:?}");
+    }
+}
+
+/// Check if a new version is ready - the page has to be reloaded to use it
+#[must_use]
+pub fn update_ready() -> bool {
+    WAITING_WORKER.with(|worker| worker.borrow().is_some())
+}
+
+/// Activate the new version and reload the page
+pub fn reload_to_update() {
+    let Some(worker) = WAITING_WORKER.with(RefCell::take) else {
+        crate::updater::reload_page();
+        return;
+    };
+    // the page is reloaded once the new service worker controls it
+    if let Some(container) = service_workers() {
+        listen(&container, "controllerchange", |_| {
+            crate::updater::reload_page();
+        });
+    }
+    if let Err(err) = call(&worker, "postMessage", &["skipWaiting".into()]) {
+        log::warn!("Cannot activate the new version: {err:?}");
+        crate::updater::reload_page();
+    }
+}
+
+/// Service worker container of the page - `None` if the browser has no service workers
+fn service_workers() -> Option<JsValue> {
+    get(&web_sys::window()?.navigator().into(), "serviceWorker")
+}
+
+/// Keep a service worker waiting to be activated - only an update when a worker already
+/// controls the page
+fn set_waiting(ctx: &egui::Context, container: &JsValue, worker: JsValue) {
+    if get(container, "controller").is_some() {
+        log::info!("New version ready");
+        WAITING_WORKER.with(|waiting| *waiting.borrow_mut() = Some(worker));
+        DISMISSED.with(|dismissed| dismissed.set(false));
+        ctx.request_repaint();
+    }
+}
+
+/// Watch the installation of the new versions
+fn watch_updates(ctx: &egui::Context, container: JsValue, registration: &JsValue) {
+    if let Some(worker) = get(registration, "waiting") {
+        set_waiting(ctx, &container, worker);
+    }
+    let ctx = ctx.clone();
+    let watched = registration.clone();
+    listen(registration, "updatefound", move |_| {
+        let Some(worker) = get(&watched, "installing") else {
+            return;
+        };
+        let (ctx, container, installing) = (ctx.clone(), container.clone(), worker.clone());
+        listen(&worker, "statechange", move |_| {
+            if get(&installing, "state")
+                .and_then(|state| state.as_string())
+                .as_deref()
+                == Some("installed")
+            {
+                set_waiting(&ctx, &container, installing.clone());
+            }
+        });
+    });
+}
+
+/// Listen to the install prompt and to the updates of the service worker
+pub(crate) fn install(ctx: &egui::Context) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let prompt_ctx = ctx.clone();
+    listen(&window, "beforeinstallprompt", move |event| {
+        // the browser shows its own prompt without this
+        let _ = call(&event, "preventDefault", &[]);
+        INSTALL_PROMPT.with(|prompt| *prompt.borrow_mut() = Some(event));
+        prompt_ctx.request_repaint();
+    });
+    listen(&window, "appinstalled", |_| {
+        INSTALL_PROMPT.with(|prompt| prompt.borrow_mut().take());
+    });
+    let Some(container) = service_workers() else {
+        return;
+    };
+    let Some(ready) = get(&container, "ready").and_then(|ready| ready.dyn_into::<Promise>().ok())
+    else {
+        return;
+    };
+    let ctx = ctx.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        match JsFuture::from(ready).await {
+            Ok(registration) => watch_updates(&ctx, container, &registration),
+            Err(err) => log::warn!("No service worker: {err:?}"),
+        }
+    });
+}
+
+/// Show the "Install app" entry of the File menu - hidden if the app cannot be installed
+pub(crate) fn menu_entry(ui: &mut egui::Ui) {
+    if can_install() && ui.button("Install app").clicked() {
+        ui.close();
+        prompt_install();
+    }
+}
+
+/// Show the toast of a new version
+pub(crate) fn show_update_toast(ctx: &egui::Context) {
+    if !update_ready() || DISMISSED.with(Cell::get) {
+        return;
+    }
+    egui::Area::new(egui::Id::new("bladvak_pwa_update"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -12.0))
+        .order(egui::Order::Foreground)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                crate::a11y::annotate(ui, egui::accesskit::Role::Alert, "New version");
+                ui.horizontal(|ui| {
+                    ui.label("A new version is available");
+                    if ui.button("Reload to update").clicked() {
+                        reload_to_update();
+                    }
+                    if ui.small_button("✖").on_hover_text("Later").clicked() {
+                        DISMISSED.with(|dismissed| dismissed.set(true));
+                    }
+                });
+            });
+        });
+}
@@ -0,0 +1,149 @@
+//! Feedback after a file is opened
+//!
+//! The name and the size of an opened file are shown in the status area and in the window
+//! title. The files opened from the local file system are kept in the recent files (most
+//! recent first) and listed in the "Open Recent" menu - not on web, where a file cannot be read
+//! again from its name.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::limits::ParseLimits;
+use crate::{
+    AppError, FileOrigin,
+    app::{Bladvak, BladvakApp, BladvakSavedState},
+};
+
+/// Number of recent files kept
+pub const RECENT_FILES_LIMIT: usize = 10;
+
+/// Name of a file shown to the user
+pub(crate) fn display_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+/// Add the name of the file to an error - unless the message already has it
+pub(crate) fn with_file_name(mut err: AppError, path: &Path) -> AppError {
+    let name = display_name(path);
+    if !err.message.contains(&name) {
+        err.message = format!("{name}: {}", err.message);
+    }
+    err
+}
+
+impl BladvakSavedState {
+    /// Put a file at the top of the recent files
+    pub(crate) fn add_recent_file(&mut self, path: &Path) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.recent_files.retain(|recent| *recent != path);
+        self.recent_files.insert(0, path);
+        self.recent_files.truncate(RECENT_FILES_LIMIT);
+    }
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Recently opened files, most recent first - empty on web
+    #[must_use]
+    pub fn recent_files(&self) -> &[PathBuf] {
+        &self.internal.recent_files
+    }
+
+    /// Remember a file opened by the app
+    pub(crate) fn file_opened(&mut self, path: &Path, size: usize, origin: &FileOrigin) {
+        if !cfg!(target_arch = "wasm32")
+            && matches!(origin, FileOrigin::Dialog | FileOrigin::LocalDrop)
+        {
+            self.internal.add_recent_file(path);
+        }
+        self.file_handler.last_opened = Some((path.to_path_buf(), size));
+    }
+
+    /// Show the last opened file in the status area and in the window title
+    pub(crate) fn process_opened_file(&mut self, ctx: &egui::Context) {
+        let Some((path, size)) = self.file_handler.last_opened.take() else {
+            return;
+        };
+        let name = display_name(&path);
+        crate::progress::show_status(
+            ctx,
+            format!(
+                "Opened {name} ({})",
+                crate::utils::format_bytes(size as u64)
+            ),
+        );
+        let title = format!("{name} - {}", M::name());
+        #[cfg(target_arch = "wasm32")]
+        if let Some(document) = eframe::web_sys::window().and_then(|window| window.document()) {
+            document.set_title(&title);
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::Title(title));
+    }
+
+    /// Open a recent file again - the file is read in the background within the parse limits
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_recent(&mut self, ctx: &egui::Context, path: &Path) {
+        let (repaint_ctx, read_path) = (ctx.clone(), path.to_path_buf());
+        let handle = crate::task::spawn_blocking(move || {
+            let file = crate::export::read_file(&read_path, &ParseLimits::default());
+            repaint_ctx.request_repaint();
+            crate::file_handler::submit_file(&repaint_ctx, file?.with_origin(FileOrigin::Dialog));
+            Ok(())
+        });
+        self.file_handler
+            .recent_reads
+            .push((path.to_path_buf(), handle));
+    }
+
+    /// Check the recent files read again - a file that cannot be read is removed from the list
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn process_recent_reads(&mut self) {
+        let reads = std::mem::take(&mut self.file_handler.recent_reads);
+        for (path, handle) in reads {
+            match handle.try_take() {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => {
+                    self.internal.recent_files.retain(|recent| *recent != path);
+                    self.error_manager.add_error(with_file_name(err, &path));
+                }
+                Err(handle) => self.file_handler.recent_reads.push((path, handle)),
+            }
+        }
+    }
+
+    /// Show the "Open Recent" menu - hidden without recent file, not on web
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn menu_recent(&mut self, ui: &mut egui::Ui) {
+        if self.internal.recent_files.is_empty() {
+            return;
+        }
+        ui.menu_button("Open Recent", |ui| {
+            let mut selected = None;
+            for path in &self.internal.recent_files {
+                if ui
+                    .button(display_name(path))
+                    .on_hover_text(path.display().to_string())
+                    .clicked()
+                {
+                    selected = Some(path.clone());
+                }
+            }
+            ui.separator();
+            if ui.button("Clear recent files").clicked() {
+                ui.close();
+                self.internal.recent_files.clear();
+            }
+            if let Some(path) = selected {
+                ui.close();
+                self.open_recent(ui.ctx(), &path);
+            }
+        });
+    }
+}
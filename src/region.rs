@@ -0,0 +1,126 @@
+//! App-defined regions - extra panels on the edges of the window (a timeline strip, a tool
+//! rail...)
+//!
+//! The app describes its regions with [`crate::BladvakApp::regions`]. The wrapper shows them
+//! around the central panel, persists their visibility and lets the user toggle them in the
+//! panel settings and the panels menu. The size of a resizable region is kept by egui.
+
+use eframe::egui;
+use std::fmt::{Debug, Display};
+
+use crate::errors::ErrorManager;
+
+/// Edge of the window where a region is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionSide {
+    /// Below the menu bar
+    Top,
+    /// Above the status area
+    Bottom,
+    /// Left of the central panel
+    Left,
+    /// Right of the central panel
+    Right,
+}
+
+impl Display for RegionSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Top => write!(f, "Top"),
+            Self::Bottom => write!(f, "Bottom"),
+            Self::Left => write!(f, "Left"),
+            Self::Right => write!(f, "Right"),
+        }
+    }
+}
+
+/// Ui of a region
+pub type RegionUi<App> = dyn Fn(&mut App, &mut egui::Ui, &mut ErrorManager);
+
+/// Region registered by the app
+pub struct RegionDescriptor<App> {
+    /// Unique id - used to persist the visibility
+    pub id: String,
+    /// Label displayed in the settings and the menus
+    pub label: String,
+    /// Edge of the window
+    pub side: RegionSide,
+    /// Height (top and bottom) or width (left and right) when first shown
+    pub default_size: Option<f32>,
+    /// Can the user resize the region
+    pub resizable: bool,
+    /// Is the region visible without saved state
+    pub visible_by_default: bool,
+    /// Ui
+    ui: Box<RegionUi<App>>,
+}
+
+impl<App> Debug for RegionDescriptor<App> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegionDescriptor")
+            .field("id", &self.id)
+            .field("label", &self.label)
+            .field("side", &self.side)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<App> RegionDescriptor<App> {
+    /// Create a new region - resizable and visible by default
+    pub fn new<I, L, F>(id: I, label: L, side: RegionSide, ui: F) -> Self
+    where
+        I: Into<String>,
+        L: Into<String>,
+        F: Fn(&mut App, &mut egui::Ui, &mut ErrorManager) + 'static,
+    {
+        Self {
+            id: id.into(),
+            label: label.into(),
+            side,
+            default_size: None,
+            resizable: true,
+            visible_by_default: true,
+            ui: Box::new(ui),
+        }
+    }
+
+    /// Set the size when first shown
+    #[must_use]
+    pub fn with_default_size(mut self, default_size: f32) -> Self {
+        self.default_size = Some(default_size);
+        self
+    }
+
+    /// Set if the user can resize the region
+    #[must_use]
+    pub fn with_resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    /// Set if the region is visible without saved state
+    #[must_use]
+    pub fn with_visible_by_default(mut self, visible: bool) -> Self {
+        self.visible_by_default = visible;
+        self
+    }
+
+    /// Show the region
+    pub(crate) fn show(&self, app: &mut App, ui: &mut egui::Ui, error_manager: &mut ErrorManager) {
+        let id = egui::Id::new(("bladvak_region", &self.id));
+        let mut panel = match self.side {
+            RegionSide::Top => egui::Panel::top(id),
+            RegionSide::Bottom => egui::Panel::bottom(id),
+            RegionSide::Left => egui::Panel::left(id),
+            RegionSide::Right => egui::Panel::right(id),
+        }
+        .resizable(self.resizable);
+        if let Some(default_size) = self.default_size {
+            panel = panel.default_size(default_size);
+        }
+        panel.show(ui, |ui| {
+            crate::a11y::annotate(ui, egui::accesskit::Role::Region, &self.label);
+            (self.ui)(app, ui, error_manager);
+        });
+    }
+}
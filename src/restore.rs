@@ -0,0 +1,115 @@
+//! Progressive loading of large saved states
+//!
+//! A saved state bigger than [`BladvakApp::progressive_load_threshold`] is not parsed in
+//! `try_new_with_args`: the app starts with its default state, a splash screen is displayed
+//! (and the wasm loading screen removed), then the state is parsed and swapped in
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+use crate::app::{Bladvak, BladvakApp};
+
+/// Saved state waiting to be parsed
+pub(crate) struct PendingRestore {
+    /// Serialized state
+    data: String,
+    /// Was the splash screen displayed
+    shown: bool,
+}
+
+impl Debug for PendingRestore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PendingRestore")
+            .field("data", &self.data.len())
+            .field("shown", &self.shown)
+            .finish()
+    }
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Debug + Default + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Get the saved state if it is too big to be parsed at the start
+    pub(crate) fn get_deferred_state(
+        cc: &eframe::CreationContext<'_>,
+        key: &str,
+    ) -> Option<PendingRestore> {
+        let threshold = M::progressive_load_threshold()?;
+        let data = Self::saved_state_string(cc.storage?, key)?;
+        if data.len() < threshold {
+            return None;
+        }
+        log::info!(
+            "Deferring the load of the saved state ({} bytes)",
+            data.len()
+        );
+        Some(PendingRestore { data, shown: false })
+    }
+
+    /// Show the splash screen then restore the saved state
+    ///
+    /// Returns `true` while the splash screen is displayed
+    pub(crate) fn restore_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let Some(pending) = &mut self.pending_restore else {
+            return false;
+        };
+        if !pending.shown {
+            pending.shown = true;
+            egui::CentralPanel::default().show(ui, |ui| {
+                crate::utils::central_ui(ui, |ui| {
+                    if let Some(logo) = crate::assets::themed_image(ui.ctx(), crate::assets::LOGO) {
+                        ui.add(logo.max_height(128.0));
+                    }
+                    ui.heading(M::name());
+                    ui.horizontal(|ui| {
+                        crate::a11y::busy_indicator(ui);
+                        ui.label("Loading saved state…");
+                    });
+                });
+            });
+            ui.ctx().request_repaint();
+            return true;
+        }
+        let Some(pending) = self.pending_restore.take() else {
+            return false;
+        };
+        trace_span!("restore");
+        match ron::from_str::<Self>(&pending.data) {
+            Ok(saved) if saved.ignore_saved_state => {
+                log::info!("Explicitly ignoring saved state");
+            }
+            Ok(saved) => {
+                log::info!("Using saved state");
+                self.apply_saved_state(ui, saved);
+            }
+            Err(err) => {
+                // e.g. the format changed
+                log::warn!("Failed to decode the saved state: {err}");
+            }
+        }
+        self.startup.phase("deferred state load");
+        false
+    }
+
+    /// Swap in a saved state parsed after the start
+    pub(crate) fn apply_saved_state(&mut self, ctx: &egui::Context, saved: Self) {
+        self.app = saved.app;
+        let restore_session = saved.internal.settings.restore_session;
+        self.internal = Self::merge_saved_state(Some(saved.internal), &self.panel_list);
+        if restore_session {
+            self.restore_session(ctx);
+        } else {
+            // the egui memory is already used by the running app: only the layout is reset
+            self.internal.discard_session();
+        }
+        Self::merge_region_state(&mut self.internal, &self.regions);
+        self.apply_window_settings(ctx);
+        self.check_version(ctx);
+        for err in self.panel_overrides.apply(&mut self.internal.panel_state) {
+            self.error_manager.add_error(err);
+        }
+        self.app.on_restore(&mut self.error_manager);
+    }
+}
@@ -0,0 +1,276 @@
+//! Global search
+//!
+//! The search box (Ctrl+Shift+F, or the 🔍 button of the menu bar) asks the [`SearchProvider`]s
+//! of the app ([`crate::BladvakApp::search_providers`]) and of its panels
+//! ([`crate::app::BladvakPanel::search_providers`]) for results. Selecting a result shows its
+//! panel - brought to the front when it is a window - and runs its jump action.
+
+use eframe::egui::{self, KeyboardShortcut};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+use crate::{
+    app::{Bladvak, BladvakApp, PanelOpen},
+    errors::ErrorManager,
+};
+
+/// Shortcut opening the search box
+pub const SEARCH_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(
+    egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT),
+    egui::Key::F,
+);
+
+/// Maximum number of results shown per provider
+const RESULTS_PER_PROVIDER: usize = 20;
+
+/// Jump action of a search result
+pub type SearchJump<App> = dyn Fn(&mut App, &mut ErrorManager);
+
+/// Result of a search
+pub struct SearchResult<App> {
+    /// Label shown in the results
+    pub label: String,
+    /// Icon - an emoji or a short text
+    pub icon: Option<String>,
+    /// Panel shown when the result is selected
+    pub panel: Option<String>,
+    /// Action run when the result is selected
+    jump: Option<Box<SearchJump<App>>>,
+}
+
+impl<App> Debug for SearchResult<App> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SearchResult")
+            .field("label", &self.label)
+            .field("icon", &self.icon)
+            .field("panel", &self.panel)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<App> SearchResult<App> {
+    /// Create a result
+    pub fn new<S: Into<String>>(label: S) -> Self {
+        Self {
+            label: label.into(),
+            icon: None,
+            panel: None,
+            jump: None,
+        }
+    }
+
+    /// Set the icon
+    #[must_use]
+    pub fn with_icon<S: Into<String>>(mut self, icon: S) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+
+    /// Show a panel when the result is selected (see [`crate::app::BladvakPanel::name`])
+    #[must_use]
+    pub fn with_panel<S: Into<String>>(mut self, panel: S) -> Self {
+        self.panel = Some(panel.into());
+        self
+    }
+
+    /// Set the action run when the result is selected - e.g. select the matching document
+    #[must_use]
+    pub fn with_jump<F: Fn(&mut App, &mut ErrorManager) + 'static>(mut self, jump: F) -> Self {
+        self.jump = Some(Box::new(jump));
+        self
+    }
+}
+
+/// Source of search results
+pub trait SearchProvider<App> {
+    /// Name of the provider - the header of its results
+    fn name(&self) -> &str;
+
+    /// Results matching a query - the query is not empty
+    fn search(&self, app: &App, query: &str) -> Vec<SearchResult<App>>;
+}
+
+/// Search box state
+pub(crate) struct SearchBox<App> {
+    /// Is the search box open
+    pub(crate) open: bool,
+    /// Query
+    query: String,
+    /// Query of the results
+    searched: String,
+    /// Results by provider name
+    results: Vec<(String, Vec<SearchResult<App>>)>,
+    /// The app or its panels have providers - checked on the first frame
+    available: Option<bool>,
+}
+
+impl<App> Default for SearchBox<App> {
+    fn default() -> Self {
+        Self {
+            open: false,
+            query: String::new(),
+            searched: String::new(),
+            results: Vec::new(),
+            available: None,
+        }
+    }
+}
+
+impl<App> Debug for SearchBox<App> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SearchBox")
+            .field("open", &self.open)
+            .field("query", &self.query)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Providers of the app and of its panels
+    fn search_providers(&self) -> Vec<Box<dyn SearchProvider<M>>> {
+        let mut providers = self.app.search_providers();
+        for panel in &self.panel_list {
+            providers.extend(panel.search_providers());
+        }
+        providers
+    }
+
+    /// Check if the search is available - the app or one of its panels has a provider
+    fn search_available(&mut self) -> bool {
+        if let Some(available) = self.search.available {
+            return available;
+        }
+        let available = !self.search_providers().is_empty();
+        self.search.available = Some(available);
+        available
+    }
+
+    /// Ask the providers for the results of the query
+    fn run_search(&mut self) {
+        let query = self.search.query.trim().to_string();
+        self.search.results = if query.is_empty() {
+            Vec::new()
+        } else {
+            self.search_providers()
+                .iter()
+                .filter_map(|provider| {
+                    let mut results = provider.search(&self.app, &query);
+                    results.truncate(RESULTS_PER_PROVIDER);
+                    (!results.is_empty()).then(|| (provider.name().to_string(), results))
+                })
+                .collect()
+        };
+        self.search.searched = self.search.query.clone();
+    }
+
+    /// Show the panel of a result and run its jump action
+    fn select_result(&mut self, ctx: &egui::Context, group: usize, index: usize) {
+        if group >= self.search.results.len() {
+            return;
+        }
+        let mut results = self.search.results.swap_remove(group).1;
+        if index >= results.len() {
+            return;
+        }
+        let result = results.swap_remove(index);
+        if let Some(panel) = &result.panel
+            && let Some(state) = self.internal.panel_state.get_mut(panel)
+        {
+            if state.open == PanelOpen::None {
+                state.open = PanelOpen::default();
+            }
+            if state.open == PanelOpen::AsWindows {
+                ctx.move_to_top(egui::LayerId::new(
+                    egui::Order::Middle,
+                    egui::Id::new(panel.as_str()),
+                ));
+            }
+        }
+        if let Some(jump) = &result.jump {
+            jump(&mut self.app, &mut self.error_manager);
+        }
+        self.search.open = false;
+    }
+
+    /// Open the search box with its shortcut
+    pub(crate) fn process_search_shortcut(&mut self, ctx: &egui::Context) {
+        if ctx.input_mut(|i| i.consume_shortcut(&SEARCH_SHORTCUT)) && self.search_available() {
+            self.search.open = true;
+        }
+    }
+
+    /// Show the search button of the menu bar - hidden without provider
+    pub(crate) fn search_button(&mut self, ui: &mut egui::Ui) {
+        if !self.search_available() {
+            return;
+        }
+        let button = ui.button("🔍").on_hover_text(format!(
+            "Search ({})",
+            ui.ctx().format_shortcut(&SEARCH_SHORTCUT)
+        ));
+        crate::a11y::label(&button, "Search");
+        if button.clicked() {
+            self.search.open = true;
+        }
+    }
+
+    /// Show the search box
+    pub(crate) fn show_search(&mut self, ctx: &egui::Context) {
+        if !self.search.open {
+            return;
+        }
+        let mut selected = None;
+        let modal = egui::Modal::new(egui::Id::new("bladvak_search")).show(ctx, |ui| {
+            ui.set_width(420.0);
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.search.query)
+                    .hint_text("Search")
+                    .desired_width(f32::INFINITY),
+            );
+            response.request_focus();
+            if self.search.query != self.search.searched {
+                self.run_search();
+            }
+            if response.lost_focus()
+                && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                && !self.search.results.is_empty()
+            {
+                selected = Some((0, 0));
+            }
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .max_height(360.0)
+                .show(ui, |ui| {
+                    if self.search.results.is_empty() && !self.search.query.trim().is_empty() {
+                        ui.label("No result");
+                    }
+                    for (group, (name, results)) in self.search.results.iter().enumerate() {
+                        ui.strong(name);
+                        for (index, result) in results.iter().enumerate() {
+                            let label = match &result.icon {
+                                Some(icon) => format!("{icon} {}", result.label),
+                                None => result.label.clone(),
+                            };
+                            if ui.add(egui::Button::new(label).frame(false)).clicked() {
+                                selected = Some((group, index));
+                            }
+                        }
+                    }
+                });
+        });
+        if let Some((group, index)) = selected {
+            self.select_result(ctx, group, index);
+        }
+        if modal.should_close() {
+            self.search.open = false;
+        }
+        if !self.search.open {
+            self.search.query.clear();
+            self.search.searched.clear();
+            self.search.results.clear();
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Self-update on native - `self-update` feature
+//!
+//! When an update is available (see [`crate::updater`]), the update menu of the menu bar offers
+//! to install it. The release asset of the current target (see
+//! [`crate::BladvakApp::is_update_asset`]) is downloaded in a task, checked against the
+//! SHA-256 digest of the release and, if the app sets [`crate::BladvakApp::update_public_key`],
+//! against its Ed25519 signature (the raw 64 bytes of the `<asset>.sig` asset). The running
+//! binary is then swapped and the app offers to restart. The asset must be the binary itself,
+//! not an archive.
+
+use eframe::egui;
+use ring::{
+    digest::{SHA256, digest},
+    signature::{ED25519, UnparsedPublicKey},
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+use crate::{
+    AppError,
+    app::{Bladvak, BladvakApp},
+    progress::ProgressHandle,
+    task::{FinishedTask, TaskId, TaskResult},
+    updater::ReleaseAsset,
+};
+
+/// Installation of an update
+#[derive(Debug, Default)]
+pub(crate) struct SelfUpdate {
+    /// Running installation task
+    task: Option<TaskId>,
+    /// Is the update installed - a restart is needed
+    installed: bool,
+}
+
+impl SelfUpdate {
+    /// Check if a finished task is the installation
+    pub(crate) fn owns(&self, id: TaskId) -> bool {
+        self.task == Some(id)
+    }
+}
+
+/// Download a file
+fn download(url: &str) -> Result<Vec<u8>, AppError> {
+    let response = ehttp::fetch_blocking(&ehttp::Request::get(url))?;
+    if !response.ok {
+        return Err(AppError::new(format!(
+            "Cannot download {url}: {} {}",
+            response.status, response.status_text
+        )));
+    }
+    Ok(response.bytes)
+}
+
+/// Check the SHA-256 digest of the asset
+fn verify_digest(asset: &ReleaseAsset, bytes: &[u8]) -> Result<(), AppError> {
+    let Some(digest_value) = &asset.digest else {
+        return Err(AppError::new(format!("No checksum for {}", asset.name)));
+    };
+    let Some(expected) = digest_value.strip_prefix("sha256:") else {
+        return Err(AppError::new(format!(
+            "Unsupported checksum for {}: {digest_value}",
+            asset.name
+        )));
+    };
+    let actual = hex::encode(digest(&SHA256, bytes));
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(AppError::new(format!(
+            "Checksum mismatch for {}",
+            asset.name
+        )))
+    }
+}
+
+/// Replace the running binary - the previous one is kept with the `old` extension
+fn replace_binary(bytes: &[u8]) -> Result<(), AppError> {
+    let exe = std::env::current_exe()?;
+    let new = exe.with_extension("new");
+    let old = exe.with_extension("old");
+    fs::write(&new, bytes)?;
+    fs::set_permissions(&new, fs::metadata(&exe)?.permissions())?;
+    if old.exists() {
+        fs::remove_file(&old)?;
+    }
+    fs::rename(&exe, &old)?;
+    if let Err(err) = fs::rename(&new, &exe) {
+        // put the running binary back
+        fs::rename(&old, &exe)?;
+        return Err(err.into());
+    }
+    Ok(())
+}
+
+/// Download, check and install an update
+fn install(
+    asset: &ReleaseAsset,
+    signature: Option<&ReleaseAsset>,
+    public_key: Option<[u8; 32]>,
+    progress: &ProgressHandle,
+) -> TaskResult {
+    progress.set_label(format!("Downloading {}", asset.name));
+    let bytes = download(&asset.url)?;
+    progress.set_fraction(0.7);
+    progress.set_label("Checking the update");
+    verify_digest(asset, &bytes)?;
+    if let Some(public_key) = public_key {
+        let signature =
+            signature.ok_or_else(|| AppError::new(format!("No signature for {}", asset.name)))?;
+        let signature = download(&signature.url)?;
+        UnparsedPublicKey::new(&ED25519, public_key)
+            .verify(&bytes, &signature)
+            .map_err(|_| AppError::new(format!("Invalid signature for {}", asset.name)))?;
+    }
+    progress.set_fraction(0.9);
+    progress.set_label("Installing the update");
+    replace_binary(&bytes)?;
+    progress.finish();
+    Ok(format!("Installed {}", asset.name))
+}
+
+/// Start the new binary and close the app
+fn restart(ctx: &egui::Context, exe: &Path) -> Result<(), AppError> {
+    std::process::Command::new(exe)
+        .args(std::env::args().skip(1))
+        .spawn()?;
+    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+    Ok(())
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Show the update menu - the release page, then the installation or the restart
+    pub(crate) fn self_update_menu(
+        &mut self,
+        ui: &mut egui::Ui,
+        version: &str,
+        url: &str,
+        assets: &[ReleaseAsset],
+    ) {
+        crate::widgets::link(ui, "Open the release page", url);
+        let installer = &mut self.updater.installer;
+        if installer.installed {
+            if ui.button("Restart to update").clicked() {
+                let restarted = std::env::current_exe()
+                    .map_err(AppError::from)
+                    .and_then(|exe| restart(ui, &exe));
+                if let Err(err) = restarted {
+                    self.error_manager.add_error(err);
+                }
+            }
+            return;
+        }
+        if installer.task.is_some() {
+            ui.horizontal(|ui| {
+                crate::a11y::busy_indicator(ui);
+                ui.label("Installing…");
+            });
+            return;
+        }
+        let Some(asset) = assets.iter().find(|asset| M::is_update_asset(&asset.name)) else {
+            ui.label("No build for this platform");
+            return;
+        };
+        if ui.button(format!("Install {version}")).clicked() {
+            let asset = asset.clone();
+            let signature_name = format!("{}.sig", asset.name);
+            let signature = assets
+                .iter()
+                .find(|asset| asset.name == signature_name)
+                .cloned();
+            let progress = crate::progress::progress(ui, format!("Update to {version}"));
+            let public_key = M::update_public_key();
+            installer.task = Some(self.tasks.spawn("self-update", move || {
+                install(&asset, signature.as_ref(), public_key, &progress)
+            }));
+            ui.close();
+        }
+    }
+
+    /// Record the end of the installation
+    pub(crate) fn finish_self_update(&mut self, finished: FinishedTask) {
+        let installer = &mut self.updater.installer;
+        installer.task = None;
+        match finished.result {
+            Ok(message) => {
+                log::info!("{message}");
+                installer.installed = true;
+                self.error_manager
+                    .add_info("Update installed - restart the app to use it");
+            }
+            Err(err) => self.error_manager.add_error(err),
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Session save and restore
+//!
+//! With "Restore previous session" in the General settings, the wrapper restores the open
+//! documents (see [`crate::BladvakApp::save_session`]), the panel layout, the window geometry
+//! and the scroll positions of the previous run. Without it, the app starts with its saved
+//! settings but a fresh layout and no document.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+use crate::app::{Bladvak, BladvakApp, BladvakSavedState, PanelState};
+
+/// Session data of a document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSession {
+    /// Document name (e.g. its path)
+    pub name: String,
+    /// Data serialized by the app (e.g. the scroll position or the selection)
+    pub data: String,
+}
+
+impl DocumentSession {
+    /// Create the session data of a document
+    pub fn new<N: Into<String>, D: Into<String>>(name: N, data: D) -> Self {
+        Self {
+            name: name.into(),
+            data: data.into(),
+        }
+    }
+}
+
+/// Open documents of the app
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AppSession {
+    /// Open documents in order
+    pub documents: Vec<DocumentSession>,
+    /// Index of the active document
+    pub active: Option<usize>,
+}
+
+/// Geometry of the window
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) struct WindowGeometry {
+    /// Inner size
+    inner_size: [f32; 2],
+    /// Outer position - not available on every platform
+    position: Option<[f32; 2]>,
+    /// Is the window maximized
+    maximized: bool,
+}
+
+impl WindowGeometry {
+    /// Geometry of the window in the current frame
+    fn current(ctx: &egui::Context) -> Option<Self> {
+        ctx.input(|i| {
+            let viewport = i.viewport();
+            Some(Self {
+                inner_size: viewport.inner_rect?.size().into(),
+                position: viewport.outer_rect.map(|rect| rect.min.into()),
+                maximized: viewport.maximized.unwrap_or(false),
+            })
+        })
+    }
+
+    /// Move and resize the window
+    fn apply(self, ctx: &egui::Context) {
+        if self.maximized {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(true));
+            return;
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(self.inner_size.into()));
+        if let Some(position) = self.position {
+            ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(position.into()));
+        }
+    }
+}
+
+/// Saved session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Session {
+    /// Documents of the app
+    app: AppSession,
+    /// Window geometry - only on native
+    window: Option<WindowGeometry>,
+}
+
+impl BladvakSavedState {
+    /// Forget the session: the panels and the regions get their default state
+    pub(crate) fn discard_session(&mut self) {
+        for state in self.panel_state.values_mut() {
+            *state = PanelState::default();
+        }
+        self.region_state.clear();
+        self.session = Session::default();
+    }
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Forget the egui memory (scroll positions, panel sizes...) of the previous session
+    pub(crate) fn discard_egui_session(ctx: &egui::Context) {
+        log::info!("Starting a new session");
+        ctx.memory_mut(|mem| mem.data = egui::util::IdTypeMap::default());
+    }
+
+    /// Restore the documents and the window geometry of the saved session
+    pub(crate) fn restore_session(&mut self, ctx: &egui::Context) {
+        let session = std::mem::take(&mut self.internal.session);
+        if let Some(window) = session.window {
+            window.apply(ctx);
+        }
+        if !session.app.documents.is_empty() {
+            log::info!(
+                "Restoring {} documents of the previous session",
+                session.app.documents.len()
+            );
+            self.app
+                .restore_session(session.app, &mut self.error_manager);
+        }
+    }
+
+    /// Remember the window geometry - called each frame, the window is already closed when the
+    /// state is saved
+    pub(crate) fn track_session(&mut self, ctx: &egui::Context) {
+        if cfg!(target_arch = "wasm32") {
+            return;
+        }
+        if let Some(window) = WindowGeometry::current(ctx) {
+            self.internal.session.window = Some(window);
+        }
+    }
+
+    /// Collect the documents of the app before the state is saved
+    pub(crate) fn capture_session(&mut self) {
+        self.internal.session.app = self.app.save_session();
+    }
+}
@@ -1,11 +1,20 @@
 //! Settings component
 
-use eframe::egui::{self, Checkbox, Context, Frame, Id, Margin, Modal, RichText, ThemePreference};
+use eframe::egui::{
+    self, Checkbox, Context, Frame, Id, Margin, Modal, RichText, ThemePreference, accesskit::Role,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 use crate::{
-    ErrorManager,
-    app::{Bladvak, BladvakApp, PanelOpen},
+    ErrorManager, LocaleFormat, a11y,
+    app::{Bladvak, BladvakApp, PanelOpen, PanelState},
+    errors::{AppError, ErrorCategory, ErrorPresentation, Severity, TOAST_DURATION},
+    events::{PanelToggled, ThemeChanged},
+    journal::{self, JournalKind},
+    telemetry,
+    toolbar::ToolbarSettings,
+    utils::{self, time::now},
 };
 
 /// Selected Setting
@@ -15,12 +24,63 @@ pub(crate) enum SelectedSetting {
     General,
     /// Panel setting
     Panel,
+    /// Tasks and recurring jobs
+    Tasks,
+    /// Toolbar buttons
+    Toolbar,
     /// Custom setting
     String(String),
 }
 
+/// Density of the UI - spacing, padding and text size
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum Density {
+    /// Tight spacing and smaller text, for small screens
+    Compact,
+    /// Style of the app
+    #[default]
+    Normal,
+    /// Loose spacing and larger text, for large screens
+    Comfortable,
+}
+
+impl Density {
+    /// All the densities
+    pub const ALL: [Self; 3] = [Self::Compact, Self::Normal, Self::Comfortable];
+
+    /// Adjust a style to the density
+    pub(crate) fn apply(self, style: &mut egui::Style) {
+        let (spacing_factor, text_delta) = match self {
+            Self::Compact => (0.7, -1.0),
+            Self::Normal => return,
+            Self::Comfortable => (1.4, 2.0),
+        };
+        let spacing = &mut style.spacing;
+        spacing.item_spacing *= spacing_factor;
+        spacing.button_padding *= spacing_factor;
+        spacing.indent *= spacing_factor;
+        // the widgets keep room for their text
+        spacing.interact_size.y *= f32::midpoint(1.0, spacing_factor);
+        for font_id in style.text_styles.values_mut() {
+            font_id.size = (font_id.size + text_delta).max(6.0);
+        }
+    }
+}
+
+impl std::fmt::Display for Density {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Compact => "Compact",
+            Self::Normal => "Normal",
+            Self::Comfortable => "Comfortable",
+        })
+    }
+}
+
 /// Settings object
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
 pub(crate) struct Settings {
     /// Is setting modal open
     pub open: bool,
@@ -31,17 +91,121 @@ pub(crate) struct Settings {
     /// Debug and inspection toggle
     pub show_inspection: bool,
 
+    /// Performance overlay toggle
+    pub show_perf_overlay: bool,
+
+    /// Style editor toggle
+    pub show_style_editor: bool,
+
+    /// Restore the documents, the layout and the window of the previous session
+    pub restore_session: bool,
+
+    /// Always enable `AccessKit`
+    pub screen_reader: bool,
+
+    /// Keep the window above the others - not on web
+    pub always_on_top: bool,
+
+    /// Keep a `.bak` of the overwritten files - not on web
+    pub save_backup: bool,
+
+    /// Show the changelog after an upgrade
+    pub show_whats_new: bool,
+
+    /// Show the URL of the external links before opening them
+    pub confirm_links: bool,
+
+    /// Presentation of the new errors by severity - the one of the app for the missing
+    /// severities (see [`crate::BladvakApp::error_presentation`])
+    pub error_presentation: BTreeMap<Severity, ErrorPresentation>,
+
+    /// Check the updates at start - only if the app opts in
+    pub check_updates: bool,
+
+    /// Consent to the telemetry - `None` until asked
+    pub telemetry: Option<bool>,
+
+    /// Theme chosen by the user - `None` until chosen
+    pub theme: Option<ThemePreference>,
+
+    /// High contrast theme
+    pub high_contrast: bool,
+
+    /// Avoid the animations - `None` to follow the system
+    pub reduce_motion: Option<bool>,
+
+    /// Density of the UI
+    pub density: Density,
+
+    /// Locale of the formats (e.g. "fr-FR") - `None` to use the one of the system
+    pub locale: Option<String>,
+
+    /// Toolbar visibility and buttons
+    pub toolbar: ToolbarSettings,
+
     /// Selected Panel
     pub selected_setting: SelectedSetting,
+
+    /// Remembered trust decisions for external file origins
+    pub trusted_origins: BTreeMap<String, bool>,
+}
+
+impl Settings {
+    /// Check if the animations are avoided - the preference of the system is used until the user
+    /// chooses
+    pub(crate) fn reduced_motion(&self) -> bool {
+        self.reduce_motion
+            .unwrap_or_else(a11y::system_reduced_motion)
+    }
+
+    /// Reset the settings to their defaults - the settings window stays open on the same page
+    pub(crate) fn reset(&mut self) {
+        *self = Self {
+            open: self.open,
+            selected_setting: self.selected_setting.clone(),
+            ..Self::default()
+        };
+    }
+}
+
+/// Id of the confirmation of the global reset
+fn confirm_reset_id() -> Id {
+    Id::new("bladvak_confirm_reset")
+}
+
+/// Show a "Reset to defaults" button at the top right of a settings page
+fn reset_button(ui: &mut egui::Ui) -> bool {
+    ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+        ui.button("Reset to defaults").clicked()
+    })
+    .inner
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Self {
             show_inspection: false,
+            show_perf_overlay: false,
+            show_style_editor: false,
+            restore_session: true,
+            screen_reader: false,
+            always_on_top: false,
+            save_backup: false,
+            show_whats_new: true,
+            confirm_links: false,
+            error_presentation: BTreeMap::new(),
+            check_updates: true,
+            telemetry: None,
+            theme: None,
+            high_contrast: false,
+            reduce_motion: None,
+            density: Density::Normal,
+            locale: None,
+            toolbar: ToolbarSettings::default(),
             open: false,
             min_width_sidebar: 200.0,
             selected_setting: SelectedSetting::General,
+            trusted_origins: BTreeMap::new(),
         }
     }
 }
@@ -50,24 +214,314 @@ impl<M> Bladvak<M>
 where
     M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
 {
+    /// Presentation of the new errors of a severity
+    fn error_presentation(&self, severity: Severity) -> ErrorPresentation {
+        self.internal
+            .settings
+            .error_presentation
+            .get(&severity)
+            .copied()
+            .unwrap_or_else(|| M::error_presentation(severity))
+    }
+
+    /// Present the errors added since the last frame - returns `true` if the error window
+    /// must be opened
+    fn present_new_errors(&mut self, ctx: &Context) -> bool {
+        let seen = self.error_manager.seen.min(self.error_manager.errors.len());
+        let expires = ctx.input(|i| i.time) + TOAST_DURATION;
+        let mut open_window = false;
+        let mut toasts = Vec::new();
+        let mut status_errors = 0;
+        for error in &self.error_manager.errors[seen..] {
+            match self.error_presentation(error.severity) {
+                ErrorPresentation::Window => open_window = true,
+                ErrorPresentation::Toast => toasts.push((error.clone(), expires)),
+                ErrorPresentation::StatusBar => status_errors += 1,
+            }
+        }
+        self.error_manager.toasts.extend(toasts);
+        self.error_manager.status_errors += status_errors;
+        open_window
+    }
+
+    /// Show the toasts of the new errors
+    fn show_error_toasts(&mut self, ctx: &Context) {
+        let now = ctx.input(|i| i.time);
+        let toasts = &mut self.error_manager.toasts;
+        toasts.retain(|(_, expires)| *expires > now);
+        let Some(next) = toasts.iter().map(|(_, expires)| *expires).reduce(f64::min) else {
+            return;
+        };
+        ctx.request_repaint_after(std::time::Duration::from_secs_f64(next - now));
+        let mut dismissed = None;
+        let mut show_window = false;
+        egui::Area::new(Id::new("bladvak_error_toasts"))
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-12.0, 40.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                for (idx, (error, _)) in toasts.iter().enumerate() {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        a11y::annotate(ui, Role::Alert, &error.message);
+                        ui.horizontal(|ui| {
+                            ui.colored_label(Self::severity_color(ui, error.severity), "⚠");
+                            ui.label(&error.message);
+                            if ui.small_button("Show").clicked() {
+                                show_window = true;
+                                dismissed = Some(idx);
+                            }
+                            if ui.small_button("🗙").on_hover_text("Dismiss").clicked() {
+                                dismissed = Some(idx);
+                            }
+                        });
+                    });
+                }
+            });
+        if let Some(idx) = dismissed {
+            toasts.remove(idx);
+        }
+        if show_window {
+            self.error_manager.is_open = true;
+        }
+    }
+
     /// Show the error manager ui
     pub fn show_error_manager(&mut self, ctx: &Context) {
-        if !self.error_manager.was_open && !self.error_manager.errors.is_empty() {
+        let window_id = Id::new("bladvak_errors_windows");
+        let layer_id = egui::LayerId::new(egui::Order::Middle, window_id);
+        if self.present_new_errors(ctx) {
+            // bring the window to front and take the keyboard focus
             self.error_manager.is_open = true;
+            ctx.move_to_top(layer_id);
+            ctx.memory_mut(|mem| {
+                if let Some(focused) = mem.focused() {
+                    mem.surrender_focus(focused);
+                }
+            });
+        }
+        if self.error_manager.is_open
+            && ctx.top_layer_id() == Some(layer_id)
+            && ctx.memory(|mem| mem.focused().is_none())
+        {
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+                self.error_manager.is_open = false;
+            } else if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Enter))
+                && let Some(latest) = self.error_manager.errors.len().checked_sub(1)
+            {
+                self.error_manager.dismiss(latest);
+            }
         }
+        let mut is_open = self.error_manager.is_open;
         egui::Window::new("Errors")
-            .id("bladvak_errors_windows".into())
-            .open(&mut self.error_manager.is_open)
+            .id(window_id)
+            .open(&mut is_open)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                a11y::annotate(ui, Role::Dialog, "Errors");
+                Self::show_errors_toolbar(&mut self.error_manager, ui);
+                if self.error_manager.dropped() > 0 {
+                    ui.weak(format!(
+                        "{} older errors dropped (keeping the last {})",
+                        self.error_manager.dropped(),
+                        self.error_manager.capacity()
+                    ));
+                }
+                ui.separator();
+                Self::show_errors_list(&mut self.error_manager, ui);
+            });
+        self.error_manager.is_open = is_open;
+        if is_open {
+            self.error_manager.status_errors = 0;
+        }
+        self.error_manager.seen = self.error_manager.errors.len();
+        self.show_error_toasts(ctx);
+    }
+
+    /// Show the messages window (warnings and information)
+    pub(crate) fn show_messages(&mut self, ctx: &Context) {
+        let error_manager = &mut self.error_manager;
+        if !error_manager.messages_open {
+            return;
+        }
+        error_manager.unread_messages = 0;
+        let mut open = true;
+        egui::Window::new("Messages")
+            .id(Id::new("bladvak_messages_window"))
+            .open(&mut open)
             .vscroll(true)
             .show(ctx, |ui| {
-                for error in &self.error_manager.errors {
-                    ui.label(error.message.clone());
+                a11y::annotate(ui, Role::Dialog, "Messages");
+                if ui.button("Clear all").clicked() {
+                    error_manager.clear_messages();
+                }
+                ui.separator();
+                if error_manager.messages().is_empty() {
+                    ui.label("No message");
+                }
+                let mut dismissed = None;
+                for (idx, message) in error_manager.messages().iter().enumerate().rev() {
+                    if Self::show_error_entry(ui, idx, message) {
+                        dismissed = Some(idx);
+                    }
+                }
+                if let Some(idx) = dismissed {
+                    error_manager.dismiss_message(idx);
                 }
             });
-        if !self.error_manager.is_open {
-            self.error_manager.errors.clear();
+        error_manager.messages_open = open;
+    }
+
+    /// Actions, search and severity filter of the error window
+    fn show_errors_toolbar(error_manager: &mut ErrorManager, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let errors = &error_manager.errors;
+            if ui.button("Copy all").clicked() {
+                ui.ctx().copy_text(Self::errors_report(errors));
+            }
+            if let Some(issue_url) = M::issue_url()
+                && ui.button("Report issue").clicked()
+            {
+                let title = errors
+                    .first()
+                    .map_or_else(String::new, |error| error.message.clone());
+                let body = Self::errors_report(errors);
+                crate::widgets::open_link(
+                    ui.ctx(),
+                    &crate::feedback::issue_link(&issue_url, &title, &body),
+                );
+            }
+            if ui.button("Clear all").clicked() {
+                error_manager.clear();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut error_manager.search)
+                    .hint_text("Search")
+                    .desired_width(160.0),
+            );
+            let filter = &mut error_manager.severity_filter;
+            egui::ComboBox::from_id_salt("bladvak_errors_severity")
+                .selected_text(filter.map_or_else(|| "All".to_string(), |s| s.to_string()))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(filter, None, "All");
+                    for severity in [Severity::Error, Severity::Warning, Severity::Info] {
+                        ui.selectable_value(filter, Some(severity), severity.to_string());
+                    }
+                });
+            ui.checkbox(&mut error_manager.group_by_category, "Group by category");
+        });
+    }
+
+    /// Errors matching the search and the severity filter - grouped by category if enabled
+    fn show_errors_list(error_manager: &mut ErrorManager, ui: &mut egui::Ui) {
+        let shown = error_manager
+            .errors
+            .iter()
+            .enumerate()
+            .filter(|(_, error)| error_manager.is_shown(error))
+            .collect::<Vec<_>>();
+        if shown.is_empty() {
+            ui.label("No error");
+        }
+        let mut dismissed = None;
+        if error_manager.group_by_category {
+            let mut groups = BTreeMap::<Option<&ErrorCategory>, Vec<_>>::new();
+            for (idx, error) in shown {
+                groups
+                    .entry(error.category.as_ref())
+                    .or_default()
+                    .push((idx, error));
+            }
+            for (category, errors) in groups {
+                let name =
+                    category.map_or_else(|| "uncategorized".to_string(), ToString::to_string);
+                egui::CollapsingHeader::new(format!("{name} ({})", errors.len()))
+                    .id_salt(("bladvak_error_category", &name))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        for (idx, error) in errors {
+                            if Self::show_error_entry(ui, idx, error) {
+                                dismissed = Some(idx);
+                            }
+                        }
+                    });
+            }
+        } else {
+            for (idx, error) in shown {
+                if Self::show_error_entry(ui, idx, error) {
+                    dismissed = Some(idx);
+                }
+            }
+        }
+        if let Some(idx) = dismissed {
+            error_manager.dismiss(idx);
         }
-        self.error_manager.was_open = self.error_manager.is_open;
+    }
+
+    /// Color of a severity
+    fn severity_color(ui: &egui::Ui, severity: Severity) -> egui::Color32 {
+        match severity {
+            Severity::Info => ui.visuals().text_color(),
+            Severity::Warning => ui.visuals().warn_fg_color,
+            Severity::Error => ui.visuals().error_fg_color,
+        }
+    }
+
+    /// Show an error - returns `true` if the error is dismissed
+    fn show_error_entry(ui: &mut egui::Ui, idx: usize, error: &AppError) -> bool {
+        let color = Self::severity_color(ui, error.severity);
+        ui.horizontal(|ui| {
+            let dismissed = ui.small_button("🗙").on_hover_text("Dismiss").clicked();
+            if ui.small_button("📋").on_hover_text("Copy").clicked() {
+                ui.ctx().copy_text(error.report());
+            }
+            let header =
+                egui::CollapsingHeader::new(RichText::new(error.message.clone()).color(color))
+                    .id_salt(("bladvak_error", idx))
+                    .show(ui, |ui| {
+                        if let Some(timestamp) = error.timestamp {
+                            let format = LocaleFormat::get(ui.ctx());
+                            ui.label(format!("Time: {}", format.format_date_time(timestamp)));
+                        }
+                        ui.label(format!("Severity: {}", error.severity));
+                        if let Some(tag) = error.tag() {
+                            ui.label(format!("Category: {tag}"));
+                        }
+                        for source in error.chain().iter().skip(1) {
+                            ui.label(format!("Caused by: {source}"));
+                        }
+                        if let Some(details) = &error.details {
+                            ui.label(RichText::new(details.as_ref()).monospace());
+                        }
+                    });
+            let mut copy = false;
+            crate::context_menu::show_context_menu(
+                &header.header_response,
+                crate::context_menu::ERROR_LIST_CONTEXT,
+                |ui| copy = ui.button("Copy").clicked(),
+            );
+            if copy {
+                ui.ctx().copy_text(error.report());
+            }
+            dismissed
+        })
+        .inner
+    }
+
+    /// Report of the errors with the versions and the target
+    fn errors_report(errors: &[AppError]) -> String {
+        let mut report = format!(
+            "{}@{} (bladvak@{}, {})\n\n",
+            M::name(),
+            M::version(),
+            env!("CARGO_PKG_VERSION"),
+            utils::target_info()
+        );
+        for error in errors {
+            report.push_str(&error.report());
+            report.push('\n');
+        }
+        report
     }
 
     /// show setting popop bottom
@@ -94,12 +548,10 @@ where
                             if repo_url.is_empty() {
                                 ui.label(format!("{}@{}", M::name(), M::version()));
                             } else {
-                                ui.add(
-                                    egui::Hyperlink::from_label_and_url(
-                                        format!("{}@{}", M::name(), M::version()),
-                                        M::repo_url(),
-                                    )
-                                    .open_in_new_tab(true),
+                                crate::widgets::link(
+                                    ui,
+                                    format!("{}@{}", M::name(), M::version()),
+                                    &M::repo_url(),
                                 );
                             }
                         });
@@ -108,6 +560,10 @@ where
                         if modal_ui_right.button("Close").clicked() {
                             modal_ui_right.close();
                         }
+                        if modal_ui_right.button("Reset all…").clicked() {
+                            modal_ui_right
+                                .data_mut(|data| data.insert_temp(confirm_reset_id(), true));
+                        }
                     },
                 );
             });
@@ -143,6 +599,16 @@ where
                                 "Panels",
                             );
                         }
+                        ui.selectable_value(
+                            &mut self.internal.settings.selected_setting,
+                            SelectedSetting::Tasks,
+                            "Tasks",
+                        );
+                        ui.selectable_value(
+                            &mut self.internal.settings.selected_setting,
+                            SelectedSetting::Toolbar,
+                            "Toolbar",
+                        );
                         for one_panel in &self.panel_list {
                             if one_panel.has_settings() {
                                 let one_setting_name = one_panel.name();
@@ -166,10 +632,23 @@ where
                     SelectedSetting::Panel => {
                         self.show_panel_setting(ui);
                     }
+                    SelectedSetting::Tasks => {
+                        self.tasks.ui(ui);
+                    }
+                    SelectedSetting::Toolbar => {
+                        self.show_toolbar_setting(ui);
+                    }
                     SelectedSetting::String(value) => {
                         for one_panel in &self.panel_list {
                             let panel_name = one_panel.name();
                             if panel_name == value {
+                                if reset_button(ui) {
+                                    one_panel.reset_settings(&mut self.app);
+                                    journal::record(
+                                        JournalKind::Settings,
+                                        format!("Reset {panel_name} settings"),
+                                    );
+                                }
                                 ui.heading(format!("{panel_name} settings"));
                                 ui.separator();
                                 one_panel.ui_settings(&mut self.app, ui, &mut self.error_manager);
@@ -196,40 +675,269 @@ where
         self.internal.settings.show_inspection = show_inspection;
         if self.internal.settings.open {
             let modal = Modal::new(Id::new("Modal settings")).show(ctx, |ui| {
+                a11y::annotate(ui, Role::Dialog, "Settings");
                 self.show_settings_modal(ui, frame);
             });
             if modal.should_close() {
                 self.internal.settings.open = false;
             }
+            self.show_reset_confirmation(ctx);
         }
     }
 
+    /// Ask the confirmation of the global reset
+    fn show_reset_confirmation(&mut self, ctx: &Context) {
+        if !ctx.data(|data| data.get_temp::<bool>(confirm_reset_id()).unwrap_or(false)) {
+            return;
+        }
+        let mut answered = false;
+        let modal = Modal::new(Id::new("Modal reset settings")).show(ctx, |ui| {
+            a11y::annotate(ui, Role::AlertDialog, "Reset all settings");
+            ui.heading("Reset all settings");
+            ui.label("The settings and the panels of every page get their default values.");
+            ui.horizontal(|ui| {
+                if ui.button("Reset").clicked() {
+                    self.reset_all_settings(ui.ctx());
+                    answered = true;
+                }
+                if ui.button("Cancel").clicked() {
+                    answered = true;
+                }
+            });
+        });
+        if answered || modal.should_close() {
+            ctx.data_mut(|data| data.remove::<bool>(confirm_reset_id()));
+        }
+    }
+
+    /// Reset the panel states and the visibility of the regions
+    fn reset_panel_settings(&mut self) {
+        for state in self.internal.panel_state.values_mut() {
+            *state = PanelState::default();
+        }
+        for region in &self.regions {
+            self.internal
+                .region_state
+                .insert(region.id.clone(), region.visible_by_default);
+        }
+    }
+
+    /// Reset every settings page
+    pub(crate) fn reset_all_settings(&mut self, ctx: &Context) {
+        self.reset_general_settings(ctx);
+        self.reset_panel_settings();
+        for one_panel in &self.panel_list {
+            one_panel.reset_settings(&mut self.app);
+        }
+        journal::record(JournalKind::Settings, "Reset all settings");
+    }
+
     /// Show setting of selected
     pub(crate) fn show_panel_setting(&mut self, ui: &mut egui::Ui) {
+        if reset_button(ui) {
+            self.reset_panel_settings();
+            journal::record(JournalKind::Settings, "Reset panel settings");
+        }
         ui.heading("Panels");
         for one_panel in &self.panel_list {
             if one_panel.has_ui() {
                 let panel_name = one_panel.name();
                 if let Some(state) = self.internal.panel_state.get_mut(panel_name) {
                     let is_side_panel = self.app.is_side_panel();
+                    let previous = state.open.clone();
                     ui.horizontal(|ui| {
-                        ui.label(panel_name);
+                        let label = ui.label(panel_name).id;
                         if is_side_panel {
-                            ui.selectable_value(&mut state.open, PanelOpen::AsSideBar, "Sidebar");
+                            ui.selectable_value(&mut state.open, PanelOpen::AsSideBar, "Sidebar")
+                                .labelled_by(label);
                         } else if state.open == PanelOpen::AsSideBar {
                             // set the default to None (hidden)
                             state.open = PanelOpen::None;
                         }
-                        ui.selectable_value(&mut state.open, PanelOpen::AsWindows, "Windows");
-                        ui.selectable_value(&mut state.open, PanelOpen::None, "None");
+                        ui.selectable_value(&mut state.open, PanelOpen::AsWindows, "Windows")
+                            .labelled_by(label);
+                        ui.selectable_value(&mut state.open, PanelOpen::None, "None")
+                            .labelled_by(label);
                     });
+                    if previous != state.open {
+                        journal::record(
+                            JournalKind::Settings,
+                            format!("Panel {panel_name}: {}", state.open),
+                        );
+                        telemetry::panel_toggled(panel_name, &state.open);
+                        crate::events::publish(
+                            ui.ctx(),
+                            PanelToggled {
+                                panel: panel_name.to_string(),
+                                state: state.open.clone(),
+                            },
+                        );
+                    }
                 }
             }
         }
+        if !self.regions.is_empty() {
+            ui.separator();
+            ui.heading("Regions");
+            self.region_toggles(ui);
+        }
+    }
+
+    /// Show the toggles of the regions
+    pub(crate) fn region_toggles(&mut self, ui: &mut egui::Ui) {
+        for region in &self.regions {
+            let visible = self
+                .internal
+                .region_state
+                .entry(region.id.clone())
+                .or_insert(region.visible_by_default);
+            if ui
+                .checkbox(visible, format!("{} ({})", region.label, region.side))
+                .changed()
+            {
+                journal::record(
+                    JournalKind::Settings,
+                    format!(
+                        "Region {}: {}",
+                        region.label,
+                        if *visible { "shown" } else { "hidden" }
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Show the toggles of the general settings
+    fn show_general_toggles(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.error_manager.is_open, "Show Error panel");
+        ui.checkbox(
+            &mut self.internal.settings.show_inspection,
+            "Show Debug panel",
+        );
+        ui.checkbox(
+            &mut self.internal.settings.show_perf_overlay,
+            "Show performance overlay",
+        );
+        ui.checkbox(
+            &mut self.internal.settings.show_style_editor,
+            "Show Style panel",
+        );
+        ui.checkbox(
+            &mut self.internal.settings.restore_session,
+            "Restore previous session",
+        )
+        .on_hover_text("Reopen the documents, the layout and the window on start");
+        if ui
+            .checkbox(
+                &mut self.internal.settings.screen_reader,
+                "Screen reader support",
+            )
+            .on_hover_text("Always enable AccessKit, even without a screen reader detected")
+            .changed()
+        {
+            a11y::set_enabled(ui, self.internal.settings.screen_reader);
+        }
+        ui.checkbox(
+            &mut self.internal.settings.confirm_links,
+            "Confirm before opening external links",
+        )
+        .on_hover_text("Show the full URL before launching the browser");
+        self.show_telemetry_setting(ui);
+        if M::check_updates() {
+            ui.checkbox(
+                &mut self.internal.settings.check_updates,
+                "Check for updates at start",
+            );
+        }
+        if M::whats_new().is_some() {
+            ui.checkbox(
+                &mut self.internal.settings.show_whats_new,
+                "Show what's new after an upgrade",
+            );
+        }
+        if !cfg!(target_arch = "wasm32")
+            && ui
+                .checkbox(&mut self.internal.settings.always_on_top, "Always on top")
+                .changed()
+        {
+            self.apply_always_on_top(ui);
+        }
+        if !cfg!(target_arch = "wasm32")
+            && ui
+                .checkbox(
+                    &mut self.internal.settings.save_backup,
+                    "Keep a backup of overwritten files",
+                )
+                .on_hover_text("Copy the previous version to <name>.bak before saving")
+                .changed()
+        {
+            crate::utils::set_save_backup(self.internal.settings.save_backup);
+        }
+    }
+
+    /// Reset the general settings and undo their effects on the window, the theme, the locale
+    /// and the accessibility
+    fn reset_general_settings(&mut self, ctx: &Context) {
+        self.internal.settings.reset();
+        self.reapply_general_settings(ctx);
+    }
+
+    /// Apply the general settings again, the default ones included - after a reset
+    pub(crate) fn reapply_general_settings(&self, ctx: &Context) {
+        crate::utils::set_save_backup(self.internal.settings.save_backup);
+        a11y::set_enabled(ctx, self.internal.settings.screen_reader);
+        self.apply_always_on_top(ctx);
+        ctx.set_theme(self.internal.settings.theme.unwrap_or_default());
+        self.apply_style_overrides(ctx);
+        self.apply_locale(ctx);
+    }
+
+    /// Apply the saved settings acting on the window and the saves at start
+    pub(crate) fn apply_window_settings(&self, ctx: &Context) {
+        crate::utils::set_save_backup(self.internal.settings.save_backup);
+        if self.internal.settings.screen_reader {
+            a11y::set_enabled(ctx, true);
+        }
+        if self.internal.settings.always_on_top {
+            self.apply_always_on_top(ctx);
+        }
+        if let Some(theme) = self.internal.settings.theme {
+            ctx.set_theme(theme);
+        }
+        self.apply_style_overrides(ctx);
+        self.apply_locale(ctx);
+    }
+
+    /// Install the formats of the locale of the settings
+    pub(crate) fn apply_locale(&self, ctx: &Context) {
+        let format = self
+            .internal
+            .settings
+            .locale
+            .as_deref()
+            .map_or_else(LocaleFormat::current, LocaleFormat::for_locale);
+        format.install(ctx);
+    }
+
+    /// Set the window level from the "Always on top" setting - nothing on web
+    pub(crate) fn apply_always_on_top(&self, ctx: &Context) {
+        if cfg!(target_arch = "wasm32") {
+            return;
+        }
+        let level = if self.internal.settings.always_on_top {
+            egui::WindowLevel::AlwaysOnTop
+        } else {
+            egui::WindowLevel::Normal
+        };
+        ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(level));
     }
 
     /// Show setting of selected
     pub(crate) fn show_general_setting(&mut self, ui: &mut egui::Ui) {
+        if reset_button(ui) {
+            self.reset_general_settings(ui.ctx());
+            journal::record(JournalKind::Settings, "Reset general settings");
+        }
         ui.heading(format!("{} settings", M::name()));
         ui.separator();
         ui.horizontal(|ui| {
@@ -241,30 +949,218 @@ where
                 );
             }
         });
+        if ui.button("Clear storage now…").clicked() {
+            crate::persist::request_clear_storage(ui);
+        }
         ui.horizontal(|ui| {
             ui.label(format!("Reset {}", ErrorManager::title()));
-            ui.button("⟳").clicked().then(|| {
-                self.error_manager = ErrorManager::default();
+            let button = ui.button("⟳");
+            a11y::label(&button, "Reset errors");
+            button.clicked().then(|| {
+                self.error_manager.reset();
             });
         });
-        ui.checkbox(&mut self.error_manager.is_open, "Show Error panel");
-        ui.checkbox(
-            &mut self.internal.settings.show_inspection,
-            "Show Debug panel",
-        );
+        self.show_general_toggles(ui);
+        self.show_error_presentation_setting(ui);
+        if !self.internal.settings.trusted_origins.is_empty() {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{} remembered trust decision(s)",
+                    self.internal.settings.trusted_origins.len()
+                ));
+                if ui.button("Forget").clicked() {
+                    self.internal.settings.trusted_origins.clear();
+                }
+            });
+        }
         if ui.button("Organize windows").clicked() {
             ui.memory_mut(eframe::egui::Memory::reset_areas);
         }
+        if let Some(log_path) = crate::logger::log_file_path() {
+            ui.horizontal_wrapped(|ui| {
+                ui.label(format!("Log file: {}", log_path.display()));
+                if ui.button("Open log folder").clicked()
+                    && let Err(err) = crate::utils::reveal_in_file_manager(log_path)
+                {
+                    self.error_manager.add_error(err);
+                }
+            });
+        }
+        if let Some(handoff) = &self.handoff {
+            let peers = crate::handoff::handoff_peers(ui.ctx());
+            ui.label(format!(
+                "Handoff: listening as {} ({} peer(s){})",
+                handoff.name(),
+                peers.len(),
+                if peers.is_empty() {
+                    String::new()
+                } else {
+                    format!(": {}", peers.join(", "))
+                }
+            ));
+        }
+        ui.separator();
+        self.show_appearance_setting(ui);
+        self.show_locale_setting(ui);
         ui.separator();
+        Self::show_about(ui);
+    }
+
+    /// Show the presentation of the new errors by severity
+    fn show_error_presentation_setting(&mut self, ui: &mut egui::Ui) {
+        ui.label("Show new errors as")
+            .on_hover_text("A window takes the focus, a toast and the status bar do not");
+        egui::Grid::new("bladvak_error_presentation").show(ui, |ui| {
+            for severity in [Severity::Error, Severity::Warning, Severity::Info] {
+                ui.label(severity.to_string());
+                let current = self.error_presentation(severity);
+                let mut selected = current;
+                egui::ComboBox::from_id_salt(("bladvak_error_presentation", severity))
+                    .selected_text(selected.to_string())
+                    .show_ui(ui, |ui| {
+                        for presentation in ErrorPresentation::ALL {
+                            ui.selectable_value(
+                                &mut selected,
+                                presentation,
+                                presentation.to_string(),
+                            );
+                        }
+                    });
+                if selected != current {
+                    self.internal
+                        .settings
+                        .error_presentation
+                        .insert(severity, selected);
+                    journal::record(
+                        JournalKind::Settings,
+                        format!("{severity} presentation: {selected}"),
+                    );
+                }
+                ui.end_row();
+            }
+        });
+    }
+
+    /// Show the theme and the density
+    fn show_appearance_setting(&mut self, ui: &mut egui::Ui) {
         ui.heading("Theme");
         ui.horizontal(|ui| {
-            let mut theme_preference = ui.ctx().options(|opt| opt.theme_preference);
+            let current = self
+                .internal
+                .settings
+                .theme
+                .unwrap_or_else(|| ui.ctx().options(|opt| opt.theme_preference));
+            let mut theme_preference = current;
             ui.selectable_value(&mut theme_preference, ThemePreference::Light, "☀ Light");
             ui.selectable_value(&mut theme_preference, ThemePreference::Dark, "🌙 Dark");
             ui.selectable_value(&mut theme_preference, ThemePreference::System, "💻 System");
-            ui.ctx().set_theme(theme_preference);
+            if theme_preference != current {
+                journal::record(
+                    JournalKind::Settings,
+                    format!("Theme: {theme_preference:?}"),
+                );
+                self.internal.settings.theme = Some(theme_preference);
+                ui.ctx().set_theme(theme_preference);
+                crate::events::publish(
+                    ui.ctx(),
+                    ThemeChanged {
+                        theme: theme_preference,
+                    },
+                );
+            }
+            if ui
+                .toggle_value(&mut self.internal.settings.high_contrast, "◐ High contrast")
+                .on_hover_text("Plain colors, strong borders and a larger focus ring")
+                .changed()
+            {
+                journal::record(
+                    JournalKind::Settings,
+                    format!("High contrast: {}", self.internal.settings.high_contrast),
+                );
+                self.apply_style_overrides(ui.ctx());
+            }
         });
-        ui.separator();
+        let mut reduce_motion = self.internal.settings.reduced_motion();
+        if ui
+            .checkbox(&mut reduce_motion, "Reduce motion")
+            .on_hover_text("Disable the animations")
+            .changed()
+        {
+            self.internal.settings.reduce_motion = Some(reduce_motion);
+            journal::record(
+                JournalKind::Settings,
+                format!("Reduce motion: {reduce_motion}"),
+            );
+            self.apply_style_overrides(ui.ctx());
+        }
+        ui.horizontal(|ui| {
+            ui.label("Density");
+            let current = self.internal.settings.density;
+            for density in Density::ALL {
+                ui.selectable_value(
+                    &mut self.internal.settings.density,
+                    density,
+                    density.to_string(),
+                );
+            }
+            if self.internal.settings.density != current {
+                journal::record(
+                    JournalKind::Settings,
+                    format!("Density: {}", self.internal.settings.density),
+                );
+                self.apply_style_overrides(ui.ctx());
+            }
+        });
+    }
+
+    /// Show the locale of the formats and a preview
+    fn show_locale_setting(&mut self, ui: &mut egui::Ui) {
+        let system = crate::utils::format::locale();
+        let selected = self.internal.settings.locale.clone();
+        ui.horizontal(|ui| {
+            ui.label("Locale");
+            egui::ComboBox::from_id_salt("bladvak_locale")
+                .selected_text(
+                    selected
+                        .clone()
+                        .unwrap_or_else(|| format!("System ({system})")),
+                )
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(
+                        &mut self.internal.settings.locale,
+                        None,
+                        format!("System ({system})"),
+                    );
+                    for locale in crate::locale::KNOWN_LOCALES {
+                        ui.selectable_value(
+                            &mut self.internal.settings.locale,
+                            Some(locale.to_string()),
+                            locale,
+                        );
+                    }
+                });
+        });
+        if self.internal.settings.locale != selected {
+            journal::record(
+                JournalKind::Settings,
+                format!(
+                    "Locale: {}",
+                    self.internal.settings.locale.as_deref().unwrap_or("system")
+                ),
+            );
+            self.apply_locale(ui.ctx());
+        }
+        let format = LocaleFormat::get(ui.ctx());
+        ui.weak(format!(
+            "{} · {} · weeks start on {}",
+            format.format_date_time(now()),
+            format.format_number(1_234_567.89, 2),
+            format.first_day_of_week
+        ));
+    }
+
+    /// Show the about section
+    fn show_about(ui: &mut egui::Ui) {
         ui.heading("About");
         ui.horizontal_wrapped(|ui| {
             ui.spacing_mut().item_spacing.x = 0.0;
@@ -276,30 +1172,42 @@ where
             let repo_url = M::repo_url();
             if !repo_url.is_empty() {
                 ui.label("Link to ");
-                ui.add(
-                    egui::Hyperlink::from_label_and_url(
-                        format!("{} repository", M::name()),
-                        M::repo_url(),
-                    )
-                    .open_in_new_tab(true),
-                );
+                crate::widgets::link(ui, format!("{} repository", M::name()), &repo_url);
             }
         });
         ui.horizontal_wrapped(|ui| {
             ui.spacing_mut().item_spacing.x = 0.0;
             ui.label("Using ");
-            ui.add(
-                egui::Hyperlink::from_label_and_url(
-                    concat!("bladvak@", env!("CARGO_PKG_VERSION")),
-                    "https://github.com/Its-Just-Nans/bladvak",
-                )
-                .open_in_new_tab(true),
+            crate::widgets::link(
+                ui,
+                concat!("bladvak@", env!("CARGO_PKG_VERSION")),
+                "https://github.com/Its-Just-Nans/bladvak",
             );
         });
     }
 
     /// Show debug information
     fn show_debug_setting(&mut self, ui: &mut egui::Ui) {
+        if ui.button("Export debug snapshot").clicked()
+            && let Err(err) = self.save_debug_snapshot(ui.ctx())
+        {
+            self.error_manager.add_error(err);
+        }
+        ui.collapsing("Startup", |ui| {
+            let report = &self.startup.report;
+            egui::Grid::new("bladvak_startup")
+                .striped(true)
+                .show(ui, |ui| {
+                    for phase in &report.phases {
+                        ui.label(&phase.name);
+                        ui.label(format!("{:.2?}", phase.duration));
+                        ui.end_row();
+                    }
+                    ui.strong("total");
+                    ui.strong(format!("{:.2?}", report.total()));
+                    ui.end_row();
+                });
+        });
         ui.collapsing("App state", |ui| {
             ui.add_enabled_ui(false, |ui| {
                 if let Ok(serialized) = serde_json::to_string_pretty(&self.app) {
@@ -0,0 +1,77 @@
+//! Diagnostic state snapshot
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::{
+    app::{Bladvak, BladvakApp, BladvakSavedState},
+    errors::AppError,
+    utils::time::{now, unix_millis},
+};
+
+/// Diagnostic snapshot of a running app
+#[derive(Debug, Serialize)]
+pub struct DebugSnapshot<'a> {
+    /// Name of the app
+    pub app_name: String,
+    /// Version of the app
+    pub app_version: String,
+    /// Version of bladvak
+    pub bladvak_version: &'static str,
+    /// Time of the snapshot (milliseconds since the unix epoch)
+    pub timestamp: u64,
+    /// Target platform
+    pub platform: &'static str,
+    /// Settings and panel states
+    pub internal: &'a BladvakSavedState,
+    /// Error history
+    pub errors: Vec<String>,
+    /// Serialized app state
+    pub app: serde_json::Value,
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Export a JSON snapshot of the settings, panel states, error history and app state
+    ///
+    /// Useful to get a reproducible state dump from the users of an app
+    /// # Errors
+    /// Fails if the state cannot be serialized
+    pub fn export_debug_snapshot(&self) -> Result<Vec<u8>, AppError> {
+        let snapshot = DebugSnapshot {
+            app_name: M::name(),
+            app_version: M::version(),
+            bladvak_version: env!("CARGO_PKG_VERSION"),
+            timestamp: unix_millis(now()),
+            platform: if cfg!(target_arch = "wasm32") {
+                "web"
+            } else {
+                std::env::consts::OS
+            },
+            internal: &self.internal,
+            errors: self
+                .error_manager
+                .errors
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            app: serde_json::to_value(&self.app)
+                .map_err(|e| AppError::from(("Cannot serialize the app state", e)))?,
+        };
+        serde_json::to_vec_pretty(&snapshot)
+            .map_err(|e| AppError::from(("Cannot serialize the debug snapshot", e)))
+    }
+
+    /// Export the debug snapshot and ask where to save it
+    /// # Errors
+    /// Fails if the snapshot cannot be exported
+    pub fn save_debug_snapshot(&self, ctx: &egui::Context) -> Result<(), AppError> {
+        let data = self.export_debug_snapshot()?;
+        let filename = format!("{}-snapshot.json", M::name());
+        crate::dialog::save_file_dialog(ctx, data, Path::new(&filename));
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+//! Web loading screen
+//!
+//! The page shows a loading element until the app is started (see
+//! [`crate::BladvakApp::loading_element_id`]). The element is removed once the app runs, or
+//! replaced by the page of [`crate::BladvakApp::startup_error_page`] when the start fails.
+
+/// Id of the loading element in the default `index.html`
+pub const LOADING_ELEMENT_ID: &str = "loading_text";
+
+/// Escape a text for HTML
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Error page shown in place of the loading element when the app cannot start
+#[must_use]
+pub fn error_page(name: &str, error: &str) -> String {
+    format!(
+        "<p>{} has crashed.</p>\
+         <pre style=\"white-space:pre-wrap;text-align:left\">{}</pre>\
+         <p>See the developer console for details.</p>",
+        escape_html(name),
+        escape_html(error)
+    )
+}
@@ -0,0 +1,143 @@
+//! Startup performance report
+//!
+//! The startup phases (state load, app construction, first frame...) are measured from the
+//! start of [`crate::Bladvak::bladvak_main`]. With the `--startup-profile` argument, the
+//! report is printed once the app is ready and the app exits.
+
+use eframe::egui;
+use std::{fmt, sync::OnceLock};
+
+use crate::utils::time::{Duration, Instant};
+
+/// Argument printing the startup report and exiting
+pub const STARTUP_PROFILE_ARG: &str = "--startup-profile";
+
+/// Start of the process
+static PROCESS_START: OnceLock<Instant> = OnceLock::new();
+
+/// Mark the start of the process - the first startup phase starts here
+pub(crate) fn mark_process_start() {
+    PROCESS_START.get_or_init(Instant::now);
+}
+
+/// Measured startup phase
+#[derive(Debug, Clone)]
+pub struct StartupPhase {
+    /// Phase name
+    pub name: String,
+    /// Phase duration
+    pub duration: Duration,
+}
+
+/// Startup phases
+#[derive(Debug, Clone, Default)]
+pub struct StartupReport {
+    /// Phases in order
+    pub phases: Vec<StartupPhase>,
+}
+
+impl StartupReport {
+    /// Total startup duration
+    #[must_use]
+    pub fn total(&self) -> Duration {
+        self.phases.iter().map(|phase| phase.duration).sum()
+    }
+}
+
+impl fmt::Display for StartupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for phase in &self.phases {
+            writeln!(f, "{:<24} {:>10.2?}", phase.name, phase.duration)?;
+        }
+        write!(f, "{:<24} {:>10.2?}", "total", self.total())
+    }
+}
+
+/// Startup profiler
+#[derive(Debug)]
+pub(crate) struct StartupProfiler {
+    /// End of the last phase
+    last: Instant,
+    /// Measured phases
+    pub(crate) report: StartupReport,
+    /// Number of frames started
+    frames: usize,
+    /// Print the report and exit when done
+    exit_when_done: bool,
+    /// Is the startup done
+    done: bool,
+    /// Called at the end of each phase
+    progress: Option<fn(&str)>,
+}
+
+impl Default for StartupProfiler {
+    fn default() -> Self {
+        Self {
+            last: Instant::now(),
+            report: StartupReport::default(),
+            frames: 0,
+            exit_when_done: false,
+            done: true,
+            progress: None,
+        }
+    }
+}
+
+impl StartupProfiler {
+    /// Create a new profiler - the time since the process start is the first phase
+    pub(crate) fn new(args: &[String]) -> Self {
+        let mut profiler = Self {
+            last: PROCESS_START.get().copied().unwrap_or_else(Instant::now),
+            report: StartupReport::default(),
+            frames: 0,
+            exit_when_done: args.iter().any(|arg| arg == STARTUP_PROFILE_ARG),
+            done: false,
+            progress: None,
+        };
+        profiler.phase("runtime init");
+        profiler
+    }
+
+    /// Set the callback called at the end of each phase
+    pub(crate) fn with_progress(mut self, progress: fn(&str)) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// End the current phase
+    pub(crate) fn phase(&mut self, name: &str) {
+        if self.done {
+            return;
+        }
+        let now = Instant::now();
+        self.report.phases.push(StartupPhase {
+            name: name.to_string(),
+            duration: now - self.last,
+        });
+        self.last = now;
+        if let Some(progress) = self.progress {
+            progress(name);
+        }
+    }
+
+    /// Called at the start of each frame - the startup is done after the first frame, once the
+    /// saved state is loaded
+    pub(crate) fn frame(&mut self, ctx: &egui::Context, restoring: bool) {
+        if self.done {
+            return;
+        }
+        self.frames += 1;
+        if self.frames == 2 {
+            self.phase("first frame");
+        }
+        if self.frames < 2 || restoring {
+            return;
+        }
+        self.done = true;
+        log::info!("Startup done in {:.2?}", self.report.total());
+        if self.exit_when_done {
+            println!("{}", self.report);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+    }
+}
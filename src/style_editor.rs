@@ -0,0 +1,205 @@
+//! Style editor
+//!
+//! The "Style" debug panel edits the egui style of the current theme and the chrome of the
+//! wrapper. Unlike the style UI of egui, the changes survive a restart: they are kept in the
+//! saved state as a diff against the style set by the app at start (adjusted to the high
+//! contrast, density and reduced motion settings), so that a new default of the app still applies to the fields the user did not
+//! change.
+
+use eframe::egui::{self, Style, Theme};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+use crate::{
+    app::{Bladvak, BladvakApp},
+    journal::{self, JournalKind},
+};
+
+/// Style changes of the user - the JSON of the changed fields, by theme
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct StyleOverrides {
+    /// Changes of the dark style
+    pub dark: Option<String>,
+    /// Changes of the light style
+    pub light: Option<String>,
+}
+
+impl StyleOverrides {
+    /// Changes of a theme
+    fn get(&self, theme: Theme) -> Option<&str> {
+        match theme {
+            Theme::Dark => self.dark.as_deref(),
+            Theme::Light => self.light.as_deref(),
+        }
+    }
+
+    /// Replace the changes of a theme
+    fn set(&mut self, theme: Theme, diff: Option<String>) {
+        match theme {
+            Theme::Dark => self.dark = diff,
+            Theme::Light => self.light = diff,
+        }
+    }
+}
+
+/// Styles set by the app at start - the base of the diffs
+#[derive(Clone)]
+struct BaseStyles {
+    /// Dark style
+    dark: Arc<Style>,
+    /// Light style
+    light: Arc<Style>,
+}
+
+/// Style set by the app for a theme - recorded on the first call
+fn base_style(ctx: &egui::Context, theme: Theme) -> Arc<Style> {
+    let id = egui::Id::new("bladvak_base_styles");
+    let base = ctx.data_mut(|data| data.get_temp::<BaseStyles>(id));
+    let base = base.unwrap_or_else(|| {
+        let base = BaseStyles {
+            dark: ctx.style_of(Theme::Dark),
+            light: ctx.style_of(Theme::Light),
+        };
+        ctx.data_mut(|data| data.insert_temp(id, base.clone()));
+        base
+    });
+    match theme {
+        Theme::Dark => base.dark,
+        Theme::Light => base.light,
+    }
+}
+
+/// Fields of `value` which differ from `base` - `None` if there is none
+fn diff_value(base: &Value, value: &Value) -> Option<Value> {
+    match (base, value) {
+        (Value::Object(base), Value::Object(value)) => {
+            let changed: Map<String, Value> = value
+                .iter()
+                .filter_map(|(key, field)| {
+                    let changed = match base.get(key) {
+                        Some(base_field) => diff_value(base_field, field)?,
+                        None => field.clone(),
+                    };
+                    Some((key.clone(), changed))
+                })
+                .collect();
+            (!changed.is_empty()).then_some(Value::Object(changed))
+        }
+        (base, value) => (base != value).then(|| value.clone()),
+    }
+}
+
+/// Write the fields of a diff over `base`
+fn merge_value(base: &mut Value, diff: Value) {
+    match (base, diff) {
+        (Value::Object(base), Value::Object(diff)) => {
+            for (key, field) in diff {
+                match base.get_mut(&key) {
+                    Some(base_field) => merge_value(base_field, field),
+                    None => {
+                        base.insert(key, field);
+                    }
+                }
+            }
+        }
+        (base, diff) => *base = diff,
+    }
+}
+
+/// Diff of a style against its base - `None` if the style is unchanged
+fn style_diff(base: &Style, style: &Style) -> Option<String> {
+    let base = serde_json::to_value(base).ok()?;
+    let style = serde_json::to_value(style).ok()?;
+    diff_value(&base, &style).map(|diff| diff.to_string())
+}
+
+/// Apply a diff to a style
+fn apply_diff(base: &Style, diff: &str) -> Result<Style, serde_json::Error> {
+    let mut style = serde_json::to_value(base)?;
+    merge_value(&mut style, serde_json::from_str(diff)?);
+    let mut style: Style = serde_json::from_value(style)?;
+    // not serialized
+    style.number_formatter = base.number_formatter.clone();
+    Ok(style)
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Style of the app for a theme, adjusted to the high contrast, density and reduced motion
+    /// settings
+    fn settings_style(&self, ctx: &egui::Context, theme: Theme) -> Arc<Style> {
+        let mut style = base_style(ctx, theme);
+        let settings = &self.internal.settings;
+        if settings.high_contrast {
+            crate::a11y::high_contrast_style(Arc::make_mut(&mut style));
+        }
+        settings.density.apply(Arc::make_mut(&mut style));
+        if settings.reduced_motion() {
+            crate::a11y::reduced_motion_style(Arc::make_mut(&mut style));
+        }
+        style
+    }
+
+    /// Apply the accessibility settings and the saved style changes - the styles without
+    /// changes are set back to the ones of the app
+    pub(crate) fn apply_style_overrides(&self, ctx: &egui::Context) {
+        crate::a11y::set_high_contrast(ctx, self.internal.settings.high_contrast);
+        crate::a11y::set_reduced_motion(ctx, self.internal.settings.reduced_motion());
+        for theme in [Theme::Dark, Theme::Light] {
+            let base = self.settings_style(ctx, theme);
+            let style = match self.internal.style_overrides.get(theme) {
+                Some(diff) => match apply_diff(&base, diff) {
+                    Ok(style) => Arc::new(style),
+                    Err(err) => {
+                        log::warn!("Cannot apply the saved {theme:?} style: {err}");
+                        base
+                    }
+                },
+                None => base,
+            };
+            ctx.set_style_of(theme, style);
+        }
+    }
+
+    /// Remove the style changes of the user
+    pub(crate) fn reset_style(&mut self, ctx: &egui::Context) {
+        self.internal.style_overrides = StyleOverrides::default();
+        self.apply_style_overrides(ctx);
+    }
+
+    /// Show the "Style" debug panel
+    pub(crate) fn show_style_editor(&mut self, ctx: &egui::Context) {
+        let mut open = self.internal.settings.show_style_editor;
+        egui::Window::new("Style")
+            .id("bladvak_style_editor".into())
+            .open(&mut open)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                ui.heading("Chrome");
+                ui.add(
+                    egui::Slider::new(&mut self.internal.settings.min_width_sidebar, 100.0..=600.0)
+                        .text("Sidebar minimum width"),
+                );
+                ui.checkbox(&mut self.internal.settings.toolbar.visible, "Show toolbar");
+                ui.separator();
+                let theme = ctx.theme();
+                ui.horizontal(|ui| {
+                    ui.heading(format!("{theme:?} style"));
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("Reset style").clicked() {
+                            self.reset_style(ctx);
+                            journal::record(JournalKind::Settings, "Reset style");
+                        }
+                    });
+                });
+                ctx.style_ui(ui, theme);
+                let diff = style_diff(&self.settings_style(ctx, theme), &ctx.style_of(theme));
+                self.internal.style_overrides.set(theme, diff);
+            });
+        self.internal.settings.show_style_editor = open;
+    }
+}
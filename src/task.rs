@@ -0,0 +1,769 @@
+//! Background tasks
+
+use eframe::egui;
+use poll_promise::Promise;
+use std::{
+    any::Any,
+    fmt,
+    fmt::Debug,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use crate::{
+    errors::{AppError, ErrorCategory},
+    inbox::InboxSender,
+    limits::LimitError,
+    locale::LocaleFormat,
+    utils::time::{Duration, Instant, SystemTime, UNIX_EPOCH, now, unix_millis},
+};
+
+/// Result of a task - a success message or an error
+pub type TaskResult = Result<String, AppError>;
+
+/// Handle to a computation running in the background - polled by the ui
+pub struct TaskHandle<T: Send + 'static>(Promise<T>);
+
+impl<T: Send + 'static> Debug for TaskHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskHandle")
+            .field("ready", &self.is_ready())
+            .finish()
+    }
+}
+
+impl<T: Send + 'static> TaskHandle<T> {
+    /// Get the result if the computation is done
+    #[must_use]
+    pub fn ready(&self) -> Option<&T> {
+        self.0.ready()
+    }
+
+    /// Get the result if the computation is done
+    pub fn ready_mut(&mut self) -> Option<&mut T> {
+        self.0.ready_mut()
+    }
+
+    /// Check if the computation is done
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.0.ready().is_some()
+    }
+
+    /// Take the result if the computation is done, get the handle back otherwise
+    /// # Errors
+    /// Returns the handle if the computation is not done
+    pub fn try_take(self) -> Result<T, Self> {
+        self.0.try_take().map_err(Self)
+    }
+
+    /// Underlying promise
+    pub(crate) fn into_promise(self) -> Promise<T> {
+        self.0
+    }
+}
+
+/// Run a future in the background
+///
+/// On native the future runs in its own thread, on web it runs on the browser event loop
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn<T, F>(future: F) -> TaskHandle<T>
+where
+    T: Send + 'static,
+    F: Future<Output = T> + Send + 'static,
+{
+    TaskHandle(Promise::spawn_thread("bladvak-task", move || {
+        pollster::block_on(future)
+    }))
+}
+
+/// Run a future in the background
+///
+/// On native the future runs in its own thread, on web it runs on the browser event loop
+#[cfg(target_arch = "wasm32")]
+pub fn spawn<T, F>(future: F) -> TaskHandle<T>
+where
+    T: Send + 'static,
+    F: Future<Output = T> + 'static,
+{
+    TaskHandle(Promise::spawn_local(future))
+}
+
+/// Run a blocking function in the background
+///
+/// On native the function runs in its own thread, on web it runs directly. A panic of the
+/// function is turned into an [`ErrorCategory::Internal`] error
+pub fn spawn_blocking<T, F>(func: F) -> TaskHandle<Result<T, AppError>>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+{
+    spawn_blocking_named("bladvak-task", func)
+}
+
+/// Run a blocking function in the background in a named thread
+pub(crate) fn spawn_blocking_named<T, F>(
+    name: impl Into<String>,
+    func: F,
+) -> TaskHandle<Result<T, AppError>>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+{
+    let name = name.into();
+    let thread_name = name.clone();
+    let run = move || {
+        std::panic::catch_unwind(AssertUnwindSafe(func))
+            .unwrap_or_else(|payload| Err(panic_error(&name, payload.as_ref())))
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        TaskHandle(Promise::spawn_thread(thread_name, run))
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = thread_name;
+        TaskHandle(Promise::from_ready(run()))
+    }
+}
+
+/// Error of a background function that panicked
+fn panic_error(name: &str, payload: &(dyn Any + Send)) -> AppError {
+    let reason = payload
+        .downcast_ref::<&str>()
+        .map(ToString::to_string)
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown reason".to_string());
+    AppError::new(format!("Task {name} panicked: {reason}")).with_category(ErrorCategory::Internal)
+}
+
+/// Task identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TaskId(u64);
+
+/// Next task id - shared by the task manager and the detached tasks
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+impl TaskId {
+    /// Create a new task id
+    fn next() -> Self {
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Delivery of the value produced by a task to the inbox of the app which started it
+type Delivery = Box<dyn FnOnce() -> bool + Send>;
+
+/// Output of a task - its message and the value to deliver to the app inbox
+struct TaskOutput {
+    /// Success message
+    message: String,
+    /// Value delivered to the inbox when the task is collected
+    delivery: Option<Delivery>,
+}
+
+/// Running task
+pub struct Task {
+    /// Task id
+    pub id: TaskId,
+    /// Task name
+    pub name: String,
+    /// Task promise
+    promise: Promise<Result<TaskOutput, AppError>>,
+    /// Deadline of the task
+    deadline: Option<(Instant, Duration)>,
+    /// Start of the task
+    started: Instant,
+}
+
+impl Debug for Task {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Task")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Task {
+    /// Start a task
+    fn start<F>(name: String, timeout: Option<Duration>, func: F) -> Self
+    where
+        F: FnOnce() -> Result<TaskOutput, AppError> + Send + 'static,
+    {
+        log::info!("Starting task {name}");
+        let promise = spawn_blocking_named(name.clone(), func).into_promise();
+        let started = Instant::now();
+        Self {
+            id: TaskId::next(),
+            name,
+            promise,
+            deadline: timeout.map(|timeout| (started + timeout, timeout)),
+            started,
+        }
+    }
+}
+
+/// Task abandoned at its timeout - it cannot be cancelled and runs until it finishes
+struct TimedOutTask {
+    /// Task name
+    name: String,
+    /// Task promise - its result is ignored
+    promise: Promise<Result<TaskOutput, AppError>>,
+    /// Start of the task
+    started: Instant,
+}
+
+impl Debug for TimedOutTask {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TimedOutTask")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Tasks started outside of the task manager (see [`crate::limits::ParseLimits::spawn`]),
+/// shared through the egui context - taken over by the task manager of the app at its next poll
+#[derive(Clone, Default)]
+struct DetachedHandle(Arc<Mutex<Vec<Task>>>);
+
+impl DetachedHandle {
+    /// Id of the shared state in the egui context
+    fn id() -> egui::Id {
+        egui::Id::new("bladvak_detached_tasks")
+    }
+
+    /// Get the shared state from the egui context
+    fn get(ctx: &egui::Context) -> Self {
+        ctx.data_mut(|data| data.get_temp_mut_or_default::<Self>(Self::id()).clone())
+    }
+}
+
+/// Start a task outside of the task manager - at the end of the task its value is sent to the
+/// inbox of the app being run (see [`crate::inbox::sender`]), unless the task timed out
+pub(crate) fn spawn_detached<T, F>(
+    ctx: &egui::Context,
+    name: String,
+    timeout: Option<Duration>,
+    func: F,
+) -> TaskId
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+{
+    let inbox = crate::inbox::sender(ctx);
+    let task = Task::start(name, timeout, move || {
+        let value = func()?;
+        Ok(TaskOutput {
+            message: "result sent to the inbox".to_string(),
+            delivery: Some(Box::new(move || {
+                inbox.is_some_and(|inbox: InboxSender| inbox.send(value))
+            })),
+        })
+    });
+    let id = task.id;
+    if let Ok(mut detached) = DetachedHandle::get(ctx).0.lock() {
+        detached.push(task);
+    }
+    ctx.request_repaint();
+    id
+}
+
+/// Finished task
+#[derive(Debug, Clone)]
+pub struct FinishedTask {
+    /// Task id
+    pub id: TaskId,
+    /// Task name
+    pub name: String,
+    /// Task result
+    pub result: TaskResult,
+    /// Running time of the task
+    pub duration: Duration,
+    /// Recurring job which started the task
+    pub job: Option<JobId>,
+}
+
+/// Send the value of a finished task to the app inbox - returns the message of the task
+fn deliver(output: TaskOutput) -> String {
+    if let Some(delivery) = output.delivery
+        && !delivery()
+    {
+        log::warn!("The result of a task was not delivered - no app inbox");
+    }
+    output.message
+}
+
+/// Recurring job identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JobId(u64);
+
+/// Schedule of a recurring job - the times are in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    /// Every interval - the first run is one interval after the scheduling
+    Every(Duration),
+    /// Every hour at the given minute
+    Hourly {
+        /// Minute (0-59)
+        minute: u8,
+    },
+    /// Every day at the given time
+    Daily {
+        /// Hour (0-23)
+        hour: u8,
+        /// Minute (0-59)
+        minute: u8,
+    },
+}
+
+impl Schedule {
+    /// Next run strictly after `time`
+    #[must_use]
+    pub fn next_after(&self, time: SystemTime) -> SystemTime {
+        let (period, offset) = match *self {
+            Self::Every(interval) => return time + interval,
+            Self::Hourly { minute } => (3600, u64::from(minute.min(59)) * 60),
+            Self::Daily { hour, minute } => (
+                86400,
+                u64::from(hour.min(23)) * 3600 + u64::from(minute.min(59)) * 60,
+            ),
+        };
+        let secs = unix_millis(time) / 1000;
+        let next = secs - (secs + period - offset) % period + period;
+        UNIX_EPOCH + Duration::from_secs(next)
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Every(interval) => write!(f, "every {}s", interval.as_secs()),
+            Self::Hourly { minute } => write!(f, "hourly at :{minute:02}"),
+            Self::Daily { hour, minute } => write!(f, "daily at {hour:02}:{minute:02}"),
+        }
+    }
+}
+
+/// What to do when a recurring job is due while its previous run is still running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverlapPolicy {
+    /// Skip the run
+    #[default]
+    SkipIfRunning,
+    /// Start the run anyway
+    Allow,
+}
+
+/// Function run by a recurring job
+pub type JobFn = dyn Fn() -> TaskResult + Send + Sync;
+
+/// Recurring job - polls a device, refreshes remote data, autosaves...
+pub struct RecurringJob {
+    /// Job name
+    pub name: String,
+    /// Schedule
+    pub schedule: Schedule,
+    /// Overlap policy
+    pub policy: OverlapPolicy,
+    /// Function run at each run
+    func: Arc<JobFn>,
+}
+
+impl Debug for RecurringJob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecurringJob")
+            .field("name", &self.name)
+            .field("schedule", &self.schedule)
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RecurringJob {
+    /// Create a new recurring job
+    pub fn new<S, F>(name: S, schedule: Schedule, func: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn() -> TaskResult + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            schedule,
+            policy: OverlapPolicy::default(),
+            func: Arc::new(func),
+        }
+    }
+
+    /// Set the overlap policy
+    #[must_use]
+    pub fn with_policy(mut self, policy: OverlapPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+/// Scheduled recurring job
+#[derive(Debug)]
+pub struct ScheduledJob {
+    /// Job id
+    pub id: JobId,
+    /// Job
+    pub job: RecurringJob,
+    /// Next run
+    pub next_run: SystemTime,
+    /// Last run
+    pub last_run: Option<SystemTime>,
+    /// Running tasks of the job
+    running: Vec<TaskId>,
+}
+
+impl ScheduledJob {
+    /// Check if a run of the job is running
+    #[must_use]
+    pub fn is_running(&self) -> bool {
+        !self.running.is_empty()
+    }
+}
+
+/// Task manager - runs tasks in the background
+#[derive(Debug, Default)]
+pub struct TaskManager {
+    /// Running tasks
+    tasks: Vec<Task>,
+    /// Tasks abandoned at their timeout and still running
+    timed_out: Vec<TimedOutTask>,
+    /// Recurring jobs
+    jobs: Vec<ScheduledJob>,
+    /// Next job id
+    next_job_id: u64,
+    /// Are the recurring jobs paused
+    paused: bool,
+}
+
+impl TaskManager {
+    /// Spawn a task
+    ///
+    /// On native the task runs in its own thread, on web it runs directly
+    pub fn spawn<S, F>(&mut self, name: S, func: F) -> TaskId
+    where
+        S: Into<String>,
+        F: FnOnce() -> TaskResult + Send + 'static,
+    {
+        self.spawn_inner(name.into(), None, func)
+    }
+
+    /// Spawn a task failing with [`LimitError::Timeout`] if it takes longer than `timeout`
+    ///
+    /// The task is not cancelled at the timeout: it runs until it finishes, listed as timed out
+    /// in the task list, and its result is ignored - the timeout is not enforced on web
+    pub fn spawn_with_timeout<S, F>(&mut self, name: S, timeout: Duration, func: F) -> TaskId
+    where
+        S: Into<String>,
+        F: FnOnce() -> TaskResult + Send + 'static,
+    {
+        self.spawn_inner(name.into(), Some(timeout), func)
+    }
+
+    /// Spawn a task
+    fn spawn_inner<F>(&mut self, name: String, timeout: Option<Duration>, func: F) -> TaskId
+    where
+        F: FnOnce() -> TaskResult + Send + 'static,
+    {
+        let task = Task::start(name, timeout, move || {
+            func().map(|message| TaskOutput {
+                message,
+                delivery: None,
+            })
+        });
+        let id = task.id;
+        self.tasks.push(task);
+        id
+    }
+
+    /// Schedule a recurring job
+    pub fn schedule(&mut self, job: RecurringJob) -> JobId {
+        let id = JobId(self.next_job_id);
+        self.next_job_id += 1;
+        log::info!("Scheduling job {} ({})", job.name, job.schedule);
+        self.jobs.push(ScheduledJob {
+            id,
+            next_run: job.schedule.next_after(now()),
+            last_run: None,
+            running: Vec::new(),
+            job,
+        });
+        id
+    }
+
+    /// Remove a recurring job - its running tasks are not cancelled
+    pub fn unschedule(&mut self, id: JobId) -> bool {
+        let count = self.jobs.len();
+        self.jobs.retain(|job| job.id != id);
+        self.jobs.len() != count
+    }
+
+    /// Pause or resume the recurring jobs - the runs missed while paused are run once on resume
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Are the recurring jobs paused
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Recurring jobs
+    pub fn jobs(&self) -> impl Iterator<Item = &ScheduledJob> {
+        self.jobs.iter()
+    }
+
+    /// Start the due recurring jobs - returns the time of the next run
+    fn run_due_jobs(&mut self) -> Option<SystemTime> {
+        let current = now();
+        let mut due = Vec::new();
+        for scheduled in &mut self.jobs {
+            if scheduled.next_run > current {
+                continue;
+            }
+            scheduled.next_run = scheduled.job.schedule.next_after(current);
+            if scheduled.job.policy == OverlapPolicy::SkipIfRunning && scheduled.is_running() {
+                log::warn!("Skipping job {} - still running", scheduled.job.name);
+                continue;
+            }
+            scheduled.last_run = Some(current);
+            due.push((
+                scheduled.id,
+                scheduled.job.name.clone(),
+                scheduled.job.func.clone(),
+            ));
+        }
+        for (job_id, name, func) in due {
+            let task_id = self.spawn_inner(name, None, move || func());
+            if let Some(scheduled) = self.jobs.iter_mut().find(|job| job.id == job_id) {
+                scheduled.running.push(task_id);
+            }
+        }
+        self.jobs.iter().map(|job| job.next_run).min()
+    }
+
+    /// Job of a task
+    fn job_of(&mut self, task_id: TaskId) -> Option<JobId> {
+        let scheduled = self
+            .jobs
+            .iter_mut()
+            .find(|job| job.running.contains(&task_id))?;
+        scheduled.running.retain(|id| *id != task_id);
+        Some(scheduled.id)
+    }
+
+    /// Number of running tasks - the timed out ones included
+    #[must_use]
+    pub fn pending_count(&self) -> usize {
+        self.tasks.len() + self.timed_out.len()
+    }
+
+    /// Forget the finished timed out tasks
+    fn poll_timed_out(&mut self) {
+        self.timed_out.retain(|task| {
+            let finished = task.promise.ready().is_some();
+            if finished {
+                log::info!(
+                    "Timed out task {} finished after {}",
+                    task.name,
+                    crate::utils::format_duration(task.started.elapsed())
+                );
+            }
+            !finished
+        });
+    }
+
+    /// Running tasks
+    pub fn tasks(&self) -> impl Iterator<Item = &Task> {
+        self.tasks.iter()
+    }
+
+    /// Collect the finished tasks and start the due jobs - repaint while some tasks are running
+    pub fn poll(&mut self, ctx: &egui::Context) -> Vec<FinishedTask> {
+        let next_run = if self.paused {
+            None
+        } else {
+            self.run_due_jobs()
+        };
+        if let Ok(mut detached) = DetachedHandle::get(ctx).0.lock() {
+            self.tasks.append(&mut detached);
+        }
+        let mut finished = Vec::new();
+        let mut running = Vec::with_capacity(self.tasks.len());
+        for task in self.tasks.drain(..) {
+            match task.promise.try_take() {
+                Ok(result) => finished.push(FinishedTask {
+                    id: task.id,
+                    name: task.name,
+                    result: result.map(deliver),
+                    duration: task.started.elapsed(),
+                    job: None,
+                }),
+                Err(promise) => match task.deadline {
+                    Some((deadline, limit)) if Instant::now() > deadline => {
+                        log::warn!("Task {} timed out", task.name);
+                        finished.push(FinishedTask {
+                            id: task.id,
+                            name: task.name.clone(),
+                            result: Err(LimitError::Timeout { limit }.into()),
+                            duration: task.started.elapsed(),
+                            job: None,
+                        });
+                        self.timed_out.push(TimedOutTask {
+                            name: task.name,
+                            promise,
+                            started: task.started,
+                        });
+                    }
+                    _ => running.push(Task { promise, ..task }),
+                },
+            }
+        }
+        self.tasks = running;
+        self.poll_timed_out();
+        for task in &mut finished {
+            task.job = self.job_of(task.id);
+        }
+        if !self.tasks.is_empty() {
+            ctx.request_repaint();
+        } else if !self.timed_out.is_empty() {
+            ctx.request_repaint_after(Duration::from_secs(1));
+        } else if let Some(next_run) = next_run {
+            ctx.request_repaint_after(next_run.duration_since(now()).unwrap_or_default());
+        }
+        finished
+    }
+
+    /// Show the running tasks and the recurring jobs
+    pub(crate) fn ui(&self, ui: &mut egui::Ui) {
+        ui.heading("Tasks");
+        if self.tasks.is_empty() {
+            ui.label("No running task");
+        }
+        for task in &self.tasks {
+            ui.label(format!(
+                "⏳ {} ({})",
+                task.name,
+                crate::utils::format_duration(task.started.elapsed())
+            ));
+        }
+        for task in &self.timed_out {
+            ui.label(format!(
+                "⌛ {} (timed out, still running - {})",
+                task.name,
+                crate::utils::format_duration(task.started.elapsed())
+            ))
+            .on_hover_text("The task cannot be cancelled, its result will be ignored");
+        }
+        ui.separator();
+        ui.heading("Recurring jobs");
+        if self.jobs.is_empty() {
+            ui.label("No recurring job");
+        } else if self.paused {
+            ui.label("⏸ Paused while the window is in the background");
+        }
+        let locale = LocaleFormat::get(ui.ctx());
+        egui::Grid::new("bladvak_jobs")
+            .striped(true)
+            .num_columns(4)
+            .show(ui, |ui| {
+                for scheduled in &self.jobs {
+                    ui.label(&scheduled.job.name);
+                    ui.label(scheduled.job.schedule.to_string());
+                    ui.label(format!("next: {}", locale.format_time(scheduled.next_run)));
+                    ui.label(if scheduled.is_running() {
+                        "running".to_string()
+                    } else {
+                        scheduled.last_run.map_or_else(
+                            || "never run".to_string(),
+                            |t| format!("last: {}", locale.format_time(t)),
+                        )
+                    });
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Panics of the background tasks
+
+    use super::{FinishedTask, TaskId, TaskManager, spawn_blocking};
+    use crate::{errors::ErrorCategory, inbox::Inbox, limits::ParseLimits, utils::time::Duration};
+    use eframe::egui;
+
+    /// Poll until a task finishes
+    fn wait(tasks: &mut TaskManager, ctx: &egui::Context, id: TaskId) -> FinishedTask {
+        loop {
+            if let Some(finished) = tasks.poll(ctx).into_iter().find(|task| task.id == id) {
+                return finished;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// A panicking task finishes with an internal error instead of crashing the poll
+    #[test]
+    fn panicking_task() {
+        let ctx = egui::Context::default();
+        let mut tasks = TaskManager::default();
+        let id = tasks.spawn("panicking", || panic!("boom"));
+        let finished = wait(&mut tasks, &ctx, id);
+        let Err(error) = &finished.result else {
+            panic!("the panicking task succeeded");
+        };
+        assert_eq!(error.category, Some(ErrorCategory::Internal));
+        assert!(error.message.contains("boom"));
+    }
+
+    /// A panicking blocking function gives an error through its handle
+    #[test]
+    fn panicking_handle() {
+        let mut handle = spawn_blocking(|| -> Result<(), _> { panic!("boom") });
+        let result = loop {
+            match handle.try_take() {
+                Ok(result) => break result,
+                Err(pending) => handle = pending,
+            }
+            std::thread::yield_now();
+        };
+        assert!(result.is_err());
+    }
+
+    /// The parsed value reaches the inbox, a timed out parsing gives an error and no value
+    #[test]
+    fn parse_with_timeout() {
+        let ctx = egui::Context::default();
+        let inbox = Inbox::new(&ctx, "test");
+        let mut tasks = TaskManager::default();
+        let limits = ParseLimits {
+            timeout: Some(Duration::from_millis(50)),
+            ..ParseLimits::default()
+        };
+        let id = limits.spawn(&ctx, "fast", || Ok(42_u32));
+        assert!(wait(&mut tasks, &ctx, id).result.is_ok());
+        let messages = inbox.drain();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].is::<u32>());
+
+        let id = limits.spawn(&ctx, "slow", || {
+            std::thread::sleep(Duration::from_millis(200));
+            Ok(0_u32)
+        });
+        assert!(wait(&mut tasks, &ctx, id).result.is_err());
+        std::thread::sleep(Duration::from_millis(300));
+        tasks.poll(&ctx);
+        assert!(inbox.drain().is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+//! Opt-in telemetry
+//!
+//! The wrapper emits a few structured events (app started, file opened, error occurred, panel
+//! toggled) and the app receives them in [`crate::BladvakApp::on_telemetry`] to send them where
+//! it wants. Nothing is delivered before the user agrees: the consent is asked once when the
+//! app opts in with [`crate::BladvakApp::collects_telemetry`], and can be changed in the
+//! settings. The events carry no file name, path or error message.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, fmt::Display, sync::Mutex};
+
+use crate::{
+    app::{Bladvak, BladvakApp, PanelOpen},
+    errors::Severity,
+    journal::{self, JournalKind},
+};
+
+/// Maximum number of events kept while waiting for the consent
+pub const TELEMETRY_CAPACITY: usize = 100;
+
+/// Events waiting to be delivered
+static EVENTS: Mutex<VecDeque<TelemetryEvent>> = Mutex::new(VecDeque::new());
+
+/// Telemetry event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum TelemetryEvent {
+    /// The app is ready
+    AppStarted {
+        /// Version of the app
+        version: String,
+        /// Target platform
+        platform: String,
+    },
+    /// A file was opened
+    FileOpened {
+        /// Extension of the file - the name is not sent
+        extension: Option<String>,
+    },
+    /// An error or a message was reported
+    ErrorOccurred {
+        /// Severity
+        severity: Severity,
+        /// Category and code (see [`crate::AppError::tag`]) - the message is not sent
+        tag: Option<String>,
+    },
+    /// The state of a panel was changed
+    PanelToggled {
+        /// Panel name
+        panel: String,
+        /// New state
+        state: String,
+    },
+}
+
+impl Display for TelemetryEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AppStarted { version, platform } => {
+                write!(f, "App started ({version}, {platform})")
+            }
+            Self::FileOpened { extension } => {
+                write!(f, "File opened ({})", extension.as_deref().unwrap_or("-"))
+            }
+            Self::ErrorOccurred { severity, tag } => {
+                write!(f, "{severity} ({})", tag.as_deref().unwrap_or("-"))
+            }
+            Self::PanelToggled { panel, state } => write!(f, "Panel {panel}: {state}"),
+        }
+    }
+}
+
+/// Queue an event - delivered at the next frame if the user agreed
+pub(crate) fn emit(event: TelemetryEvent) {
+    if let Ok(mut events) = EVENTS.lock() {
+        events.push_back(event);
+        let excess = events.len().saturating_sub(TELEMETRY_CAPACITY);
+        events.drain(..excess);
+    }
+}
+
+/// Queue a panel state change
+pub(crate) fn panel_toggled(panel: &str, state: &PanelOpen) {
+    emit(TelemetryEvent::PanelToggled {
+        panel: panel.to_string(),
+        state: state.to_string(),
+    });
+}
+
+/// Take the queued events
+fn take_events() -> VecDeque<TelemetryEvent> {
+    EVENTS
+        .lock()
+        .map(|mut events| std::mem::take(&mut *events))
+        .unwrap_or_default()
+}
+
+/// Id of the "app started" flag
+fn started_id() -> egui::Id {
+    egui::Id::new("bladvak_telemetry_started")
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Deliver the queued events - kept until the user answers the consent, dropped if refused
+    pub(crate) fn process_telemetry(&mut self, ctx: &egui::Context) {
+        if !M::collects_telemetry() {
+            take_events();
+            return;
+        }
+        if !ctx.data(|data| data.get_temp::<bool>(started_id()).unwrap_or(false)) {
+            ctx.data_mut(|data| data.insert_temp(started_id(), true));
+            emit(TelemetryEvent::AppStarted {
+                version: M::version(),
+                platform: crate::utils::target_info(),
+            });
+        }
+        match self.internal.settings.telemetry {
+            Some(true) => {
+                for event in take_events() {
+                    self.app.on_telemetry(&event);
+                }
+            }
+            Some(false) => {
+                take_events();
+            }
+            None => {}
+        }
+    }
+
+    /// Explain the telemetry events
+    fn telemetry_explanation(ui: &mut egui::Ui) {
+        ui.label(format!(
+            "{} can send anonymous usage events to its developers:",
+            M::name()
+        ));
+        for line in [
+            "the version and the platform at start",
+            "the extension of the opened files",
+            "the category of the errors",
+            "the panels shown or hidden",
+        ] {
+            ui.label(format!("• {line}"));
+        }
+        ui.label("No file name, path or error message is sent.");
+    }
+
+    /// Ask the consent once
+    pub(crate) fn show_telemetry_consent(&mut self, ctx: &egui::Context) {
+        if !M::collects_telemetry() || self.internal.settings.telemetry.is_some() {
+            return;
+        }
+        let mut answer = None;
+        egui::Modal::new(egui::Id::new("Modal telemetry consent")).show(ctx, |ui| {
+            crate::a11y::annotate(ui, egui::accesskit::Role::AlertDialog, "Usage events");
+            ui.heading("Usage events");
+            Self::telemetry_explanation(ui);
+            ui.label("You can change your choice in the settings.");
+            ui.horizontal(|ui| {
+                if ui.button("Allow").clicked() {
+                    answer = Some(true);
+                }
+                if ui.button("Don't allow").clicked() {
+                    answer = Some(false);
+                }
+            });
+        });
+        if let Some(answer) = answer {
+            self.set_telemetry(answer);
+        }
+    }
+
+    /// Show the telemetry setting
+    pub(crate) fn show_telemetry_setting(&mut self, ui: &mut egui::Ui) {
+        if !M::collects_telemetry() {
+            return;
+        }
+        let mut enabled = self.internal.settings.telemetry.unwrap_or(false);
+        let response = ui
+            .checkbox(&mut enabled, "Send anonymous usage events")
+            .on_hover_ui(Self::telemetry_explanation);
+        if response.changed() {
+            self.set_telemetry(enabled);
+        }
+    }
+
+    /// Record the consent
+    fn set_telemetry(&mut self, enabled: bool) {
+        self.internal.settings.telemetry = Some(enabled);
+        journal::record(
+            JournalKind::Settings,
+            format!(
+                "Usage events: {}",
+                if enabled { "allowed" } else { "refused" }
+            ),
+        );
+    }
+}
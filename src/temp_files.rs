@@ -0,0 +1,223 @@
+//! Scratch files and folders - e.g. to hand a path to an external tool
+//!
+//! The [`TempFileRegistry`] creates them under `<cache dir>/<app name>/tmp/<process id>`. The
+//! folder is removed when the app exits, and the folders left by a crashed instance are removed
+//! at the next start once they are older than [`STALE_TEMP_AGE`] - the folder of a running
+//! instance is kept, it holds a lock on a file of its folder. There is no file system on web: the
+//! registry returns errors.
+
+use eframe::egui;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use crate::{AppError, utils::time::Duration};
+
+/// Age of the folders of other instances removed at start
+pub const STALE_TEMP_AGE: Duration = Duration::from_hours(24);
+
+/// File of the folder of an instance locked while the instance is running
+const LOCK_FILE: &str = ".lock";
+
+/// Cache directory of the OS
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn os_cache_dir() -> Option<PathBuf> {
+    let env_dir = |name: &str| std::env::var_os(name).map(PathBuf::from);
+    if cfg!(target_os = "windows") {
+        env_dir("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library").join("Caches"))
+    } else {
+        env_dir("XDG_CACHE_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".cache")))
+    }
+}
+
+/// Folder of the temporary files of an app - the system temporary folder is used if the OS has
+/// no cache directory
+#[cfg(not(target_arch = "wasm32"))]
+#[must_use]
+pub fn temp_root(app_name: &str) -> Option<PathBuf> {
+    let cache = os_cache_dir().unwrap_or_else(std::env::temp_dir);
+    Some(cache.join(app_name).join("tmp"))
+}
+
+/// Folder of the temporary files of an app - no folder on web
+#[cfg(target_arch = "wasm32")]
+#[must_use]
+pub fn temp_root(_app_name: &str) -> Option<PathBuf> {
+    None
+}
+
+/// Shared state of the registry
+#[derive(Debug)]
+struct Inner {
+    /// Folder of this instance
+    folder: Option<PathBuf>,
+    /// Counter of the created entries
+    next: AtomicU64,
+    /// Lock file of the folder - held until the cleanup
+    lock: Mutex<Option<fs::File>>,
+}
+
+/// Creates scratch files and folders and removes them on exit - cheap to clone
+#[derive(Debug, Clone)]
+pub struct TempFileRegistry(Arc<Inner>);
+
+impl Default for TempFileRegistry {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl TempFileRegistry {
+    /// Create a registry using a sub-folder of `root` for this instance
+    #[must_use]
+    pub fn new(root: Option<&Path>) -> Self {
+        Self(Arc::new(Inner {
+            folder: root.map(|root| root.join(std::process::id().to_string())),
+            next: AtomicU64::new(0),
+            lock: Mutex::new(None),
+        }))
+    }
+
+    /// Id of the registry in the egui context
+    fn id() -> egui::Id {
+        egui::Id::new("bladvak_temp_files")
+    }
+
+    /// Get the registry of the app
+    #[must_use]
+    pub fn get(ctx: &egui::Context) -> Self {
+        ctx.data_mut(|data| data.get_temp::<Self>(Self::id()).unwrap_or_default())
+    }
+
+    /// Folder of this instance - `None` on web
+    #[must_use]
+    pub fn folder(&self) -> Option<&Path> {
+        self.0.folder.as_deref()
+    }
+
+    /// New empty folder - the entries keep their name in a numbered folder
+    fn new_slot(&self) -> Result<PathBuf, AppError> {
+        let folder = self
+            .folder()
+            .ok_or_else(|| AppError::new("No temporary files on this platform".to_string()))?;
+        let slot = folder.join(self.0.next.fetch_add(1, Ordering::Relaxed).to_string());
+        fs::create_dir_all(&slot)?;
+        self.hold_lock(folder)?;
+        Ok(slot)
+    }
+
+    /// Lock the lock file of the folder so that the other instances keep the folder
+    fn hold_lock(&self, folder: &Path) -> Result<(), AppError> {
+        if let Ok(mut lock) = self.0.lock.lock()
+            && lock.is_none()
+        {
+            let file = fs::File::create(folder.join(LOCK_FILE))?;
+            file.try_lock().map_err(std::io::Error::from)?;
+            *lock = Some(file);
+        }
+        Ok(())
+    }
+
+    /// Create a file with the data - the file name is kept (e.g. for the extension)
+    /// # Errors
+    /// Error if the file cannot be written or on web
+    pub fn create_file(&self, name: &str, data: &[u8]) -> Result<PathBuf, AppError> {
+        let path = self.new_slot()?.join(name);
+        fs::write(&path, data)?;
+        Ok(path)
+    }
+
+    /// Create an empty folder
+    /// # Errors
+    /// Error if the folder cannot be created or on web
+    pub fn create_dir(&self, name: &str) -> Result<PathBuf, AppError> {
+        let path = self.new_slot()?.join(name);
+        fs::create_dir(&path)?;
+        Ok(path)
+    }
+
+    /// Remove the files and the folders of this instance
+    pub fn cleanup(&self) {
+        let Some(folder) = self.folder() else {
+            return;
+        };
+        // the lock file cannot be removed while it is open on Windows
+        if let Ok(mut lock) = self.0.lock.lock() {
+            lock.take();
+        }
+        if folder.exists()
+            && let Err(err) = fs::remove_dir_all(folder)
+        {
+            log::warn!("Cannot remove {}: {err}", folder.display());
+        }
+    }
+}
+
+/// Check if a folder is held by a running instance
+fn is_locked(folder: &Path) -> bool {
+    fs::File::open(folder.join(LOCK_FILE))
+        .is_ok_and(|file| matches!(file.try_lock(), Err(fs::TryLockError::WouldBlock)))
+}
+
+/// Remove the folders left by the previous instances
+fn remove_stale(root: &Path, current: Option<&Path>) {
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > STALE_TEMP_AGE);
+        if stale && Some(path.as_path()) != current && !is_locked(&path) {
+            log::info!("Removing stale temporary files {}", path.display());
+            if let Err(err) = fs::remove_dir_all(&path) {
+                log::warn!("Cannot remove {}: {err}", path.display());
+            }
+        }
+    }
+}
+
+/// Create the registry of the app and remove the stale files
+pub(crate) fn install(ctx: &egui::Context, app_name: &str) -> TempFileRegistry {
+    let root = temp_root(app_name);
+    let registry = TempFileRegistry::new(root.as_deref());
+    if let Some(root) = &root {
+        remove_stale(root, registry.folder());
+    }
+    ctx.data_mut(|data| data.insert_temp(TempFileRegistry::id(), registry.clone()));
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    //! Stale folders of the other instances
+
+    use super::{TempFileRegistry, is_locked};
+
+    /// The folder of a running instance is locked until its cleanup
+    #[test]
+    fn running_instance_is_locked() {
+        let root = std::env::temp_dir().join(format!("bladvak_temp_test_{}", std::process::id()));
+        let registry = TempFileRegistry::new(Some(&root));
+        let Some(folder) = registry.folder().map(std::path::Path::to_path_buf) else {
+            panic!("no folder");
+        };
+        assert!(!is_locked(&folder));
+        assert!(registry.create_file("data.bin", b"data").is_ok());
+        assert!(is_locked(&folder));
+        registry.cleanup();
+        assert!(!folder.exists());
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,162 @@
+//! Headless test harness for apps built on bladvak
+//!
+//! Enabled with the `testing` feature - the app runs without the eframe runner in an
+//! [`egui_kittest::Harness`], so UI integration tests can run in CI
+
+use eframe::egui;
+use egui_kittest::{Harness, kittest::Queryable};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Debug, path::Path};
+
+pub use egui_kittest;
+
+use crate::{
+    app::{Bladvak, BladvakApp, PanelOpen},
+    errors::AppError,
+    file_handler::{File, FilePicker, submit_file},
+};
+
+/// Headless harness running a [`Bladvak`] app
+pub struct BladvakHarness<'a, M> {
+    /// kittest harness
+    harness: Harness<'a, Bladvak<M>>,
+}
+
+impl<M> Debug for BladvakHarness<'_, M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BladvakHarness")
+            .field("harness", &self.harness)
+            .finish()
+    }
+}
+
+impl<M> Default for BladvakHarness<'_, M>
+where
+    M: for<'a> BladvakApp<'a> + Debug + Default + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'h, M> BladvakHarness<'h, M>
+where
+    M: for<'a> BladvakApp<'a> + Debug + Default + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Create a harness with the app built without args
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_args(&[])
+    }
+
+    /// Create a harness with the app built with args
+    #[must_use]
+    pub fn with_args(args: &[String]) -> Self {
+        let args = args.to_vec();
+        let harness = Harness::builder()
+            .with_size(egui::vec2(1024.0, 768.0))
+            .with_max_steps(64)
+            .build_eframe(move |cc| Bladvak::try_new_with_args(cc, &args));
+        Self { harness }
+    }
+
+    /// Get the kittest harness - e.g. to query the widgets
+    pub fn harness(&mut self) -> &mut Harness<'h, Bladvak<M>> {
+        &mut self.harness
+    }
+
+    /// Run one frame
+    pub fn step(&mut self) {
+        self.harness.step();
+    }
+
+    /// Run frames until no more repaints are requested
+    /// # Panics
+    /// Panics if the app keeps requesting repaints (e.g. a task never finishes)
+    pub fn run(&mut self) -> u64 {
+        self.harness.run()
+    }
+
+    /// Get the app
+    #[must_use]
+    pub fn app(&self) -> &M {
+        &self.harness.state().app
+    }
+
+    /// Get the app mutably
+    pub fn app_mut(&mut self) -> &mut M {
+        &mut self.harness.state_mut().app
+    }
+
+    /// Get the app wrapper
+    #[must_use]
+    pub fn bladvak(&self) -> &Bladvak<M> {
+        self.harness.state()
+    }
+
+    /// Replace the file picker used by "Open" - e.g. with a [`crate::file_handler::CannedPicker`]
+    pub fn set_file_picker<P: FilePicker + 'static>(&mut self, picker: P) {
+        self.harness
+            .state_mut()
+            .file_handler
+            .set_picker(Box::new(picker));
+    }
+
+    /// Simulate the drop of a file from the file system and run the frames
+    /// # Panics
+    /// Panics if the app keeps requesting repaints
+    pub fn drop_path<P: AsRef<Path>>(&mut self, path: P) {
+        self.harness
+            .input_mut()
+            .dropped_files
+            .push(egui::DroppedFile {
+                path: Some(path.as_ref().to_path_buf()),
+                ..Default::default()
+            });
+        self.run();
+    }
+
+    /// Submit a file to the file pipeline and run the frames
+    /// # Panics
+    /// Panics if the app keeps requesting repaints
+    pub fn open_file(&mut self, file: File) {
+        submit_file(&self.harness.ctx, file);
+        self.run();
+    }
+
+    /// Click the menus and the menu item with the labels, e.g. `&["File", "Open As…"]`
+    /// # Panics
+    /// Panics if a label is not found
+    pub fn click_menu(&mut self, labels: &[&str]) {
+        for label in labels {
+            self.harness.get_by_label(label).click();
+            self.run();
+        }
+    }
+
+    /// Errors reported in the error manager
+    #[must_use]
+    pub fn errors(&self) -> &[AppError] {
+        &self.harness.state().error_manager.errors
+    }
+
+    /// Assert that no error was reported
+    /// # Panics
+    /// Panics if an error was reported
+    #[track_caller]
+    pub fn assert_no_errors(&self) {
+        let errors = self.errors();
+        assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+    }
+
+    /// Open state of a panel
+    #[must_use]
+    pub fn panel_open(&self, name: &str) -> Option<&PanelOpen> {
+        self.harness
+            .state()
+            .internal
+            .panel_state
+            .get(name)
+            .map(|state| &state.open)
+    }
+}
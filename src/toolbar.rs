@@ -0,0 +1,165 @@
+//! Toolbar below the menu bar
+//!
+//! The toolbar shows the commands with an icon (see [`crate::Command::with_icon`]). The user
+//! can hide it, hide some buttons and reorder them in the settings; the order and the hidden
+//! buttons are persisted with the settings.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use crate::{
+    app::{Bladvak, BladvakApp},
+    command::Command,
+    journal::{self, JournalKind},
+};
+
+/// Settings of the toolbar
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ToolbarSettings {
+    /// Is the toolbar shown
+    pub visible: bool,
+    /// Command ids in the order chosen by the user - the other commands follow
+    pub order: Vec<String>,
+    /// Hidden command ids
+    pub hidden: BTreeSet<String>,
+}
+
+impl Default for ToolbarSettings {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            order: Vec::new(),
+            hidden: BTreeSet::new(),
+        }
+    }
+}
+
+impl ToolbarSettings {
+    /// Commands of the toolbar in order, hidden included
+    fn items<'c, App>(&self, commands: &'c [Command<App>]) -> Vec<&'c Command<App>> {
+        let with_icon = commands.iter().filter(|command| command.icon.is_some());
+        let mut items: Vec<_> = self
+            .order
+            .iter()
+            .filter_map(|id| with_icon.clone().find(|command| &command.id == id))
+            .collect();
+        items.extend(with_icon.filter(|command| !self.order.contains(&command.id)));
+        items
+    }
+
+    /// Move a command by an offset in the order
+    fn move_item(&mut self, ids: Vec<String>, index: usize, offset: isize) {
+        let Some(target) = index.checked_add_signed(offset) else {
+            return;
+        };
+        if target < ids.len() {
+            self.order = ids;
+            self.order.swap(index, target);
+        }
+    }
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Show the toolbar - nothing if hidden or if no command has an icon
+    pub(crate) fn show_toolbar(&mut self, ui: &mut egui::Ui) {
+        let settings = &self.internal.settings.toolbar;
+        if !settings.visible {
+            return;
+        }
+        let items: Vec<_> = settings
+            .items(&self.commands)
+            .into_iter()
+            .filter(|command| !settings.hidden.contains(&command.id))
+            .collect();
+        if items.is_empty() {
+            return;
+        }
+        let mut clicked = None;
+        egui::Panel::top("bladvak_toolbar").show(ui, |ui| {
+            crate::a11y::annotate(ui, egui::accesskit::Role::Toolbar, "Toolbar");
+            ui.horizontal(|ui| {
+                for command in items {
+                    let icon = command.icon.as_deref().unwrap_or_default();
+                    let button = egui::Button::new(icon)
+                        .selected(command.is_checked(&self.app).unwrap_or(false));
+                    let mut tooltip = command.label.clone();
+                    if let Some(shortcut) = &command.shortcut {
+                        tooltip = format!("{tooltip} ({})", ui.ctx().format_shortcut(shortcut));
+                    }
+                    let response = ui
+                        .add_enabled(command.is_enabled(&self.app), button)
+                        .on_hover_text(&tooltip)
+                        .on_disabled_hover_text(&tooltip);
+                    crate::a11y::label(&response, &command.label);
+                    if response.clicked() {
+                        clicked = Some(command.id.clone());
+                    }
+                }
+            });
+        });
+        if let Some(id) = clicked {
+            crate::command::run_command(ui, id);
+        }
+    }
+
+    /// Show the toolbar settings
+    pub(crate) fn show_toolbar_setting(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Toolbar");
+        let settings = &mut self.internal.settings.toolbar;
+        ui.checkbox(&mut settings.visible, "Show the toolbar");
+        let ids: Vec<_> = settings
+            .items(&self.commands)
+            .into_iter()
+            .map(|command| {
+                (
+                    command.id.clone(),
+                    command.label.clone(),
+                    command.icon.clone(),
+                )
+            })
+            .collect();
+        if ids.is_empty() {
+            ui.label("No command has an icon");
+            return;
+        }
+        let order: Vec<_> = ids.iter().map(|(id, _, _)| id.clone()).collect();
+        let mut moved = None;
+        for (index, (id, label, icon)) in ids.iter().enumerate() {
+            ui.horizontal(|ui| {
+                let mut shown = !settings.hidden.contains(id);
+                let text = format!("{} {label}", icon.as_deref().unwrap_or_default());
+                if ui.checkbox(&mut shown, text).changed() {
+                    if shown {
+                        settings.hidden.remove(id);
+                    } else {
+                        settings.hidden.insert(id.clone());
+                    }
+                    journal::record(
+                        JournalKind::Settings,
+                        format!(
+                            "Toolbar {label}: {}",
+                            if shown { "shown" } else { "hidden" }
+                        ),
+                    );
+                }
+                if ui.add_enabled(index > 0, egui::Button::new("⏶")).clicked() {
+                    moved = Some((index, -1));
+                }
+                if ui
+                    .add_enabled(index + 1 < ids.len(), egui::Button::new("⏷"))
+                    .clicked()
+                {
+                    moved = Some((index, 1));
+                }
+            });
+        }
+        if let Some((index, offset)) = moved {
+            settings.move_item(order, index, offset);
+        }
+    }
+}
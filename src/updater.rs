@@ -0,0 +1,256 @@
+//! Update check against the GitHub releases
+//!
+//! When the app opts in with [`crate::BladvakApp::check_updates`], the latest release of
+//! [`crate::BladvakApp::repo_url`] is fetched in the background at start and compared with
+//! [`crate::BladvakApp::version`]. A newer release is announced in the messages and with a link
+//! in the menu bar - on web, the link reloads the page to get the new build. The user can turn
+//! the check off in the settings.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    AppError,
+    app::{Bladvak, BladvakApp},
+};
+
+/// Status of the update check
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum UpdateStatus {
+    /// Not checked yet
+    #[default]
+    NotChecked,
+    /// Request in flight
+    Checking,
+    /// The app is at the latest version
+    UpToDate,
+    /// A newer release exists
+    Available {
+        /// Version of the release
+        version: String,
+        /// Page of the release
+        url: String,
+        /// Files of the release
+        assets: Vec<ReleaseAsset>,
+    },
+    /// The check failed
+    Failed(String),
+}
+
+/// File of a release
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ReleaseAsset {
+    /// File name
+    pub name: String,
+    /// Download URL
+    #[serde(rename = "browser_download_url")]
+    pub url: String,
+    /// Digest computed by GitHub (e.g. "sha256:…")
+    #[serde(default)]
+    pub digest: Option<String>,
+}
+
+/// Latest release returned by the GitHub API
+#[derive(Debug, Deserialize)]
+struct Release {
+    /// Tag of the release (e.g. "v1.2.0")
+    tag_name: String,
+    /// Page of the release
+    html_url: String,
+    /// Files of the release
+    #[serde(default)]
+    assets: Vec<ReleaseAsset>,
+}
+
+/// Update checker
+#[derive(Debug, Default)]
+pub(crate) struct Updater {
+    /// Status shared with the request callback
+    status: Arc<Mutex<UpdateStatus>>,
+    /// Was the available update announced
+    announced: bool,
+    /// Installation of the update
+    #[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+    pub(crate) installer: crate::self_update::SelfUpdate,
+}
+
+/// URL of the latest release in the GitHub API - `None` if the repo is not on GitHub
+fn latest_release_url(repo_url: &str) -> Option<String> {
+    let path = repo_url
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .strip_prefix("https://github.com/")?;
+    let (owner, repo) = path.split_once('/')?;
+    Some(format!(
+        "https://api.github.com/repos/{owner}/{repo}/releases/latest"
+    ))
+}
+
+/// Check if a release asset is built for the current target - its name contains the
+/// architecture and the OS (e.g. "app-x86_64-linux")
+#[must_use]
+pub fn matches_current_target(name: &str) -> bool {
+    let name = name.to_lowercase();
+    let os_names: &[&str] = match std::env::consts::OS {
+        "macos" => &["macos", "darwin", "apple"],
+        os => &[os],
+    };
+    name.contains(std::env::consts::ARCH)
+        && os_names.iter().any(|os| name.contains(os))
+        && std::path::Path::new(&name)
+            .extension()
+            .is_none_or(|extension| extension != "sig")
+}
+
+/// Numbers of a version - "v1.10.0-beta" gives `[1, 10, 0]`
+fn version_numbers(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+/// Check if a version is newer than another
+fn is_newer(candidate: &str, current: &str) -> bool {
+    version_numbers(candidate) > version_numbers(current)
+}
+
+impl Updater {
+    /// Current status
+    pub(crate) fn status(&self) -> UpdateStatus {
+        self.status
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or_default()
+    }
+
+    /// Fetch the latest release in the background
+    fn start(&self, ctx: &egui::Context, repo_url: &str, current: String) {
+        let Some(url) = latest_release_url(repo_url) else {
+            if let Ok(mut status) = self.status.lock() {
+                *status = UpdateStatus::Failed(format!("{repo_url} is not a GitHub repo"));
+            }
+            return;
+        };
+        if let Ok(mut status) = self.status.lock() {
+            *status = UpdateStatus::Checking;
+        }
+        let request = ehttp::Request::get(url)
+            .with_header("Accept", "application/vnd.github+json")
+            .with_header("User-Agent", "bladvak");
+        let shared = Arc::clone(&self.status);
+        let ctx = ctx.clone();
+        ehttp::fetch(request, move |result| {
+            let new_status = match result {
+                Ok(response) if response.ok => {
+                    match serde_json::from_slice::<Release>(&response.bytes) {
+                        Ok(release) if is_newer(&release.tag_name, &current) => {
+                            UpdateStatus::Available {
+                                version: release.tag_name,
+                                url: release.html_url,
+                                assets: release.assets,
+                            }
+                        }
+                        Ok(_) => UpdateStatus::UpToDate,
+                        Err(err) => UpdateStatus::Failed(format!("Invalid release: {err}")),
+                    }
+                }
+                Ok(response) => {
+                    UpdateStatus::Failed(format!("{} {}", response.status, response.status_text))
+                }
+                Err(err) => UpdateStatus::Failed(err),
+            };
+            if let Ok(mut status) = shared.lock() {
+                *status = new_status;
+            }
+            ctx.request_repaint();
+        });
+    }
+}
+
+/// Reload the page without the cache - to get the new build on web
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn reload_page() {
+    if let Some(window) = eframe::web_sys::window()
+        && let Err(err) = window.location().reload()
+    {
+        log::warn!("Cannot reload the page: {err:?}");
+    }
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Status of the update check
+    #[must_use]
+    pub fn update_status(&self) -> UpdateStatus {
+        self.updater.status()
+    }
+
+    /// Start the update check and announce the available update
+    pub(crate) fn process_updates(&mut self, ctx: &egui::Context) {
+        if !M::check_updates() || !self.internal.settings.check_updates {
+            return;
+        }
+        match self.updater.status() {
+            UpdateStatus::NotChecked => {
+                self.updater.start(ctx, &M::repo_url(), M::version());
+            }
+            UpdateStatus::Available { version, url, .. } if !self.updater.announced => {
+                self.updater.announced = true;
+                log::info!("Update available: {version}");
+                self.error_manager.add_info(
+                    AppError::new(format!("Update available: {} {version}", M::name()))
+                        .with_details(url),
+                );
+            }
+            UpdateStatus::Failed(err) if !self.updater.announced => {
+                self.updater.announced = true;
+                log::warn!("Cannot check the updates: {err}");
+            }
+            _ => {}
+        }
+    }
+
+    /// Show the link to the available update - hidden without update
+    pub(crate) fn update_button(&mut self, ui: &mut egui::Ui) {
+        let UpdateStatus::Available {
+            version,
+            url,
+            assets,
+        } = self.updater.status()
+        else {
+            return;
+        };
+        let text = egui::RichText::new(format!("⬆ {version}")).color(ui.visuals().warn_fg_color);
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = (url, assets);
+            if ui
+                .button(text)
+                .on_hover_text("Update available - reload to get the new build")
+                .clicked()
+            {
+                reload_page();
+            }
+        }
+        #[cfg(all(feature = "self-update", not(target_arch = "wasm32")))]
+        ui.menu_button(text, |ui| {
+            self.self_update_menu(ui, &version, &url, &assets);
+        })
+        .response
+        .on_hover_text("Update available");
+        #[cfg(all(not(feature = "self-update"), not(target_arch = "wasm32")))]
+        {
+            let _ = assets;
+            crate::widgets::link(ui, text, &url)
+                .on_hover_text("Update available - open the release page");
+        }
+    }
+}
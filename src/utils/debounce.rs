@@ -0,0 +1,110 @@
+//! Debounce and throttle helpers keyed to the egui time
+//!
+//! Both schedule the repaint they need: the app polls them in its ui and does not run a timer
+
+use eframe::egui;
+
+use crate::utils::time::Duration;
+
+/// Current egui time in seconds
+fn time(ctx: &egui::Context) -> f64 {
+    ctx.input(|i| i.time)
+}
+
+/// Value released after a quiet period - e.g. re-parse a text 300 ms after the last key
+///
+/// Each [`Debounced::set`] replaces the value and restarts the delay, [`Debounced::poll`]
+/// returns the last value once the delay is over
+#[derive(Debug, Clone)]
+pub struct Debounced<T> {
+    /// Quiet period
+    delay: Duration,
+    /// Last value and the time it can be released
+    pending: Option<(T, f64)>,
+}
+
+impl<T> Debounced<T> {
+    /// Create a debouncer
+    #[must_use]
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            pending: None,
+        }
+    }
+
+    /// Replace the pending value and restart the delay
+    pub fn set(&mut self, ctx: &egui::Context, value: T) {
+        self.pending = Some((value, time(ctx) + self.delay.as_secs_f64()));
+        ctx.request_repaint_after(self.delay);
+    }
+
+    /// Take the value if the delay is over - a repaint is requested for the end of the delay
+    pub fn poll(&mut self, ctx: &egui::Context) -> Option<T> {
+        let (_, deadline) = self.pending.as_ref()?;
+        let remaining = deadline - time(ctx);
+        if remaining > 0.0 {
+            ctx.request_repaint_after(Duration::from_secs_f64(remaining));
+            return None;
+        }
+        self.pending.take().map(|(value, _)| value)
+    }
+
+    /// Is a value waiting for the end of the delay
+    #[must_use]
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Take the value without waiting - e.g. when the text input loses the focus
+    pub fn flush(&mut self) -> Option<T> {
+        self.pending.take().map(|(value, _)| value)
+    }
+
+    /// Drop the pending value
+    pub fn cancel(&mut self) {
+        self.pending = None;
+    }
+}
+
+/// Rate limit - e.g. update a preview at most every 100 ms while a slider is dragged
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    /// Minimum time between two runs
+    interval: Duration,
+    /// Time of the last run
+    last: Option<f64>,
+}
+
+impl Throttle {
+    /// Create a throttle
+    #[must_use]
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: None,
+        }
+    }
+
+    /// Check if the work can run now - at most once per interval
+    ///
+    /// When it cannot, a repaint is requested for the end of the interval so the last change is
+    /// not lost if the app calls it again
+    pub fn ready(&mut self, ctx: &egui::Context) -> bool {
+        let now = time(ctx);
+        if let Some(last) = self.last {
+            let remaining = last + self.interval.as_secs_f64() - now;
+            if remaining > 0.0 {
+                ctx.request_repaint_after(Duration::from_secs_f64(remaining));
+                return false;
+            }
+        }
+        self.last = Some(now);
+        true
+    }
+
+    /// Let the next call run immediately
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
@@ -0,0 +1,299 @@
+//! Human-readable formatting - sizes, durations and numbers
+
+use crate::utils::time::Duration;
+
+/// Units of [`format_bytes`]
+const BYTE_UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// Format a size with binary units - one decimal above 1 KiB
+///
+/// ```
+/// use bladvak::utils::format::format_bytes;
+///
+/// assert_eq!(format_bytes(0), "0 B");
+/// assert_eq!(format_bytes(1023), "1023 B");
+/// assert_eq!(format_bytes(1024), "1.0 KiB");
+/// assert_eq!(format_bytes(1536), "1.5 KiB");
+/// // rounded up to the next unit
+/// assert_eq!(format_bytes(1024 * 1024 - 1), "1.0 MiB");
+/// assert_eq!(format_bytes(u64::MAX), "16.0 EiB");
+/// ```
+#[must_use]
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    // 1023.95 would be displayed as "1024.0"
+    while value >= 1023.95 && unit < BYTE_UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", BYTE_UNITS[unit])
+}
+
+/// Format a duration with its two largest units
+///
+/// ```
+/// use bladvak::utils::{format::format_duration, time::Duration};
+///
+/// assert_eq!(format_duration(Duration::ZERO), "0 µs");
+/// assert_eq!(format_duration(Duration::from_micros(250)), "250 µs");
+/// assert_eq!(format_duration(Duration::from_millis(350)), "350 ms");
+/// assert_eq!(format_duration(Duration::from_millis(1250)), "1.3 s");
+/// // rounded up to the next unit
+/// assert_eq!(format_duration(Duration::from_millis(59_960)), "1 min 0 s");
+/// assert_eq!(format_duration(Duration::from_secs(125)), "2 min 5 s");
+/// assert_eq!(format_duration(Duration::from_secs(3600 + 90)), "1 h 2 min");
+/// assert_eq!(format_duration(Duration::from_secs(3 * 86_400 + 7200)), "3 d 2 h");
+/// ```
+#[must_use]
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis == 0 {
+        return format!("{} µs", duration.as_micros());
+    }
+    if millis < 1000 {
+        return format!("{millis} ms");
+    }
+    let tenths = (millis + 50) / 100;
+    if tenths < 600 {
+        return format!("{}.{} s", tenths / 10, tenths % 10);
+    }
+    let seconds = (millis + 500) / 1000;
+    if seconds < 3600 {
+        return format!("{} min {} s", seconds / 60, seconds % 60);
+    }
+    let minutes = (seconds + 30) / 60;
+    if minutes < 24 * 60 {
+        return format!("{} h {} min", minutes / 60, minutes % 60);
+    }
+    let hours = (minutes + 30) / 60;
+    format!("{} d {} h", hours / 24, hours % 24)
+}
+
+/// Locale of the user (e.g. "en-US") - the language of the browser on web, the `LC_ALL`,
+/// `LC_NUMERIC` or `LANG` variable on native, "en-US" if unknown
+#[must_use]
+pub fn locale() -> String {
+    #[cfg(target_arch = "wasm32")]
+    let locale = eframe::web_sys::window().and_then(|window| window.navigator().language());
+    #[cfg(not(target_arch = "wasm32"))]
+    let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        // e.g. "fr_FR.UTF-8"
+        .map(|value| {
+            value
+                .split(['.', '@'])
+                .next()
+                .unwrap_or_default()
+                .replace('_', "-")
+        });
+    locale
+        .filter(|locale| !locale.is_empty() && locale != "C" && locale != "POSIX")
+        .unwrap_or_else(|| "en-US".to_string())
+}
+
+/// Separators of the numbers in a locale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// Decimal separator
+    pub decimal: char,
+    /// Separator of the groups of thousands
+    pub group: char,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self::ENGLISH
+    }
+}
+
+impl NumberFormat {
+    /// English separators - "1,234.5"
+    pub const ENGLISH: Self = Self {
+        decimal: '.',
+        group: ',',
+    };
+
+    /// Separators of a locale (e.g. `de-DE`, `fr_FR` or `pt`) - English if unknown
+    ///
+    /// ```
+    /// use bladvak::utils::format::NumberFormat;
+    ///
+    /// assert_eq!(NumberFormat::for_locale("en-GB"), NumberFormat::ENGLISH);
+    /// assert_eq!(NumberFormat::for_locale("de_DE").decimal, ',');
+    /// assert_eq!(NumberFormat::for_locale("fr").group, '\u{a0}');
+    /// assert_eq!(NumberFormat::for_locale(""), NumberFormat::ENGLISH);
+    /// ```
+    #[must_use]
+    pub fn for_locale(locale: &str) -> Self {
+        let language = locale
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        match language.as_str() {
+            "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "hr" | "sl"
+            | "sr" => Self {
+                decimal: ',',
+                group: '.',
+            },
+            "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "nn" | "no" | "uk" | "hu"
+            | "bg" | "lt" | "lv" | "et" => Self {
+                decimal: ',',
+                group: '\u{a0}',
+            },
+            _ => Self::ENGLISH,
+        }
+    }
+
+    /// Separators of the locale of the user (see [`locale`])
+    #[must_use]
+    pub fn current() -> Self {
+        Self::for_locale(&locale())
+    }
+
+    /// Format a number with a fixed number of decimals
+    ///
+    /// ```
+    /// use bladvak::utils::format::NumberFormat;
+    ///
+    /// let english = NumberFormat::ENGLISH;
+    /// assert_eq!(english.format(0.0, 0), "0");
+    /// assert_eq!(english.format(999.0, 0), "999");
+    /// assert_eq!(english.format(1234.5, 1), "1,234.5");
+    /// assert_eq!(english.format(-1_234_567.891, 2), "-1,234,567.89");
+    /// // no negative zero
+    /// assert_eq!(english.format(-0.04, 1), "0.0");
+    /// assert_eq!(english.format(f64::NAN, 2), "NaN");
+    ///
+    /// let german = NumberFormat::for_locale("de-DE");
+    /// assert_eq!(german.format(1234.5, 2), "1.234,50");
+    /// ```
+    #[must_use]
+    pub fn format(&self, value: f64, decimals: usize) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        let digits = format!("{:.*}", decimals, value.abs());
+        let (integer, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+        let mut formatted = String::with_capacity(digits.len() + digits.len() / 3 + 1);
+        if value.is_sign_negative()
+            && digits
+                .bytes()
+                .any(|byte| byte.is_ascii_digit() && byte != b'0')
+        {
+            formatted.push('-');
+        }
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                formatted.push(self.group);
+            }
+            formatted.push(digit);
+        }
+        if !fraction.is_empty() {
+            formatted.push(self.decimal);
+            formatted.push_str(fraction);
+        }
+        formatted
+    }
+}
+
+/// Format a number with the separators of the locale of the user (see [`NumberFormat`])
+#[must_use]
+pub fn format_number(value: f64, decimals: usize) -> String {
+    NumberFormat::current().format(value, decimals)
+}
+
+#[cfg(test)]
+mod tests {
+    //! Edge cases of the formatting
+
+    use super::{NumberFormat, format_bytes, format_duration};
+    use crate::utils::time::Duration;
+
+    /// Sizes at zero, at the unit boundaries and at the maximum
+    #[test]
+    fn bytes_boundaries() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1), "1 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KiB");
+        assert_eq!(format_bytes(1024 * 1024 - 1), "1.0 MiB");
+        assert_eq!(format_bytes(1024 * 1024), "1.0 MiB");
+        assert_eq!(format_bytes(1024 * 1024 * 1024 - 1), "1.0 GiB");
+        assert_eq!(format_bytes(1 << 60), "1.0 EiB");
+        assert_eq!(format_bytes(u64::MAX), "16.0 EiB");
+    }
+
+    /// Durations below the millisecond and over several days
+    #[test]
+    fn durations() {
+        assert_eq!(format_duration(Duration::ZERO), "0 µs");
+        assert_eq!(format_duration(Duration::from_nanos(999)), "0 µs");
+        assert_eq!(format_duration(Duration::from_micros(1)), "1 µs");
+        assert_eq!(format_duration(Duration::from_micros(999)), "999 µs");
+        assert_eq!(format_duration(Duration::from_millis(1)), "1 ms");
+        assert_eq!(format_duration(Duration::from_millis(999)), "999 ms");
+        assert_eq!(format_duration(Duration::from_secs(1)), "1.0 s");
+        assert_eq!(format_duration(Duration::from_secs(59)), "59.0 s");
+        assert_eq!(format_duration(Duration::from_mins(1)), "1 min 0 s");
+        assert_eq!(format_duration(Duration::from_secs(3599)), "59 min 59 s");
+        assert_eq!(format_duration(Duration::from_hours(1)), "1 h 0 min");
+        assert_eq!(format_duration(Duration::from_secs(86_369)), "23 h 59 min");
+        // rounded up to the next unit
+        assert_eq!(format_duration(Duration::from_secs(86_399)), "1 d 0 h");
+        assert_eq!(format_duration(Duration::from_hours(24)), "1 d 0 h");
+        assert_eq!(
+            format_duration(Duration::from_hours(400 * 24 + 5)),
+            "400 d 5 h"
+        );
+    }
+
+    /// Negative numbers, with and without rounding to zero
+    #[test]
+    fn negative_numbers() {
+        let english = NumberFormat::ENGLISH;
+        assert_eq!(english.format(-1.0, 0), "-1");
+        assert_eq!(english.format(-999.5, 1), "-999.5");
+        assert_eq!(english.format(-1000.0, 0), "-1,000");
+        assert_eq!(english.format(-0.0, 2), "0.00");
+        assert_eq!(english.format(-0.004, 2), "0.00");
+        assert_eq!(english.format(-0.005_1, 2), "-0.01");
+        assert_eq!(english.format(f64::NEG_INFINITY, 2), "-inf");
+    }
+
+    /// Grouping and decimal separators of every group of locales
+    #[test]
+    fn locale_separators() {
+        let cases = [
+            ("en-US", "1,234,567.89"),
+            ("ja-JP", "1,234,567.89"),
+            ("de-DE", "1.234.567,89"),
+            ("es_ES", "1.234.567,89"),
+            ("pt-BR", "1.234.567,89"),
+            ("fr-FR", "1\u{a0}234\u{a0}567,89"),
+            ("sv", "1\u{a0}234\u{a0}567,89"),
+            ("RU", "1\u{a0}234\u{a0}567,89"),
+            ("", "1,234,567.89"),
+        ];
+        for (locale, expected) in cases {
+            assert_eq!(
+                NumberFormat::for_locale(locale).format(1_234_567.891, 2),
+                expected,
+                "{locale}"
+            );
+        }
+        // no separator below a thousand, one per group above
+        let german = NumberFormat::for_locale("de");
+        assert_eq!(german.format(999.0, 0), "999");
+        assert_eq!(german.format(1000.0, 0), "1.000");
+        assert_eq!(german.format(-12_345.0, 0), "-12.345");
+        assert_eq!(german.format(100_000.0, 1), "100.000,0");
+    }
+}
@@ -1,7 +1,17 @@
-//! Grid
+//! Grid - background grid lines, virtual-scrolling data grid and sortable table
 
 use eframe::egui::{self, Color32, Painter, Rect, Stroke, Vec2, emath::Rot2, vec2};
-use std::f32::consts::PI;
+use egui_extras::{Column, TableBody, TableBuilder, TableRow};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::BTreeSet,
+    f32::consts::PI,
+    fmt::Debug,
+    path::{Path, PathBuf},
+};
+
+use crate::context_menu::{GRID_CONTEXT, show_context_menu};
 
 /// Grid options
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
@@ -116,3 +126,652 @@ impl Grid {
         }
     }
 }
+
+/// Data of a [`VirtualGrid`] - only the visible cells are asked
+pub trait GridSource {
+    /// Number of rows
+    fn row_count(&self) -> usize;
+
+    /// Number of columns
+    fn column_count(&self) -> usize;
+
+    /// Header of a column - no header row if every name is empty
+    fn column_name(&self, _column: usize) -> String {
+        String::new()
+    }
+
+    /// Show a cell
+    fn cell_ui(&mut self, ui: &mut egui::Ui, row: usize, column: usize);
+}
+
+/// Default height of a [`VirtualGrid`] row
+const DEFAULT_ROW_HEIGHT: f32 = 18.0;
+
+/// Grid laying out only its visible rows - the cost of a frame does not depend on the number
+/// of rows, as long as they all have the same height
+#[derive(Debug, Clone)]
+pub struct VirtualGrid {
+    /// Id of the grid in the parent ui
+    id: egui::Id,
+    /// Height of every row
+    row_height: f32,
+    /// Alternate the row background
+    striped: bool,
+}
+
+impl VirtualGrid {
+    /// Create a grid - the id salt must be unique in the parent ui
+    pub fn new(id_salt: impl egui::AsId) -> Self {
+        Self {
+            id: egui::Id::new(id_salt),
+            row_height: DEFAULT_ROW_HEIGHT,
+            striped: true,
+        }
+    }
+
+    /// Set the height of the rows
+    #[must_use]
+    pub fn with_row_height(mut self, row_height: f32) -> Self {
+        self.row_height = row_height;
+        self
+    }
+
+    /// Set if the row background alternates
+    #[must_use]
+    pub fn with_striped(mut self, striped: bool) -> Self {
+        self.striped = striped;
+        self
+    }
+
+    /// Show the rows of a source
+    pub fn show<S: GridSource + ?Sized>(&self, ui: &mut egui::Ui, source: &mut S) {
+        let columns = source.column_count();
+        let rows = source.row_count();
+        let names: Vec<_> = (0..columns)
+            .map(|column| source.column_name(column))
+            .collect();
+        let row_height = self.row_height;
+        let builder = TableBuilder::new(ui)
+            .id_salt(self.id)
+            .striped(self.striped)
+            .columns(Column::auto().at_least(40.0).resizable(true), columns);
+        let add_rows = |body: TableBody<'_>| {
+            body.rows(row_height, rows, |row| Self::row_ui(row, source, columns));
+        };
+        if names.iter().all(String::is_empty) {
+            builder.body(add_rows);
+        } else {
+            builder
+                .header(row_height, |mut header| {
+                    for name in &names {
+                        header.col(|ui| {
+                            ui.strong(name);
+                        });
+                    }
+                })
+                .body(add_rows);
+        }
+    }
+
+    /// Show the cells of a row
+    fn row_ui<S: GridSource + ?Sized>(mut row: TableRow<'_, '_>, source: &mut S, columns: usize) {
+        let index = row.index();
+        for column in 0..columns {
+            row.col(|ui| source.cell_ui(ui, index, column));
+        }
+    }
+}
+
+/// Cell of a [`GridColumn`]
+pub type CellUi<T> = dyn Fn(&T, &mut egui::Ui);
+
+/// Sort comparator of a [`GridColumn`]
+pub type CellCompare<T> = dyn Fn(&T, &T) -> Ordering;
+
+/// Text of a cell of a [`GridColumn`] - matched by the filter, copied and exported
+pub type CellText<T> = dyn Fn(&T) -> String;
+
+/// Column of a [`SortableTable`]
+pub struct GridColumn<T> {
+    /// Header
+    pub name: String,
+    /// Cell
+    cell: Box<CellUi<T>>,
+    /// Sort comparator - the column is not sortable if `None`
+    compare: Option<Box<CellCompare<T>>>,
+    /// Text of the cells - matched by the filter, copied and exported
+    text: Option<Box<CellText<T>>>,
+    /// Show a filter input
+    filterable: bool,
+}
+
+impl<T> Debug for GridColumn<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GridColumn")
+            .field("name", &self.name)
+            .field("sortable", &self.compare.is_some())
+            .field("filterable", &self.filterable)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> GridColumn<T> {
+    /// Create a column - neither sortable nor filterable
+    pub fn new<S, F>(name: S, cell: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&T, &mut egui::Ui) + 'static,
+    {
+        Self {
+            name: name.into(),
+            cell: Box::new(cell),
+            compare: None,
+            text: None,
+            filterable: false,
+        }
+    }
+
+    /// Make the column sortable
+    #[must_use]
+    pub fn with_sort<F: Fn(&T, &T) -> Ordering + 'static>(mut self, compare: F) -> Self {
+        self.compare = Some(Box::new(compare));
+        self
+    }
+
+    /// Set the text of the cells - copied with the selection and exported
+    #[must_use]
+    pub fn with_text<F: Fn(&T) -> String + 'static>(mut self, text: F) -> Self {
+        self.text = Some(Box::new(text));
+        self
+    }
+
+    /// Add a filter input matching the text of the cells (case insensitive)
+    #[must_use]
+    pub fn with_filter<F: Fn(&T) -> String + 'static>(mut self, text: F) -> Self {
+        self.filterable = true;
+        self.with_text(text)
+    }
+}
+
+/// Sort of a [`SortableTable`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortState {
+    /// Index of the sorted column
+    pub column: usize,
+    /// Sort in descending order
+    pub descending: bool,
+}
+
+/// Selection mode of a [`SortableTable`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SelectionMode {
+    /// No selection
+    #[default]
+    None,
+    /// Whole rows
+    Rows,
+    /// Single cells
+    Cells,
+}
+
+/// Selected cells of a [`SortableTable`] - by index of the row in the data and index of the
+/// column
+///
+/// A click selects a row or a cell, Shift+click a range and Ctrl+click (Cmd on mac) adds or
+/// removes one. Ctrl+C copies the selection as tab-separated values.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GridSelection {
+    /// Selected cells
+    cells: BTreeSet<(usize, usize)>,
+    /// Start of the Shift+click range
+    anchor: Option<(usize, usize)>,
+}
+
+impl GridSelection {
+    /// Check if nothing is selected
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Check if a cell is selected
+    #[must_use]
+    pub fn contains(&self, row: usize, column: usize) -> bool {
+        self.cells.contains(&(row, column))
+    }
+
+    /// Rows with a selected cell
+    #[must_use]
+    pub fn rows(&self) -> BTreeSet<usize> {
+        self.cells.iter().map(|(row, _)| *row).collect()
+    }
+
+    /// Unselect everything
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.anchor = None;
+    }
+}
+
+/// Sort, filters and selection of a [`SortableTable`] - to keep in the state of the app to
+/// persist the sort and the filters
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TableState {
+    /// Sort - in the order of the rows if `None`
+    pub sort: Option<SortState>,
+    /// Filter of each column
+    pub filters: Vec<String>,
+    /// Selection - not persisted
+    #[serde(skip)]
+    pub selection: GridSelection,
+}
+
+impl TableState {
+    /// Next sort when the header of a column is clicked: ascending, descending, unsorted
+    fn toggle_sort(&mut self, column: usize) {
+        self.sort = match self.sort {
+            Some(sort) if sort.column == column && !sort.descending => Some(SortState {
+                column,
+                descending: true,
+            }),
+            Some(sort) if sort.column == column => None,
+            _ => Some(SortState {
+                column,
+                descending: false,
+            }),
+        };
+    }
+}
+
+/// Action of the context menu of a [`SortableTable`]
+#[derive(Debug, Clone, Copy)]
+enum GridAction {
+    /// Copy the selection
+    Copy,
+    /// Save the rows as CSV
+    Export {
+        /// Only the rows matching the filters
+        visible_only: bool,
+    },
+}
+
+/// Entries of the context menu of a [`SortableTable`] cell - the contributions to
+/// [`GRID_CONTEXT`] follow
+fn context_menu_ui(ui: &mut egui::Ui, has_selection: bool, action: &mut Option<GridAction>) {
+    if has_selection && ui.button("Copy").clicked() {
+        *action = Some(GridAction::Copy);
+        ui.close();
+    }
+    if ui.button("Export CSV…").clicked() {
+        *action = Some(GridAction::Export { visible_only: true });
+        ui.close();
+    }
+    if ui.button("Export all rows as CSV…").clicked() {
+        *action = Some(GridAction::Export {
+            visible_only: false,
+        });
+        ui.close();
+    }
+}
+
+/// Table with sortable columns and filter inputs - the visible rows are laid out only
+///
+/// The rows are filtered and sorted at each frame
+#[derive(Debug)]
+pub struct SortableTable<T> {
+    /// Id of the table in the parent ui
+    id: egui::Id,
+    /// Columns
+    columns: Vec<GridColumn<T>>,
+    /// Height of every row
+    row_height: f32,
+    /// Selection mode
+    selection: SelectionMode,
+    /// Suggested file of the CSV export
+    export_path: PathBuf,
+}
+
+impl<T> SortableTable<T> {
+    /// Create a table - the id salt must be unique in the parent ui
+    pub fn new(id_salt: impl egui::AsId, columns: Vec<GridColumn<T>>) -> Self {
+        Self {
+            id: egui::Id::new(id_salt),
+            columns,
+            row_height: DEFAULT_ROW_HEIGHT,
+            selection: SelectionMode::None,
+            export_path: PathBuf::from("table.csv"),
+        }
+    }
+
+    /// Set the suggested file of the CSV export - "table.csv" by default
+    #[must_use]
+    pub fn with_export_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.export_path = path.as_ref().to_path_buf();
+        self
+    }
+
+    /// Set the selection mode
+    #[must_use]
+    pub fn with_selection(mut self, selection: SelectionMode) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Set the height of the rows
+    #[must_use]
+    pub fn with_row_height(mut self, row_height: f32) -> Self {
+        self.row_height = row_height;
+        self
+    }
+
+    /// Indices of the rows matching the filters, in the sort order
+    #[must_use]
+    pub fn visible_rows(&self, rows: &[T], state: &TableState) -> Vec<usize> {
+        let filters: Vec<_> = self
+            .columns
+            .iter()
+            .zip(&state.filters)
+            .filter(|(column, filter)| column.filterable && !filter.is_empty())
+            .filter_map(|(column, filter)| Some((column.text.as_ref()?, filter.to_lowercase())))
+            .collect();
+        let mut indices: Vec<_> = (0..rows.len())
+            .filter(|index| {
+                filters
+                    .iter()
+                    .all(|(text, filter)| text(&rows[*index]).to_lowercase().contains(filter))
+            })
+            .collect();
+        if let Some(sort) = state.sort
+            && let Some(compare) = self
+                .columns
+                .get(sort.column)
+                .and_then(|column| column.compare.as_ref())
+        {
+            indices.sort_by(|a, b| {
+                let order = compare(&rows[*a], &rows[*b]);
+                if sort.descending {
+                    order.reverse()
+                } else {
+                    order
+                }
+            });
+        }
+        indices
+    }
+
+    /// Show the header of a column - the sort button and the filter input
+    fn header_ui(&self, ui: &mut egui::Ui, index: usize, state: &mut TableState) {
+        let column = &self.columns[index];
+        if column.compare.is_some() {
+            let arrow = match state.sort {
+                Some(sort) if sort.column == index && sort.descending => " ⏷",
+                Some(sort) if sort.column == index => " ⏶",
+                _ => "",
+            };
+            let text = egui::RichText::new(format!("{}{arrow}", column.name)).strong();
+            if ui.add(egui::Button::new(text).frame(false)).clicked() {
+                state.toggle_sort(index);
+            }
+        } else {
+            ui.strong(&column.name);
+        }
+        if column.filterable {
+            ui.add(
+                egui::TextEdit::singleline(&mut state.filters[index])
+                    .hint_text("Filter")
+                    .desired_width(f32::INFINITY),
+            );
+        }
+    }
+
+    /// Update the selection after a click on a cell - `position` is the index of the row in
+    /// the visible rows
+    fn select(
+        &self,
+        selection: &mut GridSelection,
+        indices: &[usize],
+        position: usize,
+        column: usize,
+        modifiers: egui::Modifiers,
+    ) {
+        let row = indices[position];
+        let columns = |first: usize, last: usize| match self.selection {
+            SelectionMode::Rows => 0..self.columns.len(),
+            SelectionMode::Cells | SelectionMode::None => first.min(last)..first.max(last) + 1,
+        };
+        let anchor = selection.anchor.and_then(|(anchor_row, anchor_column)| {
+            let anchor_position = indices.iter().position(|index| *index == anchor_row)?;
+            Some((anchor_position, anchor_column))
+        });
+        if modifiers.shift
+            && let Some((anchor_position, anchor_column)) = anchor
+        {
+            if !modifiers.command {
+                selection.cells.clear();
+            }
+            let first = anchor_position.min(position);
+            let last = anchor_position.max(position);
+            for index in &indices[first..=last] {
+                for column in columns(anchor_column, column) {
+                    selection.cells.insert((*index, column));
+                }
+            }
+            return;
+        }
+        if modifiers.command {
+            let cells: Vec<_> = columns(column, column)
+                .map(|column| (row, column))
+                .collect();
+            if cells.iter().all(|cell| selection.cells.contains(cell)) {
+                for cell in &cells {
+                    selection.cells.remove(cell);
+                }
+            } else {
+                selection.cells.extend(cells);
+            }
+        } else {
+            selection.cells.clear();
+            selection
+                .cells
+                .extend(columns(column, column).map(|column| (row, column)));
+        }
+        selection.anchor = Some((row, column));
+    }
+
+    /// Selection as tab-separated values - in the order of the visible rows, with the text of
+    /// the columns (see [`GridColumn::with_text`])
+    #[must_use]
+    pub fn selection_tsv(&self, rows: &[T], state: &TableState) -> String {
+        let clean = |text: String| text.replace(['\t', '\n', '\r'], " ");
+        let mut lines = Vec::new();
+        for index in self.visible_rows(rows, state) {
+            let cells: Vec<_> = self
+                .columns
+                .iter()
+                .enumerate()
+                .filter(|(column, _)| state.selection.contains(index, *column))
+                .map(|(_, column)| {
+                    column
+                        .text
+                        .as_ref()
+                        .map(|text| clean(text(&rows[index])))
+                        .unwrap_or_default()
+                })
+                .collect();
+            if !cells.is_empty() {
+                lines.push(cells.join("\t"));
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// Rows as comma-separated values (RFC 4180) with a header line, with the text of the
+    /// columns (see [`GridColumn::with_text`])
+    ///
+    /// With `visible_only`, only the rows matching the filters are exported, in the sort order,
+    /// otherwise all the rows in the order of the data
+    #[must_use]
+    pub fn export_csv(&self, rows: &[T], state: &TableState, visible_only: bool) -> String {
+        let field = |text: &str| {
+            if text.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            } else {
+                text.to_string()
+            }
+        };
+        let header: Vec<_> = self
+            .columns
+            .iter()
+            .map(|column| field(&column.name))
+            .collect();
+        let mut csv = header.join(",");
+        csv.push_str("\r\n");
+        let indices = if visible_only {
+            self.visible_rows(rows, state)
+        } else {
+            (0..rows.len()).collect()
+        };
+        for index in indices {
+            let cells: Vec<_> = self
+                .columns
+                .iter()
+                .map(|column| {
+                    column
+                        .text
+                        .as_ref()
+                        .map(|text| field(&text(&rows[index])))
+                        .unwrap_or_default()
+                })
+                .collect();
+            csv.push_str(&cells.join(","));
+            csv.push_str("\r\n");
+        }
+        csv
+    }
+
+    /// Show the rows
+    pub fn show(&self, ui: &mut egui::Ui, rows: &[T], state: &mut TableState) {
+        state.filters.resize(self.columns.len(), String::new());
+        let indices = self.visible_rows(rows, state);
+        let has_filter = self.columns.iter().any(|column| column.filterable);
+        let header_height = if has_filter {
+            self.row_height * 2.0 + ui.spacing().item_spacing.y
+        } else {
+            self.row_height
+        };
+        let mut clicked = None;
+        let mut action = None;
+        let has_selection = !state.selection.is_empty();
+        TableBuilder::new(ui)
+            .id_salt(self.id)
+            .striped(true)
+            // clicks select, secondary clicks open the context menu
+            .sense(egui::Sense::click())
+            .columns(
+                Column::auto().at_least(40.0).resizable(true),
+                self.columns.len(),
+            )
+            .header(header_height, |mut header| {
+                for index in 0..self.columns.len() {
+                    header.col(|ui| {
+                        ui.vertical(|ui| self.header_ui(ui, index, state));
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(self.row_height, indices.len(), |mut row| {
+                    let position = row.index();
+                    let index = indices[position];
+                    let item = &rows[index];
+                    for (column_index, column) in self.columns.iter().enumerate() {
+                        row.set_selected(state.selection.contains(index, column_index));
+                        let (_, response) = row.col(|ui| (column.cell)(item, ui));
+                        if response.clicked() && self.selection != SelectionMode::None {
+                            clicked = Some((position, column_index));
+                        }
+                        show_context_menu(&response, GRID_CONTEXT, |ui| {
+                            context_menu_ui(ui, has_selection, &mut action);
+                        });
+                    }
+                });
+            });
+        if let Some((position, column)) = clicked {
+            let modifiers = ui.input(|i| i.modifiers);
+            self.select(&mut state.selection, &indices, position, column, modifiers);
+        }
+        let copy = ui.input(|i| i.events.contains(&egui::Event::Copy));
+        if copy && !state.selection.is_empty() && ui.memory(|memory| memory.focused().is_none()) {
+            action = Some(GridAction::Copy);
+        }
+        match action {
+            Some(GridAction::Copy) => ui.ctx().copy_text(self.selection_tsv(rows, state)),
+            Some(GridAction::Export { visible_only }) => {
+                let csv = self.export_csv(rows, state, visible_only);
+                crate::dialog::save_file_dialog(ui.ctx(), csv.into_bytes(), &self.export_path);
+            }
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! CSV export
+
+    use super::{GridColumn, SortState, SortableTable, TableState};
+
+    /// Table of (name, value) rows
+    fn table() -> SortableTable<(String, i32)> {
+        SortableTable::new(
+            "test",
+            vec![
+                GridColumn::new("Name, full", |_: &(String, i32), _| {})
+                    .with_filter(|row: &(String, i32)| row.0.clone()),
+                GridColumn::new("Value", |_: &(String, i32), _| {})
+                    .with_sort(|a: &(String, i32), b: &(String, i32)| a.1.cmp(&b.1))
+                    .with_text(|row: &(String, i32)| row.1.to_string()),
+            ],
+        )
+    }
+
+    /// The fields containing a comma, a quote or a line break are quoted (RFC 4180)
+    #[test]
+    fn csv_quoting() {
+        let rows = vec![
+            ("plain".to_string(), 1),
+            ("a,b".to_string(), 2),
+            ("say \"hi\"".to_string(), 3),
+            ("two\r\nlines".to_string(), 4),
+        ];
+        let csv = table().export_csv(&rows, &TableState::default(), false);
+        assert_eq!(
+            csv,
+            "\"Name, full\",Value\r\n\
+             plain,1\r\n\
+             \"a,b\",2\r\n\
+             \"say \"\"hi\"\"\",3\r\n\
+             \"two\r\nlines\",4\r\n"
+        );
+    }
+
+    /// Only the rows matching the filters are exported, in the sort order
+    #[test]
+    fn csv_visible_rows() {
+        let rows = vec![
+            ("ab".to_string(), 2),
+            ("b".to_string(), 1),
+            ("c".to_string(), 3),
+        ];
+        let state = TableState {
+            sort: Some(SortState {
+                column: 1,
+                descending: false,
+            }),
+            filters: vec!["b".to_string(), String::new()],
+            ..TableState::default()
+        };
+        let csv = table().export_csv(&rows, &state, true);
+        assert_eq!(csv, "\"Name, full\",Value\r\nb,1\r\nab,2\r\n");
+    }
+}
@@ -3,27 +3,76 @@
 use eframe::egui;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::AppError;
 
 pub mod clipboard;
+pub mod debounce;
 pub mod document;
+pub mod format;
 pub mod grid;
+pub mod texture;
+pub mod time;
 
 pub use clipboard::{BladvakClipBoard, LazyFile};
+pub use debounce::{Debounced, Throttle};
 pub use document::Documents;
+pub use format::{format_bytes, format_duration, format_number};
+pub use texture::{TextureCache, TextureKey, TextureState};
+
+/// Keep a backup of the overwritten files - see [`set_save_backup`]
+static SAVE_BACKUP: AtomicBool = AtomicBool::new(false);
+
+/// Keep a copy of the previous version (`<name>.bak`) when [`save_file`] overwrites a file
+pub fn set_save_backup(enabled: bool) {
+    SAVE_BACKUP.store(enabled, Ordering::Relaxed);
+}
+
+/// Path with a suffix added to the file name (e.g. "doc.txt" to "doc.txt.bak")
+#[cfg(not(target_arch = "wasm32"))]
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
 
 /// Save the data to a file
+///
+/// The data is written to a temporary file renamed over the target, so a crash never leaves a
+/// partially written file. The previous version is kept if [`set_save_backup`] is enabled.
 /// # Errors
 /// Error if fails to save the file
 #[cfg(not(target_arch = "wasm32"))]
 pub fn save_file(data: &[u8], path_file: &Path) -> Result<(), String> {
-    use std::fs::File;
+    use std::fs::{self, File};
     use std::io::prelude::*;
 
-    let mut file = File::create(path_file).map_err(|e| format!("Cannot create file: {e}"))?;
-    file.write_all(data)
-        .map_err(|e| format!("Cannot write file: {e}"))
+    let temp_path = with_suffix(path_file, ".tmp");
+    let write_temp = || -> std::io::Result<()> {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        if let Ok(metadata) = fs::metadata(path_file) {
+            fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+        Ok(())
+    };
+    if let Err(e) = write_temp() {
+        let _ = fs::remove_file(&temp_path);
+        return Err(format!("Cannot write file: {e}"));
+    }
+    if SAVE_BACKUP.load(Ordering::Relaxed)
+        && path_file.is_file()
+        && let Err(e) = fs::copy(path_file, with_suffix(path_file, ".bak"))
+    {
+        let _ = fs::remove_file(&temp_path);
+        return Err(format!("Cannot create the backup: {e}"));
+    }
+    fs::rename(&temp_path, path_file).map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        format!("Cannot replace file: {e}")
+    })
 }
 
 /// Save the data as file
@@ -36,6 +85,17 @@ pub fn save_file(data: &[u8], path_file: &Path) -> Result<(), String> {
     use js_sys::Array;
 
     log::info!("Saving file to {:?}", path_file);
+    // a file chosen with the save picker is written in the background
+    if crate::web_save::has_handle(path_file) {
+        let path = path_file.to_path_buf();
+        let data = data.to_vec();
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = crate::web_save::write(&path, &data).await {
+                log::error!("{err}");
+            }
+        });
+        return Ok(());
+    }
     let filename = match path_file.file_name() {
         Some(name) => name.to_str().ok_or("Cannot get filename")?,
         None => "file.png",
@@ -70,6 +130,8 @@ pub fn save_file(data: &[u8], path_file: &Path) -> Result<(), String> {
 }
 
 /// Get the save path
+///
+/// Blocks the ui thread until the user answers - prefer [`crate::dialog::save_path`]
 /// # Errors
 /// Failed if the input is wrong
 #[cfg(not(target_arch = "wasm32"))]
@@ -90,7 +152,8 @@ pub fn get_save_path(current_path: Option<&Path>) -> Result<Option<PathBuf>, App
         .save_file();
     Ok(path)
 }
-/// Get a new path
+/// Get a new path - the browser picker is asynchronous: use [`crate::dialog::save_path`] to
+/// let the user choose the destination
 /// # Errors
 /// No error in wasm
 #[cfg(target_arch = "wasm32")]
@@ -101,6 +164,51 @@ pub fn get_save_path(current_path: Option<&Path>) -> Result<Option<PathBuf>, App
     }
 }
 
+/// Show a file in the file manager of the OS - e.g. "Show in folder" after saving
+///
+/// The file is selected in Explorer and in the Finder, other file managers open the folder
+/// of the file with `xdg-open`
+/// # Errors
+/// Error if the file manager cannot be started
+#[cfg(not(target_arch = "wasm32"))]
+pub fn reveal_in_file_manager(path: &Path) -> Result<(), AppError> {
+    use std::process::Command;
+
+    let path = path.canonicalize()?;
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("explorer");
+        command.arg("/select,").arg(&path);
+        command
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg("-R").arg(&path);
+        command
+    } else {
+        let folder = if path.is_dir() {
+            path.as_path()
+        } else {
+            path.parent().unwrap_or(&path)
+        };
+        let mut command = Command::new("xdg-open");
+        command.arg(folder);
+        command
+    };
+    command
+        .spawn()
+        .map_err(|err| AppError::new(format!("Cannot open the file manager: {err}")))?;
+    Ok(())
+}
+
+/// Show a file in the file manager of the OS - no-op on web, the saved files are downloaded
+/// by the browser
+/// # Errors
+/// Never fails on web
+#[cfg(target_arch = "wasm32")]
+pub fn reveal_in_file_manager(path: &Path) -> Result<(), AppError> {
+    log::info!("No file manager on web for {}", path.display());
+    Ok(())
+}
+
 /// Is running on web
 #[inline]
 #[must_use]
@@ -115,6 +223,31 @@ pub const fn is_native() -> bool {
     !is_web()
 }
 
+/// Target description (e.g. `linux-x86_64` or `web`)
+#[must_use]
+pub fn target_info() -> String {
+    if is_web() {
+        "web".to_string()
+    } else {
+        format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+    }
+}
+
+/// Percent-encode a string to use it in an URL query
+#[must_use]
+pub fn percent_encode(input: &str) -> String {
+    use std::fmt::Write as _;
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(char::from(byte));
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
+
 /// Copy the image to clipboard
 /// # Errors
 /// Error if fails to copy the image to clipboard
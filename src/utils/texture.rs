@@ -0,0 +1,267 @@
+//! Image decoding and texture cache
+//!
+//! Viewer-style apps receive large images in [`crate::BladvakApp::handle_file`]: the
+//! [`TextureCache`] decodes them in the background (see [`crate::task::spawn_blocking`]) and
+//! keeps the most recently used textures. A panic of the decoder gives a failed image.
+//!
+//! On web there are no threads: the image is decoded when it is inserted, blocking the ui for
+//! the duration of the decoding - keep the images small or decode them in a web worker.
+
+use eframe::egui;
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use crate::{AppError, task::TaskHandle};
+
+/// Decode an image (any format supported by the `image` crate)
+/// # Errors
+/// Error if the format is unknown or the image is invalid
+pub fn decode_image(bytes: &[u8]) -> Result<egui::ColorImage, AppError> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|err| AppError::new(format!("Cannot decode the image: {err}")))?;
+    let size = [image.width() as usize, image.height() as usize];
+    Ok(egui::ColorImage::from_rgba_unmultiplied(
+        size,
+        image.to_rgba8().as_flat_samples().as_slice(),
+    ))
+}
+
+/// Decode an image and upload it - blocks the ui, see [`TextureCache`] for large images
+/// # Errors
+/// Error if the format is unknown or the image is invalid
+pub fn load_texture(
+    ctx: &egui::Context,
+    name: impl Into<String>,
+    bytes: &[u8],
+) -> Result<egui::TextureHandle, AppError> {
+    let image = decode_image(bytes)?;
+    Ok(ctx.load_texture(name, image, egui::TextureOptions::default()))
+}
+
+/// Key of a [`TextureCache`] entry
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TextureKey {
+    /// Path of the image
+    Path(PathBuf),
+    /// Hash of the content of the image (see [`TextureKey::from_bytes`])
+    Hash(u64),
+}
+
+impl TextureKey {
+    /// Key of an image by its content
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        Self::Hash(hasher.finish())
+    }
+
+    /// Name of the texture
+    fn name(&self) -> String {
+        match self {
+            Self::Path(path) => path.display().to_string(),
+            Self::Hash(hash) => format!("{hash:016x}"),
+        }
+    }
+}
+
+impl From<&Path> for TextureKey {
+    fn from(path: &Path) -> Self {
+        Self::Path(path.to_path_buf())
+    }
+}
+
+impl From<PathBuf> for TextureKey {
+    fn from(path: PathBuf) -> Self {
+        Self::Path(path)
+    }
+}
+
+/// State of an image in a [`TextureCache`]
+#[derive(Clone)]
+pub enum TextureState {
+    /// Not in the cache
+    Missing,
+    /// Decoding in the background
+    Loading,
+    /// Decoded
+    Ready(egui::TextureHandle),
+    /// The decoding failed
+    Failed(String),
+}
+
+impl Debug for TextureState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing => f.write_str("Missing"),
+            Self::Loading => f.write_str("Loading"),
+            Self::Ready(texture) => f.debug_tuple("Ready").field(&texture.id()).finish(),
+            Self::Failed(err) => f.debug_tuple("Failed").field(err).finish(),
+        }
+    }
+}
+
+/// Cached image
+enum Entry {
+    /// Decoding in the background
+    Loading(TaskHandle<Result<egui::ColorImage, AppError>>),
+    /// Decoded
+    Ready(egui::TextureHandle),
+    /// The decoding failed
+    Failed(String),
+}
+
+/// Cache of textures decoded in the background - the least recently used textures are dropped
+/// above the capacity
+pub struct TextureCache {
+    /// Maximum number of decoded textures
+    capacity: usize,
+    /// Entries and the time of their last use
+    entries: HashMap<TextureKey, (Entry, u64)>,
+    /// Use counter
+    clock: u64,
+}
+
+impl Debug for TextureCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TextureCache")
+            .field("capacity", &self.capacity)
+            .field("entries", &self.entries.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for TextureCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_TEXTURE_CAPACITY)
+    }
+}
+
+/// Default capacity of a [`TextureCache`]
+pub const DEFAULT_TEXTURE_CAPACITY: usize = 32;
+
+impl TextureCache {
+    /// Create a cache keeping at most `capacity` textures
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Start decoding an image - nothing is done if the key is already in the cache
+    ///
+    /// On native the image is decoded on a worker thread, on web it is decoded directly
+    pub fn insert(&mut self, ctx: &egui::Context, key: impl Into<TextureKey>, bytes: Vec<u8>) {
+        let key = key.into();
+        if self.entries.contains_key(&key) {
+            return;
+        }
+        let repaint_ctx = ctx.clone();
+        let handle = crate::task::spawn_blocking(move || {
+            let image = decode_image(&bytes);
+            repaint_ctx.request_repaint();
+            image
+        });
+        self.clock += 1;
+        self.entries
+            .insert(key, (Entry::Loading(handle), self.clock));
+    }
+
+    /// Get the state of an image - the decoded images are uploaded here
+    pub fn get(&mut self, ctx: &egui::Context, key: impl Into<TextureKey>) -> TextureState {
+        let key = key.into();
+        self.clock += 1;
+        let Some((entry, last_used)) = self.entries.get_mut(&key) else {
+            return TextureState::Missing;
+        };
+        *last_used = self.clock;
+        if let Entry::Loading(_) = entry {
+            let Entry::Loading(handle) = std::mem::replace(entry, Entry::Failed(String::new()))
+            else {
+                return TextureState::Missing;
+            };
+            *entry = match handle.try_take() {
+                Ok(Ok(image)) => Entry::Ready(ctx.load_texture(
+                    key.name(),
+                    image,
+                    egui::TextureOptions::default(),
+                )),
+                Ok(Err(err)) => Entry::Failed(err.to_string()),
+                Err(handle) => Entry::Loading(handle),
+            };
+        }
+        let state = match entry {
+            Entry::Loading(_) => TextureState::Loading,
+            Entry::Ready(texture) => TextureState::Ready(texture.clone()),
+            Entry::Failed(err) => TextureState::Failed(err.clone()),
+        };
+        if matches!(state, TextureState::Ready(_)) {
+            self.evict();
+        }
+        state
+    }
+
+    /// Get the state of an image, decoding it if it is not in the cache - e.g. with the
+    /// bytes of a [`crate::File`]
+    pub fn get_or_insert(
+        &mut self,
+        ctx: &egui::Context,
+        key: impl Into<TextureKey>,
+        bytes: impl FnOnce() -> Vec<u8>,
+    ) -> TextureState {
+        let key = key.into();
+        if !self.entries.contains_key(&key) {
+            self.insert(ctx, key.clone(), bytes());
+        }
+        self.get(ctx, key)
+    }
+
+    /// Drop the least recently used textures above the capacity
+    fn evict(&mut self) {
+        loop {
+            let ready = self
+                .entries
+                .iter()
+                .filter(|(_, (entry, _))| matches!(entry, Entry::Ready(_)));
+            if ready.clone().count() <= self.capacity {
+                return;
+            }
+            let Some(oldest) = ready
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                return;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    /// Remove an image
+    pub fn remove(&mut self, key: impl Into<TextureKey>) {
+        self.entries.remove(&key.into());
+    }
+
+    /// Remove all the images
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Number of images (decoded, decoding or failed)
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the cache is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
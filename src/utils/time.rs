@@ -0,0 +1,69 @@
+//! Time utilities (working on native and web)
+
+pub use web_time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Current time
+#[must_use]
+pub fn now() -> SystemTime {
+    SystemTime::now()
+}
+
+/// Milliseconds since the unix epoch
+#[must_use]
+pub fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| u64::try_from(duration.as_millis()).unwrap_or(u64::MAX))
+        .unwrap_or_default()
+}
+
+/// Format the time of the day (UTC) as `HH:MM:SS`
+#[must_use]
+pub fn format_time(time: SystemTime) -> String {
+    let seconds = unix_millis(time) / 1000;
+    let (hours, minutes, seconds) = ((seconds / 3600) % 24, (seconds / 60) % 60, seconds % 60);
+    format!("{hours:02}:{minutes:02}:{seconds:02}")
+}
+
+/// Format a time (UTC) as `YYYY-MM-DDTHH:MM:SS.mmmZ` (RFC 3339) - e.g. in the log file
+///
+/// ```
+/// use bladvak::utils::time::{Duration, UNIX_EPOCH, format_rfc3339};
+///
+/// let time = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+/// assert_eq!(format_rfc3339(time), "2023-11-14T22:13:20.123Z");
+/// ```
+#[must_use]
+pub fn format_rfc3339(time: SystemTime) -> String {
+    let millis = unix_millis(time);
+    let days = i64::try_from(millis / 86_400_000).unwrap_or_default();
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{}.{:03}Z",
+        format_time(time),
+        millis % 1000
+    )
+}
+
+/// Year, month and day of a number of days since the unix epoch
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (
+        year,
+        u32::try_from(month).unwrap_or(1),
+        u32::try_from(day).unwrap_or(1),
+    )
+}
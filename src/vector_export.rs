@@ -0,0 +1,576 @@
+//! Vector export of a view
+//!
+//! [`capture`] runs some UI in an offscreen egui context and keeps its paint commands, which
+//! [`render`] writes as an SVG or a PDF document: the shapes and the text stay sharp at any
+//! zoom, and no rendering dependency is needed. [`export_view`] saves the document with the save
+//! dialog. Images and custom paint callbacks are skipped, and the text of a PDF uses the
+//! standard Helvetica font - the characters outside of Latin-1 are replaced with `?`.
+
+use eframe::egui::{
+    self, Color32, Pos2, Rect, Shape, Stroke, StrokeKind, Vec2,
+    epaint::{ClippedShape, ColorMode, CornerRadius, Mesh, PathStroke, RectShape, TextShape},
+};
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+/// Number of segments of a circle or an ellipse
+const ELLIPSE_SEGMENTS: u16 = 32;
+
+/// Number of segments of a rounded corner
+const CORNER_SEGMENTS: u16 = 6;
+
+/// Format of a vector export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorFormat {
+    /// Scalable Vector Graphics
+    Svg,
+    /// Portable Document Format - one page of the size of the view
+    Pdf,
+}
+
+impl VectorFormat {
+    /// Extension of the format (without the dot)
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Svg => "svg",
+            Self::Pdf => "pdf",
+        }
+    }
+}
+
+/// Drawing primitive of an export
+#[derive(Debug)]
+enum Primitive {
+    /// Polyline or polygon
+    Path {
+        /// Points of the path
+        points: Vec<Pos2>,
+        /// The last point is joined to the first one
+        closed: bool,
+        /// Fill of a closed path
+        fill: Color32,
+        /// Stroke of the path
+        stroke: Stroke,
+    },
+    /// Row of text
+    Text {
+        /// Position of the baseline
+        pos: Pos2,
+        /// Text of the row
+        text: String,
+        /// Font size
+        size: f32,
+        /// Color of the text
+        color: Color32,
+    },
+}
+
+/// Capture the paint commands of some UI laid out in a view of the given size
+///
+/// The UI runs in an offscreen context with the style and the fonts of `ctx`, in a central
+/// panel covering the view.
+pub fn capture(
+    ctx: &egui::Context,
+    size: Vec2,
+    mut add_contents: impl FnMut(&mut egui::Ui),
+) -> Vec<ClippedShape> {
+    let offscreen = egui::Context::default();
+    offscreen.set_global_style(ctx.global_style());
+    offscreen.set_fonts(ctx.fonts(|fonts| fonts.definitions().clone()));
+    let mut shapes = Vec::new();
+    // the first pass measures the widgets, the second one paints them at their place
+    for _ in 0..2 {
+        let input = egui::RawInput {
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, size)),
+            ..Default::default()
+        };
+        shapes = offscreen
+            .run_ui(input, |ui| {
+                egui::Frame::central_panel(ui.style()).show(ui, |ui| {
+                    ui.set_min_size(ui.available_size());
+                    add_contents(ui);
+                });
+            })
+            .shapes;
+    }
+    shapes
+}
+
+/// Write paint commands as a document of the given size
+#[must_use]
+pub fn render(format: VectorFormat, shapes: &[ClippedShape], size: Vec2) -> Vec<u8> {
+    let mut primitives = Vec::new();
+    for clipped in shapes {
+        if clipped
+            .clip_rect
+            .intersects(clipped.shape.visual_bounding_rect())
+        {
+            flatten(&clipped.shape, &mut primitives);
+        }
+    }
+    match format {
+        VectorFormat::Svg => svg(&primitives, size).into_bytes(),
+        VectorFormat::Pdf => pdf(&primitives, size),
+    }
+}
+
+/// Capture some UI and save it with the save dialog - the extension of the file name is
+/// replaced by the one of the format
+pub fn export_view(
+    ctx: &egui::Context,
+    format: VectorFormat,
+    size: Vec2,
+    file_name: &Path,
+    add_contents: impl FnMut(&mut egui::Ui),
+) {
+    let shapes = capture(ctx, size, add_contents);
+    crate::dialog::save_file_dialog(
+        ctx,
+        render(format, &shapes, size),
+        &file_name.with_extension(format.extension()),
+    );
+}
+
+/// Color of a path stroke - the gradients are not exported
+fn path_stroke(stroke: &PathStroke) -> Stroke {
+    match stroke.color {
+        ColorMode::Solid(color) => Stroke::new(stroke.width, color),
+        ColorMode::UV(_) => Stroke::NONE,
+    }
+}
+
+/// Points of an ellipse
+fn ellipse(center: Pos2, radius: Vec2, angle: f32) -> Vec<Pos2> {
+    let rotation = egui::emath::Rot2::from_angle(angle);
+    (0..ELLIPSE_SEGMENTS)
+        .map(|i| {
+            let t = std::f32::consts::TAU * f32::from(i) / f32::from(ELLIPSE_SEGMENTS);
+            center + rotation * Vec2::new(radius.x * t.cos(), radius.y * t.sin())
+        })
+        .collect()
+}
+
+/// Points of a rounded rectangle, clockwise from the top left corner
+fn rounded_rect(rect: Rect, corner_radius: CornerRadius) -> Vec<Pos2> {
+    let max_radius = rect.width().min(rect.height()) / 2.0;
+    let corners = [
+        (corner_radius.nw, Pos2::new(1.0, 1.0), 0.5),
+        (corner_radius.ne, Pos2::new(-1.0, 1.0), 0.75),
+        (corner_radius.se, Pos2::new(-1.0, -1.0), 0.0),
+        (corner_radius.sw, Pos2::new(1.0, -1.0), 0.25),
+    ];
+    let mut points = Vec::new();
+    for ((radius, inward, start), corner) in corners.into_iter().zip([
+        rect.left_top(),
+        rect.right_top(),
+        rect.right_bottom(),
+        rect.left_bottom(),
+    ]) {
+        let radius = f32::from(radius).min(max_radius);
+        if radius <= 0.0 {
+            points.push(corner);
+            continue;
+        }
+        let center = corner + inward.to_vec2() * radius;
+        for i in 0..=CORNER_SEGMENTS {
+            let turn = start + 0.25 * f32::from(i) / f32::from(CORNER_SEGMENTS);
+            points.push(center + Vec2::angled(std::f32::consts::TAU * turn) * radius);
+        }
+    }
+    points
+}
+
+/// Points of a rectangle - the stroke is centered on the exported path
+fn rect_points(shape: &RectShape) -> Vec<Pos2> {
+    let rect = match shape.stroke_kind {
+        StrokeKind::Inside => shape.rect.shrink(shape.stroke.width / 2.0),
+        StrokeKind::Middle => shape.rect,
+        StrokeKind::Outside => shape.rect.expand(shape.stroke.width / 2.0),
+    };
+    let mut points = rounded_rect(rect, shape.corner_radius);
+    if shape.angle != 0.0 {
+        let rotation = egui::emath::Rot2::from_angle(shape.angle);
+        let center = shape.rect.center();
+        for point in &mut points {
+            *point = center + rotation * (*point - center);
+        }
+    }
+    points
+}
+
+/// Add the triangles of a mesh - the textured meshes (images) are skipped
+fn mesh_triangles(mesh: &Mesh, primitives: &mut Vec<Primitive>) {
+    if mesh.texture_id != egui::TextureId::default() {
+        return;
+    }
+    for triangle in mesh.indices.chunks_exact(3) {
+        let vertices: Vec<_> = triangle
+            .iter()
+            .filter_map(|index| mesh.vertices.get(*index as usize))
+            .collect();
+        if let Some(first) = vertices.first() {
+            primitives.push(Primitive::Path {
+                points: vertices.iter().map(|vertex| vertex.pos).collect(),
+                closed: true,
+                fill: first.color,
+                stroke: Stroke::NONE,
+            });
+        }
+    }
+}
+
+/// Add the rows of a text - the color and the size of the first section are used for the
+/// whole text
+fn text_rows(shape: &TextShape, primitives: &mut Vec<Primitive>) {
+    let format = shape
+        .galley
+        .job
+        .sections
+        .first()
+        .map(|section| &section.format);
+    let mut color = shape
+        .override_text_color
+        .or_else(|| format.map(|format| format.color))
+        .unwrap_or(shape.fallback_color);
+    if color == Color32::PLACEHOLDER {
+        color = shape.fallback_color;
+    }
+    let color = color.gamma_multiply(shape.opacity_factor);
+    let size = format.map_or(14.0, |format| format.font_id.size);
+    for placed in &shape.galley.rows {
+        let Some(first) = placed.row.glyphs.first() else {
+            continue;
+        };
+        let text: String = placed.row.glyphs.iter().map(|glyph| glyph.chr).collect();
+        if text.trim().is_empty() {
+            continue;
+        }
+        primitives.push(Primitive::Text {
+            pos: shape.pos + placed.pos.to_vec2() + first.pos.to_vec2(),
+            text,
+            size,
+            color,
+        });
+    }
+}
+
+/// Add the primitives of a shape
+fn flatten(shape: &Shape, primitives: &mut Vec<Primitive>) {
+    let mut path = |points: Vec<Pos2>, closed: bool, fill: Color32, stroke: Stroke| {
+        if points.len() >= 2 {
+            primitives.push(Primitive::Path {
+                points,
+                closed,
+                fill,
+                stroke,
+            });
+        }
+    };
+    match shape {
+        Shape::Vec(shapes) => {
+            for shape in shapes {
+                flatten(shape, primitives);
+            }
+        }
+        Shape::Circle(circle) => path(
+            ellipse(circle.center, Vec2::splat(circle.radius), 0.0),
+            true,
+            circle.fill,
+            circle.stroke,
+        ),
+        Shape::Ellipse(shape) => path(
+            ellipse(shape.center, shape.radius, shape.angle),
+            true,
+            shape.fill,
+            shape.stroke,
+        ),
+        Shape::LineSegment { points, stroke } => {
+            path(points.to_vec(), false, Color32::TRANSPARENT, *stroke);
+        }
+        Shape::Path(shape) => path(
+            shape.points.clone(),
+            shape.closed,
+            shape.fill,
+            path_stroke(&shape.stroke),
+        ),
+        Shape::Rect(shape) => path(rect_points(shape), true, shape.fill, shape.stroke),
+        Shape::QuadraticBezier(shape) => path(
+            shape.flatten(None),
+            shape.closed,
+            shape.fill,
+            path_stroke(&shape.stroke),
+        ),
+        Shape::CubicBezier(shape) => path(
+            shape.flatten(None),
+            shape.closed,
+            shape.fill,
+            path_stroke(&shape.stroke),
+        ),
+        Shape::Mesh(mesh) => mesh_triangles(mesh, primitives),
+        Shape::Text(shape) => text_rows(shape, primitives),
+        Shape::Noop | Shape::Callback(_) => {}
+    }
+}
+
+/// Opaque color and opacity of a color
+fn split_alpha(color: Color32) -> ([u8; 3], f32) {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    ([r, g, b], f32::from(a) / 255.0)
+}
+
+/// Escape a text for XML
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// SVG paint attribute (`fill` or `stroke`) of a color
+fn svg_paint(attribute: &str, color: Color32) -> String {
+    if color.a() == 0 {
+        return format!(r#"{attribute}="none""#);
+    }
+    let ([r, g, b], alpha) = split_alpha(color);
+    let mut paint = format!(r#"{attribute}="rgb({r},{g},{b})""#);
+    if alpha < 1.0 {
+        let _ = write!(paint, r#" {attribute}-opacity="{alpha:.3}""#);
+    }
+    paint
+}
+
+/// Write the primitives as an SVG document
+fn svg(primitives: &[Primitive], size: Vec2) -> String {
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = size.x,
+        h = size.y
+    );
+    svg.push('\n');
+    for primitive in primitives {
+        match primitive {
+            Primitive::Path {
+                points,
+                closed,
+                fill,
+                stroke,
+            } => {
+                let mut data = String::new();
+                for (i, point) in points.iter().enumerate() {
+                    let command = if i == 0 { 'M' } else { 'L' };
+                    let _ = write!(data, "{command}{:.2} {:.2} ", point.x, point.y);
+                }
+                if *closed {
+                    data.push('Z');
+                }
+                let fill = if *closed { *fill } else { Color32::TRANSPARENT };
+                let _ = write!(
+                    svg,
+                    r#"<path d="{}" {} {}"#,
+                    data.trim_end(),
+                    svg_paint("fill", fill),
+                    svg_paint("stroke", stroke.color)
+                );
+                if !stroke.is_empty() {
+                    let _ = write!(
+                        svg,
+                        r#" stroke-width="{:.2}" stroke-linejoin="round""#,
+                        stroke.width
+                    );
+                }
+                svg.push_str("/>\n");
+            }
+            Primitive::Text {
+                pos,
+                text,
+                size,
+                color,
+            } => {
+                let _ = writeln!(
+                    svg,
+                    r#"<text x="{:.2}" y="{:.2}" font-family="sans-serif" font-size="{size:.2}" {} xml:space="preserve">{}</text>"#,
+                    pos.x,
+                    pos.y,
+                    svg_paint("fill", *color),
+                    escape_xml(text)
+                );
+            }
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Encode a text as a PDF string of the `WinAnsiEncoding`
+fn pdf_string(text: &str) -> String {
+    let mut string = String::with_capacity(text.len() + 2);
+    string.push('(');
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                string.push('\\');
+                string.push(c);
+            }
+            ' '..='~' => string.push(c),
+            // the Latin-1 supplement has the same codes in the encoding
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(string, "\\{:03o}", u32::from(c));
+            }
+            _ => string.push('?'),
+        }
+    }
+    string.push(')');
+    string
+}
+
+/// PDF color operands of a color
+fn pdf_color(color: [u8; 3]) -> String {
+    let [r, g, b] = color.map(|component| f32::from(component) / 255.0);
+    format!("{r:.3} {g:.3} {b:.3}")
+}
+
+/// Content stream of a PDF page
+struct PdfContent {
+    /// Operators of the page
+    operators: String,
+    /// Height of the page - the PDF y axis goes up
+    height: f32,
+    /// Graphics states of the opacities, by alpha
+    opacities: BTreeMap<u8, usize>,
+}
+
+impl PdfContent {
+    /// Select the graphics state of an opacity
+    fn opacity(&mut self, alpha: u8) {
+        let count = self.opacities.len();
+        let index = *self.opacities.entry(alpha).or_insert(count);
+        let _ = writeln!(self.operators, "/GS{index} gs");
+    }
+
+    /// Add a path, then paint it with an operator
+    fn path(&mut self, points: &[Pos2], closed: bool, color: Color32, paint: &str) {
+        let ([r, g, b], _) = split_alpha(color);
+        self.opacity(color.to_srgba_unmultiplied()[3]);
+        let color = pdf_color([r, g, b]);
+        let color_operator = if paint == "f" { "rg" } else { "RG" };
+        let _ = writeln!(self.operators, "{color} {color_operator}");
+        for (i, point) in points.iter().enumerate() {
+            let operator = if i == 0 { 'm' } else { 'l' };
+            let _ = writeln!(
+                self.operators,
+                "{:.2} {:.2} {operator}",
+                point.x,
+                self.height - point.y
+            );
+        }
+        if closed {
+            self.operators.push_str("h ");
+        }
+        let _ = writeln!(self.operators, "{paint}");
+    }
+
+    /// Add a primitive
+    fn add(&mut self, primitive: &Primitive) {
+        match primitive {
+            Primitive::Path {
+                points,
+                closed,
+                fill,
+                stroke,
+            } => {
+                if *closed && fill.a() > 0 {
+                    self.path(points, true, *fill, "f");
+                }
+                if !stroke.is_empty() {
+                    let _ = writeln!(self.operators, "{:.2} w", stroke.width);
+                    self.path(points, *closed, stroke.color, "S");
+                }
+            }
+            Primitive::Text {
+                pos,
+                text,
+                size,
+                color,
+            } => {
+                let (rgb, _) = split_alpha(*color);
+                self.opacity(color.to_srgba_unmultiplied()[3]);
+                let _ = writeln!(
+                    self.operators,
+                    "BT /F1 {size:.2} Tf {} rg {:.2} {:.2} Td {} Tj ET",
+                    pdf_color(rgb),
+                    pos.x,
+                    self.height - pos.y,
+                    pdf_string(text)
+                );
+            }
+        }
+    }
+}
+
+/// Write the primitives as a one page PDF document
+fn pdf(primitives: &[Primitive], size: Vec2) -> Vec<u8> {
+    let mut content = PdfContent {
+        operators: String::from("1 j 1 J\n"),
+        height: size.y,
+        opacities: BTreeMap::new(),
+    };
+    for primitive in primitives {
+        content.add(primitive);
+    }
+    let mut states = String::new();
+    for index in content.opacities.values() {
+        let _ = write!(states, "/GS{index} {} 0 R ", 6 + index);
+    }
+    let mut objects = vec![
+        String::from("<< /Type /Catalog /Pages 2 0 R >>"),
+        String::from("<< /Type /Pages /Kids [3 0 R] /Count 1 >>"),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources << /Font << /F1 5 0 R >> /ExtGState << {states}>> >> /Contents 4 0 R >>",
+            size.x, size.y
+        ),
+        format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.operators.len(),
+            content.operators
+        ),
+        String::from(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>",
+        ),
+    ];
+    let mut opacities: Vec<_> = content.opacities.into_iter().collect();
+    opacities.sort_by_key(|(_, index)| *index);
+    for (alpha, _) in opacities {
+        let alpha = f32::from(alpha) / 255.0;
+        objects.push(format!(
+            "<< /Type /ExtGState /ca {alpha:.3} /CA {alpha:.3} >>"
+        ));
+    }
+    let mut document = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(document.len());
+        let _ = write!(document, "{} 0 obj\n{object}\nendobj\n", i + 1);
+    }
+    let xref = document.len();
+    let _ = write!(
+        document,
+        "xref\n0 {}\n0000000000 65535 f \n",
+        objects.len() + 1
+    );
+    for offset in offsets {
+        let _ = writeln!(document, "{offset:010} 00000 n ");
+    }
+    let _ = write!(
+        document,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    );
+    document.into_bytes()
+}
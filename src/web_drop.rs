@@ -0,0 +1,109 @@
+//! Drop of folders on web
+//!
+//! The browser only gives the dropped files to eframe: the dropped folders are traversed with
+//! `webkitGetAsEntry` and their files are submitted with their path relative to the drop, with
+//! the same [`FolderDrop`] options as on native
+
+use eframe::{
+    egui,
+    wasm_bindgen::{JsCast, closure::Closure},
+    web_sys::{self, DragEvent, FileSystemDirectoryEntry, FileSystemEntry, FileSystemFileEntry},
+};
+use std::{path::Path, rc::Rc};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::file_handler::{File, FileOrigin, FolderDrop, submit_file};
+
+/// Listen to the drops of folders
+pub(crate) fn install(ctx: &egui::Context, folder_drop: FolderDrop) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let ctx = ctx.clone();
+    let folder_drop = Rc::new(folder_drop);
+    let on_drop = Closure::<dyn FnMut(DragEvent)>::new(move |event: DragEvent| {
+        let Some(items) = event.data_transfer().map(|transfer| transfer.items()) else {
+            return;
+        };
+        for idx in 0..items.length() {
+            if let Some(item) = items.get(idx)
+                && let Ok(Some(entry)) = item.webkit_get_as_entry()
+                && entry.is_directory()
+            {
+                visit(&entry, &ctx, &folder_drop, true);
+            }
+        }
+    });
+    // capture: the entries are only available during the event
+    if window
+        .add_event_listener_with_callback_and_bool("drop", on_drop.as_ref().unchecked_ref(), true)
+        .is_err()
+    {
+        log::warn!("Cannot listen to the folder drops");
+    }
+    on_drop.forget();
+}
+
+/// Submit a file or the files of a folder - the sub-folders are only read if `recursive`
+fn visit(entry: &FileSystemEntry, ctx: &egui::Context, folder_drop: &Rc<FolderDrop>, root: bool) {
+    if entry.is_file() {
+        let path = entry.full_path().trim_start_matches('/').to_string();
+        if !folder_drop.accepts(Path::new(&path)) {
+            return;
+        }
+        let ctx = ctx.clone();
+        let on_file = Closure::once_into_js(move |file: web_sys::File| {
+            wasm_bindgen_futures::spawn_local(async move {
+                match JsFuture::from(file.array_buffer()).await {
+                    Ok(buffer) => {
+                        let data = js_sys::Uint8Array::new(&buffer).to_vec();
+                        submit_file(
+                            &ctx,
+                            File::new(data, path)
+                                .with_origin(FileOrigin::BrowserDrop)
+                                .with_web_file(&file),
+                        );
+                    }
+                    Err(err) => log::warn!("Cannot read {path}: {err:?}"),
+                }
+            });
+        });
+        entry
+            .unchecked_ref::<FileSystemFileEntry>()
+            .file_with_callback(on_file.unchecked_ref());
+    } else if entry.is_directory() && (root || folder_drop.recursive) {
+        let reader = entry
+            .unchecked_ref::<FileSystemDirectoryEntry>()
+            .create_reader();
+        read_entries(&reader, ctx.clone(), folder_drop.clone());
+    }
+}
+
+/// Read the entries of a folder - `readEntries` returns them by batches until an empty batch
+fn read_entries(
+    reader: &web_sys::FileSystemDirectoryReader,
+    ctx: egui::Context,
+    folder_drop: Rc<FolderDrop>,
+) {
+    let next_reader = reader.clone();
+    let on_entries = Closure::once_into_js(move |entries: js_sys::Array| {
+        if entries.length() == 0 {
+            return;
+        }
+        for entry in entries.iter() {
+            visit(
+                entry.unchecked_ref::<FileSystemEntry>(),
+                &ctx,
+                &folder_drop,
+                false,
+            );
+        }
+        read_entries(&next_reader, ctx, folder_drop);
+    });
+    if reader
+        .read_entries_with_callback(on_entries.unchecked_ref())
+        .is_err()
+    {
+        log::warn!("Cannot read the dropped folder");
+    }
+}
@@ -0,0 +1,113 @@
+//! Saves with the File System Access API on web
+//!
+//! Where the browser has `showSaveFilePicker` (e.g. Chromium), the user chooses a real
+//! destination and the file handle is kept by name: saving again to the same path writes to the
+//! same file without asking. The other browsers download the file.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use eframe::{
+    wasm_bindgen::{JsCast, JsValue},
+    web_sys,
+};
+use js_sys::{Function, Object, Promise, Reflect, Uint8Array};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::AppError;
+
+thread_local! {
+    /// Handles of the files chosen with the picker, by path
+    static HANDLES: RefCell<HashMap<PathBuf, JsValue>> = RefCell::new(HashMap::new());
+}
+
+/// Error of a JS call
+fn js_error(action: &str, err: &JsValue) -> AppError {
+    let message = Reflect::get(err, &"message".into())
+        .ok()
+        .and_then(|message| message.as_string())
+        .unwrap_or_else(|| format!("{err:?}"));
+    AppError::new(format!("{action}: {message}"))
+}
+
+/// Call a method of a JS object and wait for the returned promise
+async fn call_async(target: &JsValue, method: &str, args: &[JsValue]) -> Result<JsValue, JsValue> {
+    let function: Function = Reflect::get(target, &method.into())?.dyn_into()?;
+    let result = match args {
+        [] => function.call0(target)?,
+        [arg] => function.call1(target, arg)?,
+        _ => function.apply(target, &args.iter().collect())?,
+    };
+    JsFuture::from(result.dyn_into::<Promise>()?).await
+}
+
+/// Check if the browser has the save picker
+pub(crate) fn is_supported() -> bool {
+    web_sys::window().is_some_and(|window| {
+        Reflect::get(&window, &"showSaveFilePicker".into()).is_ok_and(|picker| picker.is_function())
+    })
+}
+
+/// Check if a file handle is kept for the path
+pub(crate) fn has_handle(path: &Path) -> bool {
+    HANDLES.with(|handles| handles.borrow().contains_key(path))
+}
+
+/// Ask where to save with the picker - `None` if cancelled
+/// # Errors
+/// Error if the picker cannot be shown (e.g. without a user gesture)
+pub(crate) async fn pick(suggested: &Path) -> Result<Option<PathBuf>, AppError> {
+    let window: JsValue = web_sys::window()
+        .ok_or_else(|| AppError::new("Cannot get the website window".to_string()))?
+        .into();
+    let options = Object::new();
+    if let Some(name) = suggested.file_name() {
+        Reflect::set(
+            &options,
+            &"suggestedName".into(),
+            &name.to_string_lossy().as_ref().into(),
+        )
+        .map_err(|err| js_error("Cannot show the save picker", &err))?;
+    }
+    let handle = match call_async(&window, "showSaveFilePicker", &[options.into()]).await {
+        Ok(handle) => handle,
+        Err(err) => {
+            let name = Reflect::get(&err, &"name".into()).ok();
+            if name.and_then(|name| name.as_string()).as_deref() == Some("AbortError") {
+                return Ok(None);
+            }
+            return Err(js_error("Cannot show the save picker", &err));
+        }
+    };
+    let name = Reflect::get(&handle, &"name".into())
+        .ok()
+        .and_then(|name| name.as_string())
+        .unwrap_or_else(|| "file".to_string());
+    let path = PathBuf::from(name);
+    HANDLES.with(|handles| handles.borrow_mut().insert(path.clone(), handle));
+    Ok(Some(path))
+}
+
+/// Write the data to the file handle kept for the path
+/// # Errors
+/// Error if no handle is kept for the path or if the file cannot be written
+pub(crate) async fn write(path: &Path, data: &[u8]) -> Result<(), AppError> {
+    let handle = HANDLES
+        .with(|handles| handles.borrow().get(path).cloned())
+        .ok_or_else(|| AppError::new(format!("No file handle for {}", path.display())))?;
+    let writable = call_async(&handle, "createWritable", &[])
+        .await
+        .map_err(|err| js_error("Cannot open the file", &err))?;
+    if let Err(err) = call_async(&writable, "write", &[Uint8Array::from(data).into()]).await {
+        // the previous content is kept when the stream is aborted
+        let _ = call_async(&writable, "abort", &[]).await;
+        return Err(js_error("Cannot write the file", &err));
+    }
+    call_async(&writable, "close", &[])
+        .await
+        .map_err(|err| js_error("Cannot close the file", &err))?;
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! Protection of the unsaved changes on web
+//!
+//! While [`crate::BladvakApp::is_dirty`] is true, a `beforeunload` handler asks the browser to
+//! confirm before the tab is closed or reloaded. The handler is removed once the state is clean.
+
+use std::cell::RefCell;
+
+use eframe::{
+    wasm_bindgen::{JsCast, closure::Closure},
+    web_sys::{self, BeforeUnloadEvent},
+};
+
+/// Handler of the `beforeunload` event
+type Handler = Closure<dyn FnMut(BeforeUnloadEvent)>;
+
+thread_local! {
+    /// Registered handler - `None` when the state is clean
+    static HANDLER: RefCell<Option<Handler>> = const { RefCell::new(None) };
+}
+
+/// Register or remove the `beforeunload` handler
+pub(crate) fn set_dirty(dirty: bool) {
+    HANDLER.with(|handler| {
+        let mut handler = handler.borrow_mut();
+        if dirty == handler.is_some() {
+            return;
+        }
+        let Some(window) = web_sys::window() else {
+            return;
+        };
+        if let Some(closure) = handler.take() {
+            let _ = window.remove_event_listener_with_callback(
+                "beforeunload",
+                closure.as_ref().unchecked_ref(),
+            );
+            return;
+        }
+        let closure = Handler::new(|event: BeforeUnloadEvent| {
+            event.prevent_default();
+            // required by the older browsers to show the prompt
+            event.set_return_value("");
+        });
+        if window
+            .add_event_listener_with_callback("beforeunload", closure.as_ref().unchecked_ref())
+            .is_err()
+        {
+            log::warn!("Cannot protect the unsaved changes");
+            return;
+        }
+        *handler = Some(closure);
+    });
+}
@@ -0,0 +1,68 @@
+//! "What's new" dialog
+//!
+//! The version of the app is saved with the internal state. When it changes, the changelog of
+//! [`crate::BladvakApp::whats_new`] is shown once, unless the user turned it off with
+//! "Don't show again". The changelog is markdown (see [`crate::widgets::markdown`]).
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{Bladvak, BladvakApp};
+
+/// Id of the open state of the dialog
+fn whats_new_id() -> egui::Id {
+    egui::Id::new("bladvak_whats_new")
+}
+
+/// Show a changelog - see [`crate::widgets::markdown`]
+pub fn changelog_ui(ui: &mut egui::Ui, changelog: &str) {
+    crate::widgets::markdown(ui, changelog);
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Save the version and open the dialog if the app was upgraded
+    pub(crate) fn check_version(&mut self, ctx: &egui::Context) {
+        let version = M::version();
+        let previous = self.internal.last_version.replace(version.clone());
+        let upgraded = previous.is_some_and(|previous| previous != version);
+        if upgraded && self.internal.settings.show_whats_new && M::whats_new().is_some() {
+            log::info!("Upgraded to {version}");
+            ctx.data_mut(|data| data.insert_temp(whats_new_id(), true));
+        }
+    }
+
+    /// Show the "What's new" dialog
+    pub(crate) fn show_whats_new(&mut self, ctx: &egui::Context) {
+        if !ctx.data(|data| data.get_temp::<bool>(whats_new_id()).unwrap_or(false)) {
+            return;
+        }
+        let Some(changelog) = M::whats_new() else {
+            return;
+        };
+        let mut closed = false;
+        let modal = egui::Modal::new(egui::Id::new("Modal whats new")).show(ctx, |ui| {
+            crate::a11y::annotate(ui, egui::accesskit::Role::Dialog, "What's new");
+            ui.heading(format!("What's new in {} {}", M::name(), M::version()));
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .max_height(ui.ctx().content_rect().height() * 0.6)
+                .show(ui, |ui| changelog_ui(ui, &changelog));
+            ui.separator();
+            ui.horizontal(|ui| {
+                let mut dont_show = !self.internal.settings.show_whats_new;
+                if ui.checkbox(&mut dont_show, "Don't show again").changed() {
+                    self.internal.settings.show_whats_new = !dont_show;
+                }
+                if ui.button("Close").clicked() {
+                    closed = true;
+                }
+            });
+        });
+        if closed || modal.should_close() {
+            ctx.data_mut(|data| data.remove::<bool>(whats_new_id()));
+        }
+    }
+}
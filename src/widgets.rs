@@ -0,0 +1,121 @@
+//! Widgets
+//!
+//! [`markdown`] shows the markdown of the help topics, of the "What's new" dialog and of the
+//! apps. With the `markdown` feature it is rendered by `egui_commonmark` (re-exported as
+//! `bladvak::egui_commonmark`), without it the text is shown as is.
+//!
+//! The external links go through [`link`] and [`open_link`]: with the "Confirm before opening
+//! external links" setting, the full URL is shown in a confirmation dialog before the browser
+//! is launched.
+
+use eframe::egui::{self, RichText};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{Bladvak, BladvakApp};
+
+/// Id of the confirmation setting
+fn confirm_links_id() -> egui::Id {
+    egui::Id::new("bladvak_confirm_links")
+}
+
+/// Id of the link waiting for a confirmation
+fn pending_link_id() -> egui::Id {
+    egui::Id::new("bladvak_pending_link")
+}
+
+/// Open an external link in a new tab - after a confirmation if the setting is enabled
+pub fn open_link(ctx: &egui::Context, url: &str) {
+    if ctx.data(|data| data.get_temp::<bool>(confirm_links_id()).unwrap_or(false)) {
+        ctx.data_mut(|data| data.insert_temp(pending_link_id(), url.to_string()));
+    } else {
+        ctx.open_url(egui::OpenUrl::new_tab(url));
+    }
+}
+
+/// Show an external link - opened with [`open_link`]
+pub fn link(ui: &mut egui::Ui, label: impl Into<egui::WidgetText>, url: &str) -> egui::Response {
+    let response = ui.add(egui::Link::new(label)).on_hover_text(url);
+    if response.clicked() {
+        open_link(ui.ctx(), url);
+    }
+    response
+}
+
+/// Cache of the markdown renders - shared through the egui context
+#[cfg(feature = "markdown")]
+type MarkdownCache = std::sync::Arc<std::sync::Mutex<egui_commonmark::CommonMarkCache>>;
+
+/// Render markdown with `egui_commonmark` - the links it opens go through [`open_link`]
+#[cfg(feature = "markdown")]
+fn commonmark(ui: &mut egui::Ui, text: &str) {
+    let cache = ui.data_mut(|data| {
+        data.get_temp_mut_or_default::<MarkdownCache>(egui::Id::new("bladvak_markdown_cache"))
+            .clone()
+    });
+    let Ok(mut cache) = cache.lock() else {
+        ui.label(text);
+        return;
+    };
+    let ctx = ui.ctx().clone();
+    let before = ctx.output(|output| output.commands.len());
+    egui_commonmark::CommonMarkViewer::new().show(ui, &mut cache, text);
+    let commands = ctx.output_mut(|output| output.commands.split_off(before));
+    for command in commands {
+        match command {
+            egui::OutputCommand::OpenUrl(open) => open_link(&ctx, &open.url),
+            command => ctx.send_cmd(command),
+        }
+    }
+}
+
+/// Show a markdown text - rendered with the `markdown` feature, shown as plain text without
+///
+/// ```
+/// use bladvak::eframe::egui;
+///
+/// egui::__run_test_ui(|ui| {
+///     bladvak::widgets::markdown(
+///         ui,
+///         "# Title\nSome **strong** and *emphasized* `code`, see [the docs](https://docs.rs).\n\n- item\n  - sub item\n1. first\n\n```\nlet x = 1;\n```",
+///     );
+/// });
+/// ```
+pub fn markdown(ui: &mut egui::Ui, text: &str) {
+    #[cfg(feature = "markdown")]
+    commonmark(ui, text);
+    #[cfg(not(feature = "markdown"))]
+    ui.label(text);
+}
+
+impl<M> Bladvak<M>
+where
+    M: for<'a> BladvakApp<'a> + Serialize + for<'a> Deserialize<'a> + 'static,
+{
+    /// Show the confirmation of the external link waiting to be opened
+    pub(crate) fn show_link_confirmation(&self, ctx: &egui::Context) {
+        let confirm = self.internal.settings.confirm_links;
+        ctx.data_mut(|data| data.insert_temp(confirm_links_id(), confirm));
+        let Some(url) = ctx.data(|data| data.get_temp::<String>(pending_link_id())) else {
+            return;
+        };
+        let mut done = false;
+        let modal = egui::Modal::new(egui::Id::new("Modal link confirmation")).show(ctx, |ui| {
+            crate::a11y::annotate(ui, egui::accesskit::Role::AlertDialog, "Open external link");
+            ui.heading("Open external link?");
+            ui.add(egui::Label::new(RichText::new(&url).monospace()).wrap());
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Open").clicked() {
+                    ui.ctx().open_url(egui::OpenUrl::new_tab(&url));
+                    done = true;
+                }
+                if ui.button("Cancel").clicked() {
+                    done = true;
+                }
+            });
+        });
+        if done || modal.should_close() {
+            ctx.data_mut(|data| data.remove::<String>(pending_link_id()));
+        }
+    }
+}